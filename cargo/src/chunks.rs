@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::storage;
+use crate::verify;
+
+/// One NDJSON record of `chunks` output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChunkRecord {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub version: String,
+    pub path: String,
+    pub chunk_index: usize,
+    pub text: String,
+}
+
+/// Splits `text` into windows of `chunk_size` whitespace-separated tokens,
+/// each starting `chunk_size - overlap` tokens after the previous one.
+///
+/// Chunks are slices of the original text, so line breaks and markdown
+/// structure inside a chunk are preserved. Callers ensure `overlap < chunk_size`.
+pub fn chunk_text(text: &str, chunk_size: usize, overlap: usize) -> Vec<&str> {
+    let spans = token_spans(text);
+    if spans.is_empty() || chunk_size == 0 {
+        return Vec::new();
+    }
+
+    let step = chunk_size.saturating_sub(overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + chunk_size).min(spans.len());
+        chunks.push(&text[spans[start].0..spans[end - 1].1]);
+        if end == spans.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+fn token_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut token_start = None;
+    for (idx, ch) in text.char_indices() {
+        match (ch.is_whitespace(), token_start) {
+            (true, Some(start)) => {
+                spans.push((start, idx));
+                token_start = None;
+            }
+            (false, None) => token_start = Some(idx),
+            _ => {}
+        }
+    }
+    if let Some(start) = token_start {
+        spans.push((start, text.len()));
+    }
+    spans
+}
+
+/// Chunks every saved doc file of one `crate@version` dir (provenance headers
+/// stripped), in file name order.
+pub fn collect_crate_chunks(
+    crate_name: &str,
+    version: &str,
+    crate_dir: &Path,
+    chunk_size: usize,
+    overlap: usize,
+) -> Vec<ChunkRecord> {
+    let mut files = verify::stored_doc_files(crate_dir);
+    files.sort();
+
+    let mut records = Vec::new();
+    for path in files {
+        let Ok(content) = fs::read_to_string(crate_dir.join(&path)) else {
+            continue;
+        };
+        let body = storage::strip_injected_header(&content);
+        records.extend(
+            chunk_text(body, chunk_size, overlap)
+                .into_iter()
+                .enumerate()
+                .map(|(chunk_index, text)| ChunkRecord {
+                    crate_name: crate_name.to_string(),
+                    version: version.to_string(),
+                    path: path.clone(),
+                    chunk_index,
+                    text: text.to_string(),
+                }),
+        );
+    }
+    records
+}
+
+/// One JSON object per line.
+pub fn format_chunks_ndjson(
+    records: &[ChunkRecord],
+) -> std::result::Result<String, serde_json::Error> {
+    let mut out = String::new();
+    for record in records {
+        out.push_str(&serde_json::to_string(record)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chunk_text, collect_crate_chunks, format_chunks_ndjson};
+    use std::fs;
+
+    #[test]
+    fn chunks_overlap_and_keep_original_formatting() {
+        let text = "# Title\n\none two  three\nfour five";
+        assert_eq!(
+            chunk_text(text, 4, 1),
+            vec!["# Title\n\none two", "two  three\nfour five"]
+        );
+        assert_eq!(chunk_text(text, 10, 2), vec![text]);
+        assert!(chunk_text("  \n ", 4, 1).is_empty());
+    }
+
+    #[test]
+    fn collects_records_without_provenance_header() {
+        let tmp = std::env::temp_dir().join(format!("aifd-chunks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).expect("create crate dir");
+        fs::write(
+            tmp.join("README.md"),
+            "<!-- AI-FDOCS: source=github.com/o/r ref=v1 path=README.md fetched=2026-01-01 -->\n\nalpha beta gamma\n",
+        )
+        .expect("write readme");
+        fs::write(tmp.join("_SUMMARY.md"), "ignored").expect("write summary");
+
+        let records = collect_crate_chunks("demo", "1.0.0", &tmp, 2, 0);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].text, "alpha beta");
+        assert_eq!(records[1].chunk_index, 1);
+
+        let ndjson = format_chunks_ndjson(&records).expect("serialize");
+        let first = ndjson.lines().next().expect("first line");
+        assert_eq!(
+            first,
+            r#"{"crate":"demo","version":"1.0.0","path":"README.md","chunk_index":0,"text":"alpha beta"}"#
+        );
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -1,23 +1,50 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 
 use serde::de::{self, Deserializer};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
+use tracing::info;
 
 use crate::error::{AiDocsError, Result};
+use crate::workspace::WorkspaceMember;
 
-#[derive(Debug, Deserialize)]
+/// Environment variable overriding `settings.output_dir`.
+pub const OUTPUT_DIR_ENV: &str = "AI_FDOCS_OUTPUT_DIR";
+
+/// `[crates."*"]`: defaults for every `Cargo.lock` crate without an entry.
+pub const WILDCARD_CRATE: &str = "*";
+pub const DEFAULT_REGISTRY_API_BASE: &str = "https://crates.io/api/v1";
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub settings: Settings,
 
     #[serde(default)]
     pub crates: HashMap<String, CrateDoc>,
+
+    /// The `[crates."*"]` entry, taken out of `crates` at load time.
+    #[serde(skip)]
+    pub wildcard: Option<CrateDoc>,
+
+    /// `Cargo.lock` crates covered by `wildcard` whose repo is unknown this
+    /// run (lookup failed or not done yet); prune keeps their docs.
+    #[serde(skip)]
+    pub wildcard_unresolved: BTreeSet<String>,
+
+    /// Directory of the config file (empty for one in the working dir).
+    /// `Cargo.lock`, the workspace, crate `path`s and a relative `output_dir`
+    /// resolve from it.
+    #[serde(skip)]
+    pub root: PathBuf,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DocsSource {
     GitHub,
+    /// Shallow clone with the `git` CLI instead of per-file HTTP fetches.
+    Git,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -54,6 +81,27 @@ impl<'de> Deserialize<'de> for SyncMode {
     }
 }
 
+impl Serialize for SyncMode {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl Serialize for DocsSource {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(match self {
+            Self::GitHub => "github",
+            Self::Git => "git",
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for DocsSource {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
@@ -62,13 +110,178 @@ impl<'de> Deserialize<'de> for DocsSource {
         let value = String::deserialize(deserializer)?;
         match value.as_str() {
             "github" => Ok(Self::GitHub),
+            "git" => Ok(Self::Git),
+            _ => Err(de::Error::custom(format!(
+                "docs source must be \"github\" or \"git\", got: {value}"
+            ))),
+        }
+    }
+}
+
+/// Per-crate `version_source`: which version a crate is synced at,
+/// regardless of `settings.sync_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionSource {
+    /// The `Cargo.lock` version.
+    Lockfile,
+    /// The newest crates.io release (or the newest matching `version_req`).
+    Latest,
+}
+
+impl<'de> Deserialize<'de> for VersionSource {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        match value.as_str() {
+            "lockfile" => Ok(Self::Lockfile),
+            "latest" => Ok(Self::Latest),
+            _ => Err(de::Error::custom(format!(
+                "version_source must be \"lockfile\" or \"latest\", got: {value}"
+            ))),
+        }
+    }
+}
+
+/// Per-crate `docs_format`: where a crate's docs come from, regardless of
+/// `settings.sync_mode` (which still picks the version).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocsFormat {
+    /// Repository files only (the lockfile-mode pipeline).
+    GithubReadme,
+    /// The docs.rs crate page, with the GitHub fallback of latest-docs mode.
+    DocsRs,
+    /// docs.rs API markdown as `README.md`, other files from GitHub.
+    Hybrid,
+}
+
+impl DocsFormat {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::GithubReadme => "github_readme",
+            Self::DocsRs => "docsrs",
+            Self::Hybrid => "hybrid",
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DocsFormat {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        match value.as_str() {
+            "github_readme" => Ok(Self::GithubReadme),
+            "docsrs" => Ok(Self::DocsRs),
+            "hybrid" => Ok(Self::Hybrid),
             _ => Err(de::Error::custom(format!(
-                "settings.docs_source must be \"github\", got: {value}"
+                "docs_format must be \"github_readme\", \"docsrs\" or \"hybrid\", got: {value}"
             ))),
         }
     }
 }
 
+/// How latest-docs mode saves the docs.rs crate page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocsRsFormat {
+    /// `API.md`, converted from the page HTML (default).
+    Markdown,
+    /// `API.html`, the page's `main-content` with scripts and styles stripped.
+    Html,
+}
+
+impl DocsRsFormat {
+    pub const fn artifact_file(self) -> &'static str {
+        match self {
+            Self::Markdown => "API.md",
+            Self::Html => "API.html",
+        }
+    }
+}
+
+impl Serialize for DocsRsFormat {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(match self {
+            Self::Markdown => "markdown",
+            Self::Html => "html",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for DocsRsFormat {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        match value.as_str() {
+            "markdown" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            _ => Err(de::Error::custom(format!(
+                "settings.docsrs_format must be \"markdown\" or \"html\", got: {value}"
+            ))),
+        }
+    }
+}
+
+/// What to do when no version tag matches and a branch would be used instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FallbackPolicy {
+    /// Use the branch silently.
+    Allow,
+    /// Use the branch and emit a warning (default).
+    Warn,
+    /// Refuse the branch; the crate sync fails.
+    Error,
+}
+
+impl Serialize for FallbackPolicy {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(match self {
+            Self::Allow => "allow",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for FallbackPolicy {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        match value.as_str() {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            _ => Err(de::Error::custom(format!(
+                "settings.fallback_policy must be \"allow\", \"warn\", or \"error\", got: {value}"
+            ))),
+        }
+    }
+}
+
+const fn default_changelog_keep_sections() -> usize {
+    crate::processor::changelog::DEFAULT_KEEP_SECTIONS
+}
+
+const fn default_fallback_policy() -> FallbackPolicy {
+    FallbackPolicy::Warn
+}
+
+const fn default_docsrs_format() -> DocsRsFormat {
+    DocsRsFormat::Markdown
+}
+
 const fn default_docs_source() -> DocsSource {
     DocsSource::GitHub
 }
@@ -77,7 +290,7 @@ const fn default_sync_mode() -> SyncMode {
     SyncMode::Lockfile
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Settings {
     #[serde(default = "default_output_dir")]
     pub output_dir: PathBuf,
@@ -85,12 +298,37 @@ pub struct Settings {
     #[serde(default = "default_max_file_size_kb")]
     pub max_file_size_kb: usize,
 
+    /// Cap on the estimated tokens (bytes / 4) of one crate's saved files;
+    /// changelogs are cut first, READMEs last. Unset means unlimited.
+    #[serde(default)]
+    pub max_tokens_per_crate: Option<usize>,
+
     #[serde(default = "default_true")]
     pub prune: bool,
 
+    /// Let `prune` remove only dirs of crates no longer in the config, keeping
+    /// other versions of configured crates.
+    #[serde(default)]
+    pub prune_unconfigured_only: bool,
+
+    /// Keep `status`/`check` scans of the output dir in
+    /// `.aifd-status-cache.json`, revalidated by mtime and size on every run.
+    #[serde(default)]
+    pub status_cache: bool,
+
     #[serde(default = "default_sync_concurrency")]
     pub sync_concurrency: usize,
 
+    /// In-flight GitHub API/raw requests across all crate workers; unset
+    /// means only `sync_concurrency` bounds them.
+    #[serde(default)]
+    pub github_concurrency: Option<usize>,
+
+    /// In-flight docs.rs requests across all crate workers; unset means only
+    /// `sync_concurrency` bounds them.
+    #[serde(default)]
+    pub docsrs_concurrency: Option<usize>,
+
     #[serde(default = "default_docs_source")]
     pub docs_source: DocsSource,
 
@@ -102,9 +340,188 @@ pub struct Settings {
 
     #[serde(default = "default_true")]
     pub docsrs_single_page: bool,
+
+    /// Latest-docs mode: rustdoc links listed per kind (modules, structs, …)
+    /// under "API Reference" in `API.md`.
+    #[serde(default = "default_docsrs_max_links")]
+    pub docsrs_max_links: usize,
+
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+
+    /// Resolve latest versions from the crates.io sparse index, falling back
+    /// to the JSON API when the index lookup fails.
+    #[serde(default)]
+    pub crates_io_sparse_index: bool,
+
+    /// Base URL of a crates.io-compatible registry API used for latest-version
+    /// lookups (`{base}/crates/{name}`). A non-default registry skips the
+    /// crates.io sparse index.
+    #[serde(default = "default_registry_api_base")]
+    pub registry_api_base: String,
+
+    /// Explicit proxy for `http://` requests. When no proxy is configured,
+    /// reqwest's `HTTP_PROXY`/`HTTPS_PROXY` env detection stays in effect.
+    #[serde(default)]
+    pub http_proxy: Option<String>,
+
+    /// Explicit proxy for `https://` requests.
+    #[serde(default)]
+    pub https_proxy: Option<String>,
+
+    /// Comma-separated hosts that bypass the explicit proxies (same syntax as `NO_PROXY`).
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+
+    /// Abort remaining sync jobs after the first failed crate.
+    #[serde(default)]
+    pub fail_fast: bool,
+
+    #[serde(default = "default_fallback_policy")]
+    pub fallback_policy: FallbackPolicy,
+
+    /// Remove a leading YAML front matter block from saved markdown files.
+    #[serde(default)]
+    pub strip_frontmatter: bool,
+
+    /// Remove rows of shields.io badge images from saved markdown files.
+    #[serde(default)]
+    pub strip_badges: bool,
+
+    /// Convert CRLF to LF and strip trailing whitespace (outside code fences)
+    /// in saved files, keeping committed docs stable across OSes.
+    #[serde(default)]
+    pub normalize_line_endings: bool,
+
+    /// Prefix saved text files with the `AI-FDOCS` provenance header. When
+    /// off, the provenance is only kept in `.aifd-meta.toml`.
+    #[serde(default = "default_true")]
+    pub inject_header: bool,
+
+    /// Extensions of saved files that get the `AI-FDOCS` provenance header, in
+    /// the format's comment syntax (plain lines for e.g. `txt`). Extension-less
+    /// files such as `LICENSE` always get one.
+    #[serde(default = "default_inject_header_extensions")]
+    pub inject_header_extensions: Vec<String>,
+
+    /// Files fetched for crates without explicit `files`, relative to the
+    /// crate's `subpath`. `README.md`/`CHANGELOG.md` also try their usual
+    /// case variants.
+    #[serde(default = "default_default_files")]
+    pub default_files: Vec<String>,
+
+    /// Paths tried in order for a root `CHANGELOG.md` entry of
+    /// `default_files`, relative to `subpath`; the first one found is saved
+    /// as `CHANGELOG.md`. Empty means `CHANGELOG.md` only.
+    #[serde(default = "default_changelog_candidates")]
+    pub changelog_candidates: Vec<String>,
+
+    /// Only fetch the README entries of `default_files` (still with their case
+    /// variants), e.g. to keep AI context small. Per-crate `files` are kept.
+    #[serde(default)]
+    pub include_readme_only: bool,
+
+    /// Extensions of repository files that are saved, and fetched from
+    /// trailing-slash directory entries in `files`; `md` and `mdx` are always
+    /// included.
+    #[serde(default = "default_include_extensions")]
+    pub include_extensions: Vec<String>,
+
+    /// Prepend each crate's `ai_notes` as a blockquote below the provenance
+    /// header of every saved markdown file.
+    #[serde(default)]
+    pub embed_ai_notes: bool,
+
+    /// Inline mdBook `{{#include path}}` directives of fetched markdown files
+    /// with the referenced files from the same repo and ref.
+    #[serde(default)]
+    pub resolve_includes: bool,
+
+    /// Latest-docs mode: also fetch crates.io `categories`/`keywords` for each
+    /// synced crate (one extra request per crate), stored in `.aifd-meta.toml`
+    /// and listed in `_INDEX.md`.
+    #[serde(default)]
+    pub fetch_crate_metadata: bool,
+
+    /// Classic-token scopes (e.g. `["repo"]`) checked against `GET /user` at
+    /// sync start; missing ones are warned about. Empty disables the preflight.
+    #[serde(default)]
+    pub min_github_token_scopes: Vec<String>,
+
+    /// File holding the GitHub token (e.g. a mounted CI secret). Overridden by
+    /// `GITHUB_TOKEN_FILE`/`GH_TOKEN_FILE`; wins over `GITHUB_TOKEN`/`GH_TOKEN`.
+    #[serde(default)]
+    pub github_token_file: Option<PathBuf>,
+
+    /// Fetch from the new slug for the current run when a configured repo was
+    /// renamed upstream (a suggestion to update `repo` is logged either way).
+    #[serde(default)]
+    pub follow_repo_renames: bool,
+
+    /// Latest-docs mode: for crates with a GitHub repo, use the raw README
+    /// markdown from the repo instead of the docs.rs-rendered README. The
+    /// title, API Reference and Example sections still come from docs.rs.
+    #[serde(default)]
+    pub prefer_source_readme: bool,
+
+    /// Latest-docs mode: `"markdown"` saves `API.md`; `"html"` saves the
+    /// sanitized docs.rs `main-content` as `API.html` instead (no markdown
+    /// conversion, `prefer_source_readme` does not apply).
+    #[serde(default = "default_docsrs_format")]
+    pub docsrs_format: DocsRsFormat,
+
+    /// Add every member of the Cargo workspace in the working directory that
+    /// is not configured yet as a `path` crate, read from its own directory.
+    #[serde(default)]
+    pub sync_workspace_members: bool,
+
+    /// For crates without `subpath` or `files`, look for a README under
+    /// `<crate>/` or `crates/<crate>/` via the tree API (one extra request per
+    /// synced crate) and fetch from there.
+    #[serde(default)]
+    pub infer_subpath: bool,
+
+    /// For crates without `subpath` or `files` whose README is missing or
+    /// thin, also fetch `docs/README.md` and `book/src/README.md` when they
+    /// exist (two extra requests per such crate).
+    #[serde(default)]
+    pub probe_docs_dirs: bool,
+
+    /// Cap on bytes downloaded by one sync run; once exceeded, the crate that
+    /// crossed it and all later ones are not saved. Unset means unlimited.
+    #[serde(default)]
+    pub max_total_size_mb: Option<u64>,
+
+    /// Retries shared by all file fetches of one crate; once used up, the
+    /// crate's remaining files fail fast.
+    #[serde(default = "default_max_retries_per_crate")]
+    pub max_retries_per_crate: usize,
+
+    /// Truncate changelogs to the sections around the synced version.
+    /// Crates can opt out with `full_changelog = true`.
+    #[serde(default = "default_true")]
+    pub truncate_changelog: bool,
+
+    /// Custom changelog heading regex; capture group 1 is the version label
+    /// (e.g. `^##\s+(\d{4}-\d{2}-\d{2})` for date headings).
+    #[serde(default)]
+    pub changelog_version_regex: Option<String>,
+
+    /// Saved file name of each fetched path; see `storage::render_filename`.
+    #[serde(default = "default_filename_template")]
+    pub filename_template: String,
+
+    /// Changelog sections kept when headings aren't comparable semver versions.
+    #[serde(default = "default_changelog_keep_sections")]
+    pub changelog_keep_sections: usize,
+
+    /// Extra HTTP headers sent with raw file requests. Values are marked
+    /// sensitive so they never show up in debug output (nor serialized).
+    #[serde(default, skip_serializing)]
+    pub extra_headers: HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct CrateDoc {
     /// New format: explicit repository in crate section.
     pub repo: Option<String>,
@@ -112,12 +529,60 @@ pub struct CrateDoc {
     pub subpath: Option<String>,
     /// Optional explicit file list.
     pub files: Option<Vec<String>>,
+    /// Branch preferred over the repo default branch when no version tag matches
+    /// (e.g. `gh-pages` or `docs`).
+    pub doc_branch: Option<String>,
+
+    /// Name the crate is published and tagged under when it differs from the
+    /// config key (e.g. after a rename). Used for crates.io/docs.rs lookups and
+    /// `{crate}` tag candidates; the config key still matches `Cargo.lock`.
+    pub upstream_name: Option<String>,
+
+    /// Keep the whole changelog (still capped by `max_file_size_kb`); overrides
+    /// `settings.truncate_changelog` for this crate.
+    pub full_changelog: Option<bool>,
+
+    /// Latest-docs freshness window for this crate; overrides
+    /// `settings.latest_ttl_hours`.
+    pub ttl_hours: Option<usize>,
+
+    /// Latest-docs mode: semver requirement (e.g. `"1"` to stay on 1.x); the
+    /// greatest non-yanked matching version is synced instead of the newest.
+    pub version_req: Option<String>,
+
+    /// How repository files are fetched; overrides `settings.docs_source`.
+    pub source: Option<DocsSource>,
+
+    /// Version this crate is synced at; overrides the version selection of
+    /// `settings.sync_mode` for this crate.
+    pub version_source: Option<VersionSource>,
+
+    /// Where this crate's docs come from; overrides the pipeline of its sync
+    /// mode.
+    pub docs_format: Option<DocsFormat>,
+
+    /// Clone URL for `source = "git"` (e.g. a self-hosted or SSH remote).
+    /// Defaults to `https://github.com/{repo}.git`.
+    pub git_url: Option<String>,
+
+    /// Local crate directory (relative to the workspace root) read instead of
+    /// a repository; the version comes from its `Cargo.toml`.
+    pub path: Option<PathBuf>,
 
     /// Legacy format compatibility.
     pub sources: Option<Vec<Source>>,
 
     #[serde(default)]
     pub ai_notes: String,
+
+    /// File (relative to the config) whose contents become `ai_notes` at
+    /// load time, for guidance too long to inline.
+    pub note_file: Option<PathBuf>,
+
+    /// Extra HTTP headers for this crate's raw file fetches, merged over
+    /// `settings.extra_headers`.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -132,6 +597,66 @@ pub enum Source {
 }
 
 impl CrateDoc {
+    /// Fetch identity: `upstream_name` when set, otherwise the config key.
+    pub fn upstream_name<'a>(&'a self, crate_name: &'a str) -> &'a str {
+        self.upstream_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(crate_name)
+    }
+
+    /// Effective latest-docs TTL: `ttl_hours` when set, otherwise `global`.
+    pub fn latest_ttl_hours(&self, global: usize) -> usize {
+        self.ttl_hours.unwrap_or(global)
+    }
+
+    /// Parsed `version_req`; validated at config load.
+    pub fn version_req(&self) -> Option<semver::VersionReq> {
+        self.version_req
+            .as_deref()
+            .and_then(|req| semver::VersionReq::parse(req).ok())
+    }
+
+    /// Mode this crate is synced and checked in: latest-docs for
+    /// `version_source = "latest"`, lockfile for `"lockfile"` under a global
+    /// latest-docs mode, otherwise `global`.
+    pub fn sync_mode(&self, global: SyncMode) -> SyncMode {
+        match (self.version_source, global) {
+            (Some(VersionSource::Latest), _) => SyncMode::LatestDocs,
+            (Some(VersionSource::Lockfile), SyncMode::LatestDocs) => SyncMode::Lockfile,
+            _ => global,
+        }
+    }
+
+    /// Pipeline this crate's docs come from when synced in `mode` (see
+    /// `sync_mode`): `docs_format` when set, otherwise the mode's own.
+    pub fn docs_format(&self, mode: SyncMode) -> DocsFormat {
+        self.docs_format.unwrap_or(match mode {
+            SyncMode::Lockfile => DocsFormat::GithubReadme,
+            SyncMode::LatestDocs => DocsFormat::DocsRs,
+            SyncMode::Hybrid => DocsFormat::Hybrid,
+        })
+    }
+
+    /// Effective fetch source: `source` when set, otherwise `global`.
+    pub fn docs_source(&self, global: DocsSource) -> DocsSource {
+        self.source.unwrap_or(global)
+    }
+
+    /// Remote cloned for `source = "git"`.
+    pub fn git_clone_url(&self) -> Option<String> {
+        self.git_url.clone().or_else(|| {
+            self.github_repo()
+                .map(|repo| format!("https://github.com/{repo}.git"))
+        })
+    }
+
+    /// Per-crate request headers; validated at config load.
+    pub fn request_headers(&self) -> reqwest::header::HeaderMap {
+        crate::fetcher::header_map(&self.headers, "crate headers").unwrap_or_default()
+    }
+
     pub fn github_repo(&self) -> Option<&str> {
         if let Some(repo) = self.repo.as_deref() {
             return Some(repo);
@@ -145,6 +670,23 @@ impl CrateDoc {
         })
     }
 
+    /// Legacy `sources` GitHub entries after the first one (which
+    /// `github_repo`/`effective_files` fall back to), as `(repo, files)` with
+    /// `None` for an entry without its own file list.
+    pub fn additional_github_sources(&self) -> Vec<(&str, Option<Vec<String>>)> {
+        self.sources
+            .iter()
+            .flatten()
+            .filter_map(|s| match s {
+                Source::GitHub { repo, files } => {
+                    Some((repo.as_str(), Some(files.clone()).filter(|f| !f.is_empty())))
+                }
+                Source::DocsRs => None,
+            })
+            .skip(1)
+            .collect()
+    }
+
     pub fn effective_files(&self) -> Option<Vec<String>> {
         if let Some(files) = &self.files {
             return Some(files.clone());
@@ -158,7 +700,10 @@ impl CrateDoc {
         })
     }
 
-    pub fn config_hash(&self) -> String {
+    /// Fingerprint of the crate entry, recorded in the meta so edits re-sync
+    /// it. The `settings` that shape saved files are hashed only when they
+    /// differ from their defaults, so existing hashes stay valid.
+    pub fn config_hash(&self, settings: &HashedSettings<'_>) -> String {
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
 
@@ -178,8 +723,50 @@ impl CrateDoc {
                 hasher.update(b",");
             }
         }
+        if let Some(git_url) = &self.git_url {
+            hasher.update(b"git_url:");
+            hasher.update(git_url.as_bytes());
+        }
+        if let Some(path) = &self.path {
+            hasher.update(b"path:");
+            hasher.update(path.to_string_lossy().as_bytes());
+        }
+        if let Some(doc_branch) = &self.doc_branch {
+            hasher.update(b"doc_branch:");
+            hasher.update(doc_branch.as_bytes());
+        }
+        if let Some(upstream_name) = &self.upstream_name {
+            hasher.update(b"upstream_name:");
+            hasher.update(upstream_name.as_bytes());
+        }
+        if let Some(version_req) = &self.version_req {
+            hasher.update(b"version_req:");
+            hasher.update(version_req.as_bytes());
+        }
+        if let Some(docs_format) = self.docs_format {
+            hasher.update(b"docs_format:");
+            hasher.update(docs_format.as_str().as_bytes());
+        }
+        // The effective value, so toggling `settings.truncate_changelog`
+        // re-syncs the crates it applies to.
+        if self.full_changelog.unwrap_or(!settings.truncate_changelog) {
+            hasher.update(b"full_changelog:1");
+        }
         hasher.update(b"notes:");
         hasher.update(self.ai_notes.as_bytes());
+        if !settings.inject_header {
+            hasher.update(b"inject_header:0");
+        }
+        if settings.header_extensions != default_inject_header_extensions() {
+            hasher.update(b"inject_header_extensions:");
+            for ext in settings.header_extensions {
+                hasher.update(ext.as_bytes());
+                hasher.update(b",");
+            }
+        }
+        if settings.prefer_source_readme {
+            hasher.update(b"prefer_source_readme:1");
+        }
 
         // Legacy sources fallback
         if let Some(sources) = &self.sources {
@@ -193,6 +780,100 @@ impl CrateDoc {
     }
 }
 
+/// The `[settings]` that change what gets saved for a crate, hashed into
+/// [`CrateDoc::config_hash`] so editing them re-syncs it.
+#[derive(Debug, Clone, Copy)]
+pub struct HashedSettings<'a> {
+    /// `settings.inject_header`.
+    pub inject_header: bool,
+    /// `settings.inject_header_extensions`.
+    pub header_extensions: &'a [String],
+    /// `settings.prefer_source_readme`.
+    pub prefer_source_readme: bool,
+    /// `settings.truncate_changelog`.
+    pub truncate_changelog: bool,
+}
+
+impl<'a> From<&'a Settings> for HashedSettings<'a> {
+    fn from(settings: &'a Settings) -> Self {
+        Self {
+            inject_header: settings.inject_header,
+            header_extensions: &settings.inject_header_extensions,
+            prefer_source_readme: settings.prefer_source_readme,
+            truncate_changelog: settings.truncate_changelog,
+        }
+    }
+}
+
+/// Looks for `file_name` in `start` and then each parent directory, like
+/// cargo finds `Cargo.toml`. The walk stops after a directory containing
+/// `.git` (the repository root) or at the filesystem root.
+pub fn discover_config(start: &Path, file_name: &str) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(file_name);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            return None;
+        }
+    }
+    None
+}
+
+fn resolve_output_dir(
+    cli_output_dir: Option<&Path>,
+    env_value: Option<OsString>,
+    configured: &Path,
+) -> (PathBuf, &'static str) {
+    if let Some(dir) = cli_output_dir {
+        return (dir.to_path_buf(), "--output-dir");
+    }
+    if let Some(dir) = env_value.filter(|v| !v.is_empty()) {
+        return (PathBuf::from(dir), OUTPUT_DIR_ENV);
+    }
+    (configured.to_path_buf(), "settings.output_dir")
+}
+
+fn default_inject_header_extensions() -> Vec<String> {
+    ["md", "mdx", "markdown", "html", "htm", "txt", "rst", "adoc"]
+        .iter()
+        .map(|ext| (*ext).to_string())
+        .collect()
+}
+
+fn default_include_extensions() -> Vec<String> {
+    ["md", "mdx", "markdown", "txt", "rst", "adoc"]
+        .iter()
+        .map(|ext| (*ext).to_string())
+        .collect()
+}
+
+fn default_default_files() -> Vec<String> {
+    vec!["README.md".to_string(), "CHANGELOG.md".to_string()]
+}
+
+fn default_changelog_candidates() -> Vec<String> {
+    [
+        "CHANGELOG.md",
+        "CHANGES.md",
+        "HISTORY.md",
+        "RELEASES.md",
+        "docs/CHANGELOG.md",
+        "docs/CHANGES.md",
+    ]
+    .map(str::to_string)
+    .to_vec()
+}
+
+fn default_filename_template() -> String {
+    crate::storage::DEFAULT_FILENAME_TEMPLATE.to_string()
+}
+
+fn default_registry_api_base() -> String {
+    DEFAULT_REGISTRY_API_BASE.to_string()
+}
+
 fn default_output_dir() -> PathBuf {
     PathBuf::from("fdocs")
 }
@@ -213,33 +894,197 @@ const fn default_latest_ttl_hours() -> usize {
     24
 }
 
+const fn default_docsrs_max_links() -> usize {
+    crate::fetcher::latest::DEFAULT_MAX_LINKS_PER_KIND
+}
+
+const fn default_max_retries_per_crate() -> usize {
+    6
+}
+
+const fn default_request_timeout_secs() -> u64 {
+    30
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
             output_dir: default_output_dir(),
             max_file_size_kb: default_max_file_size_kb(),
+            max_tokens_per_crate: None,
             prune: default_true(),
+            prune_unconfigured_only: false,
+            status_cache: false,
             sync_concurrency: default_sync_concurrency(),
+            github_concurrency: None,
+            docsrs_concurrency: None,
             docs_source: default_docs_source(),
             sync_mode: default_sync_mode(),
             latest_ttl_hours: default_latest_ttl_hours(),
             docsrs_single_page: default_true(),
+            docsrs_max_links: default_docsrs_max_links(),
+            request_timeout_secs: default_request_timeout_secs(),
+            crates_io_sparse_index: false,
+            registry_api_base: default_registry_api_base(),
+            http_proxy: None,
+            https_proxy: None,
+            no_proxy: None,
+            fail_fast: false,
+            fallback_policy: default_fallback_policy(),
+            strip_frontmatter: false,
+            strip_badges: false,
+            normalize_line_endings: false,
+            inject_header: true,
+            inject_header_extensions: default_inject_header_extensions(),
+            default_files: default_default_files(),
+            changelog_candidates: default_changelog_candidates(),
+            include_readme_only: false,
+            include_extensions: default_include_extensions(),
+            embed_ai_notes: false,
+            resolve_includes: false,
+            fetch_crate_metadata: false,
+            min_github_token_scopes: Vec::new(),
+            github_token_file: None,
+            follow_repo_renames: false,
+            prefer_source_readme: false,
+            docsrs_format: default_docsrs_format(),
+            sync_workspace_members: false,
+            infer_subpath: false,
+            probe_docs_dirs: false,
+            max_total_size_mb: None,
+            max_retries_per_crate: default_max_retries_per_crate(),
+            truncate_changelog: default_true(),
+            changelog_version_regex: None,
+            changelog_keep_sections: default_changelog_keep_sections(),
+            filename_template: default_filename_template(),
+            extra_headers: HashMap::new(),
         }
     }
 }
 
 impl Config {
-    pub fn load(path: &Path) -> Result<Self> {
+    /// Reads and validates `path`, with `[profiles.<profile>]` merged over the
+    /// base config first when a profile is given.
+    pub fn load(path: &Path, profile: Option<&str>) -> Result<Self> {
         if !path.exists() {
             return Err(AiDocsError::ConfigNotFound(path.to_path_buf()));
         }
 
         let content = std::fs::read_to_string(path)?;
-        let config: Self = toml::from_str(&content)?;
+        // Parsing straight into `Self` keeps line numbers in type errors.
+        let mut config: Self = if profile.is_none() && !content.contains("${") {
+            toml::from_str(&content)?
+        } else {
+            let mut table: toml::Table = toml::from_str(&content)?;
+            match profile {
+                Some(name) => table = apply_profile(table, name)?,
+                // Unselected profiles may reference variables set elsewhere.
+                None => {
+                    table.remove("profiles");
+                }
+            }
+            interpolate_env(&mut table, &|var| std::env::var(var).ok())?;
+            toml::Value::Table(table).try_into()?
+        };
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        config.load_note_files(&root)?;
+        config.root = root;
         config.validate()?;
+        config.wildcard = config.crates.remove(WILDCARD_CRATE);
         Ok(config)
     }
 
+    /// Replaces `ai_notes` with the contents of each crate's `note_file`, so
+    /// the notes (and `config_hash`) follow the file.
+    fn load_note_files(&mut self, config_dir: &Path) -> Result<()> {
+        for (crate_name, crate_cfg) in &mut self.crates {
+            let Some(note_file) = &crate_cfg.note_file else {
+                continue;
+            };
+            if !crate_cfg.ai_notes.is_empty() {
+                return Err(AiDocsError::InvalidConfig(format!(
+                    "crates.{crate_name} sets both `ai_notes` and `note_file`; use one"
+                )));
+            }
+            let path = config_dir.join(note_file);
+            crate_cfg.ai_notes = std::fs::read_to_string(&path).map_err(|e| {
+                AiDocsError::InvalidConfig(format!(
+                    "crates.{crate_name}.note_file {} cannot be read: {e}",
+                    path.display()
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// `settings.sync_workspace_members`: adds each member without a
+    /// `[crates.*]` entry as a `path` crate. Returns the names added.
+    pub fn add_workspace_members(&mut self, members: &[WorkspaceMember]) -> Vec<String> {
+        let mut added = Vec::new();
+        for member in members {
+            if self.crates.contains_key(&member.name) {
+                continue;
+            }
+            let crate_doc = CrateDoc {
+                path: Some(member.dir.clone()),
+                ..CrateDoc::default()
+            };
+            self.crates.insert(member.name.clone(), crate_doc);
+            added.push(member.name.clone());
+        }
+        added
+    }
+
+    /// Adds a copy of the `[crates."*"]` entry for each crate in `repos`
+    /// (crate name to GitHub `owner/repo`) without an entry of its own;
+    /// `{crate}` in its `ai_notes` becomes the crate name. Returns the names
+    /// added.
+    pub fn add_wildcard_crates(&mut self, repos: &BTreeMap<String, String>) -> Vec<String> {
+        let Some(wildcard) = &self.wildcard else {
+            return Vec::new();
+        };
+        let mut added = Vec::new();
+        for (crate_name, repo) in repos {
+            if self.crates.contains_key(crate_name) {
+                continue;
+            }
+            let crate_doc = CrateDoc {
+                repo: Some(repo.clone()),
+                ai_notes: wildcard.ai_notes.replace("{crate}", crate_name),
+                ..wildcard.clone()
+            };
+            self.crates.insert(crate_name.clone(), crate_doc);
+            added.push(crate_name.clone());
+        }
+        added
+    }
+
+    /// Applies output dir overrides: CLI flag > `AI_FDOCS_OUTPUT_DIR` > config.
+    /// A relative result is taken from `root`, like every other path.
+    pub fn apply_output_dir_override(&mut self, cli_output_dir: Option<&Path>) {
+        let (dir, source) = resolve_output_dir(
+            cli_output_dir,
+            std::env::var_os(OUTPUT_DIR_ENV),
+            &self.settings.output_dir,
+        );
+        let dir = self.root.join(dir);
+        info!("Using output dir {} (from {source})", dir.display());
+        self.settings.output_dir = dir;
+    }
+
+    /// `root` as a directory to read from (`.` when empty).
+    pub fn project_dir(&self) -> &Path {
+        if self.root.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            &self.root
+        }
+    }
+
+    pub fn cargo_lock_path(&self) -> PathBuf {
+        self.root.join("Cargo.lock")
+    }
+
     fn validate(&self) -> Result<()> {
         if self.settings.sync_concurrency == 0 {
             return Err(AiDocsError::InvalidConfig(
@@ -253,6 +1098,18 @@ impl Config {
             ));
         }
 
+        for (key, limit) in [
+            ("github_concurrency", self.settings.github_concurrency),
+            ("docsrs_concurrency", self.settings.docsrs_concurrency),
+            ("max_tokens_per_crate", self.settings.max_tokens_per_crate),
+        ] {
+            if limit == Some(0) {
+                return Err(AiDocsError::InvalidConfig(format!(
+                    "settings.{key} must be greater than 0"
+                )));
+            }
+        }
+
         if self.settings.max_file_size_kb == 0 {
             return Err(AiDocsError::InvalidConfig(
                 "settings.max_file_size_kb must be greater than 0".to_string(),
@@ -265,17 +1122,140 @@ impl Config {
             ));
         }
 
+        if let Some(wildcard) = self.crates.get(WILDCARD_CRATE) {
+            if wildcard.repo.is_some()
+                || wildcard.subpath.is_some()
+                || wildcard.upstream_name.is_some()
+                || wildcard.git_url.is_some()
+                || wildcard.path.is_some()
+                || wildcard.sources.is_some()
+            {
+                return Err(AiDocsError::InvalidConfig(
+                    "crates.\"*\" cannot set `repo`, `subpath`, `upstream_name`, `git_url`, `path` or `sources`; repos are looked up on crates.io".to_string(),
+                ));
+            }
+        }
+
+        for (crate_name, crate_cfg) in &self.crates {
+            if crate_cfg.ttl_hours == Some(0) {
+                return Err(AiDocsError::InvalidConfig(format!(
+                    "crates.{crate_name}.ttl_hours must be greater than 0"
+                )));
+            }
+            if let Some(req) = &crate_cfg.version_req {
+                if let Err(e) = semver::VersionReq::parse(req) {
+                    return Err(AiDocsError::InvalidConfig(format!(
+                        "crates.{crate_name}.version_req `{req}` is not a valid semver requirement: {e}"
+                    )));
+                }
+            }
+            // Passed to `git`, where a leading `-` would read as an option.
+            for (key, value) in [
+                ("repo", crate_cfg.repo.as_deref()),
+                ("git_url", crate_cfg.git_url.as_deref()),
+                ("doc_branch", crate_cfg.doc_branch.as_deref()),
+            ] {
+                if value.is_some_and(|value| value.trim_start().starts_with('-')) {
+                    return Err(AiDocsError::InvalidConfig(format!(
+                        "crates.{crate_name}.{key} must not start with `-`"
+                    )));
+                }
+            }
+            if crate_cfg.git_url.is_some()
+                && crate_cfg.docs_source(self.settings.docs_source) != DocsSource::Git
+            {
+                return Err(AiDocsError::InvalidConfig(format!(
+                    "crates.{crate_name}.git_url requires source = \"git\" (per crate or settings.docs_source)"
+                )));
+            }
+            if crate_cfg.path.is_some()
+                && (crate_cfg.github_repo().is_some() || crate_cfg.git_url.is_some())
+            {
+                return Err(AiDocsError::InvalidConfig(format!(
+                    "crates.{crate_name}.path cannot be combined with `repo`, `git_url` or `sources`"
+                )));
+            }
+        }
+
+        if self.settings.request_timeout_secs == 0 {
+            return Err(AiDocsError::InvalidConfig(
+                "settings.request_timeout_secs must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.settings.request_timeout_secs > 600 {
+            return Err(AiDocsError::InvalidConfig(
+                "settings.request_timeout_secs must not exceed 600".to_string(),
+            ));
+        }
+
+        for (key, value) in [
+            ("http_proxy", &self.settings.http_proxy),
+            ("https_proxy", &self.settings.https_proxy),
+        ] {
+            if let Some(url) = value.as_deref() {
+                if reqwest::Proxy::all(url).is_err() {
+                    return Err(AiDocsError::InvalidConfig(format!(
+                        "settings.{key} is not a valid proxy URL: {url}"
+                    )));
+                }
+            }
+        }
+
+        let registry = &self.settings.registry_api_base;
+        if !(registry.starts_with("https://") || registry.starts_with("http://")) {
+            return Err(AiDocsError::InvalidConfig(format!(
+                "settings.registry_api_base must be an http(s) URL: {registry}"
+            )));
+        }
+
+        if let Some(pattern) = self.settings.changelog_version_regex.as_deref() {
+            let re = crate::processor::changelog::compile_heading_regex(pattern).map_err(|e| {
+                AiDocsError::InvalidConfig(format!(
+                    "settings.changelog_version_regex is not a valid regex: {e}"
+                ))
+            })?;
+            if re.captures_len() < 2 {
+                return Err(AiDocsError::InvalidConfig(
+                    "settings.changelog_version_regex must contain a capture group for the version"
+                        .to_string(),
+                ));
+            }
+        }
+
+        crate::storage::validate_filename_template(&self.settings.filename_template)
+            .map_err(|e| AiDocsError::InvalidConfig(format!("settings.filename_template {e}")))?;
+
+        if self.settings.changelog_keep_sections == 0 {
+            return Err(AiDocsError::InvalidConfig(
+                "settings.changelog_keep_sections must be greater than 0".to_string(),
+            ));
+        }
+
+        crate::fetcher::header_map(&self.settings.extra_headers, "settings.extra_headers")?;
+        for (crate_name, crate_cfg) in &self.crates {
+            crate::fetcher::header_map(
+                &crate_cfg.headers,
+                &format!("crates.{crate_name}.headers"),
+            )?;
+        }
+
         if !self.settings.docsrs_single_page {
             return Err(AiDocsError::InvalidConfig(
                 "settings.docsrs_single_page=false is not supported yet; use true".to_string(),
             ));
         }
 
+        let require_github_repo = matches!(self.settings.sync_mode, SyncMode::Lockfile);
         if require_github_repo {
             for (crate_name, crate_cfg) in &self.crates {
-                if crate_cfg.github_repo().is_none() {
+                if crate_name != WILDCARD_CRATE
+                    && crate_cfg.github_repo().is_none()
+                    && crate_cfg.git_url.is_none()
+                    && crate_cfg.path.is_none()
+                {
                     return Err(AiDocsError::InvalidConfig(format!(
-                        "crate '{crate_name}' must define `repo` or legacy `sources` with GitHub for lockfile mode"
+                        "crate '{crate_name}' must define `repo`, `git_url`, `path` or legacy `sources` with GitHub for lockfile mode"
                     )));
                 }
             }
@@ -285,23 +1265,216 @@ impl Config {
     }
 }
 
+/// Removes `[profiles]` from `table` and merges the `name` profile into it:
+/// its `[settings]` keys override the base ones (nested tables key by key)
+/// and its `[crates]`, when present, replace the base crate list.
+fn apply_profile(mut table: toml::Table, name: &str) -> Result<toml::Table> {
+    let mut profiles = match table.remove("profiles") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => {
+            return Err(AiDocsError::InvalidConfig(
+                "`profiles` must be a table of `[profiles.<name>]` sections".to_string(),
+            ))
+        }
+        None => toml::Table::new(),
+    };
+    let Some(profile) = profiles.remove(name) else {
+        let known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+        let defined = if known.is_empty() {
+            "the config defines no [profiles.*]".to_string()
+        } else {
+            format!("defined profiles: {}", known.join(", "))
+        };
+        return Err(AiDocsError::InvalidConfig(format!(
+            "unknown profile `{name}`; {defined}"
+        )));
+    };
+    let toml::Value::Table(mut profile) = profile else {
+        return Err(AiDocsError::InvalidConfig(format!(
+            "profiles.{name} must be a table"
+        )));
+    };
+
+    if let Some(settings) = profile.remove("settings") {
+        let base = table
+            .entry("settings")
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        merge_toml(base, settings);
+    }
+    if let Some(crates) = profile.remove("crates") {
+        table.insert("crates".to_string(), crates);
+    }
+    if let Some(key) = profile.keys().next() {
+        return Err(AiDocsError::InvalidConfig(format!(
+            "profiles.{name}.{key} is not supported; profiles can only set `settings` and `crates`"
+        )));
+    }
+    Ok(table)
+}
+
+/// Expands `${VAR}` and `${VAR:-default}` in every string value of `table`
+/// (not in keys) with `lookup`; `$${` stays a literal `${`. A variable that
+/// is unset and has no default is an error naming the key.
+fn interpolate_env(table: &mut toml::Table, lookup: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+    for (key, value) in table.iter_mut() {
+        interpolate_value(value, key, lookup)?;
+    }
+    Ok(())
+}
+
+fn interpolate_value(
+    value: &mut toml::Value,
+    key_path: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    match value {
+        toml::Value::String(s) if s.contains("${") => {
+            *s = interpolate_str(s, lookup)
+                .map_err(|e| AiDocsError::InvalidConfig(format!("{key_path}: {e}")))?;
+        }
+        toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                interpolate_value(item, &format!("{key_path}[{i}]"), lookup)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                interpolate_value(item, &format!("{key_path}.{key}"), lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn interpolate_str(
+    s: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unterminated `${{` in {s:?}"))?;
+            let (var, default) = match after[..end].split_once(":-") {
+                Some((var, default)) => (var, Some(default)),
+                None => (&after[..end], None),
+            };
+            if var.is_empty() {
+                return Err(format!("empty variable name in {s:?}"));
+            }
+            match lookup(var).or_else(|| default.map(str::to_string)) {
+                Some(expanded) => out.push_str(&expanded),
+                None => {
+                    return Err(format!(
+                        "environment variable `{var}` is not set (use `${{{var}:-default}}` for a fallback)"
+                    ))
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::fs;
     use std::path::Path;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    use super::{Config, SyncMode};
+    use super::{
+        discover_config, interpolate_env, resolve_output_dir, Config, CrateDoc, DocsFormat,
+        FallbackPolicy, HashedSettings, Settings, SyncMode,
+    };
 
     #[test]
     fn example_config_parses_with_config_load() {
-        let path = Path::new("examples/ai-docs.toml");
-        let config = Config::load(path).expect("example config must parse");
+        let path = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../examples/ai-docs.toml"
+        ));
+        let config = Config::load(path, None).expect("example config must parse");
 
         assert!(config.crates.contains_key("serde"));
         assert!(config.crates.contains_key("sqlx"));
     }
 
+    #[test]
+    fn discover_config_walks_up_to_the_git_boundary() {
+        let tmp = std::env::temp_dir().join(format!("aifd-discover-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let repo = tmp.join("repo");
+        let nested = repo.join("crates/demo/src");
+        std::fs::create_dir_all(&nested).expect("create nested dirs");
+        std::fs::create_dir_all(repo.join(".git")).expect("create .git");
+        std::fs::write(tmp.join("ai-fdocs.toml"), "").expect("write outer config");
+
+        // The config above the repository root is not picked up.
+        assert_eq!(discover_config(&nested, "ai-fdocs.toml"), None);
+
+        std::fs::write(repo.join("ai-fdocs.toml"), "").expect("write root config");
+        assert_eq!(
+            discover_config(&nested, "ai-fdocs.toml"),
+            Some(repo.join("ai-fdocs.toml"))
+        );
+
+        // Relative paths resolve from the config's directory, not the cwd.
+        let mut config = Config::load(&repo.join("ai-fdocs.toml"), None).expect("load config");
+        config.apply_output_dir_override(None);
+        assert_eq!(config.settings.output_dir, repo.join("fdocs"));
+        assert_eq!(config.cargo_lock_path(), repo.join("Cargo.lock"));
+        assert_eq!(config.project_dir(), repo);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn output_dir_resolution_prefers_cli_then_env_then_config() {
+        let configured = Path::new("fdocs");
+        let cli = Path::new("/tmp/cli-docs");
+
+        let (dir, source) = resolve_output_dir(Some(cli), Some("/tmp/env-docs".into()), configured);
+        assert_eq!((dir.as_path(), source), (cli, "--output-dir"));
+
+        let (dir, source) = resolve_output_dir(None, Some("/tmp/env-docs".into()), configured);
+        assert_eq!(
+            (dir.as_path(), source),
+            (Path::new("/tmp/env-docs"), "AI_FDOCS_OUTPUT_DIR")
+        );
+
+        let (dir, source) = resolve_output_dir(None, Some("".into()), configured);
+        assert_eq!((dir.as_path(), source), (configured, "settings.output_dir"));
+    }
+
     #[test]
     fn settings_sync_mode_defaults_to_lockfile() {
         let suffix = SystemTime::now()
@@ -321,11 +1494,12 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let config = Config::load(&path).expect("config should parse");
+        let config = Config::load(&path, None).expect("config should parse");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert_eq!(config.settings.sync_mode, SyncMode::Lockfile);
         assert_eq!(config.settings.latest_ttl_hours, 24);
+        assert_eq!(config.settings.request_timeout_secs, 30);
         assert!(config.settings.docsrs_single_page);
     }
 
@@ -348,24 +1522,49 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let config = Config::load(&path).expect("config should parse");
+        let config = Config::load(&path, None).expect("config should parse");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert_eq!(config.settings.sync_mode, SyncMode::LatestDocs);
     }
 
     #[test]
-    fn settings_sync_mode_accepts_hybrid() {
+    fn settings_sync_mode_accepts_hybrid() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be valid")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("ai-fdocs-hybrid-sync-mode-{suffix}.toml"));
+
+        fs::write(
+            &path,
+            r#"[settings]
+sync_mode = "hybrid"
+
+[crates.serde]
+repo = "serde-rs/serde"
+"#,
+        )
+        .expect("must write temporary config");
+
+        let config = Config::load(&path, None).expect("config should parse");
+        fs::remove_file(&path).expect("must cleanup temporary config");
+
+        assert_eq!(config.settings.sync_mode, SyncMode::Hybrid);
+    }
+
+    #[test]
+    fn settings_fallback_policy_defaults_to_warn_and_parses_error() {
         let suffix = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("system time should be valid")
             .as_nanos();
-        let path = std::env::temp_dir().join(format!("ai-fdocs-hybrid-sync-mode-{suffix}.toml"));
+        let path = std::env::temp_dir().join(format!("ai-fdocs-fallback-policy-{suffix}.toml"));
 
         fs::write(
             &path,
             r#"[settings]
-sync_mode = "hybrid"
+fallback_policy = "error"
 
 [crates.serde]
 repo = "serde-rs/serde"
@@ -373,10 +1572,14 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let config = Config::load(&path).expect("config should parse");
+        let config = Config::load(&path, None).expect("config should parse");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
-        assert_eq!(config.settings.sync_mode, SyncMode::Hybrid);
+        assert_eq!(config.settings.fallback_policy, FallbackPolicy::Error);
+        assert_eq!(
+            super::Settings::default().fallback_policy,
+            FallbackPolicy::Warn
+        );
     }
 
     #[test]
@@ -399,7 +1602,7 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let err = Config::load(&path).expect_err("zero max_file_size_kb must fail");
+        let err = Config::load(&path, None).expect_err("zero max_file_size_kb must fail");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert!(err
@@ -428,7 +1631,7 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let err = Config::load(&path).expect_err("non-integer max_file_size_kb must fail");
+        let err = Config::load(&path, None).expect_err("non-integer max_file_size_kb must fail");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert!(err.to_string().contains("max_file_size_kb"));
@@ -454,7 +1657,7 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let err = Config::load(&path).expect_err("non-numeric max_file_size_kb must fail");
+        let err = Config::load(&path, None).expect_err("non-numeric max_file_size_kb must fail");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert!(err.to_string().contains("max_file_size_kb"));
@@ -480,7 +1683,7 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let err = Config::load(&path).expect_err("zero sync_concurrency must fail");
+        let err = Config::load(&path, None).expect_err("zero sync_concurrency must fail");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert!(err
@@ -507,12 +1710,198 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let err = Config::load(&path).expect_err("invalid docs_source must fail");
+        let err = Config::load(&path, None).expect_err("invalid docs_source must fail");
+        fs::remove_file(&path).expect("must cleanup temporary config");
+
+        assert!(err
+            .to_string()
+            .contains("docs source must be \"github\" or \"git\", got: npm_tarball"));
+    }
+
+    #[test]
+    fn version_source_overrides_the_sync_mode_per_crate() {
+        let cfg: Config = toml::from_str(
+            r#"[crates.serde]
+repo = "serde-rs/serde"
+version_source = "latest"
+
+[crates.tokio]
+repo = "tokio-rs/tokio"
+version_source = "lockfile"
+
+[crates.regex]
+repo = "rust-lang/regex"
+"#,
+        )
+        .expect("config should parse");
+        let mode = |name: &str, global| cfg.crates[name].sync_mode(global);
+        assert_eq!(mode("serde", SyncMode::Hybrid), SyncMode::LatestDocs);
+        assert_eq!(mode("tokio", SyncMode::LatestDocs), SyncMode::Lockfile);
+        assert_eq!(mode("tokio", SyncMode::Hybrid), SyncMode::Hybrid);
+        assert_eq!(mode("regex", SyncMode::LatestDocs), SyncMode::LatestDocs);
+
+        let err = toml::from_str::<Config>("[crates.serde]\nversion_source = \"newest\"\n")
+            .expect_err("unknown version_source must fail");
+        assert!(err
+            .to_string()
+            .contains("version_source must be \"lockfile\" or \"latest\", got: newest"));
+    }
+
+    #[test]
+    fn docs_format_overrides_the_pipeline_of_the_sync_mode() {
+        let cfg: Config = toml::from_str(
+            r#"[crates.serde]
+repo = "serde-rs/serde"
+docs_format = "github_readme"
+
+[crates.tokio]
+repo = "tokio-rs/tokio"
+"#,
+        )
+        .expect("config should parse");
+        let format = |name: &str, mode| cfg.crates[name].docs_format(mode);
+        assert_eq!(
+            format("serde", SyncMode::LatestDocs),
+            DocsFormat::GithubReadme
+        );
+        assert_eq!(format("tokio", SyncMode::LatestDocs), DocsFormat::DocsRs);
+        assert_eq!(format("tokio", SyncMode::Hybrid), DocsFormat::Hybrid);
+        assert_eq!(
+            format("tokio", SyncMode::Lockfile),
+            DocsFormat::GithubReadme
+        );
+        let settings = Settings::default();
+        let hashed = HashedSettings::from(&settings);
+        assert_ne!(
+            cfg.crates["serde"].config_hash(&hashed),
+            CrateDoc {
+                docs_format: None,
+                ..cfg.crates["serde"].clone()
+            }
+            .config_hash(&hashed)
+        );
+
+        let err = toml::from_str::<Config>("[crates.serde]\ndocs_format = \"html\"\n")
+            .expect_err("unknown docs_format must fail");
+        assert!(err.to_string().contains(
+            "docs_format must be \"github_readme\", \"docsrs\" or \"hybrid\", got: html"
+        ));
+    }
+
+    #[test]
+    fn version_req_is_parsed_and_validated() {
+        let mut cfg: Config = toml::from_str(
+            r#"[settings]
+sync_mode = "latest_docs"
+
+[crates.serde]
+version_req = ">=1.0.100, <2"
+"#,
+        )
+        .expect("config should parse");
+        cfg.validate().expect("valid version_req");
+        let req = cfg.crates["serde"].version_req().expect("parsed req");
+        assert!(req.matches(&semver::Version::new(1, 0, 200)));
+        assert!(!req.matches(&semver::Version::new(2, 0, 0)));
+
+        cfg.crates.get_mut("serde").expect("serde").version_req = Some("one".to_string());
+        let err = cfg.validate().expect_err("invalid version_req must fail");
+        assert!(err
+            .to_string()
+            .contains("crates.serde.version_req `one` is not a valid semver requirement"));
+    }
+
+    #[test]
+    fn wildcard_entry_fills_in_crates_without_an_entry() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be valid")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("ai-fdocs-wildcard-{suffix}.toml"));
+        fs::write(
+            &path,
+            r#"[crates."*"]
+files = ["README.md"]
+ai_notes = "Docs for {crate}."
+full_changelog = true
+
+[crates.serde]
+repo = "serde-rs/serde"
+"#,
+        )
+        .expect("must write temporary config");
+        let mut config = Config::load(&path, None).expect("config should parse");
+        assert_eq!(config.crates.len(), 1);
+
+        let repos = BTreeMap::from([
+            ("serde".to_string(), "elsewhere/serde".to_string()),
+            ("tokio".to_string(), "tokio-rs/tokio".to_string()),
+        ]);
+        assert_eq!(config.add_wildcard_crates(&repos), vec!["tokio"]);
+        assert_eq!(
+            config.crates["serde"].repo.as_deref(),
+            Some("serde-rs/serde")
+        );
+        let tokio = &config.crates["tokio"];
+        assert_eq!(tokio.repo.as_deref(), Some("tokio-rs/tokio"));
+        assert_eq!(tokio.files, Some(vec!["README.md".to_string()]));
+        assert_eq!(tokio.ai_notes, "Docs for tokio.");
+        assert_eq!(tokio.full_changelog, Some(true));
+
+        fs::write(&path, "[crates.\"*\"]\nrepo = \"owner/repo\"\n")
+            .expect("must write temporary config");
+        let err = Config::load(&path, None).expect_err("wildcard repo must fail");
         fs::remove_file(&path).expect("must cleanup temporary config");
+        assert!(err.to_string().contains("crates.\"*\" cannot set `repo`"));
+    }
+
+    #[test]
+    fn per_crate_git_source_and_git_url_validation() {
+        let mut cfg: Config = toml::from_str(
+            r#"[crates.serde]
+repo = "serde-rs/serde"
+source = "git"
+
+[crates.internal]
+source = "git"
+git_url = "git@git.example.com:team/internal.git"
+"#,
+        )
+        .expect("config should parse");
+        cfg.validate().expect("git crates without repo are valid");
+
+        let global = cfg.settings.docs_source;
+        assert_eq!(
+            cfg.crates["serde"].docs_source(global),
+            super::DocsSource::Git
+        );
+        assert_eq!(
+            cfg.crates["serde"].git_clone_url().as_deref(),
+            Some("https://github.com/serde-rs/serde.git")
+        );
+        assert_eq!(
+            cfg.crates["internal"].git_clone_url().as_deref(),
+            Some("git@git.example.com:team/internal.git")
+        );
+
+        cfg.crates
+            .get_mut("internal")
+            .expect("internal entry")
+            .source = None;
+        let err = cfg
+            .validate()
+            .expect_err("git_url without git source must fail");
+        assert!(err
+            .to_string()
+            .contains("crates.internal.git_url requires source = \"git\""));
 
+        let internal = cfg.crates.get_mut("internal").expect("internal entry");
+        internal.source = Some(super::DocsSource::Git);
+        internal.git_url = Some("--upload-pack=touch /tmp/pwned".to_string());
+        let err = cfg.validate().expect_err("option-like git_url must fail");
         assert!(err
             .to_string()
-            .contains("settings.docs_source must be \"github\", got: npm_tarball"));
+            .contains("crates.internal.git_url must not start with `-`"));
     }
 
     #[test]
@@ -534,7 +1923,7 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let cfg = Config::load(&path).expect("config without docs_source should parse");
+        let cfg = Config::load(&path, None).expect("config without docs_source should parse");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert!(matches!(
@@ -542,6 +1931,150 @@ repo = "serde-rs/serde"
             super::DocsSource::GitHub
         ));
     }
+    #[test]
+    fn profile_overrides_settings_and_crates() {
+        let dir = std::env::temp_dir().join(format!("ai-fdocs-profiles-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create dir");
+        let path = dir.join("ai-fdocs.toml");
+        fs::write(
+            &path,
+            r#"[settings]
+output_dir = "docs/full"
+sync_concurrency = 4
+
+[settings.extra_headers]
+X-Team = "docs"
+
+[crates.serde]
+repo = "serde-rs/serde"
+
+[crates.tokio]
+repo = "tokio-rs/tokio"
+
+[profiles.ci.settings]
+output_dir = "docs/ci"
+
+[profiles.ci.settings.extra_headers]
+X-Run = "ci"
+
+[profiles.ci.crates.serde]
+repo = "serde-rs/serde"
+
+[profiles.dev.settings]
+prune = false
+"#,
+        )
+        .expect("write config");
+
+        let base = Config::load(&path, None).expect("base config");
+        assert_eq!(base.settings.output_dir, Path::new("docs/full"));
+        assert_eq!(base.crates.len(), 2);
+
+        let ci = Config::load(&path, Some("ci")).expect("ci profile");
+        assert_eq!(ci.settings.output_dir, Path::new("docs/ci"));
+        assert_eq!(ci.settings.sync_concurrency, 4);
+        assert_eq!(ci.settings.extra_headers.len(), 2);
+        assert_eq!(ci.crates.keys().collect::<Vec<_>>(), vec!["serde"]);
+
+        let dev = Config::load(&path, Some("dev")).expect("dev profile");
+        assert!(!dev.settings.prune);
+        assert_eq!(dev.crates.len(), 2);
+
+        let err = Config::load(&path, Some("nightly")).expect_err("unknown profile");
+        assert!(err
+            .to_string()
+            .contains("unknown profile `nightly`; defined profiles: ci, dev"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn env_references_in_string_values_are_interpolated() {
+        let mut table: toml::Table = toml::from_str(
+            r#"[settings]
+default_files = ["README.md", "${DOCS_DIR:-docs}/guide.md"]
+
+[settings.extra_headers]
+Authorization = "Bearer ${TOKEN}"
+
+[crates.internal]
+repo = "${ORG}/internal"
+ai_notes = "cost: $5, literal: $${ORG}"
+"#,
+        )
+        .expect("parse");
+        let lookup = |var: &str| match var {
+            "ORG" => Some("acme".to_string()),
+            "TOKEN" => Some("secret".to_string()),
+            _ => None,
+        };
+        interpolate_env(&mut table, &lookup).expect("interpolate");
+        let config: Config = toml::Value::Table(table).try_into().expect("config");
+
+        assert_eq!(
+            config.crates["internal"].repo.as_deref(),
+            Some("acme/internal")
+        );
+        assert_eq!(
+            config.crates["internal"].ai_notes,
+            "cost: $5, literal: ${ORG}"
+        );
+        assert_eq!(
+            config.settings.extra_headers["Authorization"],
+            "Bearer secret"
+        );
+        assert_eq!(config.settings.default_files[1], "docs/guide.md");
+
+        let mut table: toml::Table =
+            toml::from_str("[crates.x]\nrepo = \"${MISSING_ORG}/x\"\n").expect("parse");
+        let err = interpolate_env(&mut table, &lookup).expect_err("missing var");
+        assert!(err
+            .to_string()
+            .contains("crates.x.repo: environment variable `MISSING_ORG` is not set"));
+    }
+
+    #[test]
+    fn note_file_becomes_ai_notes_and_conflicts_with_inline_notes() {
+        let dir = std::env::temp_dir().join(format!("ai-fdocs-note-file-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("notes")).expect("create notes dir");
+        fs::write(
+            dir.join("notes/serde.md"),
+            "Prefer derives.\n\nAvoid manual impls.\n",
+        )
+        .expect("write note");
+        let path = dir.join("ai-fdocs.toml");
+        fs::write(
+            &path,
+            "[crates.serde]\nrepo = \"serde-rs/serde\"\nnote_file = \"notes/serde.md\"\n",
+        )
+        .expect("write config");
+
+        let cfg = Config::load(&path, None).expect("config with note_file should load");
+        let serde = &cfg.crates["serde"];
+        assert_eq!(serde.ai_notes, "Prefer derives.\n\nAvoid manual impls.\n");
+        let settings = Settings::default();
+        let hashed = HashedSettings::from(&settings);
+        let hash = serde.config_hash(&hashed);
+
+        fs::write(dir.join("notes/serde.md"), "Changed.\n").expect("edit note");
+        let edited = Config::load(&path, None).expect("reload");
+        assert_ne!(edited.crates["serde"].config_hash(&hashed), hash);
+
+        fs::write(
+            &path,
+            "[crates.serde]\nrepo = \"serde-rs/serde\"\nai_notes = \"x\"\nnote_file = \"notes/serde.md\"\n",
+        )
+        .expect("rewrite config");
+        let err = Config::load(&path, None).expect_err("both notes must fail");
+        assert!(err
+            .to_string()
+            .contains("sets both `ai_notes` and `note_file`"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn config_without_repo_or_sources_fails_validation_in_lockfile_mode() {
         let suffix = SystemTime::now()
@@ -553,12 +2086,12 @@ repo = "serde-rs/serde"
         fs::write(&path, "[crates.serde]\nai_notes = \"x\"\n")
             .expect("must write temporary config");
 
-        let err = Config::load(&path).expect_err("config without repo/sources must fail");
+        let err = Config::load(&path, None).expect_err("config without repo/sources must fail");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert!(err
             .to_string()
-            .contains("must define `repo` or legacy `sources` with GitHub for lockfile mode"));
+            .contains("must define `repo`, `git_url`, `path` or legacy `sources` with GitHub for lockfile mode"));
     }
 
     #[test]
@@ -575,12 +2108,36 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let cfg = Config::load(&path).expect("latest_docs config without repo should parse");
+        let cfg = Config::load(&path, None).expect("latest_docs config without repo should parse");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert_eq!(cfg.settings.sync_mode, SyncMode::LatestDocs);
     }
 
+    #[test]
+    fn upstream_name_overrides_config_key_for_fetch_identity() {
+        let cfg: Config = toml::from_str(
+            r#"[crates.mylib]
+repo = "owner/mylib"
+upstream_name = "mylib-core"
+
+[crates.serde]
+repo = "serde-rs/serde"
+"#,
+        )
+        .expect("config with upstream_name must parse");
+
+        let renamed = &cfg.crates["mylib"];
+        assert_eq!(renamed.upstream_name("mylib"), "mylib-core");
+        assert_eq!(cfg.crates["serde"].upstream_name("serde"), "serde");
+
+        let mut without = renamed.clone();
+        without.upstream_name = None;
+        let settings = Settings::default();
+        let hashed = HashedSettings::from(&settings);
+        assert_ne!(renamed.config_hash(&hashed), without.config_hash(&hashed));
+    }
+
     #[test]
     fn config_with_zero_latest_ttl_hours_fails_validation() {
         let suffix = SystemTime::now()
@@ -600,7 +2157,7 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let err = Config::load(&path).expect_err("zero latest_ttl_hours must fail");
+        let err = Config::load(&path, None).expect_err("zero latest_ttl_hours must fail");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert!(err
@@ -608,6 +2165,189 @@ repo = "serde-rs/serde"
             .contains("settings.latest_ttl_hours must be greater than 0"));
     }
 
+    #[test]
+    fn per_crate_ttl_overrides_global_and_zero_fails_validation() {
+        let mut cfg: Config = toml::from_str(
+            r#"[crates.tokio]
+repo = "tokio-rs/tokio"
+ttl_hours = 6
+
+[crates.serde]
+repo = "serde-rs/serde"
+"#,
+        )
+        .expect("config with ttl_hours must parse");
+        assert_eq!(cfg.crates["tokio"].latest_ttl_hours(24), 6);
+        assert_eq!(cfg.crates["serde"].latest_ttl_hours(24), 24);
+
+        cfg.crates.get_mut("tokio").expect("tokio entry").ttl_hours = Some(0);
+        let err = cfg.validate().expect_err("zero ttl_hours must fail");
+        assert!(err
+            .to_string()
+            .contains("crates.tokio.ttl_hours must be greater than 0"));
+    }
+
+    #[test]
+    fn host_concurrency_limits_parse_and_reject_zero() {
+        let mut cfg: Config = toml::from_str(
+            r#"[settings]
+sync_concurrency = 16
+github_concurrency = 4
+"#,
+        )
+        .expect("config with host limits must parse");
+        assert_eq!(cfg.settings.github_concurrency, Some(4));
+        assert_eq!(cfg.settings.docsrs_concurrency, None);
+        assert!(cfg.validate().is_ok());
+
+        cfg.settings.docsrs_concurrency = Some(0);
+        let err = cfg
+            .validate()
+            .expect_err("zero docsrs_concurrency must fail");
+        assert!(err
+            .to_string()
+            .contains("settings.docsrs_concurrency must be greater than 0"));
+    }
+
+    #[test]
+    fn config_with_excessive_request_timeout_fails_validation() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be valid")
+            .as_nanos();
+        let path =
+            std::env::temp_dir().join(format!("ai-fdocs-invalid-request-timeout-{suffix}.toml"));
+
+        fs::write(
+            &path,
+            r#"[settings]
+request_timeout_secs = 601
+
+[crates.serde]
+repo = "serde-rs/serde"
+"#,
+        )
+        .expect("must write temporary config");
+
+        let err = Config::load(&path, None).expect_err("request_timeout_secs above 600 must fail");
+        fs::remove_file(&path).expect("must cleanup temporary config");
+
+        assert!(err
+            .to_string()
+            .contains("settings.request_timeout_secs must not exceed 600"));
+    }
+
+    #[test]
+    fn config_with_invalid_proxy_url_fails_validation() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be valid")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("ai-fdocs-invalid-proxy-{suffix}.toml"));
+
+        fs::write(
+            &path,
+            r#"[settings]
+https_proxy = "not a url"
+
+[crates.serde]
+repo = "serde-rs/serde"
+"#,
+        )
+        .expect("must write temporary config");
+
+        let err = Config::load(&path, None).expect_err("invalid proxy URL must fail");
+        fs::remove_file(&path).expect("must cleanup temporary config");
+
+        assert!(err
+            .to_string()
+            .contains("settings.https_proxy is not a valid proxy URL"));
+    }
+
+    #[test]
+    fn config_with_non_http_registry_api_base_fails_validation() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be valid")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("ai-fdocs-invalid-registry-{suffix}.toml"));
+
+        fs::write(
+            &path,
+            r#"[settings]
+registry_api_base = "registry.internal/api/v1"
+
+[crates.serde]
+repo = "serde-rs/serde"
+"#,
+        )
+        .expect("must write temporary config");
+
+        let err = Config::load(&path, None).expect_err("non-http registry must fail");
+        fs::remove_file(&path).expect("must cleanup temporary config");
+
+        assert!(err
+            .to_string()
+            .contains("settings.registry_api_base must be an http(s) URL"));
+    }
+
+    #[test]
+    fn config_with_changelog_regex_without_capture_group_fails_validation() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be valid")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("ai-fdocs-changelog-regex-{suffix}.toml"));
+
+        fs::write(
+            &path,
+            r#"[settings]
+changelog_version_regex = '^## \d{4}-\d{2}-\d{2}'
+
+[crates.serde]
+repo = "serde-rs/serde"
+"#,
+        )
+        .expect("must write temporary config");
+
+        let err = Config::load(&path, None).expect_err("regex without capture group must fail");
+        fs::remove_file(&path).expect("must cleanup temporary config");
+
+        assert!(err
+            .to_string()
+            .contains("settings.changelog_version_regex must contain a capture group"));
+    }
+
+    #[test]
+    fn config_with_invalid_crate_header_fails_validation() {
+        let suffix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be valid")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("ai-fdocs-invalid-header-{suffix}.toml"));
+
+        fs::write(
+            &path,
+            r#"[settings.extra_headers]
+X-Api-Key = "secret"
+
+[crates.serde]
+repo = "serde-rs/serde"
+
+[crates.serde.headers]
+"bad header" = "value"
+"#,
+        )
+        .expect("must write temporary config");
+
+        let err = Config::load(&path, None).expect_err("invalid header name must fail");
+        fs::remove_file(&path).expect("must cleanup temporary config");
+
+        assert!(err
+            .to_string()
+            .contains("crates.serde.headers has invalid header name `bad header`"));
+    }
+
     #[test]
     fn config_with_docsrs_single_page_false_fails_validation() {
         let suffix = SystemTime::now()
@@ -628,7 +2368,7 @@ repo = "serde-rs/serde"
         )
         .expect("must write temporary config");
 
-        let err = Config::load(&path).expect_err("docsrs_single_page=false must fail");
+        let err = Config::load(&path, None).expect_err("docsrs_single_page=false must fail");
         fs::remove_file(&path).expect("must cleanup temporary config");
 
         assert!(err
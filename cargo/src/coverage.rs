@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use serde::Serialize;
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UncoveredPackage {
+    pub crate_name: String,
+    pub version: String,
+}
+
+/// How `Cargo.lock` packages and `[crates.*]` entries line up. Purely
+/// analytical: nothing is fetched or written.
+#[derive(Debug, Default, Serialize)]
+pub struct CoverageReport {
+    pub lockfile_packages: usize,
+    pub configured_crates: usize,
+    /// Lockfile packages with their own `[crates.<name>]` entry.
+    pub covered: usize,
+    /// Whether `[crates."*"]` picks up the uncovered packages at sync time.
+    pub wildcard: bool,
+    /// Lockfile packages without a `[crates.<name>]` entry, by name.
+    pub uncovered: Vec<UncoveredPackage>,
+    /// `[crates.<name>]` entries with no package of that name in `Cargo.lock`.
+    pub not_in_lockfile: Vec<String>,
+}
+
+/// Compares `config.crates` with the `Cargo.lock` packages in `versions`.
+pub fn collect_coverage(config: &Config, versions: &HashMap<String, String>) -> CoverageReport {
+    let mut uncovered: Vec<UncoveredPackage> = versions
+        .iter()
+        .filter(|(name, _)| !config.crates.contains_key(*name))
+        .map(|(name, version)| UncoveredPackage {
+            crate_name: name.clone(),
+            version: version.clone(),
+        })
+        .collect();
+    uncovered.sort_by(|a, b| a.crate_name.cmp(&b.crate_name));
+
+    let mut not_in_lockfile: Vec<String> = config
+        .crates
+        .keys()
+        .filter(|name| !versions.contains_key(*name))
+        .cloned()
+        .collect();
+    not_in_lockfile.sort();
+
+    CoverageReport {
+        lockfile_packages: versions.len(),
+        configured_crates: config.crates.len(),
+        covered: versions.len() - uncovered.len(),
+        wildcard: config.wildcard.is_some(),
+        uncovered,
+        not_in_lockfile,
+    }
+}
+
+pub fn print_coverage_table(report: &CoverageReport) {
+    print!("{}", format_coverage_table(report));
+}
+
+pub fn format_coverage_json(
+    report: &CoverageReport,
+) -> std::result::Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(report)
+}
+
+fn format_coverage_table(report: &CoverageReport) -> String {
+    const COL_CRATE: usize = 32;
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "Not configured ({} Cargo.lock package(s)):",
+        report.uncovered.len()
+    );
+    if report.uncovered.is_empty() {
+        let _ = writeln!(output, "  -");
+    }
+    for item in &report.uncovered {
+        let _ = writeln!(output, "  {:<COL_CRATE$} {}", item.crate_name, item.version);
+    }
+
+    let _ = writeln!(
+        output,
+        "\nNot in Cargo.lock ({} config entr{}):",
+        report.not_in_lockfile.len(),
+        if report.not_in_lockfile.len() == 1 {
+            "y"
+        } else {
+            "ies"
+        }
+    );
+    if report.not_in_lockfile.is_empty() {
+        let _ = writeln!(output, "  -");
+    }
+    for name in &report.not_in_lockfile {
+        let _ = writeln!(output, "  {name}");
+    }
+
+    let _ = writeln!(output);
+    let _ = writeln!(
+        output,
+        "Covered: {}/{} Cargo.lock packages | Config entries: {} | Not configured: {} | Not in Cargo.lock: {}",
+        report.covered,
+        report.lockfile_packages,
+        report.configured_crates,
+        report.uncovered.len(),
+        report.not_in_lockfile.len()
+    );
+    if report.wildcard && !report.uncovered.is_empty() {
+        let _ = writeln!(
+            output,
+            "Note: [crates.\"*\"] syncs the unconfigured packages that list a repository on crates.io."
+        );
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect_coverage, format_coverage_json, format_coverage_table};
+    use crate::config::Config;
+    use std::collections::HashMap;
+
+    #[test]
+    fn lists_unconfigured_packages_and_entries_missing_from_the_lockfile() {
+        let config: Config = toml::from_str(
+            r#"
+[crates.serde]
+repo = "serde-rs/serde"
+
+[crates.old-dep]
+repo = "owner/old-dep"
+"#,
+        )
+        .expect("config");
+        let versions = HashMap::from([
+            ("serde".to_string(), "1.0.210".to_string()),
+            ("tokio".to_string(), "1.40.0".to_string()),
+            ("anyhow".to_string(), "1.0.89".to_string()),
+        ]);
+
+        let report = collect_coverage(&config, &versions);
+        assert_eq!(report.covered, 1);
+        assert_eq!(
+            report
+                .uncovered
+                .iter()
+                .map(|item| item.crate_name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["anyhow", "tokio"]
+        );
+        assert_eq!(report.not_in_lockfile, vec!["old-dep"]);
+        assert!(!report.wildcard);
+
+        let table = format_coverage_table(&report);
+        assert!(table.contains("Not configured (2 Cargo.lock package(s)):"));
+        assert!(table.contains("Not in Cargo.lock (1 config entry):\n  old-dep\n"));
+        assert!(table.contains("Covered: 1/3 Cargo.lock packages"));
+
+        let json = format_coverage_json(&report).expect("json");
+        assert!(json.contains("\"lockfile_packages\": 3"));
+        assert!(json.contains("\"not_in_lockfile\": [\n    \"old-dep\"\n  ]"));
+    }
+}
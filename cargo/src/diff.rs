@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::storage;
+use crate::verify;
+
+/// Changed lines shown per side of a hunk before eliding the rest.
+const PREVIEW_LINES: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// How one saved file would change. For `Changed`, the lines between the
+/// first and last differing line form a single hunk starting at `line`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub file: String,
+    pub kind: ChangeKind,
+    pub line: usize,
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+}
+
+/// `check --diff` preview for one crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateDiff {
+    pub crate_name: String,
+    pub from_version: Option<String>,
+    pub to_version: Option<String>,
+    pub files: Vec<FileChange>,
+    /// Why upstream could not be fetched; `files` is empty then.
+    pub error: Option<String>,
+}
+
+/// Saved doc bodies of one `crate@version` dir (provenance headers stripped),
+/// keyed like the bodies `sync` would write.
+pub fn read_stored_bodies(crate_dir: &Path) -> BTreeMap<String, String> {
+    verify::stored_doc_files(crate_dir)
+        .into_iter()
+        .filter_map(|name| {
+            let content = fs::read_to_string(crate_dir.join(&name)).ok()?;
+            let body = storage::strip_injected_header(&content).to_string();
+            Some((name, body))
+        })
+        .collect()
+}
+
+/// File-level changes from `cached` to `upstream`, in file name order.
+pub fn diff_bodies(
+    cached: &BTreeMap<String, String>,
+    upstream: &BTreeMap<String, String>,
+) -> Vec<FileChange> {
+    let mut names: Vec<&String> = cached.keys().chain(upstream.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old = cached.get(name).map_or("", String::as_str);
+            let new = upstream.get(name).map_or("", String::as_str);
+            let kind = match (cached.contains_key(name), upstream.contains_key(name)) {
+                (false, _) => ChangeKind::Added,
+                (_, false) => ChangeKind::Removed,
+                _ if old == new => return None,
+                _ => ChangeKind::Changed,
+            };
+            Some(diff_file(name, kind, old, new))
+        })
+        .collect()
+}
+
+fn diff_file(name: &str, kind: ChangeKind, old: &str, new: &str) -> FileChange {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let owned = |lines: &[&str]| lines.iter().map(|l| (*l).to_string()).collect();
+    FileChange {
+        file: name.to_string(),
+        kind,
+        line: prefix + 1,
+        removed: owned(&old[prefix..old.len() - suffix]),
+        added: owned(&new[prefix..new.len() - suffix]),
+    }
+}
+
+pub fn print_diff_report(diffs: &[CrateDiff]) {
+    print!("{}", format_diff_report(diffs));
+}
+
+fn format_diff_report(diffs: &[CrateDiff]) -> String {
+    let mut output = String::new();
+    let _ = writeln!(output, "\nUpstream drift (nothing was written):");
+    for diff in diffs {
+        let _ = writeln!(
+            output,
+            "\n{} {} → {}",
+            diff.crate_name,
+            diff.from_version.as_deref().unwrap_or("(missing)"),
+            diff.to_version.as_deref().unwrap_or("?")
+        );
+        if let Some(error) = &diff.error {
+            let _ = writeln!(output, "  ! preview unavailable: {error}");
+            continue;
+        }
+        if diff.files.is_empty() {
+            let _ = writeln!(output, "  = content unchanged");
+        }
+        for change in &diff.files {
+            match change.kind {
+                ChangeKind::Added => {
+                    let _ = writeln!(
+                        output,
+                        "  + {} (new, {} lines)",
+                        change.file,
+                        change.added.len()
+                    );
+                }
+                ChangeKind::Removed => {
+                    let _ = writeln!(
+                        output,
+                        "  - {} (removed, {} lines)",
+                        change.file,
+                        change.removed.len()
+                    );
+                }
+                ChangeKind::Changed => {
+                    let _ = writeln!(
+                        output,
+                        "  ~ {} (+{} -{}) @@ line {}",
+                        change.file,
+                        change.added.len(),
+                        change.removed.len(),
+                        change.line
+                    );
+                    write_preview(&mut output, '-', &change.removed);
+                    write_preview(&mut output, '+', &change.added);
+                }
+            }
+        }
+    }
+    output
+}
+
+fn write_preview(output: &mut String, marker: char, lines: &[String]) {
+    for line in lines.iter().take(PREVIEW_LINES) {
+        let _ = writeln!(output, "    {marker} {line}");
+    }
+    if lines.len() > PREVIEW_LINES {
+        let _ = writeln!(
+            output,
+            "    {marker} … {} more",
+            lines.len() - PREVIEW_LINES
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_bodies, format_diff_report, ChangeKind, CrateDiff};
+    use std::collections::BTreeMap;
+
+    fn bodies(files: &[(&str, &str)]) -> BTreeMap<String, String> {
+        files
+            .iter()
+            .map(|(name, body)| ((*name).to_string(), (*body).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn diffs_files_into_a_single_hunk_per_change() {
+        let cached = bodies(&[
+            ("CHANGELOG.md", "# Changelog\n## 1.0.0\n- first\n"),
+            ("OLD.md", "gone\n"),
+            ("README.md", "same\n"),
+        ]);
+        let upstream = bodies(&[
+            (
+                "CHANGELOG.md",
+                "# Changelog\n## 1.1.0\n- second\n## 1.0.0\n- first\n",
+            ),
+            ("README.md", "same\n"),
+            ("docs__guide.md", "a\nb\n"),
+        ]);
+
+        let changes = diff_bodies(&cached, &upstream);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.file.as_str(), c.kind, c.added.len(), c.removed.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("CHANGELOG.md", ChangeKind::Changed, 2, 0),
+                ("OLD.md", ChangeKind::Removed, 0, 1),
+                ("docs__guide.md", ChangeKind::Added, 2, 0),
+            ]
+        );
+        assert_eq!(changes[0].line, 2);
+        assert_eq!(changes[0].added, vec!["## 1.1.0", "- second"]);
+    }
+
+    #[test]
+    fn report_shows_versions_previews_and_errors() {
+        let changed = diff_bodies(
+            &bodies(&[("README.md", "v1\n")]),
+            &bodies(&[("README.md", "v2\n")]),
+        );
+        let report = format_diff_report(&[
+            CrateDiff {
+                crate_name: "serde".to_string(),
+                from_version: Some("1.0.0".to_string()),
+                to_version: Some("1.0.1".to_string()),
+                files: changed,
+                error: None,
+            },
+            CrateDiff {
+                crate_name: "tokio".to_string(),
+                from_version: None,
+                to_version: Some("1.2.0".to_string()),
+                files: Vec::new(),
+                error: Some("HTTP 404".to_string()),
+            },
+        ]);
+
+        assert!(report.contains("serde 1.0.0 → 1.0.1"));
+        assert!(report.contains("  ~ README.md (+1 -1) @@ line 1\n    - v1\n    + v2\n"));
+        assert!(report.contains("tokio (missing) → 1.2.0\n  ! preview unavailable: HTTP 404"));
+    }
+}
@@ -0,0 +1,374 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use reqwest::Client;
+use tokio::task::JoinSet;
+
+use crate::config::{Config, Settings, SyncMode};
+use crate::error::AiDocsError;
+use crate::fetcher::github::GitHubFetcher;
+use crate::resolver;
+
+/// Written and removed again to prove the output dir is writable.
+const PROBE_FILE: &str = ".aifd-doctor-probe";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckState {
+    Pass,
+    /// Works, but with a limitation worth fixing (e.g. no GitHub token).
+    Warn,
+    Fail,
+}
+
+/// One line of the `doctor` checklist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub state: CheckState,
+    pub detail: String,
+    /// Suggested fix, shown for warnings and failures.
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            state: CheckState::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            state: CheckState::Warn,
+            fix: Some(fix.into()),
+            ..Self::pass(name, detail)
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            state: CheckState::Fail,
+            fix: Some(fix.into()),
+            ..Self::pass(name, detail)
+        }
+    }
+}
+
+/// Loads and validates the config, with `profile` merged when given; the
+/// config is returned for the remaining checks when it passes.
+pub fn check_config(config_path: &Path, profile: Option<&str>) -> (DoctorCheck, Option<Config>) {
+    const NAME: &str = "config";
+    match Config::load(config_path, profile) {
+        Ok(config) => {
+            let profile = profile
+                .map(|p| format!(", profile `{p}`"))
+                .unwrap_or_default();
+            let detail = format!(
+                "{} is valid ({} crate(s), {} mode{profile})",
+                config_path.display(),
+                config.crates.len(),
+                config.settings.sync_mode.as_str()
+            );
+            (DoctorCheck::pass(NAME, detail), Some(config))
+        }
+        Err(e @ AiDocsError::ConfigNotFound(_)) => (
+            DoctorCheck::fail(
+                NAME,
+                e.to_string(),
+                "run `cargo ai-fdocs init` or pass --config",
+            ),
+            None,
+        ),
+        Err(e) => (
+            DoctorCheck::fail(
+                NAME,
+                e.to_string(),
+                "fix the reported key; `cargo ai-fdocs init --force` regenerates a template",
+            ),
+            None,
+        ),
+    }
+}
+
+/// `Cargo.lock` is only needed when versions come from the lockfile.
+pub fn check_cargo_lock(lock_path: &Path, sync_mode: SyncMode) -> DoctorCheck {
+    const NAME: &str = "Cargo.lock";
+    if sync_mode == SyncMode::LatestDocs {
+        return DoctorCheck::pass(NAME, "not needed in latest_docs mode");
+    }
+    match resolver::resolve_cargo_versions(lock_path) {
+        Ok(versions) => DoctorCheck::pass(
+            NAME,
+            format!(
+                "{} parsed ({} packages)",
+                lock_path.display(),
+                versions.len()
+            ),
+        ),
+        Err(AiDocsError::CargoLockNotFound)
+            if lock_path.with_file_name("Cargo.toml").is_file() =>
+        {
+            DoctorCheck::warn(
+                NAME,
+                format!(
+                    "{} not found; versions fall back to the minimal requirements in Cargo.toml",
+                    lock_path.display()
+                ),
+                "run `cargo generate-lockfile` (or commit Cargo.lock) for exact versions",
+            )
+        }
+        Err(AiDocsError::CargoLockNotFound) => DoctorCheck::fail(
+            NAME,
+            format!(
+                "{} not found ({} mode reads versions from it)",
+                lock_path.display(),
+                sync_mode.as_str()
+            ),
+            "run `cargo generate-lockfile` in the workspace root, or set settings.sync_mode = \"latest_docs\"",
+        ),
+        Err(e) => DoctorCheck::fail(
+            NAME,
+            e.to_string(),
+            "regenerate it with `cargo generate-lockfile`",
+        ),
+    }
+}
+
+/// Token presence and validity via `GET /rate_limit`, which costs no quota.
+pub async fn check_github_token(
+    github: std::result::Result<&GitHubFetcher, &AiDocsError>,
+) -> DoctorCheck {
+    const NAME: &str = "GitHub token";
+    let github = match github {
+        Ok(github) => github,
+        Err(e) => {
+            return DoctorCheck::fail(
+                NAME,
+                e.to_string(),
+                "fix GITHUB_TOKEN_FILE/GH_TOKEN_FILE or settings.github_token_file",
+            )
+        }
+    };
+    match (github.has_token(), github.rate_limit().await) {
+        (true, Ok(quota)) => DoctorCheck::pass(
+            NAME,
+            format!(
+                "valid, {}/{} requests left this hour",
+                quota.remaining, quota.limit
+            ),
+        ),
+        (false, Ok(quota)) => DoctorCheck::warn(
+            NAME,
+            format!(
+                "not set; unauthenticated, {}/{} requests left this hour and private repositories return 404",
+                quota.remaining, quota.limit
+            ),
+            "export GITHUB_TOKEN (or GH_TOKEN) for 5000 requests/hour",
+        ),
+        (_, Err(e @ AiDocsError::GitHubAuth { .. })) => DoctorCheck::fail(
+            NAME,
+            format!("rejected by GitHub: {e}"),
+            "the token is invalid or expired; create a new one and update GITHUB_TOKEN",
+        ),
+        (_, Err(e)) => DoctorCheck::fail(
+            NAME,
+            format!("could not be checked: {e}"),
+            "check network access to api.github.com",
+        ),
+    }
+}
+
+/// Writes a probe file into `dir`, or into its nearest existing ancestor when
+/// `dir` does not exist yet (sync creates it), so nothing is left behind.
+pub fn check_output_dir(dir: &Path) -> DoctorCheck {
+    const NAME: &str = "output dir";
+    let fix = "check permissions, or point settings.output_dir/AI_FDOCS_OUTPUT_DIR elsewhere";
+    let existing = dir
+        .ancestors()
+        .map(|ancestor| {
+            if ancestor.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                ancestor
+            }
+        })
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or_else(|| Path::new("."));
+    if !existing.is_dir() {
+        return DoctorCheck::fail(
+            NAME,
+            format!("{} is not a directory", existing.display()),
+            fix,
+        );
+    }
+
+    let probe = existing.join(PROBE_FILE);
+    if let Err(e) = fs::write(&probe, b"") {
+        return DoctorCheck::fail(
+            NAME,
+            format!("{} is not writable: {e}", existing.display()),
+            fix,
+        );
+    }
+    let _ = fs::remove_file(&probe);
+    if existing == dir {
+        DoctorCheck::pass(NAME, format!("{} is writable", dir.display()))
+    } else {
+        DoctorCheck::pass(
+            NAME,
+            format!(
+                "{} will be created under {}",
+                dir.display(),
+                existing.display()
+            ),
+        )
+    }
+}
+
+/// Hosts a sync may contact, as `(name, url)`.
+pub fn network_targets(settings: &Settings) -> Vec<(String, String)> {
+    [
+        ("github.com API", "https://api.github.com"),
+        ("github.com raw", "https://raw.githubusercontent.com"),
+        ("crates.io", settings.registry_api_base.as_str()),
+        ("docs.rs", "https://docs.rs"),
+    ]
+    .into_iter()
+    .map(|(name, url)| (format!("network: {name}"), url.to_string()))
+    .collect()
+}
+
+/// HEAD-requests every target concurrently. Any HTTP answer counts as
+/// reachable; only connection-level failures fail. Results keep `targets` order.
+pub async fn check_reachability(
+    client: &Client,
+    targets: Vec<(String, String)>,
+) -> Vec<DoctorCheck> {
+    let mut tasks = JoinSet::new();
+    for (index, (name, url)) in targets.into_iter().enumerate() {
+        let client = client.clone();
+        tasks.spawn(async move {
+            let check = match client.head(&url).send().await {
+                Ok(res) => DoctorCheck::pass(&name, format!("{url} answered HTTP {}", res.status().as_u16())),
+                Err(e) => DoctorCheck::fail(
+                    &name,
+                    format!("{url} unreachable: {e}"),
+                    "check the network, settings.http_proxy/https_proxy/no_proxy and settings.request_timeout_secs",
+                ),
+            };
+            (index, check)
+        });
+    }
+
+    let mut checks = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(result) = joined {
+            checks.push(result);
+        }
+    }
+    checks.sort_by_key(|(index, _)| *index);
+    checks.into_iter().map(|(_, check)| check).collect()
+}
+
+pub fn print_report(checks: &[DoctorCheck]) {
+    print!("{}", format_report(checks));
+}
+
+fn format_report(checks: &[DoctorCheck]) -> String {
+    let mut output = String::new();
+    for check in checks {
+        let marker = match check.state {
+            CheckState::Pass => "✅",
+            CheckState::Warn => "⚠️",
+            CheckState::Fail => "❌",
+        };
+        let _ = writeln!(output, "{marker} {}: {}", check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            let _ = writeln!(output, "   → {fix}");
+        }
+    }
+    let failed = checks
+        .iter()
+        .filter(|check| check.state == CheckState::Fail)
+        .count();
+    let warned = checks
+        .iter()
+        .filter(|check| check.state == CheckState::Warn)
+        .count();
+    let _ = writeln!(
+        output,
+        "\n{} passed, {warned} warning(s), {failed} failed",
+        checks.len() - failed - warned
+    );
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_cargo_lock, check_output_dir, format_report, CheckState, DoctorCheck};
+    use crate::config::SyncMode;
+    use std::fs;
+
+    #[test]
+    fn cargo_lock_is_only_required_outside_latest_docs_mode() {
+        let missing = std::env::temp_dir().join("aifd-doctor-missing/Cargo.lock");
+        let check = check_cargo_lock(&missing, SyncMode::Lockfile);
+        assert_eq!(check.state, CheckState::Fail);
+        assert!(check
+            .fix
+            .as_deref()
+            .is_some_and(|f| f.contains("generate-lockfile")));
+        assert_eq!(
+            check_cargo_lock(&missing, SyncMode::LatestDocs).state,
+            CheckState::Pass
+        );
+
+        let tmp = std::env::temp_dir().join(format!("aifd-doctor-manifest-{}", std::process::id()));
+        fs::create_dir_all(&tmp).expect("create tmp");
+        fs::write(tmp.join("Cargo.toml"), "[package]\nname = \"demo\"\n").expect("write manifest");
+        let check = check_cargo_lock(&tmp.join("Cargo.lock"), SyncMode::Lockfile);
+        assert_eq!(check.state, CheckState::Warn);
+        assert!(check.detail.contains("Cargo.toml"));
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn output_dir_probe_uses_nearest_existing_ancestor() {
+        let tmp = std::env::temp_dir().join(format!("aifd-doctor-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).expect("create tmp");
+
+        let existing = check_output_dir(&tmp);
+        assert_eq!(existing.state, CheckState::Pass);
+        assert!(existing.detail.ends_with("is writable"));
+        let nested = check_output_dir(&tmp.join("docs/ai/vendor-docs"));
+        assert_eq!(nested.state, CheckState::Pass);
+        assert!(nested.detail.contains("will be created under"));
+        assert!(!tmp.join("docs").exists());
+        assert!(!tmp.join(super::PROBE_FILE).exists());
+
+        fs::write(tmp.join("file"), "x").expect("write file");
+        assert_eq!(
+            check_output_dir(&tmp.join("file/docs")).state,
+            CheckState::Fail
+        );
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn report_lists_fixes_and_a_summary() {
+        let report = format_report(&[
+            DoctorCheck::pass("config", "ai-fdocs.toml is valid"),
+            DoctorCheck::warn("GitHub token", "not set", "export GITHUB_TOKEN"),
+            DoctorCheck::fail("Cargo.lock", "not found", "run `cargo generate-lockfile`"),
+        ]);
+        assert!(report.contains("✅ config: ai-fdocs.toml is valid\n"));
+        assert!(report.contains("❌ Cargo.lock: not found\n   → run `cargo generate-lockfile`\n"));
+        assert!(report.ends_with("1 passed, 1 warning(s), 1 failed\n"));
+    }
+}
@@ -1,10 +1,12 @@
 use std::path::PathBuf;
 
+use serde::Serialize;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, AiDocsError>;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SyncErrorKind {
     Auth,
     RateLimit,
@@ -58,19 +60,82 @@ pub enum AiDocsError {
     #[error("Optional file not found: {0}")]
     OptionalFileNotFound(String),
 
+    #[error("Crate '{name}' not found on the registry {registry} ({detail}); check the crate name (or `upstream_name`) in the config")]
+    CrateNotFound {
+        name: String,
+        registry: String,
+        detail: String,
+    },
+
+    #[error("Version {version} of crate '{name}' not found on the registry {registry} ({detail})")]
+    CrateVersionNotFound {
+        name: String,
+        version: String,
+        registry: String,
+        detail: String,
+    },
+
+    #[error("GitHub repository {repo} not found: {hint}")]
+    GitHubRepoNotFound { repo: String, hint: String },
+
+    #[error("Failed to read GitHub token file {path:?}: {reason}")]
+    GitHubTokenFile { path: PathBuf, reason: String },
+
+    #[error("git {command} failed for {url}: {stderr}")]
+    GitCommand {
+        url: String,
+        command: String,
+        stderr: String,
+    },
+
+    #[error(
+        "{url} redirected to {final_url} on another host (e.g. a login page); refusing to save it"
+    )]
+    RedirectedOffHost { url: String, final_url: String },
+
+    #[error("Too many redirects (more than {max}) for {url}; possible redirect loop")]
+    TooManyRedirects { url: String, max: usize },
+
+    #[error("Retry budget exhausted ({max_retries} retries per crate); gave up on {url}")]
+    RetryBudgetExhausted { url: String, max_retries: usize },
+
+    #[error(
+        "synced docs differ from {lock_file} (content hash mismatch); saved docs left unchanged"
+    )]
+    LockContentMismatch { lock_file: &'static str },
+
+    #[error("Download limit exceeded: settings.max_total_size_mb = {limit_mb} MB; remaining crates were not synced")]
+    TotalSizeExceeded { limit_mb: u64 },
+
+    #[error("Sync interrupted by Ctrl-C after {completed} crate(s) completed")]
+    Interrupted { completed: usize },
+
     #[error("{0}")]
     Other(String),
 }
 
 impl AiDocsError {
+    /// Process exit code: 130 (128 + SIGINT) for Ctrl-C, 1 otherwise.
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::Interrupted { .. } => 130,
+            _ => 1,
+        }
+    }
+
     pub fn sync_kind(&self) -> SyncErrorKind {
         match self {
             Self::GitHubAuth { .. } => SyncErrorKind::Auth,
             Self::GitHubRateLimit { .. } => SyncErrorKind::RateLimit,
-            Self::Http(_) | Self::Fetch { .. } => SyncErrorKind::Network,
-            Self::GitHubFileNotFound { .. } | Self::OptionalFileNotFound(_) => {
-                SyncErrorKind::NotFound
-            }
+            Self::Http(_)
+            | Self::Fetch { .. }
+            | Self::GitCommand { .. }
+            | Self::RetryBudgetExhausted { .. } => SyncErrorKind::Network,
+            Self::GitHubFileNotFound { .. }
+            | Self::OptionalFileNotFound(_)
+            | Self::GitHubRepoNotFound { .. }
+            | Self::CrateNotFound { .. }
+            | Self::CrateVersionNotFound { .. } => SyncErrorKind::NotFound,
             Self::HttpStatus { status, .. } if *status == 404 => SyncErrorKind::NotFound,
             Self::HttpStatus { status, .. } if *status >= 500 => SyncErrorKind::Network,
             _ => SyncErrorKind::Other,
@@ -108,6 +173,25 @@ mod tests {
             .sync_kind(),
             SyncErrorKind::NotFound
         );
+        assert_eq!(
+            AiDocsError::CrateNotFound {
+                name: "serdee".to_string(),
+                registry: "https://crates.io/api/v1".to_string(),
+                detail: "HTTP 404".to_string()
+            }
+            .sync_kind(),
+            SyncErrorKind::NotFound
+        );
+        assert_eq!(
+            AiDocsError::CrateVersionNotFound {
+                name: "serde".to_string(),
+                version: "9.9.9".to_string(),
+                registry: "https://crates.io/api/v1".to_string(),
+                detail: "HTTP 404".to_string()
+            }
+            .sync_kind(),
+            SyncErrorKind::NotFound
+        );
         assert_eq!(
             AiDocsError::Other("x".to_string()).sync_kind(),
             SyncErrorKind::Other
@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::error::{AiDocsError, Result};
+use crate::fetcher::github::{version_tag_candidates, ResolvedRef};
+use crate::fetcher::local::LocalTree;
+
+static NEXT_CHECKOUT: AtomicUsize = AtomicUsize::new(0);
+
+/// Branches and tags advertised by a remote (`git ls-remote`).
+#[derive(Debug, Default)]
+pub struct RemoteRefs {
+    /// Branch `HEAD` points at, when the remote reports it.
+    pub default_branch: Option<String>,
+    /// Full ref name to commit SHA; annotated tags are peeled.
+    commits: HashMap<String, String>,
+}
+
+impl RemoteRefs {
+    /// Parses `git ls-remote --symref` output.
+    pub fn parse(output: &str) -> Self {
+        let mut refs = Self::default();
+        for line in output.lines() {
+            if let Some(symref) = line.strip_prefix("ref: ") {
+                if let Some((target, "HEAD")) = symref.split_once('\t') {
+                    refs.default_branch = target.strip_prefix("refs/heads/").map(str::to_string);
+                }
+                continue;
+            }
+            let Some((sha, name)) = line.split_once('\t') else {
+                continue;
+            };
+            match name.strip_suffix("^{}") {
+                Some(tag) => {
+                    refs.commits.insert(tag.to_string(), sha.to_string());
+                }
+                None => {
+                    refs.commits
+                        .entry(name.to_string())
+                        .or_insert_with(|| sha.to_string());
+                }
+            }
+        }
+        refs
+    }
+
+    /// Same order as `GitHubFetcher::resolve_ref` (version tag, then
+    /// `doc_branch`, then the default branch), returning the commit the ref
+    /// points at. Repo renames are not detected for plain git remotes.
+    pub fn resolve(
+        &self,
+        url: &str,
+        crate_name: &str,
+        version: &str,
+        doc_branch: Option<&str>,
+    ) -> Result<(ResolvedRef, String)> {
+        for tag in version_tag_candidates(crate_name, version) {
+            if let Some(sha) = self.commits.get(&format!("refs/tags/{tag}")) {
+                let resolved = ResolvedRef {
+                    git_ref: tag,
+                    is_fallback: false,
+                    fallback_reason: None,
+                    renamed_to: None,
+                };
+                return Ok((resolved, sha.clone()));
+            }
+        }
+
+        let (branch, reason) = match doc_branch.map(str::trim).filter(|b| !b.is_empty()) {
+            Some(branch) => (branch, "configured doc_branch"),
+            None => {
+                let branch = self.default_branch.as_deref().ok_or_else(|| {
+                    AiDocsError::Other(format!(
+                        "no tag found for {version} and {url} does not report a default branch"
+                    ))
+                })?;
+                (branch, "default branch")
+            }
+        };
+        let sha = self
+            .commits
+            .get(&format!("refs/heads/{branch}"))
+            .ok_or_else(|| AiDocsError::Other(format!("branch `{branch}` not found on {url}")))?;
+        let resolved = ResolvedRef {
+            git_ref: branch.to_string(),
+            is_fallback: true,
+            fallback_reason: Some(format!(
+                "no tag found for {version}; used {reason} `{branch}`"
+            )),
+            renamed_to: None,
+        };
+        Ok((resolved, sha.clone()))
+    }
+}
+
+/// Branches, tags and `HEAD` of `url`.
+pub async fn ls_remote(url: &str) -> Result<RemoteRefs> {
+    let output = run_git(
+        url,
+        None,
+        &[
+            "ls-remote",
+            "--symref",
+            "--end-of-options",
+            url,
+            "HEAD",
+            "refs/heads/*",
+            "refs/tags/*",
+        ],
+    )
+    .await?;
+    Ok(RemoteRefs::parse(&output))
+}
+
+/// `host/path` of a clone URL, used as the provenance header `source`
+/// (`git@git.example.com:team/lib.git` becomes `git.example.com/team/lib`).
+pub fn source_label(url: &str) -> String {
+    let url = url.trim();
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // scp-like `user@host:path`
+        None => url.replacen(':', "/", 1),
+    };
+    let rest = match rest.split_once('/') {
+        Some((host, path)) => {
+            let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+            format!("{host}/{path}")
+        }
+        None => rest,
+    };
+    rest.trim_matches('/').trim_end_matches(".git").to_string()
+}
+
+/// Shallow clone of one ref in a temp dir, removed on drop.
+#[derive(Debug)]
+pub struct GitCheckout {
+    tree: LocalTree,
+    /// Commit that was checked out.
+    pub commit: String,
+}
+
+impl GitCheckout {
+    /// Fetches only `git_ref` (a branch, tag or commit SHA) at depth 1.
+    /// Fetching by SHA needs server support, which GitHub and most hosts have.
+    pub async fn shallow_clone(url: &str, git_ref: &str) -> Result<Self> {
+        // Config validation rejects these too; refs from `ai-fdocs.lock` are
+        // checked here.
+        if git_ref.starts_with('-') {
+            return Err(AiDocsError::GitCommand {
+                url: url.to_string(),
+                command: "fetch".to_string(),
+                stderr: format!("refusing ref `{git_ref}`: it starts with `-`"),
+            });
+        }
+        // Built before cloning so a failed clone is still cleaned up.
+        let mut checkout = Self {
+            tree: LocalTree::new(create_checkout_dir()?, url),
+            commit: String::new(),
+        };
+
+        let dir = Some(checkout.tree.root());
+        run_git(url, dir, &["init", "-q"]).await?;
+        run_git(
+            url,
+            dir,
+            &[
+                "fetch",
+                "-q",
+                "--depth",
+                "1",
+                "--no-tags",
+                "--end-of-options",
+                url,
+                git_ref,
+            ],
+        )
+        .await?;
+        run_git(url, dir, &["checkout", "-q", "FETCH_HEAD"]).await?;
+        checkout.commit = run_git(url, dir, &["rev-parse", "HEAD"])
+            .await?
+            .trim()
+            .to_string();
+        checkout.tree.source_prefix = format!("{url}@{}:", checkout.commit);
+        debug!(
+            "Cloned {url}@{git_ref} ({}) into {}",
+            checkout.commit,
+            checkout.tree.root().display()
+        );
+        Ok(checkout)
+    }
+
+    /// Files of the checkout.
+    pub fn tree(&self) -> &LocalTree {
+        &self.tree
+    }
+}
+
+impl Drop for GitCheckout {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(self.tree.root());
+    }
+}
+
+/// New empty dir under the temp dir with an unpredictable name, created
+/// exclusively (and private on Unix) so another user cannot pre-create or
+/// swap it.
+fn create_checkout_dir() -> Result<PathBuf> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    for _ in 0..16 {
+        let dir = std::env::temp_dir().join(format!("ai-fdocs-git-{:016x}", random_suffix()));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(AiDocsError::Other(
+        "cannot create a temp dir for the git checkout".to_string(),
+    ))
+}
+
+/// Random per call: `RandomState` is seeded from the OS per process, and the
+/// counter and time keep calls within one process apart.
+fn random_suffix() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_usize(NEXT_CHECKOUT.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u32(std::process::id());
+    if let Ok(elapsed) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+    hasher.finish()
+}
+
+/// Runs `git` non-interactively (credential prompts would hang a sync) and
+/// returns its stdout.
+async fn run_git(url: &str, dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.arg("-C").arg(dir);
+    }
+    cmd.args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    let git_error = |stderr: String| AiDocsError::GitCommand {
+        url: url.to_string(),
+        command: args.first().copied().unwrap_or_default().to_string(),
+        stderr,
+    };
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| git_error(format!("cannot run git: {e}")))?;
+    if !output.status.success() {
+        return Err(git_error(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ls_remote, source_label, GitCheckout, RemoteRefs};
+    use crate::fetcher::github::FileRequest;
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn parses_ls_remote_and_resolves_like_github() {
+        let refs = RemoteRefs::parse(
+            "ref: refs/heads/main\tHEAD\n\
+             aaa\tHEAD\n\
+             aaa\trefs/heads/main\n\
+             bbb\trefs/heads/docs\n\
+             ccc\trefs/tags/v1.0.0\n\
+             ddd\trefs/tags/v1.0.0^{}\n\
+             eee\trefs/tags/demo-2.0.0\n",
+        );
+        assert_eq!(refs.default_branch.as_deref(), Some("main"));
+
+        let (tag, sha) = refs.resolve("u", "demo", "1.0.0", None).expect("tag");
+        assert_eq!((tag.git_ref.as_str(), sha.as_str()), ("v1.0.0", "ddd"));
+        assert!(!tag.is_fallback);
+        let (tag, sha) = refs.resolve("u", "demo", "2.0.0", None).expect("tag");
+        assert_eq!((tag.git_ref.as_str(), sha.as_str()), ("demo-2.0.0", "eee"));
+
+        let (branch, sha) = refs
+            .resolve("u", "demo", "3.0.0", Some("docs"))
+            .expect("branch");
+        assert_eq!((branch.git_ref.as_str(), sha.as_str()), ("docs", "bbb"));
+        assert!(branch.is_fallback);
+        let (branch, _) = refs.resolve("u", "demo", "3.0.0", None).expect("default");
+        assert_eq!(branch.git_ref, "main");
+        assert!(refs.resolve("u", "demo", "3.0.0", Some("gone")).is_err());
+    }
+
+    #[tokio::test]
+    async fn option_like_refs_are_refused_before_running_git() {
+        let err = GitCheckout::shallow_clone("https://example.invalid/repo.git", "--upload-pack=x")
+            .await
+            .expect_err("option-like ref must fail");
+        assert!(err.to_string().contains("starts with `-`"));
+    }
+
+    #[test]
+    fn source_label_strips_scheme_user_and_suffix() {
+        assert_eq!(
+            source_label("https://github.com/serde-rs/serde.git"),
+            "github.com/serde-rs/serde"
+        );
+        assert_eq!(
+            source_label("git@git.example.com:team/lib.git"),
+            "git.example.com/team/lib"
+        );
+        assert_eq!(
+            source_label("ssh://git@git.example.com:2222/team/lib"),
+            "git.example.com:2222/team/lib"
+        );
+    }
+
+    #[tokio::test]
+    async fn clones_a_tag_and_reads_requested_files() {
+        let tmp = std::env::temp_dir().join(format!("aifd-git-remote-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("docs")).expect("create remote dir");
+        fs::write(tmp.join("README.md"), "# demo v1\n").expect("write readme");
+        fs::write(tmp.join("docs/guide.md"), "guide\n").expect("write guide");
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(&tmp)
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .output()
+                .is_ok_and(|out| out.status.success())
+        };
+        if !git(&["init", "-q", "-b", "main"]) {
+            // No usable git on this machine.
+            return;
+        }
+        assert!(git(&["add", "."]));
+        assert!(git(&["commit", "-q", "-m", "v1"]));
+        assert!(git(&["tag", "-a", "v1.0.0", "-m", "v1"]));
+        fs::write(tmp.join("README.md"), "# demo v2\n").expect("rewrite readme");
+        assert!(git(&["commit", "-q", "-am", "v2"]));
+
+        let url = format!("file://{}", tmp.display());
+        let (resolved, sha) = ls_remote(&url)
+            .await
+            .expect("ls-remote")
+            .resolve(&url, "demo", "1.0.0", None)
+            .expect("resolve tag");
+        assert_eq!(resolved.git_ref, "v1.0.0");
+
+        let checkout = GitCheckout::shallow_clone(&url, &resolved.git_ref)
+            .await
+            .expect("clone");
+        assert_eq!(checkout.commit, sha);
+        assert_eq!(
+            checkout.tree().list_dir_files("docs/", &["md".to_string()]),
+            vec!["docs/guide.md"]
+        );
+
+        let request = |path: &str, required| FileRequest {
+            original_path: path.to_string(),
+            candidates: vec![path.to_string()],
+            required,
+        };
+        let results = checkout
+            .tree()
+            .read_files(&[request("README.md", true), request("../escape.md", false)]);
+        assert_eq!(results[0].as_ref().expect("readme").content, "# demo v1\n");
+        assert!(results[1].is_err());
+
+        let clone_dir = checkout.tree().root().to_path_buf();
+        drop(checkout);
+        assert!(!clone_dir.exists());
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -1,27 +1,38 @@
+use std::collections::HashMap;
 use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once, OnceLock};
 use std::time::Duration;
 
 const MAX_RETRY_ATTEMPTS: usize = 3;
 const RETRY_BASE_BACKOFF_MS: u64 = 500;
 
+use reqwest::header::HeaderMap;
 use reqwest::{Client, StatusCode};
 use serde::Deserialize;
 use tokio::time::sleep;
 use tracing::{debug, warn};
 
 use crate::error::{AiDocsError, Result};
-
-const APP_USER_AGENT: &str = concat!("cargo-ai-fdocs/", env!("CARGO_PKG_VERSION"));
+use crate::fetcher::{redirect_error, same_host_policy, HostLimit, HttpOptions};
 
 #[derive(Debug, Clone)]
 pub struct ResolvedRef {
     pub git_ref: String,
     pub is_fallback: bool,
+    /// Human-readable explanation of why a fallback ref was chosen.
+    pub fallback_reason: Option<String>,
+    /// New `owner/repo` when the configured repo was renamed upstream and
+    /// `settings.follow_repo_renames` is set; fetch from this slug instead.
+    pub renamed_to: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct FetchedFile {
     pub path: String,
+    /// Candidate of the request that matched, e.g. `Readme.md` for `README.md`.
+    pub matched_candidate: String,
     pub source_url: String,
     pub content: String,
 }
@@ -33,36 +44,369 @@ pub struct FileRequest {
     pub required: bool,
 }
 
+impl FileRequest {
+    /// A trailing-slash `files` entry that must be expanded via `list_dir_files`.
+    pub fn is_directory(&self) -> bool {
+        self.exclusion_pattern().is_none() && self.original_path.ends_with('/')
+    }
+
+    /// Pattern of a `!`-prefixed `files` entry, which removes matching paths
+    /// from the other requests instead of fetching anything.
+    pub fn exclusion_pattern(&self) -> Option<&str> {
+        self.original_path.strip_prefix('!')
+    }
+}
+
+/// `.gitignore`-style match of a repo path against an exclusion pattern:
+/// `*` and `?` stay within one path segment, `**` spans any number of
+/// segments, a trailing `/` excludes everything under that directory, and a
+/// pattern without `/` matches the file name at any depth.
+pub fn matches_exclusion(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches('/');
+    let pattern = match pattern.strip_suffix('/') {
+        Some(dir) => format!("{dir}/**"),
+        None => pattern.to_string(),
+    };
+    if !pattern.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return segment_matches(&pattern, name);
+    }
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((first, rest)) => path.split_first().is_some_and(|(segment, path_rest)| {
+            segment_matches(first, segment) && segments_match(rest, path_rest)
+        }),
+    }
+}
+
+fn segment_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    wildcard_matches(&pattern, &name)
+}
+
+fn wildcard_matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| wildcard_matches(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && wildcard_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && wildcard_matches(rest, &name[1..]),
+    }
+}
+
+/// Retries shared by all file fetches of one crate
+/// (`settings.max_retries_per_crate`), so a persistently failing host can't
+/// multiply the per-request retries by the number of files.
+#[derive(Debug)]
+pub struct RetryBudget {
+    max_retries: usize,
+    remaining: AtomicUsize,
+    exhausted: AtomicBool,
+}
+
+impl RetryBudget {
+    pub fn new(max_retries: usize) -> Self {
+        Self {
+            max_retries,
+            remaining: AtomicUsize::new(max_retries),
+            exhausted: AtomicBool::new(false),
+        }
+    }
+
+    /// Takes one retry; once refused, the budget stays exhausted.
+    fn try_take(&self) -> bool {
+        let taken = self
+            .remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if !taken {
+            self.exhausted.store(true, Ordering::SeqCst);
+        }
+        taken
+    }
+
+    /// True after a retry was refused; later files fail without a request.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::SeqCst)
+    }
+
+    fn exhausted_error(&self, url: &str) -> AiDocsError {
+        AiDocsError::RetryBudgetExhausted {
+            url: url.to_string(),
+            max_retries: self.max_retries,
+        }
+    }
+}
+
 pub struct GitHubFetcher {
     client: Client,
     api_base_url: String,
     raw_base_url: String,
+    has_token: bool,
+    /// `settings.extra_headers`, added to raw file requests only.
+    raw_headers: HeaderMap,
+    /// `settings.github_concurrency`.
+    host_limit: HostLimit,
+    /// `X-OAuth-Scopes` seen by `preflight_auth` (`None` inside when GitHub
+    /// does not report scopes, e.g. for fine-grained tokens).
+    granted_scopes: OnceLock<Option<Vec<String>>>,
+    /// Index into `version_tag_candidates` of the scheme that last matched,
+    /// by lowercased repo slug, so later crates of a monorepo try it first.
+    tag_schemes: Mutex<HashMap<String, usize>>,
+    /// Commits of lightweight tags seen by `find_version_tag`, keyed by
+    /// lowercased `owner/repo@tag`, so `resolve_commit_sha` needs no request.
+    tag_commits: Mutex<HashMap<String, String>>,
+    /// Recursive trees fetched by `fetch_tree`, keyed like `tag_commits`, so
+    /// expanding several directory `files` entries lists the ref only once.
+    trees: Mutex<HashMap<String, Arc<TreeResponse>>>,
+}
+
+#[derive(Deserialize)]
+struct CommitInfo {
+    sha: String,
+}
+
+/// `GET /repos/{owner}/{repo}/git/ref/tags/{tag}`.
+#[derive(Deserialize)]
+struct GitRefInfo {
+    object: GitRefObject,
+}
+
+#[derive(Deserialize)]
+struct GitRefObject {
+    sha: String,
+    /// `commit` for lightweight tags, `tag` for annotated ones.
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+fn tag_commit_key(owner_repo: &str, git_ref: &str) -> String {
+    format!("{}@{git_ref}", owner_repo.to_ascii_lowercase())
+}
+
+/// Core REST API quota reported by `GET /rate_limit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+}
+
+#[derive(Deserialize)]
+struct RateLimitResponse {
+    resources: RateLimitResources,
+}
+
+#[derive(Deserialize)]
+struct RateLimitResources {
+    core: RateLimit,
+}
+
+/// Result of `find_version_tag`.
+struct TagLookup {
+    tag: Option<String>,
+    /// Some tag request was redirected, as GitHub does for renamed repos.
+    redirected: bool,
 }
 
 #[derive(Deserialize)]
 struct RepoInfo {
     default_branch: String,
+    /// Canonical `owner/repo`. GitHub redirects renamed repos, so this differs
+    /// from the requested slug after a rename.
+    #[serde(default)]
+    full_name: Option<String>,
+}
+
+impl RepoInfo {
+    fn renamed_slug(&self, requested: &str) -> Option<&str> {
+        self.full_name
+            .as_deref()
+            .filter(|name| !name.eq_ignore_ascii_case(requested))
+    }
+}
+
+#[derive(Deserialize)]
+struct TreeResponse {
+    tree: Vec<TreeEntry>,
+    #[serde(default)]
+    truncated: bool,
+}
+
+#[derive(Deserialize)]
+struct TreeEntry {
+    path: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Token lookup order: `GITHUB_TOKEN_FILE`, `GH_TOKEN_FILE`, the configured
+/// `settings.github_token_file`, then inline `GITHUB_TOKEN`/`GH_TOKEN`.
+/// A token file that is selected but unreadable or empty is an error rather
+/// than a silent fallback to unauthenticated requests.
+fn resolve_github_token(configured_file: Option<&Path>) -> Result<Option<String>> {
+    let env_value = |key: &str| env::var(key).ok().filter(|v| !v.trim().is_empty());
+
+    let token_file = env_value("GITHUB_TOKEN_FILE")
+        .or_else(|| env_value("GH_TOKEN_FILE"))
+        .map(PathBuf::from)
+        .or_else(|| configured_file.map(Path::to_path_buf));
+    if let Some(path) = token_file {
+        return read_token_file(&path).map(Some);
+    }
+
+    Ok(env_value("GITHUB_TOKEN").or_else(|| env_value("GH_TOKEN")))
+}
+
+/// Likely monorepo directories for `crate_name`: the `/tree/<ref>/<dir>` part
+/// of a crates.io `repository` URL when present, then `<crate>` and
+/// `crates/<crate>`.
+pub fn subpath_candidates(crate_name: &str, repository_url: Option<&str>) -> Vec<String> {
+    let mut candidates = Vec::new();
+    if let Some(dir) = repository_url.and_then(tree_url_subdir) {
+        candidates.push(dir);
+    }
+    for dir in [crate_name.to_string(), format!("crates/{crate_name}")] {
+        if !candidates.contains(&dir) {
+            candidates.push(dir);
+        }
+    }
+    candidates
+}
+
+fn tree_url_subdir(url: &str) -> Option<String> {
+    let (_, rest) = url.trim().trim_end_matches('/').split_once("/tree/")?;
+    let (_, dir) = rest.split_once('/')?;
+    let dir = dir.trim_matches('/');
+    (!dir.is_empty()).then(|| dir.to_string())
+}
+
+/// Tag names tried for `crate_name@version`, in order.
+pub fn version_tag_candidates(crate_name: &str, version: &str) -> [String; 4] {
+    [
+        format!("v{version}"),
+        version.to_string(),
+        format!("{crate_name}-v{version}"),
+        format!("{crate_name}-{version}"),
+    ]
+}
+
+/// Candidate indices to try, with the `learned` one moved to the front.
+fn tag_scheme_order(learned: Option<usize>, len: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..len).collect();
+    if let Some(index) = learned.filter(|&index| index < len) {
+        order.remove(index);
+        order.insert(0, index);
+    }
+    order
+}
+
+/// First of `candidates` with a README directly inside it, given all blob
+/// paths of a ref.
+pub fn pick_subpath(blobs: &[&str], candidates: &[String]) -> Option<String> {
+    candidates
+        .iter()
+        .find(|dir| {
+            blobs.iter().any(|path| {
+                path.strip_prefix(dir.as_str())
+                    .and_then(|rest| rest.strip_prefix('/'))
+                    .is_some_and(|name| name.eq_ignore_ascii_case("README.md"))
+            })
+        })
+        .cloned()
+}
+
+/// Sorted top-level names of a ref given all its blob paths, directories
+/// with a trailing `/`.
+pub fn top_level_entries(blobs: &[&str]) -> Vec<String> {
+    let mut entries: Vec<String> = blobs
+        .iter()
+        .map(|path| match path.split_once('/') {
+            Some((dir, _)) => format!("{dir}/"),
+            None => (*path).to_string(),
+        })
+        .collect();
+    entries.sort();
+    entries.dedup();
+    entries
+}
+
+fn parse_scopes(header: &str) -> Vec<String> {
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Required scopes not covered by `granted`; `repo` also covers `public_repo`
+/// and `repo:*`.
+fn missing_scopes(granted: &[String], required: &[String]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|req| {
+            !granted.iter().any(|g| {
+                g == *req
+                    || (g == "repo" && (req.as_str() == "public_repo" || req.starts_with("repo:")))
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+fn read_token_file(path: &Path) -> Result<String> {
+    let raw = std::fs::read_to_string(path).map_err(|e| AiDocsError::GitHubTokenFile {
+        path: path.to_path_buf(),
+        reason: e.to_string(),
+    })?;
+    let token = raw.trim();
+    if token.is_empty() {
+        return Err(AiDocsError::GitHubTokenFile {
+            path: path.to_path_buf(),
+            reason: "file is empty".to_string(),
+        });
+    }
+    Ok(token.to_string())
 }
 
 impl GitHubFetcher {
-    pub fn new() -> Self {
-        Self::with_base_urls_internal(
+    pub fn new(http: &HttpOptions) -> Result<Self> {
+        let token = resolve_github_token(http.github_token_file.as_deref())?;
+        Ok(Self::with_base_urls_internal(
             "https://api.github.com",
             "https://raw.githubusercontent.com",
+            http,
             false,
-        )
+            token,
+        ))
     }
 
     #[cfg(test)]
     fn with_base_urls_no_proxy(api_base_url: &str, raw_base_url: &str) -> Self {
-        Self::with_base_urls_internal(api_base_url, raw_base_url, true)
+        Self::with_base_urls_internal(
+            api_base_url,
+            raw_base_url,
+            &HttpOptions::default(),
+            true,
+            None,
+        )
     }
 
-    fn with_base_urls_internal(api_base_url: &str, raw_base_url: &str, no_proxy: bool) -> Self {
-        let token = env::var("GITHUB_TOKEN")
-            .or_else(|_| env::var("GH_TOKEN"))
-            .ok();
-
+    fn with_base_urls_internal(
+        api_base_url: &str,
+        raw_base_url: &str,
+        http: &HttpOptions,
+        no_proxy: bool,
+        token: Option<String>,
+    ) -> Self {
+        let has_token = token.is_some();
         let mut headers = reqwest::header::HeaderMap::new();
         if let Some(t) = token {
             if let Ok(mut auth_val) = reqwest::header::HeaderValue::from_str(&format!("Bearer {t}"))
@@ -71,15 +415,19 @@ impl GitHubFetcher {
                 headers.insert(reqwest::header::AUTHORIZATION, auth_val);
             }
         } else {
-            warn!(
-                "⚠ No GITHUB_TOKEN found. Rate limit: 60 req/hr. Set GITHUB_TOKEN for 5000 req/hr."
-            );
+            // Several fetchers may be built per run (sync, verify, fallbacks).
+            static MISSING_TOKEN_WARNING: Once = Once::new();
+            MISSING_TOKEN_WARNING.call_once(|| {
+                warn!(
+                    "⚠ No GITHUB_TOKEN found. Rate limit: 60 req/hr. Set GITHUB_TOKEN for 5000 req/hr."
+                );
+            });
         }
 
-        let mut builder = Client::builder()
-            .user_agent(APP_USER_AGENT)
+        let mut builder = http
+            .client_builder()
             .default_headers(headers)
-            .timeout(Duration::from_secs(30));
+            .redirect(same_host_policy());
 
         if no_proxy {
             builder = builder.no_proxy();
@@ -91,6 +439,92 @@ impl GitHubFetcher {
             client,
             api_base_url: api_base_url.trim_end_matches('/').to_string(),
             raw_base_url: raw_base_url.trim_end_matches('/').to_string(),
+            has_token,
+            raw_headers: http.extra_headers.clone(),
+            host_limit: HostLimit::new(http.github_concurrency),
+            granted_scopes: OnceLock::new(),
+            tag_schemes: Mutex::new(HashMap::new()),
+            tag_commits: Mutex::new(HashMap::new()),
+            trees: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `settings.min_github_token_scopes`: calls `GET /user` and warns when the
+    /// token is missing, rejected, or lacks a required scope. The scopes seen
+    /// here also sharpen later "repository not found" errors.
+    pub async fn preflight_auth(&self, required_scopes: &[String]) {
+        if !self.has_token {
+            warn!("⚠ Auth preflight: no GitHub token, so private repositories will return 404");
+            return;
+        }
+
+        let url = format!("{}/user", self.api_base_url);
+        let res = match self.send_with_retry(url.as_str(), None, None).await {
+            Ok(res) => res,
+            Err(e) => {
+                warn!("⚠ Auth preflight failed: {e}");
+                return;
+            }
+        };
+        if res.status() == StatusCode::UNAUTHORIZED {
+            warn!("⚠ Auth preflight: GitHub rejected the token (401); check GITHUB_TOKEN");
+            return;
+        }
+        if !res.status().is_success() {
+            warn!("⚠ Auth preflight: GET /user returned {}", res.status());
+            return;
+        }
+
+        let scopes = res
+            .headers()
+            .get("x-oauth-scopes")
+            .and_then(|v| v.to_str().ok())
+            .map(parse_scopes);
+        match &scopes {
+            Some(granted) => {
+                let missing = missing_scopes(granted, required_scopes);
+                if !missing.is_empty() {
+                    warn!(
+                        "⚠ Auth preflight: GitHub token lacks scope(s) {}; private repositories will return 404",
+                        missing.join(", ")
+                    );
+                }
+            }
+            None => debug!("GitHub did not report token scopes (fine-grained token?)"),
+        }
+        let _ = self.granted_scopes.set(scopes);
+    }
+
+    /// Whether requests are sent with a GitHub token.
+    pub fn has_token(&self) -> bool {
+        self.has_token
+    }
+
+    /// Current core API quota. The call itself is free, and a rejected token
+    /// fails with `GitHubAuth`.
+    pub async fn rate_limit(&self) -> Result<RateLimit> {
+        let url = format!("{}/rate_limit", self.api_base_url);
+        let res = self.send_with_retry(url.as_str(), None, None).await?;
+        if !res.status().is_success() {
+            return Err(Self::status_error(url.as_str(), res.status()));
+        }
+        let body: RateLimitResponse = res.json().await?;
+        Ok(body.resources.core)
+    }
+
+    /// Why `GET /repos/{repo}` may have returned 404, given what is known about the token.
+    fn repo_not_found_hint(&self) -> String {
+        if !self.has_token {
+            return "it may be private; set GITHUB_TOKEN (with `repo` scope) to access private repositories".to_string();
+        }
+        match self.granted_scopes.get() {
+            Some(Some(granted)) if missing_scopes(granted, &["repo".to_string()]).is_empty() => {
+                "the token has `repo` scope, so check the `repo` slug in the config".to_string()
+            }
+            Some(Some(_)) => {
+                "if it is private, the token lacks the `repo` scope needed to read it".to_string()
+            }
+            _ => "check the `repo` slug, or that the token can read this repository".to_string(),
         }
     }
 
@@ -101,34 +535,144 @@ impl GitHubFetcher {
         )
     }
 
+    fn api_commit_url(&self, owner_repo: &str, git_ref: &str) -> String {
+        format!("{}/repos/{owner_repo}/commits/{git_ref}", self.api_base_url)
+    }
+
     fn api_repo_url(&self, owner_repo: &str) -> String {
         format!("{}/repos/{owner_repo}", self.api_base_url)
     }
 
+    fn api_tree_url(&self, owner_repo: &str, git_ref: &str) -> String {
+        format!(
+            "{}/repos/{owner_repo}/git/trees/{git_ref}?recursive=1",
+            self.api_base_url
+        )
+    }
+
     fn raw_file_url(&self, repo: &str, git_ref: &str, candidate: &str) -> String {
         format!("{}/{repo}/{git_ref}/{candidate}", self.raw_base_url)
     }
 
+    /// Resolves the docs ref for `version`: a version tag, else `doc_branch`,
+    /// else the default branch.
+    ///
+    /// When a tag request was redirected (GitHub answers for renamed repos
+    /// with a 301), or no tag matched and the repo is looked up for its
+    /// default branch anyway, a different slug reported by GitHub logs a
+    /// suggestion to update `repo`; with `follow_renames`, `renamed_to` is
+    /// set so callers fetch from it (after retrying the tags there when none
+    /// matched). A configured `doc_branch` skips this check.
     pub async fn resolve_ref(
         &self,
         owner_repo: &str,
         crate_name: &str,
         version: &str,
+        doc_branch: Option<&str>,
+        follow_renames: bool,
     ) -> Result<ResolvedRef> {
-        let candidates = [
-            format!("v{version}"),
-            version.to_string(),
-            format!("{crate_name}-v{version}"),
-            format!("{crate_name}-{version}"),
-        ];
+        let lookup = self
+            .find_version_tag(owner_repo, crate_name, version)
+            .await?;
+        let doc_branch = doc_branch.map(str::trim).filter(|b| !b.is_empty());
+        let tagged = |git_ref: String, renamed_to: Option<String>| ResolvedRef {
+            git_ref,
+            is_fallback: false,
+            fallback_reason: None,
+            renamed_to,
+        };
+
+        if let Some(tag) = &lookup.tag {
+            if !lookup.redirected || doc_branch.is_some() {
+                return Ok(tagged(tag.clone(), None));
+            }
+        } else if let Some(branch) = doc_branch {
+            return Ok(ResolvedRef {
+                git_ref: branch.to_string(),
+                is_fallback: true,
+                fallback_reason: Some(format!(
+                    "no tag found for {version}; used configured doc_branch `{branch}`"
+                )),
+                renamed_to: None,
+            });
+        }
+
+        let repo_info = self.fetch_repo_info(owner_repo).await;
+        let mut renamed_to = None;
+        if let Some(new_slug) = repo_info
+            .as_ref()
+            .ok()
+            .and_then(|info| info.renamed_slug(owner_repo))
+        {
+            warn!(
+                "GitHub repository `{owner_repo}` has moved to `{new_slug}`; update `repo = \"{new_slug}\"` for `{crate_name}` in the config"
+            );
+            if follow_renames {
+                renamed_to = Some(new_slug.to_string());
+                if lookup.tag.is_none() {
+                    if let Some(tag) = self
+                        .find_version_tag(new_slug, crate_name, version)
+                        .await?
+                        .tag
+                    {
+                        return Ok(tagged(tag, renamed_to));
+                    }
+                }
+            }
+        }
 
-        for tag in candidates {
-            let url = self.api_tag_url(owner_repo, &tag);
-            let res = self.send_with_retry(url.as_str()).await?;
+        if let Some(tag) = lookup.tag {
+            return Ok(tagged(tag, renamed_to));
+        }
+        let repo_info = repo_info?;
+        Ok(ResolvedRef {
+            fallback_reason: Some(format!(
+                "no tag found for {version}; used default branch `{}`",
+                repo_info.default_branch
+            )),
+            git_ref: repo_info.default_branch,
+            is_fallback: true,
+            renamed_to,
+        })
+    }
+
+    /// First existing version tag among the usual naming schemes, starting
+    /// with the scheme that last matched in the same repo this run.
+    async fn find_version_tag(
+        &self,
+        owner_repo: &str,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<TagLookup> {
+        let mut redirected = false;
+        let repo_key = owner_repo.to_ascii_lowercase();
+        let learned = self
+            .tag_schemes
+            .lock()
+            .ok()
+            .and_then(|schemes| schemes.get(&repo_key).copied());
+        let candidates = version_tag_candidates(crate_name, version);
+        for index in tag_scheme_order(learned, candidates.len()) {
+            let tag = &candidates[index];
+            let url = self.api_tag_url(owner_repo, tag);
+            let res = self.send_with_retry(url.as_str(), None, None).await?;
+            redirected |= reqwest::Url::parse(&url).ok().as_ref() != Some(res.url());
             if res.status().is_success() {
-                return Ok(ResolvedRef {
-                    git_ref: tag,
-                    is_fallback: false,
+                if learned != Some(index) {
+                    if let Ok(mut schemes) = self.tag_schemes.lock() {
+                        schemes.insert(repo_key, index);
+                    }
+                }
+                if let Ok(info) = res.json::<GitRefInfo>().await {
+                    if info.object.kind == "commit" {
+                        if let Ok(mut commits) = self.tag_commits.lock() {
+                            commits.insert(tag_commit_key(owner_repo, tag), info.object.sha);
+                        }
+                    }
+                }
+                return Ok(TagLookup {
+                    tag: Some(tag.clone()),
+                    redirected,
                 });
             }
 
@@ -136,30 +680,155 @@ impl GitHubFetcher {
                 return Err(Self::status_error(url.as_str(), res.status()));
             }
         }
+        Ok(TagLookup {
+            tag: None,
+            redirected,
+        })
+    }
 
+    /// Commit SHA `git_ref` currently points at (pinned in `ai-fdocs.lock`).
+    /// Lightweight tags found by `resolve_ref` cost no further request.
+    pub async fn resolve_commit_sha(&self, owner_repo: &str, git_ref: &str) -> Result<String> {
+        let known = self
+            .tag_commits
+            .lock()
+            .ok()
+            .and_then(|commits| commits.get(&tag_commit_key(owner_repo, git_ref)).cloned());
+        if let Some(sha) = known {
+            return Ok(sha);
+        }
+        let url = self.api_commit_url(owner_repo, git_ref);
+        let res = self.send_with_retry(url.as_str(), None, None).await?;
+        if !res.status().is_success() {
+            return Err(Self::status_error(url.as_str(), res.status()));
+        }
+        let commit: CommitInfo = res.json().await?;
+        Ok(commit.sha)
+    }
+
+    async fn fetch_repo_info(&self, owner_repo: &str) -> Result<RepoInfo> {
         let repo_url = self.api_repo_url(owner_repo);
-        let repo_resp = self.send_with_retry(repo_url.as_str()).await?;
+        let repo_resp = self.send_with_retry(repo_url.as_str(), None, None).await?;
+        if repo_resp.status() == StatusCode::NOT_FOUND {
+            return Err(AiDocsError::GitHubRepoNotFound {
+                repo: owner_repo.to_string(),
+                hint: self.repo_not_found_hint(),
+            });
+        }
         if !repo_resp.status().is_success() {
             return Err(Self::status_error(repo_url.as_str(), repo_resp.status()));
         }
+        Ok(repo_resp.json().await?)
+    }
 
-        let repo_info: RepoInfo = repo_resp.json().await?;
+    /// Lists files under `dir` at `git_ref` whose extension (lowercase, no dot)
+    /// is in `extensions`, using the recursive tree API. Returns sorted repo paths.
+    pub async fn list_dir_files(
+        &self,
+        owner_repo: &str,
+        git_ref: &str,
+        dir: &str,
+        extensions: &[String],
+    ) -> Result<Vec<String>> {
+        let tree = self.fetch_tree(owner_repo, git_ref).await?;
+        if tree.truncated {
+            warn!("GitHub tree for {owner_repo}@{git_ref} is truncated; files under {dir} may be missing");
+        }
 
-        Ok(ResolvedRef {
-            git_ref: repo_info.default_branch,
-            is_fallback: true,
-        })
+        let dir = dir.trim_matches('/');
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{dir}/")
+        };
+        let mut files: Vec<String> = tree
+            .tree
+            .iter()
+            .filter(|entry| entry.kind == "blob" && entry.path.starts_with(&prefix))
+            .filter(|entry| {
+                std::path::Path::new(&entry.path)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+            })
+            .map(|entry| entry.path.clone())
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// First of `candidates` (directories, see `subpath_candidates`) holding a
+    /// README at `git_ref`, as a best-effort monorepo `subpath`.
+    pub async fn infer_subpath(
+        &self,
+        owner_repo: &str,
+        git_ref: &str,
+        candidates: &[String],
+    ) -> Result<Option<String>> {
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+        let tree = self.fetch_tree(owner_repo, git_ref).await?;
+        let blobs: Vec<&str> = tree
+            .tree
+            .iter()
+            .filter(|entry| entry.kind == "blob")
+            .map(|entry| entry.path.as_str())
+            .collect();
+        Ok(pick_subpath(&blobs, candidates))
     }
 
+    /// `top_level_entries` of `git_ref`, to hint where docs moved when none
+    /// of the requested files exist.
+    pub async fn list_top_level(&self, owner_repo: &str, git_ref: &str) -> Result<Vec<String>> {
+        let tree = self.fetch_tree(owner_repo, git_ref).await?;
+        let blobs: Vec<&str> = tree
+            .tree
+            .iter()
+            .filter(|entry| entry.kind == "blob")
+            .map(|entry| entry.path.as_str())
+            .collect();
+        Ok(top_level_entries(&blobs))
+    }
+
+    async fn fetch_tree(&self, owner_repo: &str, git_ref: &str) -> Result<Arc<TreeResponse>> {
+        let key = tag_commit_key(owner_repo, git_ref);
+        let known = self
+            .trees
+            .lock()
+            .ok()
+            .and_then(|trees| trees.get(&key).cloned());
+        if let Some(tree) = known {
+            return Ok(tree);
+        }
+        let url = self.api_tree_url(owner_repo, git_ref);
+        let res = self.send_with_retry(url.as_str(), None, None).await?;
+        if !res.status().is_success() {
+            return Err(Self::status_error(url.as_str(), res.status()));
+        }
+        let tree = Arc::new(res.json::<TreeResponse>().await?);
+        if let Ok(mut trees) = self.trees.lock() {
+            trees.insert(key, Arc::clone(&tree));
+        }
+        Ok(tree)
+    }
+
+    /// Fetches raw files; `settings.extra_headers` and then `headers` (e.g.
+    /// per-crate `headers`, which win on conflicts) are added to each request. Retries draw from `budget`; once it is exhausted the remaining
+    /// files fail without being requested.
     pub async fn fetch_files(
         &self,
         repo: &str,
         git_ref: &str,
         requests: &[FileRequest],
+        headers: &HeaderMap,
+        budget: &RetryBudget,
     ) -> Vec<Result<FetchedFile>> {
+        let mut merged = self.raw_headers.clone();
+        merged.extend(headers.clone());
         let mut out = Vec::with_capacity(requests.len());
         for req in requests {
-            out.push(self.fetch_file(repo, git_ref, req).await);
+            out.push(self.fetch_file(repo, git_ref, req, &merged, budget).await);
         }
         out
     }
@@ -169,13 +838,20 @@ impl GitHubFetcher {
         repo: &str,
         git_ref: &str,
         req: &FileRequest,
+        headers: &HeaderMap,
+        budget: &RetryBudget,
     ) -> Result<FetchedFile> {
         let mut tried = Vec::new();
 
         for candidate in &req.candidates {
             tried.push(candidate.clone());
             let url = self.raw_file_url(repo, git_ref, candidate);
-            let res = self.send_with_retry(url.as_str()).await?;
+            if budget.is_exhausted() {
+                return Err(budget.exhausted_error(&url));
+            }
+            let res = self
+                .send_with_retry(url.as_str(), Some(headers), Some(budget))
+                .await?;
 
             if res.status() == StatusCode::NOT_FOUND {
                 continue;
@@ -188,6 +864,7 @@ impl GitHubFetcher {
             let content = res.text().await?;
             return Ok(FetchedFile {
                 path: req.original_path.clone(),
+                matched_candidate: candidate.clone(),
                 source_url: url,
                 content,
             });
@@ -204,11 +881,22 @@ impl GitHubFetcher {
         }
     }
 
-    async fn send_with_retry(&self, url: &str) -> Result<reqwest::Response> {
+    async fn send_with_retry(
+        &self,
+        url: &str,
+        headers: Option<&HeaderMap>,
+        budget: Option<&RetryBudget>,
+    ) -> Result<reqwest::Response> {
         let mut backoff_ms = RETRY_BASE_BACKOFF_MS;
 
         for attempt in 1..=MAX_RETRY_ATTEMPTS {
-            let send_result = self.client.get(url).send().await;
+            let mut request = self.client.get(url);
+            if let Some(headers) = headers {
+                request = request.headers(headers.clone());
+            }
+            let permit = self.host_limit.acquire().await;
+            let send_result = request.send().await;
+            drop(permit);
 
             match send_result {
                 Ok(response) => {
@@ -229,6 +917,9 @@ impl GitHubFetcher {
                     }
 
                     if status.is_server_error() && attempt < MAX_RETRY_ATTEMPTS {
+                        if let Some(budget) = budget.filter(|b| !b.try_take()) {
+                            return Err(budget.exhausted_error(url));
+                        }
                         debug!(
                             "GitHub {status} for {url}; retrying attempt {}/{} after {}ms",
                             attempt + 1,
@@ -243,10 +934,16 @@ impl GitHubFetcher {
                     return Ok(response);
                 }
                 Err(source) => {
+                    if let Some(error) = redirect_error(url, &source) {
+                        return Err(error);
+                    }
                     let is_retryable_network =
                         source.is_timeout() || source.is_connect() || source.is_request();
 
                     if is_retryable_network && attempt < MAX_RETRY_ATTEMPTS {
+                        if let Some(budget) = budget.filter(|b| !b.try_take()) {
+                            return Err(budget.exhausted_error(url));
+                        }
                         debug!(
                             "Network error for {url}; retrying attempt {}/{} after {}ms: {source}",
                             attempt + 1,
@@ -299,9 +996,18 @@ mod tests {
     use std::thread;
 
     fn start_mock_server(routes: HashMap<String, (u16, String)>) -> String {
+        start_recording_mock_server(routes).0
+    }
+
+    /// Like `start_mock_server`, also returning every raw request received.
+    fn start_recording_mock_server(
+        routes: HashMap<String, (u16, String)>,
+    ) -> (String, Arc<Mutex<Vec<String>>>) {
         let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
         let addr = listener.local_addr().expect("local addr");
         let routes = Arc::new(Mutex::new(routes));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&received);
 
         thread::spawn(move || {
             for stream in listener.incoming() {
@@ -320,6 +1026,7 @@ mod tests {
                 }
 
                 let req = String::from_utf8_lossy(&buf[..read]);
+                log.lock().expect("lock log").push(req.to_string());
                 let path = req
                     .lines()
                     .next()
@@ -333,8 +1040,14 @@ mod tests {
                     .cloned()
                     .unwrap_or((404, String::new()));
 
+                // For 3xx routes the body is the `Location`.
+                let (location, body) = if (300..400).contains(&status) {
+                    (format!("Location: {body}\r\n"), String::new())
+                } else {
+                    (String::new(), body)
+                };
                 let response = format!(
-                    "HTTP/1.1 {status} OK\r\nContent-Length: {}\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{}",
+                    "HTTP/1.1 {status} OK\r\n{location}Content-Length: {}\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{}",
                     body.len(),
                     body
                 );
@@ -342,7 +1055,7 @@ mod tests {
             }
         });
 
-        format!("http://{addr}")
+        (format!("http://{addr}"), received)
     }
 
     #[tokio::test]
@@ -374,13 +1087,232 @@ mod tests {
             GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
 
         let resolved = fetcher
-            .resolve_ref("owner/repo", "demo", "1.2.3")
+            .resolve_ref("owner/repo", "demo", "1.2.3", None, false)
             .await
             .expect("resolve fallback ref");
         assert_eq!(resolved.git_ref, "main");
         assert!(resolved.is_fallback);
     }
 
+    #[tokio::test]
+    async fn tries_the_tag_scheme_that_matched_in_the_same_repo_first() {
+        let mut routes = HashMap::new();
+        for tag in ["tokio-v1.40.0", "tokio-macros-v2.4.0", "v2.4.0"] {
+            routes.insert(
+                format!("/repos/tokio-rs/tokio/git/ref/tags/{tag}"),
+                (200, "{}".to_string()),
+            );
+        }
+        let api_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let first = fetcher
+            .resolve_ref("tokio-rs/tokio", "tokio", "1.40.0", None, false)
+            .await
+            .expect("resolve tokio");
+        assert_eq!(first.git_ref, "tokio-v1.40.0");
+        // `v2.4.0` also exists but comes first only in the default order.
+        let second = fetcher
+            .resolve_ref("tokio-rs/tokio", "tokio-macros", "2.4.0", None, false)
+            .await
+            .expect("resolve tokio-macros");
+        assert_eq!(second.git_ref, "tokio-macros-v2.4.0");
+        assert_eq!(tag_scheme_order(Some(2), 4), vec![2, 0, 1, 3]);
+        assert_eq!(tag_scheme_order(None, 4), vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn reads_core_quota_from_rate_limit() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/rate_limit".to_string(),
+            (
+                200,
+                r#"{"resources":{"core":{"limit":60,"remaining":42,"reset":0},"search":{"limit":10,"remaining":10}}}"#.to_string(),
+            ),
+        );
+        let api_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let quota = fetcher.rate_limit().await.expect("rate limit");
+        assert_eq!(
+            quota,
+            RateLimit {
+                limit: 60,
+                remaining: 42
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn prefers_doc_branch_over_default_branch_when_tags_missing() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/repos/owner/repo".to_string(),
+            (200, "{\"default_branch\":\"main\"}".to_string()),
+        );
+
+        let api_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let resolved = fetcher
+            .resolve_ref("owner/repo", "demo", "1.2.3", Some("gh-pages"), false)
+            .await
+            .expect("resolve doc branch ref");
+        assert_eq!(resolved.git_ref, "gh-pages");
+        assert!(resolved.is_fallback);
+        assert!(resolved
+            .fallback_reason
+            .as_deref()
+            .is_some_and(|r| r.contains("doc_branch")));
+    }
+
+    #[tokio::test]
+    async fn follows_renamed_repo_to_version_tag_on_new_slug() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/repos/old-owner/repo".to_string(),
+            (
+                200,
+                "{\"default_branch\":\"main\",\"full_name\":\"new-owner/repo\"}".to_string(),
+            ),
+        );
+        routes.insert(
+            "/repos/new-owner/repo/git/ref/tags/v1.2.3".to_string(),
+            (200, "{}".to_string()),
+        );
+
+        let api_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let resolved = fetcher
+            .resolve_ref("old-owner/repo", "demo", "1.2.3", None, true)
+            .await
+            .expect("resolve renamed repo");
+        assert_eq!(resolved.git_ref, "v1.2.3");
+        assert!(!resolved.is_fallback);
+        assert_eq!(resolved.renamed_to.as_deref(), Some("new-owner/repo"));
+
+        let not_followed = fetcher
+            .resolve_ref("old-owner/repo", "demo", "1.2.3", None, false)
+            .await
+            .expect("resolve without following");
+        assert_eq!(not_followed.git_ref, "main");
+        assert!(not_followed.renamed_to.is_none());
+    }
+
+    #[tokio::test]
+    async fn redirected_tag_lookup_detects_rename_and_doc_branch_skips_it() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/repos/old-owner/repo/git/ref/tags/v1.2.3".to_string(),
+            (301, "/repos/new-owner/repo/git/ref/tags/v1.2.3".to_string()),
+        );
+        routes.insert(
+            "/repos/new-owner/repo/git/ref/tags/v1.2.3".to_string(),
+            (200, "{}".to_string()),
+        );
+        routes.insert(
+            "/repos/old-owner/repo".to_string(),
+            (
+                200,
+                "{\"default_branch\":\"main\",\"full_name\":\"new-owner/repo\"}".to_string(),
+            ),
+        );
+        let (api_base, received) = start_recording_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+        let repo_requests = || {
+            received
+                .lock()
+                .expect("lock log")
+                .iter()
+                .filter(|req| req.starts_with("GET /repos/old-owner/repo HTTP"))
+                .count()
+        };
+
+        let resolved = fetcher
+            .resolve_ref("old-owner/repo", "demo", "1.2.3", None, true)
+            .await
+            .expect("resolve redirected repo");
+        assert_eq!(resolved.git_ref, "v1.2.3");
+        assert_eq!(resolved.renamed_to.as_deref(), Some("new-owner/repo"));
+        assert_eq!(repo_requests(), 1);
+
+        let with_branch = fetcher
+            .resolve_ref("old-owner/repo", "demo", "1.2.3", Some("docs"), true)
+            .await
+            .expect("resolve with doc_branch");
+        assert_eq!(with_branch.git_ref, "v1.2.3");
+        assert!(with_branch.renamed_to.is_none());
+        assert_eq!(repo_requests(), 1);
+    }
+
+    #[test]
+    fn exclusion_patterns_match_like_gitignore() {
+        assert!(matches_exclusion(
+            "docs/generated/**",
+            "docs/generated/api/a.md"
+        ));
+        assert!(matches_exclusion("docs/generated/", "docs/generated/a.md"));
+        assert!(!matches_exclusion("docs/generated/**", "docs/guide.md"));
+        assert!(matches_exclusion("docs/*.mdx", "docs/intro.mdx"));
+        assert!(!matches_exclusion("docs/*.mdx", "docs/nested/intro.mdx"));
+        assert!(matches_exclusion(
+            "docs/**/draft-?.md",
+            "docs/a/b/draft-1.md"
+        ));
+        assert!(matches_exclusion("*.snap.md", "docs/nested/out.snap.md"));
+        assert!(!matches_exclusion("README.md", "docs/README.md.bak"));
+    }
+
+    #[tokio::test]
+    async fn list_dir_files_filters_by_prefix_and_extension() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/repos/owner/repo/git/trees/v1.0.0?recursive=1".to_string(),
+            (
+                200,
+                r#"{"tree":[
+                    {"path":"docs","type":"tree"},
+                    {"path":"docs/intro.md","type":"blob"},
+                    {"path":"docs/guide/setup.MDX","type":"blob"},
+                    {"path":"docs/logo.png","type":"blob"},
+                    {"path":"docs/api.txt","type":"blob"},
+                    {"path":"docsite/other.md","type":"blob"},
+                    {"path":"README.md","type":"blob"}
+                ],"truncated":false}"#
+                    .to_string(),
+            ),
+        );
+
+        let (api_base, received) = start_recording_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let extensions = vec!["md".to_string(), "mdx".to_string(), "txt".to_string()];
+        let files = fetcher
+            .list_dir_files("owner/repo", "v1.0.0", "docs/", &extensions)
+            .await
+            .expect("list docs dir");
+        assert_eq!(
+            files,
+            vec!["docs/api.txt", "docs/guide/setup.MDX", "docs/intro.md"]
+        );
+
+        // Further directories at the same ref reuse the fetched tree.
+        let guide = fetcher
+            .list_dir_files("owner/repo", "v1.0.0", "docs/guide/", &extensions)
+            .await
+            .expect("list guide dir");
+        assert_eq!(guide, vec!["docs/guide/setup.MDX"]);
+        assert_eq!(received.lock().expect("lock log").len(), 1);
+    }
+
     #[tokio::test]
     async fn fetch_files_reports_partial_failures_and_optional_miss() {
         let mut routes = HashMap::new();
@@ -416,7 +1348,15 @@ mod tests {
             },
         ];
 
-        let results = fetcher.fetch_files("owner/repo", "main", &requests).await;
+        let results = fetcher
+            .fetch_files(
+                "owner/repo",
+                "main",
+                &requests,
+                &HeaderMap::new(),
+                &RetryBudget::new(3),
+            )
+            .await;
         assert_eq!(results.len(), 3);
 
         assert!(results[0].is_ok());
@@ -429,4 +1369,332 @@ mod tests {
             Err(AiDocsError::OptionalFileNotFound(path)) if path == "LICENSE"
         ));
     }
+
+    #[tokio::test]
+    async fn exhausted_retry_budget_fails_remaining_files_fast() {
+        let mut routes = HashMap::new();
+        routes.insert("/owner/repo/main/a.md".to_string(), (500, String::new()));
+        routes.insert("/owner/repo/main/b.md".to_string(), (200, "b".to_string()));
+
+        let raw_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy("http://api.invalid", raw_base.as_str());
+
+        let requests: Vec<FileRequest> = ["a.md", "b.md"]
+            .iter()
+            .map(|path| FileRequest {
+                original_path: (*path).to_string(),
+                candidates: vec![(*path).to_string()],
+                required: true,
+            })
+            .collect();
+
+        let budget = RetryBudget::new(1);
+        let results = fetcher
+            .fetch_files("owner/repo", "main", &requests, &HeaderMap::new(), &budget)
+            .await;
+
+        assert!(budget.is_exhausted());
+        for result in &results {
+            assert!(matches!(
+                result,
+                Err(AiDocsError::RetryBudgetExhausted { max_retries: 1, .. })
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn off_host_redirects_and_redirect_loops_are_refused() {
+        let mut login = HashMap::new();
+        login.insert(
+            "/login".to_string(),
+            (200, "<html>Sign in</html>".to_string()),
+        );
+        let (login_base, login_received) = start_recording_mock_server(login);
+        let login_base = login_base.replace("127.0.0.1", "localhost");
+
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/owner/repo/main/README.md".to_string(),
+            (302, format!("{login_base}/login")),
+        );
+        routes.insert(
+            "/owner/repo/main/LOOP.md".to_string(),
+            (302, "/owner/repo/main/LOOP.md".to_string()),
+        );
+        let raw_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy("http://api.invalid", raw_base.as_str());
+
+        let requests: Vec<FileRequest> = ["README.md", "LOOP.md"]
+            .iter()
+            .map(|path| FileRequest {
+                original_path: (*path).to_string(),
+                candidates: vec![(*path).to_string()],
+                required: true,
+            })
+            .collect();
+        let results = fetcher
+            .fetch_files(
+                "owner/repo",
+                "main",
+                &requests,
+                &HeaderMap::new(),
+                &RetryBudget::new(6),
+            )
+            .await;
+
+        assert!(matches!(
+            &results[0],
+            Err(AiDocsError::RedirectedOffHost { final_url, .. }) if final_url.contains("localhost")
+        ));
+        assert!(matches!(
+            &results[1],
+            Err(AiDocsError::TooManyRedirects { max: 5, .. })
+        ));
+        assert!(login_received.lock().expect("lock log").is_empty());
+    }
+
+    #[tokio::test]
+    async fn extra_headers_go_to_raw_requests_only() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/owner/repo/main/README.md".to_string(),
+            (200, "# Demo".to_string()),
+        );
+        routes.insert(
+            "/repos/owner/repo/commits/main".to_string(),
+            (200, r#"{"sha":"abc123"}"#.to_string()),
+        );
+        let (base, received) = start_recording_mock_server(routes);
+        let mut extra = HashMap::new();
+        extra.insert("X-Api-Key".to_string(), "secret".to_string());
+        let http = HttpOptions {
+            extra_headers: crate::fetcher::header_map(&extra, "settings.extra_headers")
+                .expect("valid headers"),
+            ..HttpOptions::default()
+        };
+        let fetcher = GitHubFetcher::with_base_urls_internal(&base, &base, &http, true, None);
+
+        fetcher
+            .resolve_commit_sha("owner/repo", "main")
+            .await
+            .expect("commit sha");
+        let request = FileRequest {
+            original_path: "README.md".to_string(),
+            candidates: vec!["README.md".to_string()],
+            required: true,
+        };
+        let results = fetcher
+            .fetch_files(
+                "owner/repo",
+                "main",
+                &[request],
+                &HeaderMap::new(),
+                &RetryBudget::new(1),
+            )
+            .await;
+        assert!(results[0].is_ok());
+
+        let received = received.lock().expect("lock log");
+        let has_key = |path: &str| {
+            received
+                .iter()
+                .find(|req| req.contains(path))
+                .map(|req| req.to_ascii_lowercase().contains("x-api-key: secret"))
+        };
+        assert_eq!(has_key("/repos/owner/repo/commits/main"), Some(false));
+        assert_eq!(has_key("/owner/repo/main/README.md"), Some(true));
+    }
+
+    #[test]
+    fn token_file_is_trimmed_and_missing_file_is_an_error() {
+        let dir = std::env::temp_dir().join(format!("aifd-token-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let path = dir.join("token");
+        std::fs::write(&path, "  ghp_secret\n").expect("write token");
+
+        assert_eq!(read_token_file(&path).expect("read token"), "ghp_secret");
+
+        std::fs::write(&path, "\n").expect("write empty token");
+        assert!(matches!(
+            read_token_file(&path),
+            Err(AiDocsError::GitHubTokenFile { .. })
+        ));
+
+        let missing = dir.join("missing");
+        let err = read_token_file(&missing).expect_err("missing file");
+        assert!(err.to_string().contains("missing"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn repo_scope_covers_public_repo_and_missing_scopes_are_listed() {
+        let granted = parse_scopes("repo, read:org");
+        assert_eq!(granted, vec!["repo".to_string(), "read:org".to_string()]);
+        let required = vec![
+            "public_repo".to_string(),
+            "repo:status".to_string(),
+            "workflow".to_string(),
+        ];
+        assert_eq!(
+            missing_scopes(&granted, &required),
+            vec!["workflow".to_string()]
+        );
+        assert!(parse_scopes("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn repo_lookup_404_without_token_hints_at_private_repo() {
+        let api_base = start_mock_server(HashMap::new());
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let Err(err) = fetcher.fetch_repo_info("owner/private").await else {
+            panic!("expected missing repo error");
+        };
+        assert!(matches!(err, AiDocsError::GitHubRepoNotFound { .. }));
+        assert_eq!(err.sync_kind(), crate::error::SyncErrorKind::NotFound);
+        assert!(err.to_string().contains("GITHUB_TOKEN"));
+    }
+
+    #[tokio::test]
+    async fn resolves_commit_sha_for_ref() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/repos/owner/repo/commits/v1.2.3".to_string(),
+            (200, "{\"sha\":\"0123abcd\"}".to_string()),
+        );
+
+        let api_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let sha = fetcher
+            .resolve_commit_sha("owner/repo", "v1.2.3")
+            .await
+            .expect("resolve commit");
+        assert_eq!(sha, "0123abcd");
+        assert!(fetcher
+            .resolve_commit_sha("owner/repo", "missing")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn lightweight_tag_commit_needs_no_commits_request() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/repos/owner/repo/git/ref/tags/v2.0.0".to_string(),
+            (
+                200,
+                r#"{"object":{"sha":"feedbeef","type":"commit"}}"#.to_string(),
+            ),
+        );
+        routes.insert(
+            "/repos/owner/repo/git/ref/tags/v1.0.0".to_string(),
+            (200, r#"{"object":{"sha":"7a9","type":"tag"}}"#.to_string()),
+        );
+        routes.insert(
+            "/repos/owner/repo/commits/v1.0.0".to_string(),
+            (200, r#"{"sha":"c0ffee"}"#.to_string()),
+        );
+        let (api_base, received) = start_recording_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        for (version, expected) in [("2.0.0", "feedbeef"), ("1.0.0", "c0ffee")] {
+            let resolved = fetcher
+                .resolve_ref("owner/repo", "demo", version, None, false)
+                .await
+                .expect("tag resolves");
+            let sha = fetcher
+                .resolve_commit_sha("owner/repo", &resolved.git_ref)
+                .await
+                .expect("commit resolves");
+            assert_eq!(sha, expected);
+        }
+
+        let received = received.lock().expect("lock log");
+        assert!(!received.iter().any(|req| req.contains("/commits/v2.0.0")));
+        assert!(received.iter().any(|req| req.contains("/commits/v1.0.0")));
+    }
+
+    #[test]
+    fn subpath_candidates_prefer_repository_tree_hint() {
+        assert_eq!(
+            subpath_candidates(
+                "tokio-util",
+                Some("https://github.com/tokio-rs/tokio/tree/master/tokio-util")
+            ),
+            vec!["tokio-util", "crates/tokio-util"]
+        );
+        assert_eq!(
+            subpath_candidates("demo", Some("https://github.com/o/r/tree/main/libs/demo/")),
+            vec!["libs/demo", "demo", "crates/demo"]
+        );
+        assert_eq!(
+            subpath_candidates("demo", Some("https://github.com/o/r"))[0],
+            "demo"
+        );
+    }
+
+    #[tokio::test]
+    async fn infers_subpath_from_first_candidate_with_readme() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/repos/o/r/git/trees/HEAD?recursive=1".to_string(),
+            (
+                200,
+                r#"{"tree":[
+                    {"path":"README.md","type":"blob"},
+                    {"path":"demo","type":"tree"},
+                    {"path":"demo/src/lib.rs","type":"blob"},
+                    {"path":"crates/demo/readme.md","type":"blob"}
+                ]}"#
+                .to_string(),
+            ),
+        );
+
+        let api_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let inferred = fetcher
+            .infer_subpath("o/r", "HEAD", &subpath_candidates("demo", None))
+            .await
+            .expect("infer subpath");
+        assert_eq!(inferred.as_deref(), Some("crates/demo"));
+    }
+
+    #[tokio::test]
+    async fn lists_top_level_entries_with_directories_marked() {
+        let mut routes = HashMap::new();
+        routes.insert(
+            "/repos/o/r/git/trees/v2.0.0?recursive=1".to_string(),
+            (
+                200,
+                r#"{"tree":[
+                    {"path":"docs","type":"tree"},
+                    {"path":"docs/README.md","type":"blob"},
+                    {"path":"docs/guide/intro.md","type":"blob"},
+                    {"path":"Cargo.toml","type":"blob"},
+                    {"path":"crates/demo/src/lib.rs","type":"blob"}
+                ]}"#
+                .to_string(),
+            ),
+        );
+
+        let api_base = start_mock_server(routes);
+        let fetcher =
+            GitHubFetcher::with_base_urls_no_proxy(api_base.as_str(), "http://raw.invalid");
+
+        let entries = fetcher
+            .list_top_level("o/r", "v2.0.0")
+            .await
+            .expect("list top level");
+        assert_eq!(entries, vec!["Cargo.toml", "crates/", "docs/"]);
+    }
 }
@@ -1,23 +1,52 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 
+use regex::Regex;
 use reqwest::{Client, StatusCode};
+use semver::{Version, VersionReq};
 use serde::Deserialize;
 use tokio::time::sleep;
 use tracing::debug;
 
+use crate::config::{DocsRsFormat, DEFAULT_REGISTRY_API_BASE};
 use crate::error::{AiDocsError, Result};
+use crate::fetcher::{redirect_error, HostLimit, HttpOptions};
 
-const APP_USER_AGENT: &str = concat!("cargo-ai-fdocs/", env!("CARGO_PKG_VERSION"));
 const MAX_RETRY_ATTEMPTS: usize = 3;
 const RETRY_BASE_BACKOFF_MS: u64 = 500;
+/// Requests to this host count against `settings.docsrs_concurrency`.
+const DOCSRS_HOST: &str = "docs.rs";
+/// Default of `settings.docsrs_max_links`.
+pub const DEFAULT_MAX_LINKS_PER_KIND: usize = 20;
 
+#[derive(Clone)]
 pub struct LatestDocsFetcher {
     client: Client,
+    use_sparse_index: bool,
+    /// crates.io-compatible API base, without trailing slash.
+    registry_api_base: String,
+    max_links_per_kind: usize,
+    /// `settings.docsrs_concurrency`; registry requests are not limited.
+    docsrs_limit: HostLimit,
+    /// Categories/keywords from crate responses already fetched for the
+    /// latest version, so `fetch_crate_metadata` needs no second request.
+    crate_metadata: Arc<Mutex<HashMap<String, CrateMetadata>>>,
 }
 
+/// Raw README markdown from the crate's repository, used instead of the
+/// docs.rs-rendered README (`settings.prefer_source_readme`).
 #[derive(Debug, Clone)]
-pub struct DocsRsArtifact {
+pub struct SourceReadme {
     pub markdown: String,
+    pub source_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DocsRsArtifact {
+    /// `API.md` or `API.html`, per `settings.docsrs_format`.
+    pub file_name: &'static str,
+    pub content: String,
     pub docsrs_input_url: String,
     pub truncated: bool,
 }
@@ -32,20 +61,134 @@ struct CratesIoResponse {
 struct CratesIoCrate {
     max_stable_version: Option<String>,
     max_version: Option<String>,
+    #[serde(default)]
+    categories: Option<Vec<String>>,
+    #[serde(default)]
+    keywords: Option<Vec<String>>,
+}
+
+impl CratesIoCrate {
+    fn metadata(&self) -> CrateMetadata {
+        CrateMetadata {
+            categories: self.categories.clone().unwrap_or_default(),
+            keywords: self.keywords.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// crates.io topical metadata (`settings.fetch_crate_metadata`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrateMetadata {
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+/// One line of a sparse index file (only the fields we need).
+#[derive(Debug, Deserialize)]
+struct SparseIndexEntry {
+    vers: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+/// crates.io error payload, e.g. `{"errors":[{"detail":"crate `x` does not exist"}]}`.
+#[derive(Debug, Deserialize)]
+struct RegistryErrors {
+    errors: Vec<RegistryErrorEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryErrorEntry {
+    #[serde(default)]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CratesIoVersionResponse {
+    version: CratesIoVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct CratesIoVersion {
+    #[serde(default)]
+    yanked: bool,
+}
+
+/// `GET /crates/{name}/versions` (only the fields we need).
+#[derive(Debug, Deserialize)]
+struct CratesIoVersionsResponse {
+    versions: Vec<CratesIoVersionEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CratesIoVersionEntry {
+    num: String,
+    #[serde(default)]
+    yanked: bool,
 }
 
 impl LatestDocsFetcher {
-    pub fn new() -> Self {
-        let client = Client::builder()
-            .user_agent(APP_USER_AGENT)
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("reqwest client");
-        Self { client }
+    pub fn new(http: &HttpOptions) -> Self {
+        let client = http.client_builder().build().expect("reqwest client");
+        Self {
+            client,
+            use_sparse_index: false,
+            registry_api_base: http.registry_api_base.trim_end_matches('/').to_string(),
+            max_links_per_kind: DEFAULT_MAX_LINKS_PER_KIND,
+            docsrs_limit: HostLimit::new(http.docsrs_concurrency),
+            crate_metadata: Arc::default(),
+        }
+    }
+
+    /// Cap on "API Reference" links per kind (see `settings.docsrs_max_links`).
+    pub fn with_max_links(mut self, max_links_per_kind: usize) -> Self {
+        self.max_links_per_kind = max_links_per_kind;
+        self
+    }
+
+    /// Resolve latest versions via `index.crates.io` first (see `settings.crates_io_sparse_index`).
+    pub fn with_sparse_index(mut self, enabled: bool) -> Self {
+        self.use_sparse_index = enabled;
+        self
+    }
+
+    /// Newest stable version of `crate_name`, or with `version_req` (a crate's
+    /// `version_req`) the greatest non-yanked version matching it.
+    pub async fn resolve_latest_version(
+        &self,
+        crate_name: &str,
+        version_req: Option<&VersionReq>,
+    ) -> Result<String> {
+        // index.crates.io only knows crates.io crates.
+        if self.use_sparse_index && self.registry_api_base == DEFAULT_REGISTRY_API_BASE {
+            match self
+                .resolve_latest_version_sparse(crate_name, version_req)
+                .await
+            {
+                Ok(version) => return Ok(version),
+                Err(e) => debug!(
+                    "sparse index lookup failed for {crate_name}: {e}; falling back to crates.io API"
+                ),
+            }
+        }
+
+        match version_req {
+            Some(req) => self.resolve_matching_version_api(crate_name, req).await,
+            None => self.resolve_latest_version_api(crate_name).await,
+        }
     }
 
-    pub async fn resolve_latest_version(&self, crate_name: &str) -> Result<String> {
-        let url = format!("https://crates.io/api/v1/crates/{crate_name}");
+    async fn resolve_latest_version_sparse(
+        &self,
+        crate_name: &str,
+        version_req: Option<&VersionReq>,
+    ) -> Result<String> {
+        let Some(path) = sparse_index_path(crate_name) else {
+            return Err(AiDocsError::Other(format!(
+                "`{crate_name}` is not an ASCII crate name"
+            )));
+        };
+        let url = format!("https://index.crates.io/{path}");
         let response = self.send_with_retry(&url).await?;
         if !response.status().is_success() {
             return Err(AiDocsError::HttpStatus {
@@ -54,7 +197,30 @@ impl LatestDocsFetcher {
             });
         }
 
-        let body: CratesIoResponse = response.json().await?;
+        let body = response.text().await?;
+        if let Some(req) = version_req {
+            let entries = sparse_index_entries(&body);
+            return greatest_matching_version(
+                crate_name,
+                entries.iter().map(|e| (e.vers.as_str(), e.yanked)),
+                req,
+            );
+        }
+        latest_from_sparse_index(&body).ok_or_else(|| {
+            AiDocsError::Other(format!(
+                "sparse index entry for '{crate_name}' has no non-yanked versions"
+            ))
+        })
+    }
+
+    async fn resolve_latest_version_api(&self, crate_name: &str) -> Result<String> {
+        let url = format!("{}/crates/{crate_name}", self.registry_api_base);
+        let body = self
+            .get_json_with_retry(crate_name, None, &url, parse_crate_response)
+            .await?;
+        if let Ok(mut cache) = self.crate_metadata.lock() {
+            cache.insert(crate_name.to_string(), body.crate_data.metadata());
+        }
         body.crate_data
             .max_stable_version
             .filter(|v| !v.trim().is_empty())
@@ -66,11 +232,73 @@ impl LatestDocsFetcher {
             })
     }
 
+    /// Lists every published version, since `max_stable_version` may lie
+    /// outside the requirement.
+    async fn resolve_matching_version_api(
+        &self,
+        crate_name: &str,
+        req: &VersionReq,
+    ) -> Result<String> {
+        let url = format!("{}/crates/{crate_name}/versions", self.registry_api_base);
+        let body = self
+            .get_json_with_retry(crate_name, None, &url, parse_versions_response)
+            .await?;
+        greatest_matching_version(
+            crate_name,
+            body.versions.iter().map(|v| (v.num.as_str(), v.yanked)),
+            req,
+        )
+    }
+
+    /// The crate's crates.io categories and keywords, reusing the crate
+    /// response of `resolve_latest_version` when there was one.
+    pub async fn fetch_crate_metadata(&self, crate_name: &str) -> Result<CrateMetadata> {
+        let cached = self
+            .crate_metadata
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(crate_name).cloned());
+        if let Some(metadata) = cached {
+            return Ok(metadata);
+        }
+        let url = format!("{}/crates/{crate_name}", self.registry_api_base);
+        self.get_json_with_retry(crate_name, None, &url, parse_crate_metadata)
+            .await
+    }
+
+    /// Returns whether `crate_name@version` is yanked on crates.io.
+    pub async fn is_version_yanked(&self, crate_name: &str, version: &str) -> Result<bool> {
+        let url = format!("{}/crates/{crate_name}/{version}", self.registry_api_base);
+        self.get_json_with_retry(crate_name, Some(version), &url, parse_yanked_flag)
+            .await
+    }
+
     pub async fn fetch_api_markdown(
         &self,
         crate_name: &str,
         version: &str,
         max_file_size_kb: usize,
+    ) -> Result<DocsRsArtifact> {
+        self.fetch_api_artifact(
+            crate_name,
+            version,
+            max_file_size_kb,
+            DocsRsFormat::Markdown,
+            None,
+        )
+        .await
+    }
+
+    /// The docs.rs crate page in `format`. For markdown, `source_readme`
+    /// replaces the HTML-derived Documentation section; the rest still comes
+    /// from docs.rs. HTML output ignores it.
+    pub async fn fetch_api_artifact(
+        &self,
+        crate_name: &str,
+        version: &str,
+        max_file_size_kb: usize,
+        format: DocsRsFormat,
+        source_readme: Option<&SourceReadme>,
     ) -> Result<DocsRsArtifact> {
         let docsrs_input_url = format!("https://docs.rs/crate/{crate_name}/{version}");
         let response = self.send_with_retry(&docsrs_input_url).await?;
@@ -82,53 +310,146 @@ impl LatestDocsFetcher {
         }
 
         let html = response.text().await?;
-        let markdown = render_docsrs_markdown(crate_name, version, &html);
-        let (markdown, truncated) = crate::storage::truncate_if_needed(&markdown, max_file_size_kb);
+        let content = match format {
+            DocsRsFormat::Markdown => {
+                let links = DocsLinks {
+                    hrefs: extract_docs_links(crate_name, version, &html),
+                    max_per_kind: self.max_links_per_kind,
+                };
+                render_docsrs_markdown(crate_name, version, &html, &links, source_readme)
+            }
+            DocsRsFormat::Html => render_docsrs_html(crate_name, version, &html),
+        };
+        let (content, truncated) = crate::storage::truncate_if_needed(&content, max_file_size_kb);
  
          Ok(DocsRsArtifact {
-             markdown,
+             file_name: format.artifact_file(),
+             content,
              docsrs_input_url: format!("https://docs.rs/crate/{crate_name}/{version}"),
              truncated,
          })
      }
 
+    /// GETs a crates.io JSON endpoint. A 2xx body that fails to read or
+    /// `parse` (truncated body, HTML error page) re-issues the request, sharing
+    /// the retry budget with `send_with_retry`. A 404, or an `errors` payload
+    /// even with a 2xx status, is reported as `CrateNotFound` for `crate_name`
+    /// (`CrateVersionNotFound` for a `version` endpoint).
+    async fn get_json_with_retry<T>(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        url: &str,
+        parse: impl Fn(&str) -> Result<T>,
+    ) -> Result<T> {
+        let mut retry = RetryState::new();
+        loop {
+            let response = self.send_retrying(url, &mut retry).await?;
+            let status = response.status();
+            if status == StatusCode::NOT_FOUND {
+                let body = response.text().await.unwrap_or_default();
+                let detail = registry_error_detail(&body).unwrap_or_else(|| "HTTP 404".to_string());
+                return Err(self.not_found(crate_name, version, detail));
+            }
+            if !status.is_success() {
+                return Err(AiDocsError::HttpStatus {
+                    url: url.to_string(),
+                    status: status.as_u16(),
+                });
+            }
+
+            let error = match response.text().await {
+                Ok(body) => {
+                    if let Some(detail) = registry_error_detail(&body) {
+                        return Err(self.not_found(crate_name, version, detail));
+                    }
+                    match parse(&body) {
+                        Ok(parsed) => return Ok(parsed),
+                        Err(e) => e,
+                    }
+                }
+                Err(e) => AiDocsError::Http(e),
+            };
+            if !retry.can_retry() {
+                return Err(error);
+            }
+            debug!(
+                "latest-docs undecodable body for {url}; retrying attempt {}/{} after {}ms: {error}",
+                retry.attempt + 1,
+                MAX_RETRY_ATTEMPTS,
+                retry.backoff_ms
+            );
+            retry.wait().await;
+        }
+    }
+
+    fn not_found(&self, crate_name: &str, version: Option<&str>, detail: String) -> AiDocsError {
+        let name = crate_name.to_string();
+        let registry = self.registry_api_base.clone();
+        match version {
+            Some(version) => AiDocsError::CrateVersionNotFound {
+                name,
+                version: version.to_string(),
+                registry,
+                detail,
+            },
+            None => AiDocsError::CrateNotFound {
+                name,
+                registry,
+                detail,
+            },
+        }
+    }
+
     async fn send_with_retry(&self, url: &str) -> Result<reqwest::Response> {
-        let mut backoff_ms = RETRY_BASE_BACKOFF_MS;
+        self.send_retrying(url, &mut RetryState::new()).await
+    }
 
-        for attempt in 1..=MAX_RETRY_ATTEMPTS {
-            match self.client.get(url).send().await {
+    async fn send_retrying(&self, url: &str, retry: &mut RetryState) -> Result<reqwest::Response> {
+        let is_docsrs =
+            reqwest::Url::parse(url).is_ok_and(|parsed| parsed.host_str() == Some(DOCSRS_HOST));
+        loop {
+            let permit = if is_docsrs {
+                self.docsrs_limit.acquire().await
+            } else {
+                None
+            };
+            let send_result = self.client.get(url).send().await;
+            drop(permit);
+            match send_result {
                 Ok(response) => {
                     let status = response.status();
                     let retryable_status =
                         status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
 
-                    if retryable_status && attempt < MAX_RETRY_ATTEMPTS {
+                    if retryable_status && retry.can_retry() {
                         debug!(
                             "latest-docs upstream {status} for {url}; retrying attempt {}/{} after {}ms",
-                            attempt + 1,
+                            retry.attempt + 1,
                             MAX_RETRY_ATTEMPTS,
-                            backoff_ms
+                            retry.backoff_ms
                         );
-                        sleep(Duration::from_millis(backoff_ms)).await;
-                        backoff_ms *= 2;
+                        retry.wait().await;
                         continue;
                     }
 
                     return Ok(response);
                 }
                 Err(source) => {
+                    if let Some(error) = redirect_error(url, &source) {
+                        return Err(error);
+                    }
                     let retryable_network =
                         source.is_timeout() || source.is_connect() || source.is_request();
 
-                    if retryable_network && attempt < MAX_RETRY_ATTEMPTS {
+                    if retryable_network && retry.can_retry() {
                         debug!(
                             "latest-docs network error for {url}; retrying attempt {}/{} after {}ms: {source}",
-                            attempt + 1,
+                            retry.attempt + 1,
                             MAX_RETRY_ATTEMPTS,
-                            backoff_ms
+                            retry.backoff_ms
                         );
-                        sleep(Duration::from_millis(backoff_ms)).await;
-                        backoff_ms *= 2;
+                        retry.wait().await;
                         continue;
                     }
 
@@ -136,13 +457,145 @@ impl LatestDocsFetcher {
                 }
             }
         }
+    }
+}
+
+/// Attempts made so far for one request and the next backoff.
+struct RetryState {
+    attempt: usize,
+    backoff_ms: u64,
+}
+
+impl RetryState {
+    fn new() -> Self {
+        Self {
+            attempt: 1,
+            backoff_ms: RETRY_BASE_BACKOFF_MS,
+        }
+    }
+
+    fn can_retry(&self) -> bool {
+        self.attempt < MAX_RETRY_ATTEMPTS
+    }
 
-        Err(AiDocsError::Other(
-            "unexpected retry flow termination".to_string(),
-        ))
+    async fn wait(&mut self) {
+        sleep(Duration::from_millis(self.backoff_ms)).await;
+        self.backoff_ms *= 2;
+        self.attempt += 1;
     }
 }
 
+/// Sparse index path for a crate, e.g. `se/rd/serde`, `3/s/syn`, `1/a`;
+/// `None` for empty or non-ASCII names, which crates.io never publishes.
+fn sparse_index_path(crate_name: &str) -> Option<String> {
+    if crate_name.is_empty() || !crate_name.is_ascii() {
+        return None;
+    }
+    let name = crate_name.to_ascii_lowercase();
+    Some(match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    })
+}
+
+/// Parsed lines of a sparse index file; unparsable lines are skipped.
+fn sparse_index_entries(body: &str) -> Vec<SparseIndexEntry> {
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Picks the highest non-yanked stable version from a sparse index file,
+/// falling back to the last non-yanked line when only pre-releases exist.
+fn latest_from_sparse_index(body: &str) -> Option<String> {
+    let entries: Vec<SparseIndexEntry> = sparse_index_entries(body)
+        .into_iter()
+        .filter(|entry| !entry.yanked)
+        .collect();
+
+    entries
+        .iter()
+        .filter_map(|entry| stable_version_key(&entry.vers).map(|key| (key, &entry.vers)))
+        .max_by_key(|(key, _)| *key)
+        .map(|(_, vers)| vers.clone())
+        .or_else(|| entries.last().map(|entry| entry.vers.clone()))
+}
+
+/// Greatest non-yanked `(version, yanked)` entry matching `req`; versions
+/// that are not valid semver are skipped.
+fn greatest_matching_version<'a>(
+    crate_name: &str,
+    versions: impl Iterator<Item = (&'a str, bool)>,
+    req: &VersionReq,
+) -> Result<String> {
+    versions
+        .filter(|(_, yanked)| !yanked)
+        .filter_map(|(num, _)| Version::parse(num).ok())
+        .filter(|version| req.matches(version))
+        .max()
+        .map(|version| version.to_string())
+        .ok_or_else(|| {
+            AiDocsError::Other(format!(
+                "no non-yanked version of '{crate_name}' matches version_req `{req}`"
+            ))
+        })
+}
+
+fn stable_version_key(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.split('+').next()?;
+    if core.contains('-') {
+        return None;
+    }
+
+    let mut parts = core.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next()?.parse().ok()?;
+    Some((major, minor, patch))
+}
+
+/// Details of a non-empty `errors` payload, joined with `; `.
+fn registry_error_detail(body: &str) -> Option<String> {
+    let payload: RegistryErrors = serde_json::from_str(body).ok()?;
+    if payload.errors.is_empty() {
+        return None;
+    }
+    let details: Vec<String> = payload
+        .errors
+        .into_iter()
+        .filter_map(|entry| entry.detail)
+        .filter(|detail| !detail.trim().is_empty())
+        .collect();
+    Some(if details.is_empty() {
+        "registry returned an error".to_string()
+    } else {
+        details.join("; ")
+    })
+}
+
+fn parse_crate_response(body: &str) -> Result<CratesIoResponse> {
+    serde_json::from_str(body)
+        .map_err(|e| AiDocsError::Other(format!("invalid crates.io crate response: {e}")))
+}
+
+fn parse_versions_response(body: &str) -> Result<CratesIoVersionsResponse> {
+    serde_json::from_str(body)
+        .map_err(|e| AiDocsError::Other(format!("invalid crates.io versions response: {e}")))
+}
+
+fn parse_crate_metadata(body: &str) -> Result<CrateMetadata> {
+    Ok(parse_crate_response(body)?.crate_data.metadata())
+}
+
+fn parse_yanked_flag(body: &str) -> Result<bool> {
+    let parsed: CratesIoVersionResponse = serde_json::from_str(body)
+        .map_err(|e| AiDocsError::Other(format!("invalid crates.io version response: {e}")))?;
+    Ok(parsed.version.yanked)
+}
+
 pub fn is_docsrs_fallback_eligible(error: &AiDocsError) -> bool {
     match error {
         AiDocsError::HttpStatus { status, .. } => {
@@ -155,12 +608,20 @@ pub fn is_docsrs_fallback_eligible(error: &AiDocsError) -> bool {
     }
 }
 
-fn render_docsrs_markdown(crate_name: &str, version: &str, html: &str) -> String {
+fn render_docsrs_markdown(
+    crate_name: &str,
+    version: &str,
+    html: &str,
+    links: &DocsLinks,
+    source_readme: Option<&SourceReadme>,
+) -> String {
     let canonical_base = format!("https://docs.rs/{crate_name}/{version}");
     let input_url = format!("https://docs.rs/crate/{crate_name}/{version}");
     let title = extract_title(html).unwrap_or_else(|| format!("{crate_name} {version}"));
-    let links = extract_docs_links(crate_name, version, html);
-    let main_content = extract_main_content(crate_name, version, html);
+    let main_content = match source_readme {
+        Some(readme) => readme.markdown.trim().to_string(),
+        None => extract_main_content(crate_name, version, html),
+    };
 
     let mut out = String::new();
     out.push_str(&format!("# {crate_name}@{version}\n\n"));
@@ -168,6 +629,12 @@ fn render_docsrs_markdown(crate_name: &str, version: &str, html: &str) -> String
     out.push_str(&format!(
         "Generated from docs.rs page **{title}** for `{crate_name}` `{version}`.\n\n"
     ));
+    if let Some(readme) = source_readme {
+        out.push_str(&format!(
+            "The Documentation section is the README markdown from {}.\n\n",
+            readme.source_url
+        ));
+    }
 
     if !main_content.is_empty() {
         out.push_str("## Documentation\n\n");
@@ -180,8 +647,11 @@ fn render_docsrs_markdown(crate_name: &str, version: &str, html: &str) -> String
     out.push_str(&format!(
         "- [rustdoc root]({canonical_base}/{crate_name}/)\n"
     ));
-    for link in links.into_iter().take(20) {
-        out.push_str(&format!("- [{link}](https://docs.rs{link})\n"));
+    for (kind, items) in group_docs_links(crate_name, version, links) {
+        out.push_str(&format!("\n### {}\n\n", kind.heading()));
+        for (label, href) in items {
+            out.push_str(&format!("- [{label}](https://docs.rs{href})\n"));
+        }
     }
 
     out.push_str("\n## Example\n\n");
@@ -201,64 +671,136 @@ fn extract_title(html: &str) -> Option<String> {
     Some(html[start..end].trim().to_string())
 }
 
-fn extract_main_content(crate_name: &str, version: &str, html: &str) -> String {
-    // docs.rs usually has the main content in <div id="main-content"> or <div class="docblock">
-    let mut content = String::new();
+/// `docsrs_format = "html"`: the page's main content with `<script>`,
+/// `<style>` and `<noscript>` elements removed, after a provenance comment.
+fn render_docsrs_html(crate_name: &str, version: &str, html: &str) -> String {
+    let input_url = format!("https://docs.rs/crate/{crate_name}/{version}");
+    let main_content = main_content_html(html).map(sanitize_html).unwrap_or_default();
+    format!("<!-- {crate_name}@{version} from {input_url} -->\n{}\n", main_content.trim())
+}
 
-    if let Some(start) = html.find("<div id=\"main-content\"") {
-        let rest = &html[start..];
-        if let Some(end) = find_closing_div(rest) {
-            content = strip_html_tags(crate_name, version, &rest[..end]);
-        }
-    } else if let Some(start) = html.find("<div class=\"docblock\"") {
-        let rest = &html[start..];
-        if let Some(end) = find_closing_div(rest) {
-            content = strip_html_tags(crate_name, version, &rest[..end]);
-        }
+/// `<noscript>`, `<script>` and `<style>` elements with their content.
+fn stripped_element_res() -> &'static [Regex; 3] {
+    static RES: OnceLock<[Regex; 3]> = OnceLock::new();
+    RES.get_or_init(|| {
+        ["noscript", "script", "style"].map(|tag| {
+            Regex::new(&format!(r"(?is)<{tag}\b[^>]*>.*?</{tag}\s*>")).expect("element regex")
+        })
+    })
+}
+
+fn stray_tag_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)</?(noscript|script|style)\b[^>]*>").expect("stray tag regex")
+    })
+}
+
+fn sanitize_html(html: &str) -> String {
+    let mut out = html.to_string();
+    for element in stripped_element_res() {
+        out = element.replace_all(&out, "").into_owned();
     }
+    // Unclosed or self-closing leftovers.
+    stray_tag_re().replace_all(&out, "").into_owned()
+}
 
-    content.trim().to_string()
+fn extract_main_content(crate_name: &str, version: &str, html: &str) -> String {
+    main_content_html(html)
+        .map(|content| strip_html_tags(crate_name, version, content).trim().to_string())
+        .unwrap_or_default()
 }
 
+/// docs.rs usually has the main content in `<div id="main-content">` or
+/// `<div class="docblock">`.
+fn main_content_html(html: &str) -> Option<&str> {
+    let start = html
+        .find("<div id=\"main-content\"")
+        .or_else(|| html.find("<div class=\"docblock\""))?;
+    let rest = &html[start..];
+    find_closing_div(rest).map(|end| &rest[..end])
+}
+
+/// Offset just past the `</div>` closing the div that `html` starts with.
+/// Tags are scanned attribute-aware: a `>` inside a quoted attribute value
+/// does not end a tag, `<!-- -->` comments are skipped, and a self-closing
+/// `<div />` does not open a level.
 fn find_closing_div(html: &str) -> Option<usize> {
+    let bytes = html.as_bytes();
     let mut depth = 0;
     let mut i = 0;
-    let bytes = html.as_bytes();
-    
+
     while i < bytes.len() {
-        if bytes[i..].starts_with(b"<div") {
-            // Check if it's a real div tag start (followed by space or >)
-            let next_char = bytes.get(i + 4);
-            if next_char.is_none() || matches!(next_char, Some(b' ') | Some(b'>')) {
+        if bytes[i] != b'<' {
+            i += 1;
+            continue;
+        }
+        if bytes[i..].starts_with(b"<!--") {
+            i = html[i + 4..]
+                .find("-->")
+                .map_or(bytes.len(), |end| i + 4 + end + 3);
+            continue;
+        }
+        // A `<` that cannot start a tag is text.
+        if !bytes
+            .get(i + 1)
+            .is_some_and(|&b| b.is_ascii_alphabetic() || b == b'/' || b == b'!')
+        {
+            i += 1;
+            continue;
+        }
+
+        let end = tag_end(bytes, i)?;
+        let tag = &bytes[i + 1..end - 1];
+        if is_tag_named(tag, b"div") {
+            if !tag.ends_with(b"/") {
                 depth += 1;
-                i += 4;
-                continue;
             }
-        }
-        if bytes[i..].starts_with(b"</div>") {
+        } else if tag.first() == Some(&b'/') && is_tag_named(&tag[1..], b"div") {
             depth -= 1;
-            i += 6;
             if depth == 0 {
-                return Some(i);
+                return Some(end);
             }
-            continue;
         }
-        i += 1;
+        i = end;
+    }
+    None
+}
+
+/// Offset just past the `>` ending the tag that starts at `start`, ignoring
+/// `>` inside quoted attribute values.
+fn tag_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (offset, &b) in bytes[start + 1..].iter().enumerate() {
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if b == b'"' || b == b'\'' => quote = Some(b),
+            None if b == b'>' => return Some(start + 1 + offset + 1),
+            None => {}
+        }
     }
     None
 }
 
+/// Whether the tag body (between `<` and `>`) is a `name` tag.
+fn is_tag_named(tag: &[u8], name: &[u8]) -> bool {
+    tag.len() >= name.len()
+        && tag[..name.len()].eq_ignore_ascii_case(name)
+        && tag
+            .get(name.len())
+            .is_none_or(|&b| b.is_ascii_whitespace() || b == b'/')
+}
+
 fn strip_html_tags(crate_name: &str, version: &str, html: &str) -> String {
     let mut out = String::new();
     let mut in_tag = false;
-    let mut in_pre = false;
     let mut tag_buffer = String::new();
 
     let bytes = html.as_bytes();
     let mut i = 0;
     while i < bytes.len() {
         if bytes[i..].starts_with(b"<pre") {
-            in_pre = true;
             out.push_str("\n```rust\n");
             while i < bytes.len() && bytes[i] != b'>' {
                 i += 1;
@@ -267,7 +809,6 @@ fn strip_html_tags(crate_name: &str, version: &str, html: &str) -> String {
             continue;
         }
         if bytes[i..].starts_with(b"</pre>") {
-            in_pre = false;
             out.push_str("\n```\n");
             i += 6;
             continue;
@@ -282,20 +823,29 @@ fn strip_html_tags(crate_name: &str, version: &str, html: &str) -> String {
             // Handle basic link rewriting if we just closed an <a> tag
             let normalized_tag = tag_buffer.to_lowercase();
             if normalized_tag.starts_with("a ") {
-                if let Some(abs_href) = extract_href(&tag_buffer) {
+                // Links back into this crate's own rustdoc are already listed
+                // under "API Reference", so they are not repeated inline.
+                if let Some(abs_href) = extract_href(&tag_buffer)
+                    .filter(|href| !is_own_docs_link(crate_name, version, href))
+                {
                     // We record the link to be appended after the link text
                     out.push_str(" (");
                     out.push_str(&abs_href);
                     out.push(')');
                 }
             }
-            
+
             // Add spacing for structural tags
-            if normalized_tag.starts_with("p") || normalized_tag.starts_with("/p") || 
-               normalized_tag.starts_with("h") || normalized_tag.starts_with("/h") ||
-               normalized_tag.starts_with("li") || normalized_tag.starts_with("/li") ||
-               normalized_tag.starts_with("div") || normalized_tag.starts_with("/div") ||
-               normalized_tag.starts_with("br") {
+            let is_structural = normalized_tag.starts_with('p')
+                || normalized_tag.starts_with("/p")
+                || normalized_tag.starts_with('h')
+                || normalized_tag.starts_with("/h")
+                || normalized_tag.starts_with("li")
+                || normalized_tag.starts_with("/li")
+                || normalized_tag.starts_with("div")
+                || normalized_tag.starts_with("/div")
+                || normalized_tag.starts_with("br");
+            if is_structural && !out.is_empty() && !out.ends_with('\n') {
                 out.push('\n');
             }
         } else if in_tag {
@@ -337,25 +887,29 @@ fn strip_html_tags(crate_name: &str, version: &str, html: &str) -> String {
 fn extract_href(tag: &str) -> Option<String> {
     let tag_lower = tag.to_lowercase();
     let href_start = tag_lower.find("href=")?;
-    let mut val_part = &tag[href_start + 5..].trim_start();
+    let mut val_part = tag[href_start + 5..].trim_start();
     
     if val_part.starts_with('"') || val_part.starts_with('\'') {
         let quote = val_part.chars().next().unwrap();
         val_part = &val_part[1..];
         if let Some(end) = val_part.find(quote) {
             let href = &val_part[..end];
-            return Some(_href_to_absolute(href));
+            return Some(href_to_absolute(href));
         }
     } else {
         // Unquoted (less common but possible in messy HTML)
-        let end = val_part.find(|c| matches!(c, ' ' | '>')).unwrap_or(val_part.len());
+        let end = val_part.find([' ', '>']).unwrap_or(val_part.len());
         let href = &val_part[..end];
-        return Some(_href_to_absolute(href));
+        return Some(href_to_absolute(href));
     }
     None
 }
 
-fn _href_to_absolute(href: &str) -> String {
+fn is_own_docs_link(crate_name: &str, version: &str, abs_href: &str) -> bool {
+    abs_href.starts_with(&format!("https://docs.rs/{crate_name}/{version}/"))
+}
+
+fn href_to_absolute(href: &str) -> String {
     if href.starts_with("http") {
         href.to_string()
     } else if href.starts_with('/') {
@@ -372,7 +926,7 @@ fn clean_markdown_whitespace(s: &str) -> String {
         let trimmed = line.trim();
         if trimmed.is_empty() {
             if !last_was_empty {
-                out.push_str("\n");
+                out.push('\n');
                 last_was_empty = true;
             }
         } else {
@@ -384,6 +938,96 @@ fn clean_markdown_whitespace(s: &str) -> String {
     out.trim().to_string()
 }
 
+/// Rustdoc item kinds "API Reference" groups links by, in heading order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum DocsLinkKind {
+    Module,
+    Struct,
+    Enum,
+    Trait,
+    Function,
+    Macro,
+    Other,
+}
+
+impl DocsLinkKind {
+    const fn heading(self) -> &'static str {
+        match self {
+            Self::Module => "Modules",
+            Self::Struct => "Structs",
+            Self::Enum => "Enums",
+            Self::Trait => "Traits",
+            Self::Function => "Functions",
+            Self::Macro => "Macros",
+            Self::Other => "Other",
+        }
+    }
+}
+
+/// Links of the crate page, in page order, and how many to keep per kind.
+struct DocsLinks {
+    hrefs: Vec<String>,
+    max_per_kind: usize,
+}
+
+/// Kind and display name of a docs.rs link from the rustdoc file name:
+/// `/serde/1.0.0/serde/de/struct.Foo.html` is the struct `serde::de::Foo`,
+/// `.../serde/de/index.html` (or `.../serde/de/`) the module `serde::de`.
+fn classify_docs_link(crate_name: &str, version: &str, href: &str) -> (DocsLinkKind, String) {
+    let prefix = format!("/{crate_name}/{version}/");
+    let path = href.strip_prefix(&prefix).unwrap_or(href);
+    let (dir, file) = path.rsplit_once('/').unwrap_or(("", path));
+    let module_path = dir.replace('/', "::");
+
+    if file.is_empty() || file == "index.html" {
+        if module_path.is_empty() {
+            return (DocsLinkKind::Other, href.to_string());
+        }
+        return (DocsLinkKind::Module, module_path);
+    }
+    let Some((prefix, name)) = file
+        .strip_suffix(".html")
+        .and_then(|stem| stem.split_once('.'))
+    else {
+        return (DocsLinkKind::Other, href.to_string());
+    };
+    let kind = match prefix {
+        "struct" => DocsLinkKind::Struct,
+        "enum" => DocsLinkKind::Enum,
+        "trait" => DocsLinkKind::Trait,
+        "fn" => DocsLinkKind::Function,
+        "macro" | "derive" | "attr" => DocsLinkKind::Macro,
+        _ => return (DocsLinkKind::Other, href.to_string()),
+    };
+    let label = if module_path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{module_path}::{name}")
+    };
+    (kind, label)
+}
+
+/// `(label, href)` pairs per kind, in page order, at most
+/// `links.max_per_kind` each.
+fn group_docs_links(
+    crate_name: &str,
+    version: &str,
+    links: &DocsLinks,
+) -> BTreeMap<DocsLinkKind, Vec<(String, String)>> {
+    let mut groups: BTreeMap<DocsLinkKind, Vec<(String, String)>> = BTreeMap::new();
+    for href in &links.hrefs {
+        let (kind, label) = classify_docs_link(crate_name, version, href);
+        let group = groups.entry(kind).or_default();
+        if group.len() < links.max_per_kind {
+            group.push((label, href.clone()));
+        }
+    }
+    groups.retain(|_, items| !items.is_empty());
+    groups
+}
+
+/// Unique links into the crate's own rustdoc, in page order. Fragments are
+/// dropped, so `struct.Foo.html#method.bar` counts as `struct.Foo.html`.
 fn extract_docs_links(crate_name: &str, version: &str, html: &str) -> Vec<String> {
     let needle = format!("href=\"/{crate_name}/{version}/");
     let mut links = Vec::new();
@@ -394,7 +1038,7 @@ fn extract_docs_links(crate_name: &str, version: &str, html: &str) -> Vec<String
         let Some(end) = rest.find('"') else {
             break;
         };
-        let href = &rest[..end];
+        let href = rest[..end].split('#').next().unwrap_or_default();
         if !links.iter().any(|v| v == href) {
             links.push(href.to_string());
         }
@@ -406,9 +1050,142 @@ fn extract_docs_links(crate_name: &str, version: &str, html: &str) -> Vec<String
 #[cfg(test)]
 mod tests {
     use super::{
-        extract_docs_links, extract_title, is_docsrs_fallback_eligible,
+        classify_docs_link, extract_docs_links, extract_title, find_closing_div,
+        is_docsrs_fallback_eligible, latest_from_sparse_index, parse_crate_metadata,
+        parse_yanked_flag, sparse_index_path, CrateMetadata, DocsLinkKind, LatestDocsFetcher,
     };
     use crate::error::AiDocsError;
+    use crate::fetcher::HttpOptions;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Answers every request with the next `(status, body)` of `responses`
+    /// (the last one repeats); returns the base URL and a request counter.
+    fn start_sequence_server(responses: Vec<(u16, &'static str)>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let addr = listener.local_addr().expect("local addr");
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hits);
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0_u8; 4096];
+                if !matches!(stream.read(&mut buf), Ok(n) if n > 0) {
+                    continue;
+                }
+                let hit = counter.fetch_add(1, Ordering::SeqCst);
+                let (status, body) = responses[hit.min(responses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.1 {status} OK\r\nContent-Length: {}\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (format!("http://{addr}"), hits)
+    }
+
+    #[tokio::test]
+    async fn retries_undecodable_crates_io_body_then_resolves() {
+        let (base, hits) = start_sequence_server(vec![
+            (200, r#"{"crate":{"max_stable_ver"#),
+            (
+                200,
+                r#"{"crate":{"max_stable_version":"1.2.3","max_version":"1.3.0-rc.1"}}"#,
+            ),
+        ]);
+        let fetcher = LatestDocsFetcher::new(&HttpOptions {
+            registry_api_base: base,
+            ..HttpOptions::default()
+        });
+
+        let version = fetcher
+            .resolve_latest_version("demo", None)
+            .await
+            .expect("retry resolves version");
+        assert_eq!(version, "1.2.3");
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn crate_metadata_reuses_the_latest_version_response() {
+        let (base, hits) = start_sequence_server(vec![(
+            200,
+            r#"{"crate":{"max_stable_version":"1.2.3","categories":["encoding"],"keywords":["json"]}}"#,
+        )]);
+        let fetcher = LatestDocsFetcher::new(&HttpOptions {
+            registry_api_base: base,
+            ..HttpOptions::default()
+        });
+
+        fetcher
+            .resolve_latest_version("demo", None)
+            .await
+            .expect("resolve version");
+        let metadata = fetcher
+            .fetch_crate_metadata("demo")
+            .await
+            .expect("metadata");
+        assert_eq!(metadata.categories, ["encoding"]);
+        assert_eq!(metadata.keywords, ["json"]);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn registry_errors_payloads_and_404s_report_an_unknown_crate() {
+        for (status, body, detail) in [
+            (
+                200,
+                r#"{"errors":[{"detail":"crate `serdee` does not exist"}]}"#,
+                "crate `serdee` does not exist",
+            ),
+            (404, r#"{"errors":[{"detail":"Not Found"}]}"#, "Not Found"),
+            (404, "", "HTTP 404"),
+        ] {
+            let (base, hits) = start_sequence_server(vec![(status, body)]);
+            let fetcher = LatestDocsFetcher::new(&HttpOptions {
+                registry_api_base: base,
+                ..HttpOptions::default()
+            });
+
+            let err = fetcher
+                .resolve_latest_version("serdee", None)
+                .await
+                .expect_err("unknown crate");
+            match &err {
+                AiDocsError::CrateNotFound {
+                    name, detail: d, ..
+                } => {
+                    assert_eq!((name.as_str(), d.as_str()), ("serdee", detail));
+                }
+                other => panic!("unexpected error: {other}"),
+            }
+            assert!(err.to_string().contains("check the crate name"));
+            assert_eq!(hits.load(Ordering::SeqCst), 1, "not retried");
+        }
+
+        let (base, _) = start_sequence_server(vec![(
+            404,
+            r#"{"errors":[{"detail":"crate `serde` does not have a version `9.9.9`"}]}"#,
+        )]);
+        let fetcher = LatestDocsFetcher::new(&HttpOptions {
+            registry_api_base: base,
+            ..HttpOptions::default()
+        });
+        let err = fetcher
+            .is_version_yanked("serde", "9.9.9")
+            .await
+            .expect_err("unknown version");
+        assert!(
+            matches!(&err, AiDocsError::CrateVersionNotFound { name, version, .. } if name == "serde" && version == "9.9.9"),
+            "unexpected error: {err}"
+        );
+    }
 
     #[test]
     fn extracts_title() {
@@ -418,11 +1195,145 @@ mod tests {
 
     #[test]
     fn extracts_unique_docs_links() {
-        let html = r#"<a href="/serde/1.0.0/serde/">A</a><a href="/serde/1.0.0/serde/">B</a>"#;
+        let html = r#"<a href="/serde/1.0.0/serde/">A</a><a href="/serde/1.0.0/serde/">B</a><a href="/serde/1.0.0/serde/#modules">C</a>"#;
         let links = extract_docs_links("serde", "1.0.0", html);
         assert_eq!(links, vec!["/serde/1.0.0/serde/"]);
     }
 
+    #[test]
+    fn classifies_docs_links_by_rustdoc_kind() {
+        let classify = |href| classify_docs_link("demo", "1.0.0", href);
+        assert_eq!(
+            classify("/demo/1.0.0/demo/struct.Foo.html"),
+            (DocsLinkKind::Struct, "demo::Foo".to_string())
+        );
+        assert_eq!(
+            classify("/demo/1.0.0/demo/io/fn.bar.html"),
+            (DocsLinkKind::Function, "demo::io::bar".to_string())
+        );
+        assert_eq!(
+            classify("/demo/1.0.0/demo/io/index.html"),
+            (DocsLinkKind::Module, "demo::io".to_string())
+        );
+        let kind = |href| classify(href).0;
+        assert_eq!(kind("/demo/1.0.0/demo/macro.m.html"), DocsLinkKind::Macro);
+        assert_eq!(kind("/demo/1.0.0/demo/trait.T.html"), DocsLinkKind::Trait);
+        assert_eq!(kind("/demo/1.0.0/demo/type.T.html"), DocsLinkKind::Other);
+    }
+
+    #[test]
+    fn groups_docs_links_under_kind_headings_with_a_cap_per_kind() {
+        let links = super::DocsLinks {
+            hrefs: [
+                "/demo/1.0.0/demo/fn.a.html",
+                "/demo/1.0.0/demo/struct.S.html",
+                "/demo/1.0.0/demo/fn.b.html",
+                "/demo/1.0.0/demo/fn.c.html",
+            ]
+            .iter()
+            .map(|href| (*href).to_string())
+            .collect(),
+            max_per_kind: 2,
+        };
+        let markdown = super::render_docsrs_markdown("demo", "1.0.0", "", &links, None);
+        assert!(markdown.contains(
+            "### Structs\n\n- [demo::S](https://docs.rs/demo/1.0.0/demo/struct.S.html)\n\n\
+             ### Functions\n\n- [demo::a](https://docs.rs/demo/1.0.0/demo/fn.a.html)\n\
+             - [demo::b](https://docs.rs/demo/1.0.0/demo/fn.b.html)\n"
+        ));
+        assert!(!markdown.contains("fn.c.html"));
+    }
+
+    #[test]
+    fn builds_sparse_index_paths_by_name_length() {
+        assert_eq!(sparse_index_path("a").as_deref(), Some("1/a"));
+        assert_eq!(sparse_index_path("io").as_deref(), Some("2/io"));
+        assert_eq!(sparse_index_path("syn").as_deref(), Some("3/s/syn"));
+        assert_eq!(sparse_index_path("Serde").as_deref(), Some("se/rd/serde"));
+        assert_eq!(sparse_index_path("sérde"), None);
+        assert_eq!(sparse_index_path("日本"), None);
+        assert_eq!(sparse_index_path(""), None);
+    }
+
+    #[test]
+    fn find_closing_div_skips_quoted_attributes_comments_and_self_closing_divs() {
+        let html = r#"<div id="main-content"><div data-x="a>b" title='</div>'>inner</div><!-- </div> <div> --><div class="spacer" /><p>a < b</p></div><footer></footer>"#;
+        let end = find_closing_div(html).expect("closing div");
+        assert!(html[..end].ends_with("<p>a < b</p></div>"));
+        assert_eq!(&html[end..], "<footer></footer>");
+
+        assert_eq!(
+            find_closing_div(r#"<div class="docblock"><DIV>x</DIV>"#),
+            None
+        );
+        assert_eq!(find_closing_div(r#"<div title="unterminated>"#), None);
+    }
+
+    #[test]
+    fn picks_highest_non_yanked_stable_version_from_sparse_index() {
+        let body = r#"{"name":"demo","vers":"1.0.0","yanked":false}
+{"name":"demo","vers":"1.2.0","yanked":true}
+{"name":"demo","vers":"1.1.0","yanked":false}
+{"name":"demo","vers":"2.0.0-beta.1","yanked":false}
+{"name":"demo","vers":"0.9.9","yanked":false}
+"#;
+        assert_eq!(latest_from_sparse_index(body).as_deref(), Some("1.1.0"));
+
+        let pre_only = r#"{"name":"demo","vers":"0.1.0-alpha","yanked":false}"#;
+        assert_eq!(latest_from_sparse_index(pre_only).as_deref(), Some("0.1.0-alpha"));
+        assert_eq!(latest_from_sparse_index(""), None);
+    }
+
+    #[tokio::test]
+    async fn version_req_picks_greatest_matching_non_yanked_version() {
+        let versions = r#"{"versions":[{"num":"2.1.0","yanked":false},{"num":"1.9.0","yanked":true},{"num":"1.8.2","yanked":false},{"num":"1.10.0-rc.1","yanked":false},{"num":"0.9.0","yanked":false}]}"#;
+        let (base, _) = start_sequence_server(vec![(200, versions), (200, versions)]);
+        let fetcher = LatestDocsFetcher::new(&HttpOptions {
+            registry_api_base: base,
+            ..HttpOptions::default()
+        });
+
+        let req = semver::VersionReq::parse("1").expect("req");
+        let version = fetcher
+            .resolve_latest_version("demo", Some(&req))
+            .await
+            .expect("matching version");
+        assert_eq!(version, "1.8.2");
+
+        let req = semver::VersionReq::parse(">=3").expect("req");
+        let err = fetcher
+            .resolve_latest_version("demo", Some(&req))
+            .await
+            .expect_err("nothing matches");
+        assert!(err.to_string().contains("matches version_req `>=3`"));
+    }
+
+    #[test]
+    fn parses_yanked_flag_from_version_response() {
+        let yanked = r#"{"version":{"num":"1.0.0","yanked":true}}"#;
+        let live = r#"{"version":{"num":"1.0.1","yanked":false}}"#;
+        assert!(parse_yanked_flag(yanked).expect("parse yanked"));
+        assert!(!parse_yanked_flag(live).expect("parse live"));
+        assert!(parse_yanked_flag("{}").is_err());
+    }
+
+    #[test]
+    fn parses_categories_and_keywords_from_crate_response() {
+        let body = r#"{"crate":{"max_version":"1.0.0","categories":["encoding"],"keywords":["serde","json"]}}"#;
+        assert_eq!(
+            parse_crate_metadata(body).expect("parse metadata"),
+            CrateMetadata {
+                categories: vec!["encoding".to_string()],
+                keywords: vec!["serde".to_string(), "json".to_string()],
+            }
+        );
+        let bare = r#"{"crate":{"max_version":"1.0.0"}}"#;
+        assert_eq!(
+            parse_crate_metadata(bare).expect("parse bare"),
+            CrateMetadata::default()
+        );
+    }
+
     #[test]
     fn marks_fallback_eligible_statuses() {
         assert!(is_docsrs_fallback_eligible(&AiDocsError::HttpStatus {
@@ -478,6 +1389,37 @@ mod tests {
         assert!(content.contains("Item 2"));
     }
 
+    #[test]
+    fn source_readme_replaces_only_the_documentation_section() {
+        let html = r#"<title>demo - Rust</title><div id="main-content"><p>Rendered readme</p></div>"#;
+        let readme = super::SourceReadme {
+            markdown: "# demo\n\nRaw **markdown** readme\n".to_string(),
+            source_url: "https://raw.githubusercontent.com/o/demo/v1.0.0/README.md".to_string(),
+        };
+        let links = super::DocsLinks {
+            hrefs: Vec::new(),
+            max_per_kind: super::DEFAULT_MAX_LINKS_PER_KIND,
+        };
+        let markdown = super::render_docsrs_markdown("demo", "1.0.0", html, &links, Some(&readme));
+        assert!(markdown.contains("## Documentation\n\n# demo\n\nRaw **markdown** readme"));
+        assert!(markdown.contains(&format!("README markdown from {}", readme.source_url)));
+        assert!(!markdown.contains("Rendered readme"));
+        assert!(markdown.contains("## API Reference"));
+
+        let plain = super::render_docsrs_markdown("demo", "1.0.0", html, &links, None);
+        assert!(plain.contains("Rendered readme"));
+    }
+
+    #[test]
+    fn html_format_keeps_main_content_without_scripts_or_styles() {
+        let html = r#"<head><style>body{}</style></head><div id="main-content"><h1>demo</h1><SCRIPT type="module">alert(1)</SCRIPT><style>.x{}</style><noscript><style>.y{}</style>js off</noscript><p>Kept <a href="/demo/1.0.0/demo/">link</a></p><script src="x.js"/></div><script>tail()</script>"#;
+        let out = super::render_docsrs_html("demo", "1.0.0", html);
+        assert_eq!(
+            out,
+            "<!-- demo@1.0.0 from https://docs.rs/crate/demo/1.0.0 -->\n<div id=\"main-content\"><h1>demo</h1><p>Kept <a href=\"/demo/1.0.0/demo/\">link</a></p></div>\n"
+        );
+    }
+
     #[test]
     fn test_clean_markdown_whitespace() {
         let input = "Line 1\n\n\nLine 2\n   \nLine 3\n";
@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{AiDocsError, Result};
+use crate::fetcher::github::{pick_subpath, top_level_entries, FetchedFile, FileRequest};
+
+/// Repository files read from a local directory: a git checkout, or a
+/// workspace member synced from its own sources.
+#[derive(Debug, Clone)]
+pub struct LocalTree {
+    root: PathBuf,
+    /// Names the tree in not-found errors (a clone URL or a crate dir).
+    label: String,
+    /// Prepended to the matched path to form `FetchedFile::source_url`.
+    pub source_prefix: String,
+}
+
+impl LocalTree {
+    pub fn new(root: PathBuf, label: &str) -> Self {
+        Self {
+            source_prefix: format!("{label}/"),
+            root,
+            label: label.to_string(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Files under `dir` whose extension is in `extensions`, like
+    /// `GitHubFetcher::list_dir_files`. Returns sorted repo paths.
+    pub fn list_dir_files(&self, dir: &str, extensions: &[String]) -> Vec<String> {
+        let dir = dir.trim_matches('/');
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{dir}/")
+        };
+        let mut files: Vec<String> = self
+            .files()
+            .into_iter()
+            .filter(|path| path.starts_with(&prefix))
+            .filter(|path| {
+                Path::new(path)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// See `GitHubFetcher::infer_subpath`.
+    pub fn infer_subpath(&self, candidates: &[String]) -> Option<String> {
+        let files = self.files();
+        let blobs: Vec<&str> = files.iter().map(String::as_str).collect();
+        pick_subpath(&blobs, candidates)
+    }
+
+    /// See `GitHubFetcher::list_top_level`.
+    pub fn list_top_level(&self) -> Vec<String> {
+        let files = self.files();
+        let blobs: Vec<&str> = files.iter().map(String::as_str).collect();
+        top_level_entries(&blobs)
+    }
+
+    /// Reads each request's first existing candidate from the tree, with
+    /// the same errors as `GitHubFetcher::fetch_files`.
+    pub fn read_files(&self, requests: &[FileRequest]) -> Vec<Result<FetchedFile>> {
+        requests.iter().map(|req| self.read_file(req)).collect()
+    }
+
+    fn read_file(&self, req: &FileRequest) -> Result<FetchedFile> {
+        let canonical_root = self.root.canonicalize().ok();
+        for candidate in &req.candidates {
+            // Config paths must stay inside the tree.
+            let inside = Path::new(candidate)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+            if !inside {
+                continue;
+            }
+            // So must symlinks: a cloned repo could link `README.md` to any
+            // file of this machine.
+            let path = self.root.join(candidate);
+            let Ok(canonical) = path.canonicalize() else {
+                continue;
+            };
+            let in_tree = canonical_root
+                .as_ref()
+                .is_some_and(|root| canonical.starts_with(root));
+            if !in_tree || !canonical.is_file() {
+                continue;
+            }
+            let content = String::from_utf8_lossy(&fs::read(&path)?).into_owned();
+            return Ok(FetchedFile {
+                path: req.original_path.clone(),
+                matched_candidate: candidate.clone(),
+                source_url: format!("{}{candidate}", self.source_prefix),
+                content,
+            });
+        }
+
+        if req.required {
+            Err(AiDocsError::GitHubFileNotFound {
+                repo: self.label.clone(),
+                path: req.original_path.clone(),
+                tried_tags: req.candidates.clone(),
+            })
+        } else {
+            Err(AiDocsError::OptionalFileNotFound(req.original_path.clone()))
+        }
+    }
+
+    /// Every file of the tree as a `/`-separated repo path, `.git` excluded.
+    fn files(&self) -> Vec<String> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.file_name().is_some_and(|name| name == ".git") {
+                    continue;
+                }
+                // `file_type` does not follow symlinks, which are skipped: a
+                // link to an ancestor would make this walk endless.
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_symlink() {
+                    continue;
+                }
+                if file_type.is_dir() {
+                    pending.push(path);
+                } else if let Ok(relative) = path.strip_prefix(&self.root) {
+                    let parts: Vec<_> = relative
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect();
+                    files.push(parts.join("/"));
+                }
+            }
+        }
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalTree;
+    use crate::error::AiDocsError;
+    use crate::fetcher::github::FileRequest;
+    use std::fs;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_do_not_escape_or_loop_the_tree() {
+        use std::os::unix::fs::symlink;
+
+        let base = std::env::temp_dir().join(format!("aifd-local-links-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("repo");
+        fs::create_dir_all(root.join("docs")).expect("create tree");
+        fs::write(base.join("secret.txt"), "secret").expect("write outside file");
+        fs::write(root.join("docs/guide.md"), "# Guide").expect("write guide");
+        symlink(base.join("secret.txt"), root.join("README.md")).expect("link file");
+        symlink("..", root.join("docs/loop")).expect("link dir");
+        let tree = LocalTree::new(root, "demo");
+
+        let request = |path: &str| FileRequest {
+            original_path: path.to_string(),
+            candidates: vec![path.to_string()],
+            required: true,
+        };
+        let results = tree.read_files(&[request("README.md"), request("docs/loop/docs/guide.md")]);
+        assert!(matches!(
+            results[0],
+            Err(AiDocsError::GitHubFileNotFound { .. })
+        ));
+        assert_eq!(
+            results[1].as_ref().map(|file| file.content.as_str()).ok(),
+            Some("# Guide")
+        );
+        assert_eq!(
+            tree.list_dir_files("", &["md".to_string()]),
+            vec!["docs/guide.md"]
+        );
+
+        let _ = fs::remove_dir_all(&base);
+    }
+}
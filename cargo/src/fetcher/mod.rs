@@ -1,3 +1,270 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use reqwest::{ClientBuilder, NoProxy, Proxy};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config::{Settings, DEFAULT_REGISTRY_API_BASE};
+use crate::error::{AiDocsError, Result};
+
+pub mod git;
 pub mod github;
+pub mod local;
 
 pub mod latest;
+
+pub const APP_USER_AGENT: &str = concat!("cargo-ai-fdocs/", env!("CARGO_PKG_VERSION"));
+
+/// Redirects followed per request; one more (e.g. a loop) fails the request.
+pub const MAX_REDIRECTS: usize = 5;
+
+/// HTTP client options shared by every fetcher.
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    pub timeout: Duration,
+    pub http_proxy: Option<String>,
+    pub https_proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    /// `settings.extra_headers`, sent only with raw file requests (under any
+    /// per-crate `headers`), never to API, registry or docs.rs hosts.
+    pub extra_headers: HeaderMap,
+    /// `settings.github_token_file`, read by `GitHubFetcher::new`.
+    pub github_token_file: Option<PathBuf>,
+    /// `settings.registry_api_base`, used by `LatestDocsFetcher`.
+    pub registry_api_base: String,
+    /// `settings.github_concurrency`, enforced by `GitHubFetcher`.
+    pub github_concurrency: Option<usize>,
+    /// `settings.docsrs_concurrency`, enforced by `LatestDocsFetcher`.
+    pub docsrs_concurrency: Option<usize>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            http_proxy: None,
+            https_proxy: None,
+            no_proxy: None,
+            extra_headers: HeaderMap::new(),
+            github_token_file: None,
+            registry_api_base: DEFAULT_REGISTRY_API_BASE.to_string(),
+            github_concurrency: None,
+            docsrs_concurrency: None,
+        }
+    }
+}
+
+impl HttpOptions {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            timeout: Duration::from_secs(settings.request_timeout_secs),
+            http_proxy: settings.http_proxy.clone(),
+            https_proxy: settings.https_proxy.clone(),
+            no_proxy: settings.no_proxy.clone(),
+            extra_headers: header_map(&settings.extra_headers, "settings.extra_headers")
+                .unwrap_or_default(),
+            github_token_file: settings.github_token_file.clone(),
+            registry_api_base: settings.registry_api_base.clone(),
+            github_concurrency: settings.github_concurrency,
+            docsrs_concurrency: settings.docsrs_concurrency,
+        }
+    }
+
+    /// Base `reqwest` builder with the common user agent, timeout, redirect cap
+    /// and proxies applied. Fetchers may add their own default headers on top;
+    /// `extra_headers` are not installed here, so they only go where a fetcher
+    /// sends them explicitly.
+    ///
+    /// Configuring any explicit proxy disables reqwest's env-based proxy detection.
+    /// Proxy URLs are validated at config load, so invalid ones are skipped here.
+    pub fn client_builder(&self) -> ClientBuilder {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(APP_USER_AGENT)
+            .timeout(self.timeout)
+            .redirect(Policy::limited(MAX_REDIRECTS));
+
+        let no_proxy = || self.no_proxy.as_deref().and_then(NoProxy::from_string);
+        if let Some(url) = self.http_proxy.as_deref() {
+            if let Ok(proxy) = Proxy::http(url) {
+                builder = builder.proxy(proxy.no_proxy(no_proxy()));
+            }
+        }
+        if let Some(url) = self.https_proxy.as_deref() {
+            if let Ok(proxy) = Proxy::https(url) {
+                builder = builder.proxy(proxy.no_proxy(no_proxy()));
+            }
+        }
+
+        builder
+    }
+}
+
+/// Cap on in-flight requests to one host, shared by every clone of a fetcher
+/// (and so by all crate workers). Without a limit, requests are only bounded
+/// by `settings.sync_concurrency`.
+#[derive(Debug, Clone, Default)]
+pub struct HostLimit(Option<Arc<Semaphore>>);
+
+impl HostLimit {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self(limit.map(|limit| Arc::new(Semaphore::new(limit.max(1)))))
+    }
+
+    /// Waits for a free slot; keep the permit while the request is in flight.
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.0 {
+            Some(semaphore) => Arc::clone(semaphore).acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+}
+
+/// Redirect policy error for a hop to another host; raised before that
+/// request is sent.
+#[derive(Debug)]
+struct OffHostRedirect(String);
+
+impl std::fmt::Display for OffHostRedirect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "redirect to another host: {}", self.0)
+    }
+}
+
+impl std::error::Error for OffHostRedirect {}
+
+/// `Policy::limited(MAX_REDIRECTS)` that also stops at a redirect away from
+/// the original host, so an auth wall or login page is never requested (with
+/// any custom headers) or saved as documentation.
+pub fn same_host_policy() -> Policy {
+    Policy::custom(|attempt| {
+        let origin = attempt.previous().first().and_then(reqwest::Url::host_str);
+        if origin.is_some() && attempt.url().host_str() != origin {
+            let target = attempt.url().to_string();
+            attempt.error(OffHostRedirect(target))
+        } else if attempt.previous().len() > MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// Maps a reqwest redirect-policy failure to `RedirectedOffHost` or
+/// `TooManyRedirects`.
+pub fn redirect_error(url: &str, source: &reqwest::Error) -> Option<AiDocsError> {
+    if !source.is_redirect() {
+        return None;
+    }
+    let mut cause = std::error::Error::source(source);
+    while let Some(error) = cause {
+        if let Some(OffHostRedirect(final_url)) = error.downcast_ref() {
+            return Some(AiDocsError::RedirectedOffHost {
+                url: url.to_string(),
+                final_url: final_url.clone(),
+            });
+        }
+        cause = error.source();
+    }
+    Some(AiDocsError::TooManyRedirects {
+        url: url.to_string(),
+        max: MAX_REDIRECTS,
+    })
+}
+
+/// Parses configured headers; every value is marked sensitive. `origin` names
+/// the config key in error messages.
+pub fn header_map(headers: &HashMap<String, String>, origin: &str) -> Result<HeaderMap> {
+    let mut map = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+            AiDocsError::InvalidConfig(format!("{origin} has invalid header name `{name}`"))
+        })?;
+        let mut header_value = HeaderValue::from_str(value).map_err(|_| {
+            AiDocsError::InvalidConfig(format!("{origin} has invalid value for header `{name}`"))
+        })?;
+        header_value.set_sensitive(true);
+        map.insert(header_name, header_value);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{header_map, HostLimit, HttpOptions};
+
+    #[test]
+    fn client_builds_with_explicit_proxies() {
+        let http = HttpOptions {
+            http_proxy: Some("http://proxy.internal:3128".to_string()),
+            https_proxy: Some("http://proxy.internal:3128".to_string()),
+            no_proxy: Some("localhost,127.0.0.1,.internal".to_string()),
+            ..HttpOptions::default()
+        };
+
+        assert!(http.client_builder().build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn base_client_never_sends_extra_headers() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("local addr");
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut buf = [0_u8; 4096];
+            let read = stream.read(&mut buf).expect("read request");
+            let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n");
+            String::from_utf8_lossy(&buf[..read]).to_ascii_lowercase()
+        });
+
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), "Bearer secret".to_string());
+        let http = HttpOptions {
+            extra_headers: header_map(&headers, "settings.extra_headers").expect("valid headers"),
+            ..HttpOptions::default()
+        };
+        let client = http.client_builder().no_proxy().build().expect("client");
+        client
+            .get(format!("http://{addr}/"))
+            .send()
+            .await
+            .expect("request");
+
+        let request = server.join().expect("server thread");
+        assert!(!request.contains("authorization"), "{request}");
+    }
+
+    #[test]
+    fn header_map_marks_values_sensitive() {
+        let mut headers = HashMap::new();
+        headers.insert("PRIVATE-TOKEN".to_string(), "secret".to_string());
+
+        let map = header_map(&headers, "settings.extra_headers").expect("valid headers");
+        let value = map.get("private-token").expect("header present");
+        assert!(value.is_sensitive());
+        assert!(!format!("{map:?}").contains("secret"));
+
+        headers.insert("X-Bad".to_string(), "line\nbreak".to_string());
+        assert!(header_map(&headers, "settings.extra_headers").is_err());
+    }
+
+    #[tokio::test]
+    async fn host_limit_is_shared_by_clones() {
+        let limit = HostLimit::new(Some(1));
+        let held = limit.acquire().await.expect("first permit");
+        let clone = limit.clone();
+        let waiting = tokio::time::timeout(std::time::Duration::from_millis(50), clone.acquire());
+        assert!(waiting.await.is_err(), "second request must wait");
+        drop(held);
+        assert!(clone.acquire().await.is_some());
+
+        assert!(HostLimit::default().acquire().await.is_none());
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::error::Result;
+
+pub const HISTORY_FILE: &str = ".aifd-history.jsonl";
+
+/// Oldest entries are dropped once a crate's history exceeds this length.
+pub const HISTORY_MAX_ENTRIES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: String,
+    pub version: String,
+    pub git_ref: String,
+    pub source_kind: String,
+    pub is_fallback: bool,
+}
+
+impl HistoryEntry {
+    pub fn now(version: &str, git_ref: &str, source_kind: &str, is_fallback: bool) -> Self {
+        Self {
+            timestamp: crate::utils::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            version: version.to_string(),
+            git_ref: git_ref.to_string(),
+            source_kind: source_kind.to_string(),
+            is_fallback,
+        }
+    }
+}
+
+/// Reads a history file, skipping lines that no longer parse.
+pub fn read_history(crate_dir: &Path) -> Vec<HistoryEntry> {
+    let Ok(raw) = fs::read_to_string(crate_dir.join(HISTORY_FILE)) else {
+        return Vec::new();
+    };
+
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                debug!("Skipping unreadable history line in {:?}: {e}", crate_dir);
+                None
+            }
+        })
+        .collect()
+}
+
+/// History to carry into a fresh `crate@version` dir: the dir's own history when
+/// it exists, otherwise the most recently updated history among the crate's
+/// other version dirs (so version bumps keep their past).
+pub fn previous_history(output_dir: &Path, crate_name: &str, version: &str) -> Vec<HistoryEntry> {
+    let own = read_history(&output_dir.join(format!("{crate_name}@{version}")));
+    if !own.is_empty() {
+        return own;
+    }
+
+    let Ok(entries) = fs::read_dir(output_dir) else {
+        return Vec::new();
+    };
+
+    let prefix = format!("{crate_name}@");
+    entries
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix(&prefix))
+                .is_some_and(|v| !v.is_empty() && !v.contains('@'))
+        })
+        .map(|e| read_history(&e.path()))
+        .filter(|history| !history.is_empty())
+        .max_by(|a, b| {
+            a.last()
+                .map(|e| &e.timestamp)
+                .cmp(&b.last().map(|e| &e.timestamp))
+        })
+        .unwrap_or_default()
+}
+
+/// Appends `entry` to `history`, keeps the last `HISTORY_MAX_ENTRIES` and
+/// rewrites the file in `crate_dir`.
+pub fn write_history(
+    crate_dir: &Path,
+    mut history: Vec<HistoryEntry>,
+    entry: HistoryEntry,
+) -> Result<()> {
+    history.push(entry);
+    let skip = history.len().saturating_sub(HISTORY_MAX_ENTRIES);
+
+    let mut content = String::new();
+    for entry in &history[skip..] {
+        if let Ok(line) = serde_json::to_string(entry) {
+            content.push_str(&line);
+            content.push('\n');
+        }
+    }
+
+    fs::write(crate_dir.join(HISTORY_FILE), content)?;
+    Ok(())
+}
+
+pub fn print_history_table(histories: &[(String, Vec<HistoryEntry>)]) {
+    print!("{}", format_history_table(histories));
+}
+
+pub fn format_history_json(
+    histories: &[(String, Vec<HistoryEntry>)],
+) -> std::result::Result<String, serde_json::Error> {
+    let map: std::collections::BTreeMap<&str, &[HistoryEntry]> = histories
+        .iter()
+        .map(|(name, entries)| (name.as_str(), entries.as_slice()))
+        .collect();
+    serde_json::to_string_pretty(&map)
+}
+
+fn format_history_table(histories: &[(String, Vec<HistoryEntry>)]) -> String {
+    const COL_CRATE: usize = 24;
+    const COL_TIME: usize = 22;
+    const COL_VERSION: usize = 16;
+    const COL_SOURCE: usize = 22;
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "{:<COL_CRATE$} {:<COL_TIME$} {:<COL_VERSION$} {:<COL_SOURCE$} Ref",
+        "Crate", "Synced At", "Docs Version", "Source"
+    );
+    let _ = writeln!(
+        output,
+        "{:-<COL_CRATE$} {:-<COL_TIME$} {:-<COL_VERSION$} {:-<COL_SOURCE$} {:-<3}",
+        "", "", "", "", ""
+    );
+
+    for (crate_name, entries) in histories {
+        if entries.is_empty() {
+            let _ = writeln!(output, "{crate_name:<COL_CRATE$} (no history recorded)");
+            continue;
+        }
+        for entry in entries.iter().rev() {
+            let git_ref = if entry.is_fallback {
+                format!("{} (fallback)", entry.git_ref)
+            } else {
+                entry.git_ref.clone()
+            };
+            let _ = writeln!(
+                output,
+                "{:<COL_CRATE$} {:<COL_TIME$} {:<COL_VERSION$} {:<COL_SOURCE$} {}",
+                crate_name, entry.timestamp, entry.version, entry.source_kind, git_ref
+            );
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_history_table, previous_history, read_history, write_history, HistoryEntry,
+        HISTORY_MAX_ENTRIES,
+    };
+    use std::fs;
+
+    fn entry(timestamp: &str, version: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: timestamp.to_string(),
+            version: version.to_string(),
+            git_ref: format!("v{version}"),
+            source_kind: "github".to_string(),
+            is_fallback: false,
+        }
+    }
+
+    #[test]
+    fn history_is_bounded_and_carried_over_from_previous_version_dir() {
+        let tmp = std::env::temp_dir().join(format!("aifd-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        let old_dir = tmp.join("serde@1.0.0");
+        fs::create_dir_all(&old_dir).expect("create old dir");
+
+        let mut history = Vec::new();
+        for i in 0..HISTORY_MAX_ENTRIES {
+            write_history(
+                &old_dir,
+                history,
+                entry(&format!("2026-01-{:02}T00:00:00Z", i + 1), "1.0.0"),
+            )
+            .expect("write history");
+            history = read_history(&old_dir);
+        }
+        write_history(&old_dir, history, entry("2026-02-01T00:00:00Z", "1.0.0")).expect("write");
+
+        let history = read_history(&old_dir);
+        assert_eq!(history.len(), HISTORY_MAX_ENTRIES);
+        assert_eq!(history[0].timestamp, "2026-01-02T00:00:00Z");
+
+        let carried = previous_history(&tmp, "serde", "1.0.1");
+        assert_eq!(carried, history);
+        assert!(previous_history(&tmp, "serde_json", "1.0.0").is_empty());
+
+        let table = format_history_table(&[("serde".to_string(), carried)]);
+        assert!(table
+            .lines()
+            .nth(2)
+            .is_some_and(|l| l.contains("2026-02-01T00:00:00Z")));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -1,13 +1,82 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
-use chrono::Utc;
+use serde::Serialize;
 
-use crate::error::Result;
-use crate::storage::SavedCrate;
+use crate::config::Config;
+use crate::error::{AiDocsError, Result};
+use crate::status::scan_existing_dirs;
+use crate::storage::{self, SavedCrate};
 
+/// Rebuilds index entries from existing `name@version` dirs and their
+/// `.aifd-meta.toml` (no network). Only configured crates are included, like `sync`.
+pub fn collect_saved_crates(output_dir: &Path, config: &Config) -> Vec<SavedCrate> {
+    let mut saved: Vec<SavedCrate> = scan_existing_dirs(output_dir)
+        .into_iter()
+        .filter_map(|(crate_name, (version, _))| {
+            let crate_doc = config.crates.get(&crate_name)?;
+            storage::read_cached_info(output_dir, &crate_name, &version, crate_doc)
+        })
+        .collect();
+    saved.sort_by(|a, b| a.name.cmp(&b.name));
+    saved
+}
+
+/// Index entries for a run: crates saved by this run plus the rest found on
+/// disk, so filtered or partial syncs keep crates they did not touch. One
+/// entry per crate; this run's entry wins over the on-disk one.
+pub fn merge_saved_crates(
+    output_dir: &Path,
+    config: &Config,
+    current: Vec<SavedCrate>,
+) -> Vec<SavedCrate> {
+    let mut merged: BTreeMap<String, SavedCrate> = BTreeMap::new();
+    for saved in current {
+        match merged.entry(saved.name.clone()) {
+            Entry::Vacant(slot) => {
+                slot.insert(saved);
+            }
+            Entry::Occupied(mut slot) => {
+                if crate::utils::is_version_better(&saved.version, Some(&slot.get().version)) {
+                    slot.insert(saved);
+                }
+            }
+        }
+    }
+    for saved in collect_saved_crates(output_dir, config) {
+        merged.entry(saved.name.clone()).or_insert(saved);
+    }
+    merged.into_values().collect()
+}
+
+/// `_INDEX.json`: the `_INDEX.md` entries for tools.
+#[derive(Serialize)]
+struct JsonIndex<'a> {
+    generated: &'a str,
+    crates: Vec<JsonIndexEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonIndexEntry<'a> {
+    name: &'a str,
+    version: &'a str,
+    git_ref: &'a str,
+    is_fallback: bool,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    ai_notes: &'a str,
+    /// Paths relative to the output dir.
+    files: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    categories: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<String>,
+}
+
+/// Writes `_INDEX.md` and `_INDEX.json` for `crates`.
 pub fn generate_index(output_dir: &Path, crates: &[SavedCrate]) -> Result<()> {
-    let date = Utc::now().format("%Y-%m-%d").to_string();
+    let date = crate::utils::now().format("%Y-%m-%d").to_string();
     let mut sorted = crates.to_vec();
     sorted.sort_by(|a, b| {
         a.name.cmp(&b.name).then_with(|| {
@@ -24,6 +93,7 @@ pub fn generate_index(output_dir: &Path, crates: &[SavedCrate]) -> Result<()> {
     let fallback_count = sorted.iter().filter(|c| c.is_fallback).count();
     let total_files: usize = sorted.iter().map(|c| c.files.len()).sum();
 
+    let mut json_crates = Vec::with_capacity(sorted.len());
     let mut content = String::new();
     content.push_str("<!-- This file is auto-generated by ai-fdocs. Do not edit manually. -->\n");
     content.push_str(&format!("<!-- Generated: {date} -->\n\n"));
@@ -65,6 +135,17 @@ pub fn generate_index(output_dir: &Path, crates: &[SavedCrate]) -> Result<()> {
                 content.push_str(&format!("**AI Notes:** {}\n\n", saved.ai_notes.trim()));
             }
 
+            let (categories, keywords) =
+                storage::read_meta(output_dir, &saved.name, &saved.version)
+                    .map(|meta| (meta.categories, meta.keywords))
+                    .unwrap_or_default();
+            if !categories.is_empty() {
+                content.push_str(&format!("**Categories:** {}\n\n", categories.join(", ")));
+            }
+            if !keywords.is_empty() {
+                content.push_str(&format!("**Keywords:** {}\n\n", keywords.join(", ")));
+            }
+
             if saved.files.is_empty() {
                 content.push_str("**Files:** _No files saved._\n\n");
             } else {
@@ -75,11 +156,33 @@ pub fn generate_index(output_dir: &Path, crates: &[SavedCrate]) -> Result<()> {
                     .collect();
                 content.push_str(&format!("**Files:** {}\n\n", file_links.join(", ")));
             }
+
+            json_crates.push(JsonIndexEntry {
+                name: &saved.name,
+                version: &saved.version,
+                git_ref: &saved.git_ref,
+                is_fallback: saved.is_fallback,
+                ai_notes: saved.ai_notes.trim(),
+                files: saved
+                    .files
+                    .iter()
+                    .map(|f| format!("{}@{}/{f}", saved.name, saved.version))
+                    .collect(),
+                categories,
+                keywords,
+            });
         }
     }
 
+    let json = serde_json::to_string_pretty(&JsonIndex {
+        generated: &date,
+        crates: json_crates,
+    })
+    .map_err(|e| AiDocsError::Other(format!("failed to serialize _INDEX.json: {e}")))?;
+
     fs::create_dir_all(output_dir)?;
     fs::write(output_dir.join("_INDEX.md"), content)?;
+    fs::write(output_dir.join("_INDEX.json"), json)?;
 
     Ok(())
 }
@@ -107,8 +210,112 @@ fn section_id(saved: &SavedCrate) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::section_id;
+    use super::{collect_saved_crates, generate_index, merge_saved_crates, section_id};
+    use crate::config::{Config, CrateDoc, Settings};
     use crate::storage::SavedCrate;
+    use std::collections::HashMap;
+    use std::fs;
+
+    #[test]
+    fn rebuilds_index_from_existing_dirs_with_current_ai_notes() {
+        let tmp = std::env::temp_dir().join(format!("aifd-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("serde@1.0.0")).expect("create serde dir");
+        fs::create_dir_all(tmp.join("stale@0.1.0")).expect("create stale dir");
+        fs::write(
+            tmp.join("serde@1.0.0/.aifd-meta.toml"),
+            "schema_version = 2\nversion = \"1.0.0\"\ngit_ref = \"v1.0.0\"\nfetched_at = \"2026-01-01\"\nis_fallback = false\nkeywords = [\"serialization\", \"derive\"]\n",
+        )
+        .expect("write meta");
+        fs::write(tmp.join("serde@1.0.0/README.md"), "# serde").expect("write readme");
+        fs::write(tmp.join("serde@1.0.0/_SUMMARY.md"), "summary").expect("write summary");
+
+        let mut crates = HashMap::new();
+        crates.insert(
+            "serde".to_string(),
+            CrateDoc {
+                ai_notes: "Prefer derive macros.".to_string(),
+                ..CrateDoc::default()
+            },
+        );
+        let config = Config {
+            settings: Settings::default(),
+            crates,
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+
+        let saved = collect_saved_crates(&tmp, &config);
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].git_ref, "v1.0.0");
+        assert_eq!(saved[0].files, vec!["README.md".to_string()]);
+
+        generate_index(&tmp, &saved).expect("generate index");
+        let index = fs::read_to_string(tmp.join("_INDEX.md")).expect("read index");
+        assert!(index.contains("**AI Notes:** Prefer derive macros."));
+        assert!(index.contains("**Keywords:** serialization, derive"));
+        assert!(!index.contains("**Categories:**"));
+        assert!(!index.contains("stale@0.1.0"));
+
+        let json: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(tmp.join("_INDEX.json")).expect("read json index"),
+        )
+        .expect("parse json index");
+        let serde = &json["crates"][0];
+        assert_eq!(serde["name"], "serde");
+        assert_eq!(serde["git_ref"], "v1.0.0");
+        assert_eq!(serde["ai_notes"], "Prefer derive macros.");
+        assert_eq!(serde["files"][0], "serde@1.0.0/README.md");
+        assert_eq!(serde["keywords"][1], "derive");
+        assert!(serde.get("categories").is_none());
+        assert_eq!(json["crates"].as_array().map(Vec::len), Some(1));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn merge_keeps_untouched_on_disk_crates_and_prefers_this_run() {
+        let tmp = std::env::temp_dir().join(format!("aifd-index-merge-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        for dir in ["serde@1.0.0", "tokio@1.0.0"] {
+            fs::create_dir_all(tmp.join(dir)).expect("create crate dir");
+            fs::write(
+                tmp.join(dir).join(".aifd-meta.toml"),
+                "schema_version = 2\nversion = \"1.0.0\"\ngit_ref = \"v1.0.0\"\nfetched_at = \"2026-01-01\"\nis_fallback = false\n",
+            )
+            .expect("write meta");
+            fs::write(tmp.join(dir).join("README.md"), "# readme").expect("write readme");
+        }
+
+        let mut crates = HashMap::new();
+        crates.insert("serde".to_string(), CrateDoc::default());
+        crates.insert("tokio".to_string(), CrateDoc::default());
+        let config = Config {
+            settings: Settings::default(),
+            crates,
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+        let fresh = |version: &str| SavedCrate {
+            name: "serde".to_string(),
+            version: version.to_string(),
+            git_ref: format!("v{version}"),
+            is_fallback: false,
+            files: vec!["README.md".to_string()],
+            ai_notes: String::new(),
+        };
+
+        let merged = merge_saved_crates(&tmp, &config, vec![fresh("1.0.5"), fresh("1.1.0")]);
+        let entries: Vec<_> = merged
+            .iter()
+            .map(|c| (c.name.as_str(), c.version.as_str()))
+            .collect();
+        assert_eq!(entries, vec![("serde", "1.1.0"), ("tokio", "1.0.0")]);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
 
     #[test]
     fn section_id_normalizes_crate_name_and_version() {
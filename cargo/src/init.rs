@@ -1,13 +1,317 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use clap::ValueEnum;
 use serde::Deserialize;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use toml::Value;
-use tracing::warn;
+use tracing::{info, warn};
 
+use crate::config::DEFAULT_REGISTRY_API_BASE;
 use crate::error::{AiDocsError, Result};
+use crate::fetcher::github::{subpath_candidates, GitHubFetcher};
+use crate::fetcher::HttpOptions;
 
-pub async fn run_init(config_path: &Path, force: bool) -> Result<()> {
+/// Style of the generated `ai-fdocs.toml` (`init --template`).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum InitTemplate {
+    /// Only settings that differ from the defaults.
+    Minimal,
+    /// Every setting with its default and a comment explaining it.
+    Full,
+    /// `sync_mode = "latest_docs"` and bare crate tables (no repo lookups).
+    LatestDocs,
+}
+
+/// Default of one `[settings]` key as generated by `init`.
+enum FieldValue {
+    /// TOML literal of the default.
+    Default(&'static str),
+    /// Unset by default; `full` shows this example commented out.
+    Unset(&'static str),
+}
+
+struct SettingField {
+    key: &'static str,
+    value: FieldValue,
+    doc: &'static str,
+}
+
+/// Every `[settings]` key, shared by all templates. Keep in sync with
+/// `config::Settings` (the `full` template is tested to load as the defaults).
+const SETTING_FIELDS: &[SettingField] = &[
+    SettingField {
+        key: "output_dir",
+        value: FieldValue::Default("\"fdocs\""),
+        doc: "Where synced docs are written (Rust docs go to `<output_dir>/rust`).",
+    },
+    SettingField {
+        key: "max_file_size_kb",
+        value: FieldValue::Default("200"),
+        doc: "Files larger than this are truncated.",
+    },
+    SettingField {
+        key: "max_tokens_per_crate",
+        value: FieldValue::Unset("50000"),
+        doc: "Cap on estimated tokens per crate; changelogs are cut first (unset means unlimited).",
+    },
+    SettingField {
+        key: "prune",
+        value: FieldValue::Default("true"),
+        doc: "Remove docs of crates or versions no longer in the config/lockfile.",
+    },
+    SettingField {
+        key: "prune_unconfigured_only",
+        value: FieldValue::Default("false"),
+        doc: "Prune only crates dropped from the config; keep other versions.",
+    },
+    SettingField {
+        key: "status_cache",
+        value: FieldValue::Default("false"),
+        doc: "Cache status/check output dir scans in .aifd-status-cache.json.",
+    },
+    SettingField {
+        key: "sync_concurrency",
+        value: FieldValue::Default("8"),
+        doc: "Crates synced in parallel.",
+    },
+    SettingField {
+        key: "github_concurrency",
+        value: FieldValue::Unset("4"),
+        doc: "Cap on parallel GitHub requests (unset means `sync_concurrency`).",
+    },
+    SettingField {
+        key: "docsrs_concurrency",
+        value: FieldValue::Unset("16"),
+        doc: "Cap on parallel docs.rs requests (unset means `sync_concurrency`).",
+    },
+    SettingField {
+        key: "docs_source",
+        value: FieldValue::Default("\"github\""),
+        doc: "How repository files are fetched: \"github\" (API + raw) or \"git\" (shallow clone).",
+    },
+    SettingField {
+        key: "sync_mode",
+        value: FieldValue::Default("\"lockfile\""),
+        doc: "\"lockfile\" (Cargo.lock versions), \"latest_docs\" (docs.rs, beta) or \"hybrid\".",
+    },
+    SettingField {
+        key: "latest_ttl_hours",
+        value: FieldValue::Default("24"),
+        doc: "Latest-docs mode: how long synced docs count as fresh.",
+    },
+    SettingField {
+        key: "docsrs_single_page",
+        value: FieldValue::Default("true"),
+        doc: "Latest-docs mode: save the docs.rs crate page as a single API.md.",
+    },
+    SettingField {
+        key: "docsrs_max_links",
+        value: FieldValue::Default("20"),
+        doc: "Latest-docs mode: API Reference links listed per kind (module, struct, …).",
+    },
+    SettingField {
+        key: "request_timeout_secs",
+        value: FieldValue::Default("30"),
+        doc: "Timeout of every HTTP request.",
+    },
+    SettingField {
+        key: "crates_io_sparse_index",
+        value: FieldValue::Default("false"),
+        doc: "Resolve latest versions from index.crates.io before the crates.io API.",
+    },
+    SettingField {
+        key: "registry_api_base",
+        value: FieldValue::Default("\"https://crates.io/api/v1\""),
+        doc: "crates.io-compatible registry API used for version lookups.",
+    },
+    SettingField {
+        key: "http_proxy",
+        value: FieldValue::Unset("\"http://proxy.example.com:8080\""),
+        doc: "Proxy for http:// requests (HTTP_PROXY/HTTPS_PROXY apply when no proxy is set).",
+    },
+    SettingField {
+        key: "https_proxy",
+        value: FieldValue::Unset("\"http://proxy.example.com:8080\""),
+        doc: "Proxy for https:// requests.",
+    },
+    SettingField {
+        key: "no_proxy",
+        value: FieldValue::Unset("\"localhost,.internal\""),
+        doc: "Comma-separated hosts that bypass the proxies.",
+    },
+    SettingField {
+        key: "fail_fast",
+        value: FieldValue::Default("false"),
+        doc: "Abort remaining crates after the first failure.",
+    },
+    SettingField {
+        key: "fallback_policy",
+        value: FieldValue::Default("\"warn\""),
+        doc: "When no version tag matches: \"allow\", \"warn\" or \"error\" on using a branch.",
+    },
+    SettingField {
+        key: "strip_frontmatter",
+        value: FieldValue::Default("false"),
+        doc: "Remove leading YAML front matter from saved markdown.",
+    },
+    SettingField {
+        key: "strip_badges",
+        value: FieldValue::Default("false"),
+        doc: "Remove rows of shields.io badges from saved markdown.",
+    },
+    SettingField {
+        key: "normalize_line_endings",
+        value: FieldValue::Default("false"),
+        doc: "Convert CRLF to LF and strip trailing whitespace outside code fences.",
+    },
+    SettingField {
+        key: "inject_header",
+        value: FieldValue::Default("true"),
+        doc: "Prefix saved text files with the AI-FDOCS provenance header.",
+    },
+    SettingField {
+        key: "inject_header_extensions",
+        value: FieldValue::Default(
+            "[\"md\", \"mdx\", \"markdown\", \"html\", \"htm\", \"txt\", \"rst\", \"adoc\"]",
+        ),
+        doc: "Extensions of saved files that get the AI-FDOCS provenance header.",
+    },
+    SettingField {
+        key: "default_files",
+        value: FieldValue::Default("[\"README.md\", \"CHANGELOG.md\"]"),
+        doc: "Files fetched for crates without explicit `files`.",
+    },
+    SettingField {
+        key: "changelog_candidates",
+        value: FieldValue::Default(
+            "[\"CHANGELOG.md\", \"CHANGES.md\", \"HISTORY.md\", \"RELEASES.md\", \"docs/CHANGELOG.md\", \"docs/CHANGES.md\"]",
+        ),
+        doc: "Tried in order for the CHANGELOG.md entry of default_files.",
+    },
+    SettingField {
+        key: "include_readme_only",
+        value: FieldValue::Default("false"),
+        doc: "Only fetch the README entries of default_files (no changelog).",
+    },
+    SettingField {
+        key: "include_extensions",
+        value: FieldValue::Default("[\"md\", \"mdx\", \"markdown\", \"txt\", \"rst\", \"adoc\"]"),
+        doc: "Extensions of repository files that are saved (md/mdx always are).",
+    },
+    SettingField {
+        key: "embed_ai_notes",
+        value: FieldValue::Default("false"),
+        doc: "Prepend each crate's ai_notes to every saved markdown file.",
+    },
+    SettingField {
+        key: "resolve_includes",
+        value: FieldValue::Default("false"),
+        doc: "Inline mdBook {{#include path}} directives from the same repo and ref.",
+    },
+    SettingField {
+        key: "fetch_crate_metadata",
+        value: FieldValue::Default("false"),
+        doc: "Latest-docs mode: also store crates.io categories and keywords.",
+    },
+    SettingField {
+        key: "min_github_token_scopes",
+        value: FieldValue::Default("[]"),
+        doc: "Token scopes checked at sync start; missing ones are warned about.",
+    },
+    SettingField {
+        key: "github_token_file",
+        value: FieldValue::Unset("\"/run/secrets/github_token\""),
+        doc: "File holding the GitHub token; wins over GITHUB_TOKEN/GH_TOKEN.",
+    },
+    SettingField {
+        key: "follow_repo_renames",
+        value: FieldValue::Default("false"),
+        doc: "Fetch from the new slug when a configured repo was renamed upstream.",
+    },
+    SettingField {
+        key: "prefer_source_readme",
+        value: FieldValue::Default("false"),
+        doc: "Latest-docs mode: use the repo's raw README instead of the docs.rs one.",
+    },
+    SettingField {
+        key: "docsrs_format",
+        value: FieldValue::Default("\"markdown\""),
+        doc: "Latest-docs mode: \"markdown\" (API.md) or \"html\" (sanitized API.html).",
+    },
+    SettingField {
+        key: "sync_workspace_members",
+        value: FieldValue::Default("false"),
+        doc: "Sync the Cargo workspace's own members from their local directories.",
+    },
+    SettingField {
+        key: "infer_subpath",
+        value: FieldValue::Default("false"),
+        doc: "Look for a monorepo README under `<crate>/` or `crates/<crate>/`.",
+    },
+    SettingField {
+        key: "probe_docs_dirs",
+        value: FieldValue::Default("false"),
+        doc: "Also fetch `docs/README.md`/`book/src/README.md` when the README is thin.",
+    },
+    SettingField {
+        key: "max_total_size_mb",
+        value: FieldValue::Unset("500"),
+        doc: "Cap on bytes downloaded by one sync run (unset means unlimited).",
+    },
+    SettingField {
+        key: "max_retries_per_crate",
+        value: FieldValue::Default("6"),
+        doc: "Retries shared by all file fetches of one crate.",
+    },
+    SettingField {
+        key: "truncate_changelog",
+        value: FieldValue::Default("true"),
+        doc: "Keep only the changelog sections around the synced version.",
+    },
+    SettingField {
+        key: "changelog_version_regex",
+        value: FieldValue::Unset("'^##\\s+(\\d{4}-\\d{2}-\\d{2})'"),
+        doc: "Custom changelog heading regex; capture group 1 is the version label.",
+    },
+    SettingField {
+        key: "filename_template",
+        value: FieldValue::Default("\"{flat}\""),
+        doc: "Saved file names; placeholders {crate}, {version}, {path}, {flat}.",
+    },
+    SettingField {
+        key: "changelog_keep_sections",
+        value: FieldValue::Default("2"),
+        doc: "Sections kept when changelog headings aren't comparable semver versions.",
+    },
+    SettingField {
+        key: "extra_headers",
+        value: FieldValue::Unset("{ \"X-Api-Key\" = \"...\" }"),
+        doc: "Extra HTTP headers sent with raw file requests only.",
+    },
+];
+
+/// A `[crates.*]` entry written by `init`.
+#[derive(Debug, Default)]
+struct InitCrate {
+    repo: Option<String>,
+    subpath: Option<String>,
+    /// Workspace member directory (`init --workspace`).
+    path: Option<PathBuf>,
+    /// crates.io categories and keywords, written as comments.
+    categories: Vec<String>,
+    keywords: Vec<String>,
+}
+
+pub async fn run_init(
+    config_path: &Path,
+    force: bool,
+    registry_api_base: Option<&str>,
+    template: InitTemplate,
+    workspace: bool,
+) -> Result<()> {
     if config_path.exists() && !force {
         return Err(AiDocsError::Other(format!(
             "{} already exists. Use --force to overwrite",
@@ -23,20 +327,74 @@ pub async fn run_init(config_path: &Path, force: bool) -> Result<()> {
     let content = std::fs::read_to_string(cargo_toml_path)?;
     let root: Value = toml::from_str(&content)?;
 
-    let crate_names = collect_dependency_names(&root);
-    if crate_names.is_empty() {
+    let mut crate_names = collect_dependency_names(&root);
+    let members = if workspace {
+        crate::workspace::workspace_members(Path::new("."))?
+    } else {
+        Vec::new()
+    };
+    for member in &members {
+        crate_names.remove(&member.name);
+    }
+    if crate_names.is_empty() && members.is_empty() {
         return Err(AiDocsError::Other(
             "No dependencies found in Cargo.toml".to_string(),
         ));
     }
 
+    let mut crates = if template == InitTemplate::LatestDocs || crate_names.is_empty() {
+        crate_names
+            .into_iter()
+            .map(|name| (name, InitCrate::default()))
+            .collect()
+    } else {
+        resolve_crates(crate_names, registry_api_base).await?
+    };
+    for member in members {
+        info!(
+            "Adding workspace member '{}' from {}",
+            member.name,
+            member.dir.display()
+        );
+        let entry = InitCrate {
+            path: Some(member.dir),
+            ..InitCrate::default()
+        };
+        crates.insert(member.name, entry);
+    }
+
+    std::fs::write(
+        config_path,
+        render_config(template, registry_api_base, &crates),
+    )?;
+    Ok(())
+}
+
+/// Looks up each crate's GitHub repo (and monorepo subpath) on crates.io;
+/// crates without one are skipped with a warning.
+async fn resolve_crates(
+    crate_names: BTreeSet<String>,
+    registry_api_base: Option<&str>,
+) -> Result<BTreeMap<String, InitCrate>> {
     let client = reqwest::Client::new();
+    let registry = registry_api_base
+        .unwrap_or(DEFAULT_REGISTRY_API_BASE)
+        .trim_end_matches('/');
+    let github = GitHubFetcher::new(&HttpOptions::default())?;
     let mut resolved = BTreeMap::new();
 
     for crate_name in crate_names {
-        match resolve_github_repo(&client, &crate_name).await {
-            Ok(Some(repo)) => {
-                resolved.insert(crate_name, repo);
+        match resolve_github_repo(&client, registry, &crate_name).await {
+            Ok(Some(found)) => {
+                let subpath = infer_subpath(&github, &found.repo, &crate_name, &found.url).await;
+                let entry = InitCrate {
+                    repo: Some(found.repo),
+                    subpath,
+                    path: None,
+                    categories: found.categories,
+                    keywords: found.keywords,
+                };
+                resolved.insert(crate_name, entry);
             }
             Ok(None) => {
                 warn!("Could not infer GitHub repo for crate '{crate_name}', skipping");
@@ -53,23 +411,162 @@ pub async fn run_init(config_path: &Path, force: bool) -> Result<()> {
         ));
     }
 
+    Ok(resolved)
+}
+
+/// `[crates."*"]` repo lookups kept between runs, inside the output dir, so
+/// each lockfile crate is looked up on crates.io once.
+pub const WILDCARD_REPOS_FILE: &str = ".aifd-wildcard-repos.json";
+
+/// Result of `lookup_github_repos`.
+#[derive(Debug, Default)]
+pub struct RepoLookup {
+    /// GitHub `owner/repo` by crate; `None` when the registry lists none.
+    pub repos: BTreeMap<String, Option<String>>,
+    /// Crates whose lookup failed (e.g. a network error); unknown this run.
+    pub failed: BTreeSet<String>,
+}
+
+/// GitHub `owner/repo` of each crate on crates.io, without the subpath
+/// guess (which costs GitHub quota), with up to `concurrency` lookups in
+/// flight. Used for `[crates."*"]` entries.
+pub async fn lookup_github_repos(
+    crate_names: impl IntoIterator<Item = String>,
+    http: &HttpOptions,
+    concurrency: usize,
+) -> Result<RepoLookup> {
+    let client = http.client_builder().build()?;
+    let registry: Arc<str> = http.registry_api_base.trim_end_matches('/').into();
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for crate_name in crate_names {
+        let (client, registry) = (client.clone(), Arc::clone(&registry));
+        let semaphore = Arc::clone(&semaphore);
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let repo = resolve_github_repo(&client, &registry, &crate_name).await;
+            (crate_name, repo)
+        });
+    }
+
+    let mut lookup = RepoLookup::default();
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((crate_name, repo)) = joined else {
+            continue;
+        };
+        match repo {
+            Ok(repo) => {
+                if repo.is_none() {
+                    warn!("Could not infer GitHub repo for crate '{crate_name}', skipping");
+                }
+                lookup
+                    .repos
+                    .insert(crate_name, repo.map(|found| found.repo));
+            }
+            Err(e) => {
+                warn!("Failed to resolve metadata for crate '{crate_name}': {e}");
+                lookup.failed.insert(crate_name);
+            }
+        }
+    }
+    Ok(lookup)
+}
+
+/// `WILDCARD_REPOS_FILE` of `output_dir`; empty when missing or unreadable.
+pub fn load_wildcard_repos(output_dir: &Path) -> BTreeMap<String, Option<String>> {
+    std::fs::read_to_string(output_dir.join(WILDCARD_REPOS_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_wildcard_repos(
+    output_dir: &Path,
+    repos: &BTreeMap<String, Option<String>>,
+) -> Result<()> {
+    std::fs::create_dir_all(output_dir)?;
+    let json = serde_json::to_string_pretty(repos)
+        .map_err(|e| AiDocsError::Other(format!("failed to serialize wildcard repos: {e}")))?;
+    std::fs::write(output_dir.join(WILDCARD_REPOS_FILE), json)?;
+    Ok(())
+}
+
+/// Config text for `template`. `minimal` and `latest-docs` only write the
+/// settings they change; `full` writes every entry of `SETTING_FIELDS`.
+fn render_config(
+    template: InitTemplate,
+    registry_api_base: Option<&str>,
+    crates: &BTreeMap<String, InitCrate>,
+) -> String {
+    let mut overrides: Vec<(&str, String)> = Vec::new();
+    if template == InitTemplate::LatestDocs {
+        overrides.push(("sync_mode", "\"latest_docs\"".to_string()));
+    }
+    if let Some(base) = registry_api_base {
+        overrides.push((
+            "registry_api_base",
+            toml::Value::String(base.to_string()).to_string(),
+        ));
+    }
+    let override_for = |key: &str| {
+        overrides
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    };
+
     let mut out = String::new();
-    out.push_str("[settings]\n");
-    out.push_str("output_dir = \"fdocs\"\n");
-    out.push_str("max_file_size_kb = 200\n");
-    out.push_str("prune = true\n");
-    out.push_str("docs_source = \"github\"\n");
-    out.push_str("sync_mode = \"lockfile\"\n");
-    out.push_str("latest_ttl_hours = 24\n");
-    out.push_str("docsrs_single_page = true\n\n");
-
-    for (crate_name, repo) in resolved {
+    if template == InitTemplate::Full {
+        out.push_str("[settings]\n");
+        for field in SETTING_FIELDS {
+            out.push_str(&format!("# {}\n", field.doc));
+            match (override_for(field.key), &field.value) {
+                (Some(value), _) | (None, &FieldValue::Default(value)) => {
+                    out.push_str(&format!("{} = {value}\n", field.key));
+                }
+                (None, &FieldValue::Unset(example)) => {
+                    out.push_str(&format!("# {} = {example}\n", field.key));
+                }
+            }
+        }
+        out.push('\n');
+    } else if !overrides.is_empty() {
+        out.push_str("[settings]\n");
+        for field in SETTING_FIELDS {
+            if let Some(value) = override_for(field.key) {
+                out.push_str(&format!("{} = {value}\n", field.key));
+            }
+        }
+        out.push('\n');
+    }
+
+    for (crate_name, entry) in crates {
         out.push_str(&format!("[crates.{crate_name}]\n"));
-        out.push_str(&format!("repo = \"{repo}\"\n\n"));
+        if !entry.categories.is_empty() {
+            out.push_str(&format!("# categories: {}\n", entry.categories.join(", ")));
+        }
+        if !entry.keywords.is_empty() {
+            out.push_str(&format!("# keywords: {}\n", entry.keywords.join(", ")));
+        }
+        if let Some(repo) = &entry.repo {
+            out.push_str(&format!("repo = \"{repo}\"\n"));
+        }
+        if let Some(subpath) = &entry.subpath {
+            out.push_str(&format!(
+                "subpath = {}\n",
+                toml::Value::String(subpath.clone())
+            ));
+        }
+        if let Some(path) = &entry.path {
+            out.push_str(&format!(
+                "path = {}\n",
+                toml::Value::String(path.display().to_string())
+            ));
+        }
+        out.push('\n');
     }
 
-    std::fs::write(config_path, out)?;
-    Ok(())
+    out
 }
 
 fn collect_dependency_names(root: &Value) -> BTreeSet<String> {
@@ -102,24 +599,79 @@ struct CratesIoResponse {
 struct CrateData {
     repository: Option<String>,
     homepage: Option<String>,
+    #[serde(default)]
+    categories: Option<Vec<String>>,
+    #[serde(default)]
+    keywords: Option<Vec<String>>,
 }
 
-async fn resolve_github_repo(client: &reqwest::Client, crate_name: &str) -> Result<Option<String>> {
-    let url = format!("https://crates.io/api/v1/crates/{crate_name}");
-    let body: CratesIoResponse = client
+/// A crate's GitHub repo as found on the registry.
+#[derive(Debug)]
+struct RegistryRepo {
+    /// `owner/repo`.
+    repo: String,
+    /// The crates.io URL it was taken from.
+    url: String,
+    categories: Vec<String>,
+    keywords: Vec<String>,
+}
+
+/// Best-effort monorepo `subpath` from the repository's default-branch tree;
+/// failures (e.g. rate limits) only skip the guess.
+async fn infer_subpath(
+    github: &GitHubFetcher,
+    repo: &str,
+    crate_name: &str,
+    repo_url: &str,
+) -> Option<String> {
+    let candidates = subpath_candidates(crate_name, Some(repo_url));
+    match github.infer_subpath(repo, "HEAD", &candidates).await {
+        Ok(Some(subpath)) => {
+            info!("Inferred subpath `{subpath}` for crate '{crate_name}' in {repo}");
+            Some(subpath)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Could not infer subpath for crate '{crate_name}': {e}");
+            None
+        }
+    }
+}
+
+async fn resolve_github_repo(
+    client: &reqwest::Client,
+    registry_api_base: &str,
+    crate_name: &str,
+) -> Result<Option<RegistryRepo>> {
+    let url = format!("{registry_api_base}/crates/{crate_name}");
+    let response = client
         .get(url)
         .header(reqwest::header::USER_AGENT, "cargo-ai-fdocs")
         .send()
-        .await?
-        .error_for_status()?
-        .json()
         .await?;
+    // Not on the registry (e.g. a git dependency), so it has no repository.
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let CrateData {
+        repository,
+        homepage,
+        categories,
+        keywords,
+    } = response
+        .error_for_status()?
+        .json::<CratesIoResponse>()
+        .await?
+        .crate_data;
 
-    Ok(body
-        .crate_data
-        .repository
-        .or(body.crate_data.homepage)
-        .and_then(|url| extract_github_owner_repo(&url)))
+    Ok(repository.or(homepage).and_then(|url| {
+        extract_github_owner_repo(&url).map(|repo| RegistryRepo {
+            repo,
+            url,
+            categories: categories.unwrap_or_default(),
+            keywords: keywords.unwrap_or_default(),
+        })
+    }))
 }
 
 fn extract_github_owner_repo(url: &str) -> Option<String> {
@@ -138,7 +690,120 @@ fn extract_github_owner_repo(url: &str) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::extract_github_owner_repo;
+    use super::{
+        extract_github_owner_repo, load_wildcard_repos, lookup_github_repos, render_config,
+        save_wildcard_repos, FieldValue, InitCrate, InitTemplate, Value, SETTING_FIELDS,
+    };
+    use crate::config::{Config, Settings};
+    use crate::fetcher::HttpOptions;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn load_rendered(name: &str, rendered: &str) -> Config {
+        let dir = std::env::temp_dir().join(format!("aifd-init-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create dir");
+        let path = dir.join("ai-fdocs.toml");
+        fs::write(&path, rendered).expect("write config");
+        let config = Config::load(&path, None).expect("rendered config loads");
+        let _ = fs::remove_dir_all(&dir);
+        config
+    }
+
+    #[test]
+    fn full_template_lists_every_setting_at_its_default() {
+        let mut crates = BTreeMap::new();
+        crates.insert(
+            "serde".to_string(),
+            InitCrate {
+                repo: Some("serde-rs/serde".to_string()),
+                keywords: vec!["serde".to_string(), "serialization".to_string()],
+                ..InitCrate::default()
+            },
+        );
+
+        let rendered = render_config(InitTemplate::Full, None, &crates);
+        assert!(
+            rendered.contains("# Files larger than this are truncated.\nmax_file_size_kb = 200\n")
+        );
+        assert!(rendered.contains("\n# http_proxy = \"http://proxy.example.com:8080\"\n"));
+        assert!(rendered.ends_with(
+            "[crates.serde]\n# keywords: serde, serialization\nrepo = \"serde-rs/serde\"\n\n"
+        ));
+
+        let config = load_rendered("full", &rendered);
+        assert_eq!(
+            format!("{:?}", config.settings),
+            format!("{:?}", Settings::default())
+        );
+
+        // Uncommented keys are exactly the defaults `Settings` serializes
+        // (unset options and `extra_headers` are left out of both).
+        let rendered: toml::Table = toml::from_str(&rendered).expect("rendered toml");
+        let serialized = toml::to_string(&Settings::default()).expect("serialize settings");
+        let defaults: toml::Table = toml::from_str(&serialized).expect("serialized toml");
+        assert_eq!(rendered["settings"], Value::Table(defaults));
+        for field in SETTING_FIELDS {
+            assert_eq!(
+                matches!(field.value, FieldValue::Default(_)),
+                rendered["settings"].get(field.key).is_some(),
+                "{}",
+                field.key
+            );
+        }
+    }
+
+    #[test]
+    fn minimal_and_latest_docs_templates_only_write_overrides() {
+        let mut crates = BTreeMap::new();
+        crates.insert("serde".to_string(), InitCrate::default());
+
+        let minimal = render_config(InitTemplate::Minimal, None, &crates);
+        assert_eq!(minimal, "[crates.serde]\n\n");
+
+        let latest = render_config(
+            InitTemplate::LatestDocs,
+            Some("https://registry.example.com/api/v1"),
+            &crates,
+        );
+        assert_eq!(
+            latest,
+            "[settings]\nsync_mode = \"latest_docs\"\nregistry_api_base = \"https://registry.example.com/api/v1\"\n\n[crates.serde]\n\n"
+        );
+        let config = load_rendered("latest", &latest);
+        assert!(config.crates["serde"].repo.is_none());
+
+        let quoted = render_config(
+            InitTemplate::LatestDocs,
+            Some("https://r.example.com/\"x\\y\"\nz = 1"),
+            &crates,
+        );
+        let config = load_rendered("quoted", &quoted);
+        assert_eq!(
+            config.settings.registry_api_base,
+            "https://r.example.com/\"x\\y\"\nz = 1"
+        );
+    }
+
+    #[test]
+    fn crate_paths_are_escaped_in_the_rendered_config() {
+        let mut crates = BTreeMap::new();
+        crates.insert(
+            "demo".to_string(),
+            InitCrate {
+                subpath: Some("crates\\demo \"core\"".to_string()),
+                path: Some(PathBuf::from("C:\\src\\demo")),
+                ..InitCrate::default()
+            },
+        );
+
+        let rendered = render_config(InitTemplate::Minimal, None, &crates);
+        let table: toml::Table = toml::from_str(&rendered).expect("rendered toml parses");
+        let demo = &table["crates"]["demo"];
+        assert_eq!(demo["subpath"].as_str(), Some("crates\\demo \"core\""));
+        assert_eq!(demo["path"].as_str(), Some("C:\\src\\demo"));
+    }
 
     #[test]
     fn extracts_repo_from_https_url() {
@@ -155,4 +820,29 @@ mod tests {
             Some("serde-rs/serde".to_string())
         );
     }
+
+    #[tokio::test]
+    async fn failed_lookups_are_reported_and_cached_repos_round_trip() {
+        let http = HttpOptions {
+            registry_api_base: "http://127.0.0.1:1/api/v1".to_string(),
+            timeout: std::time::Duration::from_secs(2),
+            ..HttpOptions::default()
+        };
+        let lookup = lookup_github_repos(["serde".to_string(), "tokio".to_string()], &http, 2)
+            .await
+            .expect("client builds");
+        assert!(lookup.repos.is_empty());
+        assert_eq!(lookup.failed.len(), 2);
+
+        let dir = std::env::temp_dir().join(format!("aifd-wildcard-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(load_wildcard_repos(&dir).is_empty());
+        let repos = BTreeMap::from([
+            ("serde".to_string(), Some("serde-rs/serde".to_string())),
+            ("local-only".to_string(), None),
+        ]);
+        save_wildcard_repos(&dir, &repos).expect("save");
+        assert_eq!(load_wildcard_repos(&dir), repos);
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use regex::Regex;
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::storage;
+use crate::verify;
+
+/// One broken link found by `links`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BrokenLink {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub version: String,
+    pub file: String,
+    pub link: String,
+    pub reason: String,
+}
+
+/// Links found in one crate dir: broken relative links plus the external URLs
+/// (per file) left for `check_external_links`.
+#[derive(Debug, Default)]
+pub struct CrateLinks {
+    pub broken: Vec<BrokenLink>,
+    pub external: Vec<(String, String)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LinkTarget {
+    External,
+    Relative(String),
+    Ignored,
+}
+
+/// `[text](dest "title")` inline links and images.
+fn inline_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"\]\(\s*<?([^)\s>]+)>?(?:\s+["'(][^)]*)?\)"#).expect("inline link regex")
+    })
+}
+
+/// `[label]: dest` reference definitions.
+fn reference_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\s{0,3}\[[^\]]+\]:\s*<?([^\s>]+)>?").expect("reference regex"))
+}
+
+/// `<https://…>` autolinks.
+fn autolink_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"<(https?://[^>\s]+)>").expect("autolink regex"))
+}
+
+/// Link destinations of inline links/images, reference definitions and
+/// `<https://…>` autolinks, in document order. Fenced code blocks are skipped.
+pub fn extract_links(body: &str) -> Vec<String> {
+    let (inline, reference, autolink) = (inline_re(), reference_re(), autolink_re());
+
+    let mut links = Vec::new();
+    let mut fence: Option<&str> = None;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+
+        if let Some(caps) = reference.captures(line) {
+            links.push(caps[1].to_string());
+            continue;
+        }
+        links.extend(inline.captures_iter(line).map(|caps| caps[1].to_string()));
+        links.extend(autolink.captures_iter(line).map(|caps| caps[1].to_string()));
+    }
+    links
+}
+
+/// Classifies `link` as found in the file fetched from upstream path
+/// `original`. A relative link resolves against that file's directory to the
+/// upstream path it points at.
+fn classify_link(original: &str, link: &str) -> LinkTarget {
+    if link.starts_with("http://") || link.starts_with("https://") {
+        return LinkTarget::External;
+    }
+    let has_scheme = link
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains(['/', '#', '?']));
+    if link.starts_with('#') || link.starts_with("//") || has_scheme {
+        return LinkTarget::Ignored;
+    }
+
+    let path = link.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() {
+        return LinkTarget::Ignored;
+    }
+
+    let mut parts: Vec<&str> = Vec::new();
+    if !path.starts_with('/') {
+        parts.extend(original.split('/'));
+        parts.pop();
+    }
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            other => parts.push(other),
+        }
+    }
+    LinkTarget::Relative(parts.join("/"))
+}
+
+/// Upstream path of every saved file, from the meta's `files` manifest and
+/// `filename_template`; files it doesn't cover fall back to un-flattening.
+fn original_paths(
+    crate_name: &str,
+    version: &str,
+    crate_dir: &Path,
+) -> (String, BTreeMap<String, String>) {
+    let meta = crate_dir
+        .parent()
+        .and_then(|output_dir| storage::read_meta(output_dir, crate_name, version));
+    let template = meta
+        .as_ref()
+        .and_then(|m| m.filename_template.clone())
+        .unwrap_or_else(|| storage::DEFAULT_FILENAME_TEMPLATE.to_string());
+    let originals = meta
+        .map(|m| m.files.into_keys())
+        .into_iter()
+        .flatten()
+        .map(|path| {
+            let saved = storage::render_filename(&template, crate_name, version, &path);
+            (saved, path)
+        })
+        .collect();
+    (template, originals)
+}
+
+/// Scans every saved doc file of one `crate@version` dir (provenance headers
+/// stripped), in file name order.
+pub fn scan_crate_links(crate_name: &str, version: &str, crate_dir: &Path) -> CrateLinks {
+    let mut files = verify::stored_doc_files(crate_dir);
+    files.sort();
+    let saved: BTreeSet<&str> = files.iter().map(String::as_str).collect();
+    let (template, originals) = original_paths(crate_name, version, crate_dir);
+
+    let mut result = CrateLinks::default();
+    for file in &files {
+        let Ok(content) = fs::read_to_string(crate_dir.join(file)) else {
+            continue;
+        };
+        let original = originals
+            .get(file)
+            .cloned()
+            .unwrap_or_else(|| file.replace("__", "/"));
+        for link in extract_links(storage::strip_injected_header(&content)) {
+            let target = match classify_link(&original, &link) {
+                LinkTarget::External => {
+                    result.external.push((file.clone(), link));
+                    continue;
+                }
+                LinkTarget::Relative(path) => {
+                    storage::render_filename(&template, crate_name, version, &path)
+                }
+                LinkTarget::Ignored => continue,
+            };
+            if !saved.contains(target.as_str()) {
+                result.broken.push(BrokenLink {
+                    crate_name: crate_name.to_string(),
+                    version: version.to_string(),
+                    file: file.clone(),
+                    link,
+                    reason: format!("`{target}` is not in the synced docs"),
+                });
+            }
+        }
+    }
+    result
+}
+
+/// HEAD-requests each URL once (falling back to GET when HEAD is not allowed)
+/// and returns the failure reason for every URL that did not answer with a
+/// non-error status.
+pub async fn check_external_links(
+    client: &Client,
+    urls: BTreeSet<String>,
+    concurrency: usize,
+) -> BTreeMap<String, String> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for url in urls {
+        let client = client.clone();
+        let semaphore = Arc::clone(&semaphore);
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let reason = probe_url(&client, &url).await;
+            (url, reason)
+        });
+    }
+
+    let mut failures = BTreeMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((url, Some(reason))) = joined {
+            failures.insert(url, reason);
+        }
+    }
+    failures
+}
+
+async fn probe_url(client: &Client, url: &str) -> Option<String> {
+    let mut res = client.head(url).send().await;
+    if let Ok(r) = &res {
+        if matches!(
+            r.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            res = client.get(url).send().await;
+        }
+    }
+    match res {
+        Ok(r) if r.status().is_client_error() || r.status().is_server_error() => {
+            Some(format!("HTTP {}", r.status().as_u16()))
+        }
+        Ok(_) => None,
+        Err(e) => Some(format!("request failed: {e}")),
+    }
+}
+
+pub fn print_links_table(broken: &[BrokenLink]) {
+    print!("{}", format_links_table(broken));
+}
+
+pub fn format_links_json(broken: &[BrokenLink]) -> std::result::Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(broken)
+}
+
+fn format_links_table(broken: &[BrokenLink]) -> String {
+    const COL_CRATE: usize = 24;
+    const COL_FILE: usize = 28;
+    const COL_LINK: usize = 40;
+
+    if broken.is_empty() {
+        return "No broken links found.\n".to_string();
+    }
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "{:<COL_CRATE$} {:<COL_FILE$} {:<COL_LINK$} Reason",
+        "Crate", "File", "Link"
+    );
+    let _ = writeln!(
+        output,
+        "{:-<COL_CRATE$} {:-<COL_FILE$} {:-<COL_LINK$} {:-<6}",
+        "", "", "", ""
+    );
+    for link in broken {
+        let _ = writeln!(
+            output,
+            "{:<COL_CRATE$} {:<COL_FILE$} {:<COL_LINK$} {}",
+            format!("{}@{}", link.crate_name, link.version),
+            link.file,
+            link.link,
+            link.reason
+        );
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify_link, extract_links, format_links_table, scan_crate_links, LinkTarget};
+    use std::fs;
+
+    #[test]
+    fn extracts_links_outside_code_fences() {
+        let body = "See [guide](docs/guide.md \"Guide\") and ![logo](img/logo.png).\n\
+                    <https://example.com/a>\n\
+                    ```\n[not](a-link.md)\n```\n\
+                    [ref]: https://example.com/ref\n";
+        assert_eq!(
+            extract_links(body),
+            vec![
+                "docs/guide.md",
+                "img/logo.png",
+                "https://example.com/a",
+                "https://example.com/ref"
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_links_resolve_against_the_original_directory() {
+        assert_eq!(
+            classify_link("docs/guide.md", "../README.md#usage"),
+            LinkTarget::Relative("README.md".to_string())
+        );
+        assert_eq!(
+            classify_link("README.md", "./docs/guide.md"),
+            LinkTarget::Relative("docs/guide.md".to_string())
+        );
+        assert_eq!(classify_link("README.md", "#install"), LinkTarget::Ignored);
+        assert_eq!(
+            classify_link("README.md", "mailto:me@example.com"),
+            LinkTarget::Ignored
+        );
+        assert_eq!(
+            classify_link("README.md", "https://docs.rs/demo"),
+            LinkTarget::External
+        );
+    }
+
+    #[test]
+    fn flags_relative_links_missing_from_the_crate_dir() {
+        let tmp = std::env::temp_dir().join(format!("aifd-links-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).expect("create crate dir");
+        fs::write(
+            tmp.join("README.md"),
+            "<!-- AI-FDOCS: source=github.com/o/r ref=v1 path=README.md fetched=2026-01-01 -->\n\n\
+             [guide](docs/guide.md) [api](docs/api.md) [site](https://example.com)\n",
+        )
+        .expect("write readme");
+        fs::write(tmp.join("docs__guide.md"), "[back](../README.md)\n").expect("write guide");
+
+        let links = scan_crate_links("demo", "1.0.0", &tmp);
+        assert_eq!(links.broken.len(), 1);
+        assert_eq!(links.broken[0].file, "README.md");
+        assert_eq!(links.broken[0].link, "docs/api.md");
+        assert_eq!(
+            links.external,
+            vec![("README.md".to_string(), "https://example.com".to_string())]
+        );
+        assert!(format_links_table(&links.broken).contains("demo@1.0.0"));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,239 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AiDocsError, Result};
+use crate::storage::{self, SavedCrate, SourceRef};
+use crate::verify;
+
+pub const LOCK_FILE: &str = "ai-fdocs.lock";
+pub const LOCK_SCHEMA_VERSION: u32 = 1;
+
+/// One crate pinned in `ai-fdocs.lock`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedCrate {
+    pub name: String,
+    pub version: String,
+    pub git_ref: String,
+    /// Commit `git_ref` pointed at when synced; `--locked` fetches from it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub is_fallback: bool,
+    pub source_kind: String,
+    /// SHA-256 of the saved file bodies (provenance headers stripped).
+    pub content_sha256: String,
+    /// Extra GitHub `sources` entries; `--locked` fetches each from its
+    /// `commit`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "source")]
+    pub extra_sources: Vec<SourceRef>,
+}
+
+impl LockedCrate {
+    /// Pin of the extra `sources` entry for `repo`.
+    pub fn extra_source(&self, repo: &str) -> Option<&SourceRef> {
+        self.extra_sources
+            .iter()
+            .find(|source| source.repo.eq_ignore_ascii_case(repo))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DocsLock {
+    pub version: u32,
+    #[serde(default, rename = "crate")]
+    pub crates: Vec<LockedCrate>,
+}
+
+impl DocsLock {
+    pub fn get(&self, crate_name: &str, version: &str) -> Option<&LockedCrate> {
+        self.crates
+            .iter()
+            .find(|c| c.name == crate_name && c.version == version)
+    }
+}
+
+/// `ai-fdocs.lock` lives next to the config file, like `Cargo.lock`.
+pub fn lock_path(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(LOCK_FILE)
+}
+
+pub fn read_lock(path: &Path) -> Result<DocsLock> {
+    let raw = fs::read_to_string(path).map_err(|e| {
+        AiDocsError::Other(format!(
+            "cannot read {}: {e}; run `cargo ai-fdocs sync` without --locked to create it",
+            path.display()
+        ))
+    })?;
+    Ok(toml::from_str(&raw)?)
+}
+
+pub fn write_lock(path: &Path, lock: &DocsLock) -> Result<()> {
+    let body = toml::to_string_pretty(lock)
+        .map_err(|e| AiDocsError::Other(format!("Failed to serialize lock: {e}")))?;
+    fs::write(
+        path,
+        format!("# This file is generated by ai-fdocs sync. Do not edit manually.\n\n{body}"),
+    )?;
+    Ok(())
+}
+
+/// Hash of every saved doc file in `crate_dir`, in file name order, without
+/// provenance headers (they carry the fetch date).
+pub fn content_hash(crate_dir: &Path) -> String {
+    let files: Vec<(String, String)> = verify::stored_doc_files(crate_dir)
+        .into_iter()
+        .map(|name| {
+            let content = fs::read_to_string(crate_dir.join(&name)).unwrap_or_default();
+            (name, content)
+        })
+        .collect();
+    bodies_hash(
+        files
+            .iter()
+            .map(|(name, content)| (name.as_str(), content.as_str())),
+    )
+}
+
+/// `content_hash` of doc files not written yet, as (file name, content);
+/// provenance headers are stripped like for saved files.
+pub fn bodies_hash<'a>(files: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort_by_key(|(name, _)| *name);
+
+    let mut hasher = Sha256::new();
+    for (name, content) in files {
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update(storage::strip_injected_header(content).as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Lock entry for a saved `crate@version` dir, from its meta.
+pub fn locked_entry(output_dir: &Path, crate_name: &str, version: &str) -> Option<LockedCrate> {
+    let meta = storage::read_meta(output_dir, crate_name, version)?;
+    Some(LockedCrate {
+        name: crate_name.to_string(),
+        version: version.to_string(),
+        git_ref: meta.git_ref,
+        commit: meta.commit_sha,
+        is_fallback: meta.is_fallback,
+        source_kind: meta.source_kind.unwrap_or_else(|| "github".to_string()),
+        content_sha256: content_hash(&output_dir.join(format!("{crate_name}@{version}"))),
+        extra_sources: meta.extra_sources,
+    })
+}
+
+/// Lock for the crates saved by this run. Entries of `previous` for other
+/// crates still in the config (e.g. ones that failed or were not selected)
+/// are kept.
+pub fn build_lock(
+    output_dir: &Path,
+    saved: &[SavedCrate],
+    previous: Option<DocsLock>,
+    is_configured: impl Fn(&str) -> bool,
+) -> DocsLock {
+    let mut crates: Vec<LockedCrate> = saved
+        .iter()
+        .filter_map(|s| locked_entry(output_dir, &s.name, &s.version))
+        .collect();
+    for old in previous.map(|lock| lock.crates).unwrap_or_default() {
+        if is_configured(&old.name) && !crates.iter().any(|c| c.name == old.name) {
+            crates.push(old);
+        }
+    }
+    crates.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+
+    DocsLock {
+        version: LOCK_SCHEMA_VERSION,
+        crates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_lock, content_hash, read_lock, write_lock, DocsLock, LockedCrate, SourceRef,
+    };
+    use std::fs;
+
+    fn locked(name: &str) -> LockedCrate {
+        LockedCrate {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            git_ref: "v1.0.0".to_string(),
+            commit: Some("abc123".to_string()),
+            is_fallback: false,
+            source_kind: "github".to_string(),
+            content_sha256: "00".to_string(),
+            extra_sources: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn content_hash_ignores_provenance_header() {
+        let tmp = std::env::temp_dir().join(format!("aifd-lock-hash-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).expect("create crate dir");
+        let header = |date: &str| {
+            format!("<!-- AI-FDOCS: source=github.com/o/r ref=v1 path=README.md fetched={date} -->\n\nbody\n")
+        };
+
+        fs::write(tmp.join("README.md"), header("2026-01-01")).expect("write readme");
+        let first = content_hash(&tmp);
+        fs::write(tmp.join("README.md"), header("2026-02-02")).expect("rewrite readme");
+        assert_eq!(content_hash(&tmp), first);
+
+        fs::write(tmp.join("README.md"), "changed\n").expect("change readme");
+        assert_ne!(content_hash(&tmp), first);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn build_keeps_previous_entries_for_configured_crates_and_round_trips() {
+        let tmp = std::env::temp_dir().join(format!("aifd-lock-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).expect("create output dir");
+
+        let tokio = LockedCrate {
+            extra_sources: vec![SourceRef {
+                repo: "tokio-rs/website".to_string(),
+                git_ref: "master".to_string(),
+                commit: Some("def456".to_string()),
+                is_fallback: true,
+            }],
+            ..locked("tokio")
+        };
+        let previous = DocsLock {
+            version: 1,
+            crates: vec![tokio.clone(), locked("removed")],
+        };
+        let lock = build_lock(&tmp, &[], Some(previous), |name| name == "tokio");
+        assert_eq!(lock.crates, vec![tokio.clone()]);
+
+        let path = tmp.join("ai-fdocs.lock");
+        write_lock(&path, &lock).expect("write lock");
+        let raw = fs::read_to_string(&path).expect("read lock");
+        assert!(raw.contains("[[crate]]"));
+        assert!(raw.contains("[[crate.source]]"));
+        let reread = read_lock(&path).expect("parse lock");
+        assert_eq!(reread.get("tokio", "1.0.0"), Some(&tokio));
+        assert_eq!(
+            tokio
+                .extra_source("Tokio-rs/Website")
+                .and_then(|s| s.commit.as_deref()),
+            Some("def456")
+        );
+        assert!(reread.get("tokio", "2.0.0").is_none());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -1,76 +1,333 @@
+mod chunks;
 mod config;
+mod coverage;
+mod diff;
+mod doctor;
 mod error;
 #[path = "fetcher/mod.rs"]
 mod fetcher;
+mod history;
 mod index;
 mod init;
+mod links;
+mod lock;
 mod processor;
 mod resolver;
+mod stats;
 mod status;
+mod status_cache;
 mod storage;
 mod utils;
+mod verify;
+mod workspace;
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use tokio::sync::Semaphore;
 
-use chrono::{NaiveDate, Utc};
 use clap::{Parser, Subcommand, ValueEnum};
-use tracing::{error, info, warn};
+use serde::Serialize;
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
-use crate::config::{Config, DocsSource, SyncMode};
+use crate::config::{
+    Config, DocsFormat, DocsRsFormat, DocsSource, FallbackPolicy, HashedSettings, Settings,
+    SyncMode,
+};
 use crate::error::AiDocsError;
 use crate::error::{Result, SyncErrorKind};
-use crate::fetcher::github::{FetchedFile, FileRequest, GitHubFetcher};
-use crate::fetcher::latest::{is_docsrs_fallback_eligible, LatestDocsFetcher};
-use crate::init::run_init as run_init_command;
+use crate::fetcher::git::{self, GitCheckout};
+use crate::fetcher::github::{
+    matches_exclusion, subpath_candidates, FetchedFile, FileRequest, GitHubFetcher, ResolvedRef,
+    RetryBudget,
+};
+use crate::fetcher::latest::{
+    is_docsrs_fallback_eligible, DocsRsArtifact, LatestDocsFetcher, SourceReadme,
+};
+use crate::fetcher::local::LocalTree;
+use crate::fetcher::HttpOptions;
+use crate::init::{run_init as run_init_command, InitTemplate};
+use crate::processor::changelog::{self, ChangelogOptions};
+use crate::processor::includes;
+use crate::processor::markdown::{self, CleanupOptions};
 use crate::status::{collect_status, collect_status_latest, print_status_table, DocsStatus};
+use crate::storage::FetchMetrics;
+use crate::utils::is_latest_cache_fresh;
+use crate::verify::{CrateVerify, VerifyStatus};
 
 const DEFAULT_CONFIG_PATH: &str = "ai-fdocs.toml";
+/// `git_ref` recorded for `path` crates, which are read from the working tree.
+const WORKSPACE_REF: &str = "workspace";
 
 #[derive(Parser)]
 #[command(name = "ai-fdocs")]
 #[command(version, about = "Sync documentation from dependencies for AI context")]
 struct Cli {
+    /// Log output format; `json` emits one structured event per line for log
+    /// aggregation, with per-crate `crate`/`version`/`source_kind` span fields.
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// Color status tables; `auto` colors only when stdout is a terminal and
+    /// `NO_COLOR` is unset. JSON output is never colored.
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Merge `[profiles.<name>]` of the config over its base settings/crates.
+    #[arg(long, global = true)]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// `to_stderr` keeps stdout free for machine-readable command output.
+fn init_tracing(format: LogFormat, to_stderr: bool) {
+    let builder = tracing_subscriber::fmt().with_max_level(tracing::Level::INFO);
+    match (format, to_stderr) {
+        (LogFormat::Text, false) => builder.init(),
+        (LogFormat::Json, false) => builder.json().init(),
+        (LogFormat::Text, true) => builder.with_writer(std::io::stderr).init(),
+        (LogFormat::Json, true) => builder.json().with_writer(std::io::stderr).init(),
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Download/update vendor documentation
     Sync {
-        #[arg(short, long, default_value = DEFAULT_CONFIG_PATH)]
-        config: PathBuf,
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
         /// Sync mode override (`lockfile` is stable default, `latest-docs` is beta).
         #[arg(long, value_enum)]
         mode: Option<SyncModeArg>,
         /// Ignore local cache and re-fetch configured docs.
         #[arg(long, default_value_t = false)]
         force: bool,
+        /// Stop on the first crate that fails to sync (overrides `settings.fail_fast`).
+        #[arg(long, default_value_t = false)]
+        fail_fast: bool,
+        /// Fail crates without a matching version tag instead of syncing from a
+        /// branch (one-off `settings.fallback_policy = "error"`).
+        #[arg(long, default_value_t = false)]
+        no_fallback: bool,
+        /// Save files without the `AI-FDOCS` provenance header (one-off
+        /// `settings.inject_header = false`).
+        #[arg(long, default_value_t = false)]
+        no_header: bool,
+        /// Stop saving crates once this many MB were downloaded in the run
+        /// (overrides `settings.max_total_size_mb`).
+        #[arg(long, value_name = "MB")]
+        max_total_size: Option<u64>,
+        /// Only sync crates whose status is `Missing` in the active mode;
+        /// synced and outdated crates are left as they are.
+        #[arg(long, default_value_t = false)]
+        only_missing: bool,
+        /// Only sync this crate.
+        #[arg(long = "crate", value_name = "NAME")]
+        crate_name: Option<String>,
+        /// GitHub `owner/repo` for `--crate`, for a crate not in the config.
+        /// The entry only lives for this run and is not written to the config.
+        #[arg(long, value_name = "OWNER/REPO", requires = "crate_name")]
+        repo: Option<String>,
+        /// Version for `--crate` instead of the Cargo.lock one (lockfile/hybrid modes).
+        #[arg(long, requires = "crate_name")]
+        version: Option<String>,
+        /// Only sync the refs pinned in `ai-fdocs.lock` (lockfile/hybrid modes);
+        /// crates missing from the lock or whose content differs fail.
+        #[arg(long, default_value_t = false)]
+        locked: bool,
+        /// For crates updated from an older synced version, keep only the
+        /// changelog entries newer than that version.
+        #[arg(long, default_value_t = false)]
+        since_last_sync: bool,
+        /// Output format of the run summary; `json` prints it to stdout
+        /// (with per-crate errors) and sends logs to stderr.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
     },
     /// Show documentation sync status for configured crates.
     Status {
-        #[arg(short, long, default_value = DEFAULT_CONFIG_PATH)]
-        config: PathBuf,
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
         /// Sync mode override for status evaluation.
         #[arg(long, value_enum)]
         mode: Option<SyncModeArg>,
         /// Output format for status report.
         #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
         format: OutputFormat,
+        /// Re-run status every SECS seconds (default 5) until Ctrl-C.
+        #[arg(
+            long,
+            value_name = "SECS",
+            num_args = 0..=1,
+            default_missing_value = "5",
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        watch: Option<u64>,
     },
     /// Exit non-zero if any crate docs are not synced.
     Check {
-        #[arg(short, long, default_value = DEFAULT_CONFIG_PATH)]
-        config: PathBuf,
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
         /// Sync mode override for check evaluation.
         #[arg(long, value_enum)]
         mode: Option<SyncModeArg>,
         /// Output format for check report.
         #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
         format: OutputFormat,
+        /// On failure, fetch upstream for outdated/missing crates and print what
+        /// `sync` would change (nothing is written; table format only).
+        #[arg(long, default_value_t = false)]
+        diff: bool,
+        /// On failure, print only the one-line count summary instead of the
+        /// full table; CI annotations are still emitted (table format only).
+        #[arg(long, default_value_t = false, conflicts_with = "diff")]
+        summary_only: bool,
+    },
+    /// Re-fetch upstream sources and confirm cached content still matches.
+    Verify {
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        /// Output format for verify report.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Summarize the local docs cache (no network access).
+    Stats {
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        /// Output format for stats report.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// List Cargo.lock packages without a `[crates.*]` entry and config
+    /// entries missing from Cargo.lock (no network access).
+    Coverage {
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Output format for coverage report.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Show recorded sync events per crate (newest first, no network access).
+    History {
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        /// Only show this crate.
+        #[arg(long = "crate")]
+        crate_name: Option<String>,
+        /// Output format for history report.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Report broken links in saved docs (relative links always, URLs with `--check-external`).
+    Links {
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        /// Only check this crate.
+        #[arg(long = "crate")]
+        crate_name: Option<String>,
+        /// Also HEAD-request external http(s) links.
+        #[arg(long)]
+        check_external: bool,
+        /// Output format for links report.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Emit saved docs as overlapping NDJSON chunks for RAG/embedding pipelines.
+    Chunks {
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+        /// Only export this crate.
+        #[arg(long = "crate")]
+        crate_name: Option<String>,
+        /// Chunk length in whitespace-separated tokens.
+        #[arg(long, default_value_t = 512)]
+        chunk_size: usize,
+        /// Tokens repeated at the start of the next chunk (must be below `--chunk-size`).
+        #[arg(long, default_value_t = 64)]
+        overlap: usize,
+    },
+    /// Diagnose common setup problems (config, Cargo.lock, GitHub token,
+    /// output dir, network) and suggest fixes.
+    Doctor {
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Explain a `status`/`check` reason code and how to fix it; without a
+    /// code, list all of them.
+    Explain { reason_code: Option<String> },
+    /// Rebuild `_INDEX.md` and `_INDEX.json` from already synced docs (no
+    /// network access).
+    Index {
+        /// Config file [default: nearest `ai-fdocs.toml` in this or a parent directory].
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Override `settings.output_dir` (takes precedence over `AI_FDOCS_OUTPUT_DIR`).
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
     },
     /// Generate or refresh ai-fdocs config template.
     Init {
@@ -79,6 +336,18 @@ enum Commands {
         /// Overwrite existing config file.
         #[arg(long, default_value_t = false)]
         force: bool,
+        /// crates.io-compatible registry API used to look up repositories;
+        /// also written to the generated `settings.registry_api_base`.
+        #[arg(long, value_name = "URL")]
+        registry_api_base: Option<String>,
+        /// Config style: `minimal` (only non-defaults), `full` (every setting,
+        /// commented) or `latest-docs` (latest-docs mode, no repo lookups).
+        #[arg(long, value_enum, default_value_t = InitTemplate::Full)]
+        template: InitTemplate,
+        /// Also add the Cargo workspace's own members as `path` crates, read
+        /// from their local directories at their manifest versions.
+        #[arg(long, default_value_t = false)]
+        workspace: bool,
     },
 }
 
@@ -99,10 +368,17 @@ impl SyncModeArg {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
 enum OutputFormat {
+    #[default]
     Table,
     Json,
+    /// `status`/`check` only.
+    Csv,
+    /// `status`/`check` only: a table for PR comments.
+    GithubMarkdown,
+    /// `status`/`check` only: each crate with its saved files and sizes.
+    Tree,
 }
 
 #[derive(Default)]
@@ -116,19 +392,320 @@ struct SyncStats {
     network_errors: usize,
     not_found_errors: usize,
     other_errors: usize,
+    interrupted: usize,
+    failures: Vec<CrateSyncError>,
 }
 
 impl SyncStats {
-    fn record_error(&mut self, kind: SyncErrorKind) {
+    fn record_error(&mut self, crate_name: String, failure: SyncFailure) {
         self.errors += 1;
-        match kind {
+        match failure.kind {
             SyncErrorKind::Auth => self.auth_errors += 1,
             SyncErrorKind::RateLimit => self.rate_limit_errors += 1,
             SyncErrorKind::Network => self.network_errors += 1,
             SyncErrorKind::NotFound => self.not_found_errors += 1,
             SyncErrorKind::Other => self.other_errors += 1,
         }
+        self.failures.push(CrateSyncError {
+            crate_name,
+            kind: failure.kind,
+            message: failure.message,
+        });
+    }
+
+    /// `sync --format json` summary; errors are sorted by crate name.
+    fn format_json(&self, mode: SyncMode) -> std::result::Result<String, serde_json::Error> {
+        let mut errors: Vec<&CrateSyncError> = self.failures.iter().collect();
+        errors.sort_by(|a, b| a.crate_name.cmp(&b.crate_name));
+        serde_json::to_string_pretty(&SyncSummary {
+            mode: mode.as_str(),
+            synced: self.synced,
+            cached: self.cached,
+            skipped: self.skipped,
+            interrupted: self.interrupted,
+            errors,
+        })
+    }
+
+    /// Fails the run when Ctrl-C stopped some jobs from starting.
+    fn ensure_not_interrupted(&self) -> Result<()> {
+        if self.interrupted == 0 {
+            return Ok(());
+        }
+        let completed = self.synced + self.cached + self.skipped + self.errors;
+        warn!(
+            "⚠ Interrupted: {completed} crate(s) completed, {} not started",
+            self.interrupted
+        );
+        Err(AiDocsError::Interrupted { completed })
+    }
+}
+
+/// Why a crate failed to sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SyncFailure {
+    kind: SyncErrorKind,
+    message: String,
+}
+
+impl SyncFailure {
+    fn new(kind: SyncErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
     }
+
+    fn download_limit() -> Self {
+        Self::new(
+            SyncErrorKind::Other,
+            "download limit (settings.max_total_size_mb) exceeded",
+        )
+    }
+}
+
+impl From<&AiDocsError> for SyncFailure {
+    fn from(e: &AiDocsError) -> Self {
+        Self::new(e.sync_kind(), e.to_string())
+    }
+}
+
+/// One entry of the `errors` array of `sync --format json`.
+#[derive(Debug, Serialize)]
+struct CrateSyncError {
+    #[serde(rename = "crate")]
+    crate_name: String,
+    kind: SyncErrorKind,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SyncSummary<'a> {
+    mode: &'a str,
+    synced: usize,
+    cached: usize,
+    skipped: usize,
+    interrupted: usize,
+    errors: Vec<&'a CrateSyncError>,
+}
+
+/// Run-wide cap on downloaded bytes (`settings.max_total_size_mb`), shared by
+/// every sync worker. Crates saved before the limit trips stay valid.
+#[derive(Debug, Clone, Default)]
+struct DownloadGuard {
+    limit_mb: Option<u64>,
+    used_bytes: Arc<AtomicU64>,
+    exceeded: Arc<AtomicBool>,
+}
+
+impl DownloadGuard {
+    fn new(limit_mb: Option<u64>) -> Self {
+        Self {
+            limit_mb,
+            ..Self::default()
+        }
+    }
+
+    /// False once the limit has tripped; the crate is then not fetched.
+    fn allows_fetch(&self, crate_name: &str) -> bool {
+        if self.exceeded.load(Ordering::SeqCst) {
+            warn!("  ✗ {crate_name}: skipped, download limit already exceeded");
+            return false;
+        }
+        true
+    }
+
+    /// Adds a crate's downloaded bytes. Returns false (and trips the guard)
+    /// when they push the run over the limit; the crate must not be saved.
+    fn charge(&self, crate_name: &str, version: &str, bytes: usize) -> bool {
+        let Some(limit_mb) = self.limit_mb else {
+            return true;
+        };
+        let bytes = u64::try_from(bytes).unwrap_or(u64::MAX);
+        let used = self
+            .used_bytes
+            .fetch_add(bytes, Ordering::SeqCst)
+            .saturating_add(bytes);
+        if used <= limit_mb.saturating_mul(1024 * 1024) {
+            return true;
+        }
+        if !self.exceeded.swap(true, Ordering::SeqCst) {
+            warn!(
+                "  ✗ {crate_name}@{version}: download limit of {limit_mb} MB (settings.max_total_size_mb) exceeded; not saving it or any remaining crate"
+            );
+        }
+        false
+    }
+
+    /// `charge` for the content of files that just arrived.
+    fn charge_files(&self, crate_name: &str, version: &str, files: &[FetchedFile]) -> bool {
+        let bytes = files.iter().map(|f| f.content.len()).sum();
+        self.charge(crate_name, version, bytes)
+    }
+
+    fn ensure_within_limit(&self) -> Result<()> {
+        match self.limit_mb {
+            Some(limit_mb) if self.exceeded.load(Ordering::SeqCst) => {
+                Err(AiDocsError::TotalSizeExceeded { limit_mb })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Per-run options shared by every sync worker.
+#[derive(Debug, Clone)]
+struct SyncOptions {
+    force: bool,
+    /// Lockfile-based syncs only: hybrid takes the README from docs.rs.
+    sync_mode: SyncMode,
+    max_file_size_kb: usize,
+    /// `settings.max_tokens_per_crate`.
+    max_tokens_per_crate: Option<usize>,
+    latest_ttl_hours: usize,
+    cleanup: CleanupOptions,
+    changelog: ChangelogOptions,
+    fallback_policy: FallbackPolicy,
+    /// `settings.docs_source`; crates may override it with `source`.
+    docs_source: DocsSource,
+    /// `settings.inject_header` (`sync --no-header` turns it off).
+    inject_header: bool,
+    /// `settings.inject_header_extensions`.
+    header_extensions: Vec<String>,
+    /// `settings.filename_template`.
+    filename_template: String,
+    /// `settings.default_files` (README entries only with
+    /// `settings.include_readme_only`), for crates without explicit `files`.
+    default_files: Vec<String>,
+    /// `settings.changelog_candidates`.
+    changelog_candidates: Vec<String>,
+    /// `settings.include_extensions` (plus `md`/`mdx`), lowercased: fetched
+    /// from directory entries in `files` and the only ones saved.
+    include_extensions: Vec<String>,
+    max_retries_per_crate: usize,
+    follow_repo_renames: bool,
+    /// `settings.prefer_source_readme`, for docs.rs (`API.md`) artifacts.
+    prefer_source_readme: bool,
+    /// `settings.docsrs_format`: docs.rs artifacts of latest-docs syncs.
+    docsrs_format: DocsRsFormat,
+    /// `settings.infer_subpath`: guess a monorepo `subpath` when none is set.
+    infer_subpath: bool,
+    /// `settings.probe_docs_dirs`: add `DOCS_DIR_PROBES` to thin READMEs.
+    probe_docs_dirs: bool,
+    embed_ai_notes: bool,
+    /// `settings.resolve_includes`: inline mdBook `{{#include}}` directives.
+    resolve_includes: bool,
+    fetch_crate_metadata: bool,
+    download_guard: DownloadGuard,
+    /// `sync --locked`: refs and content hashes every crate must match.
+    locked: Option<Arc<lock::DocsLock>>,
+    /// `sync --since-last-sync`: version each crate was synced at before
+    /// this run, from its saved meta.
+    previous_versions: Option<Arc<HashMap<String, String>>>,
+    /// `Config::root`: crate `path`s are read relative to it.
+    root: PathBuf,
+}
+
+impl SyncOptions {
+    fn from_config(config: &Config, force: bool) -> Self {
+        Self {
+            root: config.root.clone(),
+            ..Self::from_settings(&config.settings, force)
+        }
+    }
+
+    fn from_settings(settings: &Settings, force: bool) -> Self {
+        Self {
+            force,
+            sync_mode: settings.sync_mode,
+            max_file_size_kb: settings.max_file_size_kb,
+            max_tokens_per_crate: settings.max_tokens_per_crate,
+            latest_ttl_hours: settings.latest_ttl_hours,
+            cleanup: CleanupOptions::from_settings(settings),
+            changelog: ChangelogOptions::from_settings(settings),
+            fallback_policy: settings.fallback_policy,
+            docs_source: settings.docs_source,
+            max_retries_per_crate: settings.max_retries_per_crate,
+            follow_repo_renames: settings.follow_repo_renames,
+            prefer_source_readme: settings.prefer_source_readme,
+            docsrs_format: settings.docsrs_format,
+            infer_subpath: settings.infer_subpath,
+            probe_docs_dirs: settings.probe_docs_dirs,
+            embed_ai_notes: settings.embed_ai_notes,
+            resolve_includes: settings.resolve_includes,
+            fetch_crate_metadata: settings.fetch_crate_metadata,
+            download_guard: DownloadGuard::new(settings.max_total_size_mb),
+            locked: None,
+            previous_versions: None,
+            root: PathBuf::new(),
+            inject_header: settings.inject_header,
+            header_extensions: settings.inject_header_extensions.clone(),
+            filename_template: settings.filename_template.clone(),
+            default_files: if settings.include_readme_only {
+                readme_only_files(&settings.default_files)
+            } else {
+                settings.default_files.clone()
+            },
+            changelog_candidates: settings.changelog_candidates.clone(),
+            include_extensions: ["md", "mdx"]
+                .iter()
+                .map(|ext| (*ext).to_string())
+                .chain(
+                    settings
+                        .include_extensions
+                        .iter()
+                        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase()),
+                )
+                .filter(|ext| !ext.is_empty())
+                .fold(Vec::new(), |mut exts, ext| {
+                    if !exts.contains(&ext) {
+                        exts.push(ext);
+                    }
+                    exts
+                }),
+        }
+    }
+
+    /// The settings hashed into each crate's `config_hash`.
+    fn hashed_settings(&self) -> HashedSettings<'_> {
+        HashedSettings {
+            inject_header: self.inject_header,
+            header_extensions: &self.header_extensions,
+            prefer_source_readme: self.prefer_source_readme,
+            truncate_changelog: self.changelog.truncate,
+        }
+    }
+
+    /// `sync --locked`: content hash pinned for `crate_name@version`.
+    fn locked_content_sha256(&self, crate_name: &str, version: &str) -> Option<&str> {
+        let entry = self.locked.as_deref()?.get(crate_name, version)?;
+        Some(entry.content_sha256.as_str())
+    }
+
+    /// `sync --since-last-sync`: cuts the changelog back to the crate's
+    /// previously synced version when that is older than `version`.
+    fn apply_since_last_sync(&mut self, crate_name: &str, version: &str) {
+        let previous = self
+            .previous_versions
+            .as_deref()
+            .and_then(|versions| versions.get(crate_name))
+            .filter(|previous| changelog::is_older_version(previous, version));
+        if let Some(previous) = previous {
+            info!("  📜 {crate_name}: changelog since {previous}");
+            self.changelog.since_version = Some(previous.clone());
+        }
+    }
+}
+
+/// Version recorded in the meta of each crate's newest saved docs dir.
+fn previously_synced_versions(rust_output_dir: &Path) -> HashMap<String, String> {
+    status::scan_existing_dirs(rust_output_dir)
+        .into_iter()
+        .filter_map(|(name, (version, _))| {
+            let meta = storage::read_meta(rust_output_dir, &name, &version)?;
+            Some((name, meta.version))
+        })
+        .collect()
 }
 
 #[derive(Debug)]
@@ -136,15 +713,13 @@ enum SyncOutcome {
     Synced(storage::SavedCrate),
     Cached(Option<storage::SavedCrate>),
     Skipped,
-    Error(SyncErrorKind),
+    Error(SyncFailure),
+    /// The job never started because Ctrl-C was received.
+    Interrupted,
 }
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
-
     let args: Vec<String> = std::env::args()
         .enumerate()
         .filter(|(i, arg)| !(*i == 1 && arg == "ai-fdocs"))
@@ -161,81 +736,536 @@ async fn main() {
         }
     };
 
+    let json_sync = matches!(
+        cli.command,
+        Commands::Sync {
+            format: OutputFormat::Json,
+            ..
+        }
+    );
+    init_tracing(cli.log_format, json_sync);
     if let Err(e) = run(cli).await {
         error!("{e}");
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
 
+/// `--config` as given, else `ai-fdocs.toml` found by walking up from the
+/// current directory (see `config::discover_config`). Relative paths
+/// (`Cargo.lock`, `output_dir`) resolve from the config's directory
+/// (`Config::root`), so a config found in a parent directory works like a
+/// run started there.
+fn resolve_config_path(explicit: Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(path) = explicit {
+        return Ok(path);
+    }
+    let cwd = std::env::current_dir()?;
+    let Some(found) = config::discover_config(&cwd, DEFAULT_CONFIG_PATH) else {
+        return Ok(PathBuf::from(DEFAULT_CONFIG_PATH));
+    };
+    if let Some(root) = found.parent().filter(|dir| *dir != cwd) {
+        info!(
+            "Using {} (running from {})",
+            found.display(),
+            root.display()
+        );
+    }
+    Ok(found)
+}
+
 async fn run(cli: Cli) -> Result<()> {
+    let profile = cli.profile.as_deref();
     match cli.command {
         Commands::Sync {
             config,
+            output_dir,
             mode,
             force,
-        } => run_sync(&config, mode, force).await,
+            fail_fast,
+            no_fallback,
+            no_header,
+            max_total_size,
+            only_missing,
+            crate_name,
+            repo,
+            version,
+            locked,
+            since_last_sync,
+            format,
+        } => {
+            let flags = SyncFlags {
+                mode,
+                force,
+                fail_fast,
+                no_fallback,
+                no_header,
+                max_total_size_mb: max_total_size,
+                only_missing,
+                crate_name,
+                repo,
+                version,
+                locked,
+                since_last_sync,
+                format,
+            };
+            run_sync(
+                &resolve_config_path(config)?,
+                ConfigOverrides {
+                    output_dir: output_dir.as_deref(),
+                    profile,
+                },
+                flags,
+            )
+            .await
+        }
         Commands::Status {
             config,
+            output_dir,
             mode,
             format,
-        } => run_status(&config, mode, format).await,
+            watch,
+        } => {
+            let color = cli.color.enabled();
+            run_status(
+                &resolve_config_path(config)?,
+                ConfigOverrides {
+                    output_dir: output_dir.as_deref(),
+                    profile,
+                },
+                mode,
+                format,
+                watch,
+                color,
+            )
+            .await
+        }
         Commands::Check {
             config,
+            output_dir,
             mode,
             format,
-        } => run_check(&config, mode, format).await,
-        Commands::Init { config, force } => run_init_command(&config, force).await,
+            diff,
+            summary_only,
+        } => {
+            let color = cli.color.enabled();
+            run_check(
+                &resolve_config_path(config)?,
+                ConfigOverrides {
+                    output_dir: output_dir.as_deref(),
+                    profile,
+                },
+                mode,
+                format,
+                diff,
+                summary_only,
+                color,
+            )
+            .await
+        }
+        Commands::Verify {
+            config,
+            output_dir,
+            format,
+        } => {
+            run_verify(
+                &resolve_config_path(config)?,
+                ConfigOverrides {
+                    output_dir: output_dir.as_deref(),
+                    profile,
+                },
+                format,
+            )
+            .await
+        }
+        Commands::Stats {
+            config,
+            output_dir,
+            format,
+        } => run_stats(
+            &resolve_config_path(config)?,
+            ConfigOverrides {
+                output_dir: output_dir.as_deref(),
+                profile,
+            },
+            format,
+        ),
+        Commands::Coverage { config, format } => run_coverage(
+            &resolve_config_path(config)?,
+            ConfigOverrides {
+                output_dir: None,
+                profile,
+            },
+            format,
+        ),
+        Commands::Index { config, output_dir } => run_index(
+            &resolve_config_path(config)?,
+            ConfigOverrides {
+                output_dir: output_dir.as_deref(),
+                profile,
+            },
+        ),
+        Commands::History {
+            config,
+            output_dir,
+            crate_name,
+            format,
+        } => run_history(
+            &resolve_config_path(config)?,
+            ConfigOverrides {
+                output_dir: output_dir.as_deref(),
+                profile,
+            },
+            crate_name.as_deref(),
+            format,
+        ),
+        Commands::Links {
+            config,
+            output_dir,
+            crate_name,
+            check_external,
+            format,
+        } => {
+            run_links(
+                &resolve_config_path(config)?,
+                ConfigOverrides {
+                    output_dir: output_dir.as_deref(),
+                    profile,
+                },
+                crate_name.as_deref(),
+                check_external,
+                format,
+            )
+            .await
+        }
+        Commands::Chunks {
+            config,
+            output_dir,
+            crate_name,
+            chunk_size,
+            overlap,
+        } => run_chunks(
+            &resolve_config_path(config)?,
+            ConfigOverrides {
+                output_dir: output_dir.as_deref(),
+                profile,
+            },
+            crate_name.as_deref(),
+            chunk_size,
+            overlap,
+        ),
+        Commands::Doctor { config } => run_doctor(&resolve_config_path(config)?, profile).await,
+        Commands::Explain { reason_code } => run_explain(reason_code.as_deref()),
+        Commands::Init {
+            config,
+            force,
+            registry_api_base,
+            template,
+            workspace,
+        } => {
+            run_init_command(
+                &config,
+                force,
+                registry_api_base.as_deref(),
+                template,
+                workspace,
+            )
+            .await
+        }
+    }
+}
+
+/// Per-run adjustments of the loaded config: `--output-dir` and `--profile`.
+#[derive(Debug, Clone, Copy, Default)]
+struct ConfigOverrides<'a> {
+    output_dir: Option<&'a Path>,
+    profile: Option<&'a str>,
+}
+
+fn load_config(config_path: &Path, overrides: ConfigOverrides<'_>) -> Result<Config> {
+    let mut config = Config::load(config_path, overrides.profile)?;
+    match overrides.profile {
+        Some(profile) => info!(
+            "Loaded config from {} (profile `{profile}`)",
+            config_path.display()
+        ),
+        None => info!("Loaded config from {}", config_path.display()),
+    }
+    config.apply_output_dir_override(overrides.output_dir);
+    if config.settings.sync_workspace_members {
+        match workspace::workspace_members(config.project_dir()) {
+            Ok(members) => {
+                let added = config.add_workspace_members(&members);
+                if !added.is_empty() {
+                    info!("Added workspace member(s): {}", added.join(", "));
+                }
+            }
+            Err(e) => warn!("settings.sync_workspace_members: {e}"),
+        }
+    }
+    Ok(config)
+}
+
+/// `[crates."*"]`: adds every `Cargo.lock` crate without an entry, with its
+/// repo from `init::WILDCARD_REPOS_FILE` or, with `lookup`, crates.io (new
+/// results are saved there). Crates whose repo stays unknown land in
+/// `config.wildcard_unresolved`, so prune keeps their docs.
+async fn expand_wildcard_crates(config: &mut Config, lookup: bool) {
+    if config.wildcard.is_none() {
+        return;
+    }
+    let versions = match resolver::resolve_versions(&config.cargo_lock_path()) {
+        Ok((versions, _)) => versions,
+        Err(e) => {
+            warn!("crates.\"*\": {e}");
+            return;
+        }
+    };
+    let names: BTreeSet<String> = versions
+        .into_keys()
+        .filter(|name| !config.crates.contains_key(name))
+        .collect();
+    let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
+    let mut known = init::load_wildcard_repos(&rust_dir);
+    let uncached: Vec<String> = names
+        .iter()
+        .filter(|name| !known.contains_key(*name))
+        .cloned()
+        .collect();
+
+    let mut unresolved = BTreeSet::new();
+    if !uncached.is_empty() && lookup {
+        let http = HttpOptions::from_settings(&config.settings);
+        let concurrency = config.settings.sync_concurrency;
+        match init::lookup_github_repos(uncached.clone(), &http, concurrency).await {
+            Ok(found) => {
+                if !found.repos.is_empty() {
+                    known.extend(found.repos);
+                    if let Err(e) = init::save_wildcard_repos(&rust_dir, &known) {
+                        let file = init::WILDCARD_REPOS_FILE;
+                        warn!("crates.\"*\": cannot save {file}: {e}");
+                    }
+                }
+                unresolved = found.failed;
+            }
+            Err(e) => {
+                warn!("crates.\"*\": {e}");
+                unresolved = uncached.into_iter().collect();
+            }
+        }
+    } else if !uncached.is_empty() {
+        info!(
+            "{} crate(s) from crates.\"*\" not looked up yet; `sync` resolves them",
+            uncached.len()
+        );
+        unresolved = uncached.into_iter().collect();
+    }
+
+    let repos: BTreeMap<String, String> = names
+        .iter()
+        .filter_map(|name| Some((name.clone(), known.get(name)?.clone()?)))
+        .collect();
+    let added = config.add_wildcard_crates(&repos);
+    if !added.is_empty() {
+        info!("Added {} crate(s) from crates.\"*\"", added.len());
+    }
+    config.wildcard_unresolved = unresolved;
+}
+
+/// Replaces the lockfile version of each `path` crate with the one in its
+/// `Cargo.toml`; crates whose manifest cannot be read keep the lockfile one.
+fn insert_workspace_versions(config: &Config, versions: &mut HashMap<String, String>) {
+    for (crate_name, crate_doc) in &config.crates {
+        let Some(dir) = &crate_doc.path else {
+            continue;
+        };
+        match workspace::member_version(&config.root, dir) {
+            Ok(version) => {
+                versions.insert(crate_name.clone(), version);
+            }
+            Err(e) => warn!("  ⚠ cannot read the version of {crate_name}: {e}"),
+        }
+    }
+}
+
+/// `sync` command-line flags, applied on top of the loaded config.
+#[derive(Debug, Default)]
+struct SyncFlags {
+    mode: Option<SyncModeArg>,
+    force: bool,
+    fail_fast: bool,
+    no_fallback: bool,
+    no_header: bool,
+    max_total_size_mb: Option<u64>,
+    only_missing: bool,
+    crate_name: Option<String>,
+    repo: Option<String>,
+    version: Option<String>,
+    locked: bool,
+    since_last_sync: bool,
+    format: OutputFormat,
+}
+
+/// `sync --crate [--repo]`: with `--repo` the crate is added to (or repointed
+/// in) this run's config only. Returns the set of crates to sync, if restricted.
+fn apply_crate_override(config: &mut Config, flags: &SyncFlags) -> Result<Option<HashSet<String>>> {
+    let Some(crate_name) = flags.crate_name.as_deref() else {
+        return Ok(None);
+    };
+
+    if let Some(repo) = flags.repo.as_deref() {
+        if repo.split('/').filter(|part| !part.is_empty()).count() != 2 {
+            return Err(AiDocsError::Other(format!(
+                "--repo must be in owner/repo form, got '{repo}'"
+            )));
+        }
+        config
+            .crates
+            .entry(crate_name.to_string())
+            .or_default()
+            .repo = Some(repo.to_string());
+    } else if !config.crates.contains_key(crate_name) {
+        return Err(AiDocsError::Other(format!(
+            "crate '{crate_name}' is not in the config; pass --repo owner/repo to sync it once"
+        )));
+    }
+
+    Ok(Some(HashSet::from([crate_name.to_string()])))
+}
+
+/// Crates to sync: the `--crate` selection, narrowed to `missing` with `--only-missing`.
+fn restrict_jobs(
+    selected: Option<HashSet<String>>,
+    missing: Option<HashSet<String>>,
+) -> Option<HashSet<String>> {
+    match (selected, missing) {
+        (Some(selected), Some(missing)) => Some(selected.intersection(&missing).cloned().collect()),
+        (selected, missing) => selected.or(missing),
     }
 }
 
 async fn run_sync(
     config_path: &Path,
-    mode_override: Option<SyncModeArg>,
-    force: bool,
+    overrides: ConfigOverrides<'_>,
+    flags: SyncFlags,
 ) -> Result<()> {
-    let config = Config::load(config_path)?;
-    info!("Loaded config from {}", config_path.display());
+    reject_status_formats(flags.format, "sync")?;
+    let mut config = load_config(config_path, overrides)?;
+    expand_wildcard_crates(&mut config, true).await;
+    if flags.fail_fast {
+        config.settings.fail_fast = true;
+    }
+    if flags.no_fallback {
+        config.settings.fallback_policy = FallbackPolicy::Error;
+    }
+    if flags.no_header {
+        config.settings.inject_header = false;
+    }
+    if flags.max_total_size_mb.is_some() {
+        config.settings.max_total_size_mb = flags.max_total_size_mb;
+    }
+    let selected = apply_crate_override(&mut config, &flags)?;
+    let force = flags.force;
 
-    let sync_mode = resolve_sync_mode(mode_override, config.settings.sync_mode);
+    let sync_mode = resolve_sync_mode(flags.mode, config.settings.sync_mode);
     info!("Resolved sync mode: {}", sync_mode.as_str());
+    config.settings.sync_mode = sync_mode;
+    let lock_path = lock::lock_path(config_path);
     if matches!(sync_mode, SyncMode::LatestDocs) {
-        return run_sync_latest_docs(config, force).await;
+        if flags.version.is_some() {
+            return Err(AiDocsError::Other(
+                "--version is not supported in latest-docs mode".to_string(),
+            ));
+        }
+        if flags.locked {
+            return Err(AiDocsError::Other(
+                "--locked is not supported in latest-docs mode".to_string(),
+            ));
+        }
+        return run_sync_latest_docs(config, &lock_path, &flags, selected).await;
     }
 
     match config.settings.docs_source {
         DocsSource::GitHub => info!("Using docs source: github"),
+        DocsSource::Git => info!("Using docs source: git (shallow clone)"),
     }
 
-    let cargo_lock_path = PathBuf::from("Cargo.lock");
-    let rust_versions = resolver::resolve_cargo_versions(&cargo_lock_path)?;
+    let cargo_lock_path = config.cargo_lock_path();
+    let mut rust_versions = match resolver::resolve_versions(&cargo_lock_path) {
+        Err(AiDocsError::CargoLockNotFound) if flags.version.is_some() => Default::default(),
+        result => result?.0,
+    };
+    insert_workspace_versions(&config, &mut rust_versions);
+    if let (Some(crate_name), Some(version)) = (&flags.crate_name, &flags.version) {
+        rust_versions.insert(crate_name.clone(), version.clone());
+    }
 
     let rust_output_dir = storage::rust_output_dir(&config.settings.output_dir);
+    // Read before pruning removes the old version dirs.
+    let previous_versions = flags
+        .since_last_sync
+        .then(|| Arc::new(previously_synced_versions(&rust_output_dir)));
     if config.settings.prune {
         storage::prune(&rust_output_dir, &config, &rust_versions)?;
     }
 
-    let fetcher = Arc::new(GitHubFetcher::new());
+    let missing = if flags.only_missing {
+        let statuses = collect_status(&config, &rust_versions, &rust_output_dir).await;
+        Some(missing_crate_names(&statuses))
+    } else {
+        None
+    };
+    let only = restrict_jobs(selected, missing);
+
+    let http = HttpOptions::from_settings(&config.settings);
+    let mut opts = SyncOptions::from_config(&config, force);
+    opts.previous_versions = previous_versions;
+    if flags.locked {
+        opts.locked = Some(Arc::new(lock::read_lock(&lock_path)?));
+        info!("Syncing refs pinned in {}", lock_path.display());
+    }
+    let fetcher = Arc::new(GitHubFetcher::new(&http)?);
+    if !config.settings.min_github_token_scopes.is_empty() {
+        fetcher
+            .preflight_auth(&config.settings.min_github_token_scopes)
+            .await;
+    }
+    let latest_fetcher = Arc::new(
+        LatestDocsFetcher::new(&http)
+            .with_sparse_index(config.settings.crates_io_sparse_index)
+            .with_max_links(config.settings.docsrs_max_links),
+    );
     let mut saved_crates = Vec::new();
     let mut stats = SyncStats::default();
 
     let outcomes = run_orchestrated_sync(
         &config,
-        config.crates.iter().map(|(n, c)| (n.clone(), c.clone())).collect(),
+        sync_jobs(&config, only.as_ref()),
         |crate_name, crate_doc| {
             let rust_output_dir = rust_output_dir.clone();
             let rust_versions = rust_versions.clone();
             let fetcher = Arc::clone(&fetcher);
-            let force = force;
-            let max_file_size_kb = max_file_size_kb;
+            let latest_fetcher = Arc::clone(&latest_fetcher);
+            let opts = opts.clone();
             async move {
+                if crate_doc.sync_mode(opts.sync_mode) == SyncMode::LatestDocs {
+                    return sync_one_crate_latest(
+                        rust_output_dir,
+                        latest_fetcher,
+                        fetcher,
+                        crate_name,
+                        crate_doc,
+                        opts,
+                    )
+                    .await;
+                }
                 sync_one_crate(
                     rust_output_dir,
                     rust_versions,
                     fetcher,
+                    latest_fetcher,
                     crate_name,
                     crate_doc,
-                    force,
-                    max_file_size_kb,
+                    opts,
                 )
                 .await
             }
@@ -243,8 +1273,8 @@ async fn run_sync(
     )
     .await;
 
-    for result in outcomes {
-        match result {
+    for (crate_name, outcome) in outcomes {
+        match outcome {
             SyncOutcome::Synced(saved) => {
                 saved_crates.push(saved);
                 stats.synced += 1;
@@ -256,11 +1286,19 @@ async fn run_sync(
                 stats.cached += 1;
             }
             SyncOutcome::Skipped => stats.skipped += 1,
-            SyncOutcome::Error(kind) => stats.record_error(kind),
+            SyncOutcome::Error(failure) => stats.record_error(crate_name, failure),
+            SyncOutcome::Interrupted => stats.interrupted += 1,
         }
     }
 
+    let saved_crates = index::merge_saved_crates(&rust_output_dir, &config, saved_crates);
     index::generate_index(&rust_output_dir, &saved_crates)?;
+    if !flags.locked {
+        write_docs_lock(&lock_path, &rust_output_dir, &config, &saved_crates)?;
+    }
+    print_sync_summary(&stats, sync_mode, flags.format)?;
+    stats.ensure_not_interrupted()?;
+    opts.download_guard.ensure_within_limit()?;
 
     info!(
         "✅ Sync complete: {} synced, {} cached, {} skipped, {} errors",
@@ -281,43 +1319,111 @@ async fn run_sync(
     Ok(())
 }
 
-async fn run_sync_latest_docs(config: Config, force: bool) -> Result<()> {
+async fn run_sync_latest_docs(
+    config: Config,
+    lock_path: &Path,
+    flags: &SyncFlags,
+    selected: Option<HashSet<String>>,
+) -> Result<()> {
     info!("Using docs source: crates.io + docs.rs (with GitHub fallback)");
 
     let rust_output_dir = storage::rust_output_dir(&config.settings.output_dir);
-    let github_fetcher = Arc::new(GitHubFetcher::new());
-    let latest_fetcher = Arc::new(LatestDocsFetcher::new());
+    let missing = if flags.only_missing {
+        let statuses =
+            collect_mode_statuses(&config, SyncMode::LatestDocs, &rust_output_dir, None).await?;
+        Some(missing_crate_names(&statuses))
+    } else {
+        None
+    };
+    // Crates with `version_source = "lockfile"` are synced at their
+    // `Cargo.lock` version.
+    let rust_versions = if config
+        .crates
+        .values()
+        .any(|crate_doc| crate_doc.sync_mode(SyncMode::LatestDocs) != SyncMode::LatestDocs)
+    {
+        let (mut rust_versions, _) = resolver::resolve_versions(&config.cargo_lock_path())?;
+        insert_workspace_versions(&config, &mut rust_versions);
+        rust_versions
+    } else {
+        HashMap::new()
+    };
+    let only = restrict_jobs(selected, missing);
+    let http = HttpOptions::from_settings(&config.settings);
+    let mut opts = SyncOptions::from_config(&config, flags.force);
+    opts.previous_versions = flags
+        .since_last_sync
+        .then(|| Arc::new(previously_synced_versions(&rust_output_dir)));
+    let github_fetcher = Arc::new(GitHubFetcher::new(&http)?);
+    if !config.settings.min_github_token_scopes.is_empty() {
+        github_fetcher
+            .preflight_auth(&config.settings.min_github_token_scopes)
+            .await;
+    }
+    let latest_fetcher = Arc::new(
+        LatestDocsFetcher::new(&http)
+            .with_sparse_index(config.settings.crates_io_sparse_index)
+            .with_max_links(config.settings.docsrs_max_links),
+    );
 
     let mut saved_crates = Vec::new();
     let mut stats = SyncStats::default();
 
     let outcomes = run_orchestrated_sync(
         &config,
-        config.crates.clone().into_iter().collect(),
+        sync_jobs(&config, only.as_ref()),
         |crate_name, crate_doc| {
             let rust_output_dir = rust_output_dir.clone();
+            let rust_versions = rust_versions.clone();
             let github_fetcher = Arc::clone(&github_fetcher);
             let latest_fetcher = Arc::clone(&latest_fetcher);
-            let max_file_size_kb = config.settings.max_file_size_kb;
-            let ttl = config.settings.latest_ttl_hours;
+            let opts = opts.clone();
             async move {
-                sync_one_crate_latest(
-                    rust_output_dir,
-                    latest_fetcher,
+                if crate_doc.sync_mode(opts.sync_mode) != SyncMode::LatestDocs {
+                    return sync_one_crate(
+                        rust_output_dir,
+                        rust_versions,
+                        github_fetcher,
+                        latest_fetcher,
+                        crate_name,
+                        crate_doc,
+                        opts,
+                    )
+                    .await;
+                }
+                let upstream_name = crate_doc.upstream_name(&crate_name).to_string();
+                let fetch_crate_metadata = opts.fetch_crate_metadata;
+                let outcome = sync_one_crate_latest(
+                    rust_output_dir.clone(),
+                    Arc::clone(&latest_fetcher),
                     github_fetcher,
                     crate_name,
                     crate_doc,
-                    force,
-                    max_file_size_kb,
-                    ttl,
+                    opts,
                 )
-                .await
+                .await;
+                let needs_metadata = match &outcome {
+                    SyncOutcome::Synced(saved) => Some(saved),
+                    // Cached before `fetch_crate_metadata` was turned on.
+                    SyncOutcome::Cached(Some(saved))
+                        if storage::read_meta(&rust_output_dir, &saved.name, &saved.version)
+                            .is_some_and(|m| m.categories.is_empty() && m.keywords.is_empty()) =>
+                    {
+                        Some(saved)
+                    }
+                    _ => None,
+                };
+                if let Some(saved) = needs_metadata.filter(|_| fetch_crate_metadata) {
+                    record_crate_metadata(&latest_fetcher, &rust_output_dir, &upstream_name, saved)
+                        .await;
+                }
+                outcome
             }
         },
     )
     .await;
 
-    for outcome in outcomes {
+    for (crate_name, outcome) in outcomes {
         match outcome {
             SyncOutcome::Synced(saved) => {
                 saved_crates.push(saved);
@@ -330,11 +1436,17 @@ async fn run_sync_latest_docs(config: Config, force: bool) -> Result<()> {
                 stats.cached += 1;
             }
             SyncOutcome::Skipped => stats.skipped += 1,
-            SyncOutcome::Error(kind) => stats.record_error(kind),
+            SyncOutcome::Error(failure) => stats.record_error(crate_name, failure),
+            SyncOutcome::Interrupted => stats.interrupted += 1,
         }
     }
 
+    let saved_crates = index::merge_saved_crates(&rust_output_dir, &config, saved_crates);
     index::generate_index(&rust_output_dir, &saved_crates)?;
+    write_docs_lock(lock_path, &rust_output_dir, &config, &saved_crates)?;
+    print_sync_summary(&stats, SyncMode::LatestDocs, flags.format)?;
+    stats.ensure_not_interrupted()?;
+    opts.download_guard.ensure_within_limit()?;
     info!(
         "✅ Latest-docs sync complete: {} synced, {} cached, {} skipped, {} errors",
         stats.synced, stats.cached, stats.skipped, stats.errors
@@ -343,28 +1455,228 @@ async fn run_sync_latest_docs(config: Config, force: bool) -> Result<()> {
     Ok(())
 }
 
+/// Prints the JSON summary for `sync --format json`; the table format only
+/// logs it.
+fn print_sync_summary(stats: &SyncStats, mode: SyncMode, format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        let json = stats
+            .format_json(mode)
+            .map_err(|e| AiDocsError::Other(format!("failed to serialize sync JSON: {e}")))?;
+        println!("{json}");
+    }
+    Ok(())
+}
+
+/// Rewrites `ai-fdocs.lock` from the crates saved (or cached) by this run.
+fn write_docs_lock(
+    lock_path: &Path,
+    rust_output_dir: &Path,
+    config: &Config,
+    saved_crates: &[storage::SavedCrate],
+) -> Result<()> {
+    let previous = lock::read_lock(lock_path).ok();
+    let docs_lock = lock::build_lock(rust_output_dir, saved_crates, previous, |name| {
+        config.crates.contains_key(name)
+    });
+    lock::write_lock(lock_path, &docs_lock)
+}
+
+/// `settings.fetch_crate_metadata`: failures only warn, the docs are already saved.
+async fn record_crate_metadata(
+    latest_fetcher: &LatestDocsFetcher,
+    rust_output_dir: &Path,
+    upstream_name: &str,
+    saved: &storage::SavedCrate,
+) {
+    let result = match latest_fetcher.fetch_crate_metadata(upstream_name).await {
+        Ok(metadata) => storage::record_crate_metadata(
+            rust_output_dir,
+            &saved.name,
+            &saved.version,
+            metadata.categories,
+            metadata.keywords,
+        ),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(
+            "  ⚠ failed to record crates.io metadata for {}@{}: {e}",
+            saved.name, saved.version
+        );
+    }
+}
+
+/// Names of crates whose status is `Missing` (`sync --only-missing`).
+fn missing_crate_names(statuses: &[crate::status::CrateStatus]) -> HashSet<String> {
+    statuses
+        .iter()
+        .filter(|s| s.status == DocsStatus::Missing)
+        .map(|s| s.crate_name.clone())
+        .collect()
+}
+
+/// Sync jobs for every configured crate, or only for `only` when given.
+fn sync_jobs(
+    config: &Config,
+    only: Option<&HashSet<String>>,
+) -> Vec<(String, crate::config::CrateDoc)> {
+    if let Some(only) = only {
+        info!(
+            "Syncing {} of {} configured crate(s)",
+            only.len(),
+            config.crates.len()
+        );
+    }
+    config
+        .crates
+        .iter()
+        .filter(|(name, _)| only.is_none_or(|names| names.contains(*name)))
+        .map(|(name, doc)| (name.clone(), doc.clone()))
+        .collect()
+}
+
+/// `API.md` (or `API.html`) for `crate@version`, with the repo's README in
+/// place of the docs.rs-rendered one when `settings.prefer_source_readme` is
+/// set and the format is markdown.
+async fn fetch_latest_artifact(
+    latest_fetcher: &LatestDocsFetcher,
+    github_fetcher: &GitHubFetcher,
+    crate_name: &str,
+    crate_doc: &crate::config::CrateDoc,
+    version: &str,
+    opts: &SyncOptions,
+) -> Result<DocsRsArtifact> {
+    let source_readme = if opts.prefer_source_readme && opts.docsrs_format == DocsRsFormat::Markdown
+    {
+        fetch_source_readme(github_fetcher, crate_name, crate_doc, version, opts).await
+    } else {
+        None
+    };
+    latest_fetcher
+        .fetch_api_artifact(
+            crate_doc.upstream_name(crate_name),
+            version,
+            opts.max_file_size_kb,
+            opts.docsrs_format,
+            source_readme.as_ref(),
+        )
+        .await
+}
+
+/// README at the exact release tag of `crate@version`. Missing repos, branch
+/// fallbacks and fetch failures yield `None` so docs.rs content is used.
+async fn fetch_source_readme(
+    github_fetcher: &GitHubFetcher,
+    crate_name: &str,
+    crate_doc: &crate::config::CrateDoc,
+    version: &str,
+    opts: &SyncOptions,
+) -> Option<SourceReadme> {
+    let repo = crate_doc.github_repo()?;
+    let resolved = match github_fetcher
+        .resolve_ref(
+            repo,
+            crate_doc.upstream_name(crate_name),
+            version,
+            None,
+            opts.follow_repo_renames,
+        )
+        .await
+    {
+        Ok(resolved) if !resolved.is_fallback => resolved,
+        Ok(_) => {
+            warn!("  ⚠ no release tag for {crate_name}@{version}; using the docs.rs README");
+            return None;
+        }
+        Err(e) => {
+            warn!("  ⚠ failed to resolve ref for {crate_name}@{version}: {e}; using the docs.rs README");
+            return None;
+        }
+    };
+    let repo = resolved.renamed_to.as_deref().unwrap_or(repo);
+    let requests = build_requests(
+        crate_doc.subpath.as_deref(),
+        None,
+        &["README.md".to_string()],
+        &[],
+    );
+    let fetched = github_fetcher
+        .fetch_files(
+            repo,
+            &resolved.git_ref,
+            &requests,
+            &crate_doc.request_headers(),
+            &RetryBudget::new(opts.max_retries_per_crate),
+        )
+        .await
+        .into_iter()
+        .next()?;
+    match fetched {
+        Ok(file) => Some(SourceReadme {
+            markdown: file.content,
+            source_url: file.source_url,
+        }),
+        Err(e) => {
+            warn!("  ⚠ failed to fetch README for {crate_name}@{version}: {e}; using the docs.rs README");
+            None
+        }
+    }
+}
+
 async fn sync_one_crate_latest(
     rust_output_dir: PathBuf,
     latest_fetcher: Arc<LatestDocsFetcher>,
     github_fetcher: Arc<GitHubFetcher>,
     crate_name: String,
     crate_doc: crate::config::CrateDoc,
-    force: bool,
-    max_file_size_kb: usize,
-    latest_ttl_hours: usize,
+    mut opts: SyncOptions,
 ) -> SyncOutcome {
-    let version = match latest_fetcher.resolve_latest_version(&crate_name).await {
+    if let Some(dir) = crate_doc.path.clone() {
+        return sync_one_crate_workspace(
+            rust_output_dir,
+            &github_fetcher,
+            crate_name,
+            crate_doc,
+            &dir,
+            opts,
+        )
+        .await;
+    }
+    let upstream_name = crate_doc.upstream_name(&crate_name).to_string();
+    let version = match latest_fetcher
+        .resolve_latest_version(&upstream_name, crate_doc.version_req().as_ref())
+        .await
+    {
         Ok(v) => v,
         Err(e) => {
             warn!("  ✗ failed to resolve latest version for {crate_name}: {e}");
-            return SyncOutcome::Error(e.sync_kind());
+            return SyncOutcome::Error(SyncFailure::from(&e));
         }
     };
+    tracing::Span::current().record("version", version.as_str());
+    opts.apply_since_last_sync(&crate_name, &version);
 
-    if !force && storage::is_cached(&rust_output_dir, &crate_name, &version, &crate_doc) {
-        if let Some(meta) = storage::read_meta(&rust_output_dir, &crate_name, &version) {
-            if is_latest_cache_fresh(&meta.fetched_at, latest_ttl_hours) {
+    if !opts.force
+        && storage::is_cached(
+            &rust_output_dir,
+            &crate_name,
+            &version,
+            &crate_doc,
+            &opts.hashed_settings(),
+            None,
+        )
+    {
+        if let Some(meta) =
+            storage::read_meta(&rust_output_dir, &crate_name, &version).filter(|meta| {
+                crate_doc.docs_format(SyncMode::LatestDocs) != DocsFormat::DocsRs
+                    || meta.artifact_path.as_deref().unwrap_or("API.md")
+                        == opts.docsrs_format.artifact_file()
+            })
+        {
+            let ttl_hours = crate_doc.latest_ttl_hours(opts.latest_ttl_hours);
+            if is_latest_cache_fresh(&meta.fetched_at, ttl_hours) {
                 info!("  ⏭ {crate_name}@{version}: cached (TTL valid), skipping");
+                storage::upgrade_meta_schema(&rust_output_dir, &crate_name, &version);
                 let cached =
                     storage::read_cached_info(&rust_output_dir, &crate_name, &version, &crate_doc);
                 return SyncOutcome::Cached(cached);
@@ -373,28 +1685,105 @@ async fn sync_one_crate_latest(
         }
     }
 
-    match latest_fetcher
-        .fetch_api_markdown(&crate_name, &version, max_file_size_kb)
-        .await
+    match crate_doc.docs_format(SyncMode::LatestDocs) {
+        DocsFormat::DocsRs => {
+            sync_one_crate_docsrs(
+                rust_output_dir,
+                &latest_fetcher,
+                github_fetcher,
+                crate_name,
+                crate_doc,
+                version,
+                opts,
+            )
+            .await
+        }
+        DocsFormat::Hybrid => {
+            sync_one_crate_hybrid(
+                rust_output_dir,
+                github_fetcher,
+                &latest_fetcher,
+                crate_name,
+                crate_doc,
+                version,
+                opts,
+            )
+            .await
+        }
+        DocsFormat::GithubReadme => {
+            sync_one_crate_from_github(
+                rust_output_dir,
+                github_fetcher,
+                crate_name,
+                crate_doc,
+                version,
+                GithubSync::Plain,
+                opts,
+            )
+            .await
+        }
+    }
+}
+
+/// Saves the docs.rs crate page for `version`, falling back to GitHub when
+/// docs.rs has no usable build.
+async fn sync_one_crate_docsrs(
+    rust_output_dir: PathBuf,
+    latest_fetcher: &LatestDocsFetcher,
+    github_fetcher: Arc<GitHubFetcher>,
+    crate_name: String,
+    crate_doc: crate::config::CrateDoc,
+    version: String,
+    opts: SyncOptions,
+) -> SyncOutcome {
+    if !opts.download_guard.allows_fetch(&crate_name) {
+        return SyncOutcome::Error(SyncFailure::download_limit());
+    }
+
+    let started = Instant::now();
+    match fetch_latest_artifact(
+        latest_fetcher,
+        &github_fetcher,
+        &crate_name,
+        &crate_doc,
+        &version,
+        &opts,
+    )
+    .await
     {
-        Ok(artifact) => match storage::save_latest_api_markdown(
-            &rust_output_dir,
-            &crate_name,
-            &version,
-            &artifact.markdown,
-            &artifact.docsrs_input_url,
-            artifact.truncated,
-            &crate_doc,
-        ) {
-            Ok(saved) => SyncOutcome::Synced(saved),
-            Err(e) => {
-                warn!("  ✗ failed to save docs.rs artifact for {crate_name}@{version}: {e}");
-                SyncOutcome::Error(e.sync_kind())
+        Ok(artifact)
+            if !opts
+                .download_guard
+                .charge(&crate_name, &version, artifact.content.len()) =>
+        {
+            SyncOutcome::Error(SyncFailure::download_limit())
+        }
+        Ok(artifact) => {
+            tracing::Span::current().record("source_kind", "docsrs");
+            match storage::save_latest_artifact(
+                &rust_output_dir,
+                &crate_name,
+                &version,
+                &artifact,
+                opts.max_file_size_kb,
+                opts.max_tokens_per_crate,
+                Some(FetchMetrics::since(started, artifact.content.len())),
+                &crate_doc,
+                &opts.hashed_settings(),
+                opts.locked_content_sha256(&crate_name, &version),
+            ) {
+                Ok(saved) => SyncOutcome::Synced(saved),
+                Err(e) => {
+                    warn!("  ✗ failed to save docs.rs artifact for {crate_name}@{version}: {e}");
+                    SyncOutcome::Error(SyncFailure::from(&e))
+                }
             }
-        },
+        }
         Err(e) if is_docsrs_fallback_eligible(&e) => {
-            warn!(
-                "  ⚠ docs.rs unavailable for {crate_name}@{version}: {e}; trying GitHub fallback"
+            emit_sync_warning(
+                &crate_name,
+                &version,
+                &format!("docs.rs unavailable: {e}; trying GitHub fallback"),
             );
             sync_one_crate_from_github(
                 rust_output_dir,
@@ -402,14 +1791,38 @@ async fn sync_one_crate_latest(
                 crate_name,
                 crate_doc,
                 version,
-                max_file_size_kb,
-                Some("github_fallback"),
+                GithubSync::LatestFallback,
+                opts,
             )
             .await
         }
         Err(e) => {
             warn!("  ✗ docs.rs fetch failed for {crate_name}@{version}: {e}");
-            SyncOutcome::Error(e.sync_kind())
+            SyncOutcome::Error(SyncFailure::from(&e))
+        }
+    }
+}
+
+/// Why a crate is synced from GitHub; decides `source_kind` and whether
+/// docs.rs supplies the README.
+#[derive(Debug)]
+enum GithubSync {
+    /// Every file comes from GitHub (lockfile mode, or `docs_format =
+    /// "github_readme"`).
+    Plain,
+    /// latest-docs mode after docs.rs was unavailable.
+    LatestFallback,
+    /// Hybrid mode: the docs.rs API markdown, when it could be fetched,
+    /// replaces the GitHub README; everything else comes from GitHub.
+    Hybrid(Option<DocsRsArtifact>),
+}
+
+impl GithubSync {
+    fn source_kind(&self) -> &'static str {
+        match self {
+            Self::Plain | Self::Hybrid(None) => "github",
+            Self::LatestFallback => "github_fallback",
+            Self::Hybrid(Some(_)) => "hybrid_docsrs_github",
         }
     }
 }
@@ -420,61 +1833,341 @@ async fn sync_one_crate_from_github(
     crate_name: String,
     crate_doc: crate::config::CrateDoc,
     version: String,
-    max_file_size_kb: usize,
-    source_kind_override: Option<&'static str>,
+    sync: GithubSync,
+    opts: SyncOptions,
 ) -> SyncOutcome {
-    let Some(repo) = crate_doc.github_repo().map(str::to_string) else {
+    let Some((repo, use_git)) = repo_location(&crate_doc, opts.docs_source) else {
         warn!("Crate '{crate_name}' has no GitHub repo in config");
-        if source_kind_override.is_some() {
-            return SyncOutcome::Error(SyncErrorKind::Other);
+        if matches!(sync, GithubSync::LatestFallback) {
+            return SyncOutcome::Error(SyncFailure::new(
+                SyncErrorKind::Other,
+                "no GitHub repo in config",
+            ));
         }
         return SyncOutcome::Skipped;
     };
+    if !opts.download_guard.allows_fetch(&crate_name) {
+        return SyncOutcome::Error(SyncFailure::download_limit());
+    }
 
-    let resolved = match fetcher
-        .resolve_ref(&repo, &crate_name, version.as_str())
-        .await
+    let started = Instant::now();
+    let locked = opts
+        .locked
+        .as_deref()
+        .and_then(|docs_lock| docs_lock.get(&crate_name, &version));
+    let (resolved, known_commit) = match locked {
+        // The tag or branch may have moved since; only a commit is reproducible.
+        Some(entry) if entry.commit.is_none() => {
+            warn!(
+                "  ✗ {crate_name}@{version}: no commit pinned in {}; run sync without --locked to update it",
+                lock::LOCK_FILE
+            );
+            return SyncOutcome::Error(SyncFailure::new(
+                SyncErrorKind::Other,
+                format!("no commit pinned in {}", lock::LOCK_FILE),
+            ));
+        }
+        Some(entry) => (
+            ResolvedRef {
+                git_ref: entry.git_ref.clone(),
+                is_fallback: entry.is_fallback,
+                fallback_reason: None,
+                renamed_to: None,
+            },
+            entry.commit.clone(),
+        ),
+        None if use_git => match git::ls_remote(&repo).await.and_then(|refs| {
+            refs.resolve(
+                &repo,
+                crate_doc.upstream_name(&crate_name),
+                &version,
+                crate_doc.doc_branch.as_deref(),
+            )
+        }) {
+            Ok((resolved, sha)) => (resolved, Some(sha)),
+            Err(e) => {
+                warn!("  ✗ failed to resolve ref for {crate_name}@{version}: {e}");
+                return SyncOutcome::Error(SyncFailure::from(&e));
+            }
+        },
+        None => match fetcher
+            .resolve_ref(
+                &repo,
+                crate_doc.upstream_name(&crate_name),
+                version.as_str(),
+                crate_doc.doc_branch.as_deref(),
+                opts.follow_repo_renames,
+            )
+            .await
+        {
+            Ok(r) => (r, None),
+            Err(e) => {
+                warn!("  ✗ failed to resolve ref for {crate_name}@{version}: {e}");
+                return SyncOutcome::Error(SyncFailure::from(&e));
+            }
+        },
+    };
+    if !fallback_allowed(opts.fallback_policy, &resolved, &crate_name, &version) {
+        return SyncOutcome::Error(SyncFailure::new(
+            SyncErrorKind::NotFound,
+            format!("no tag found for {version}; refused by fallback policy \"error\""),
+        ));
+    }
+    let repo = resolved.renamed_to.clone().unwrap_or(repo);
+
+    let commit_sha = if locked.is_some() || use_git {
+        known_commit
+    } else {
+        match fetcher.resolve_commit_sha(&repo, &resolved.git_ref).await {
+            Ok(sha) => Some(sha),
+            Err(e) => {
+                warn!(
+                    "  ⚠ could not resolve commit of {} for {crate_name}@{version}: {e}",
+                    resolved.git_ref
+                );
+                None
+            }
+        }
+    };
+    // Locked syncs fetch the pinned commit, so a moved tag or branch cannot
+    // change the result; the recorded ref stays the tag/branch name.
+    let fetch_ref = match (locked, commit_sha.as_deref()) {
+        (Some(_), Some(sha)) => sha.to_string(),
+        _ => resolved.git_ref.clone(),
+    };
+
+    let checkout = if use_git {
+        match GitCheckout::shallow_clone(&repo, &fetch_ref).await {
+            Ok(checkout) => Some(checkout),
+            Err(e) => {
+                warn!("  ✗ failed to clone {repo} at {fetch_ref} for {crate_name}@{version}: {e}");
+                return SyncOutcome::Error(SyncFailure::from(&e));
+            }
+        }
+    } else {
+        None
+    };
+    // What was actually checked out wins over what the remote advertised.
+    let commit_sha = checkout
+        .as_ref()
+        .map(|checkout| checkout.commit.clone())
+        .or(commit_sha);
+    let access = RepoAccess {
+        repo: &repo,
+        git_ref: &fetch_ref,
+        github: &fetcher,
+        local: checkout.as_ref().map(GitCheckout::tree),
+    };
+
+    let inferred_subpath = if opts.infer_subpath
+        && crate_doc.subpath.is_none()
+        && crate_doc.effective_files().is_none()
     {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("  ✗ failed to resolve ref for {crate_name}@{version}: {e}");
-            return SyncOutcome::Error(e.sync_kind());
+        let candidates = subpath_candidates(crate_doc.upstream_name(&crate_name), None);
+        match access.infer_subpath(&candidates).await {
+            Ok(Some(dir)) => {
+                info!("  🔎 {crate_name}: inferred subpath `{dir}`");
+                Some(dir)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("  ⚠ subpath inference failed for {crate_name}@{version}: {e}");
+                None
+            }
         }
+    } else {
+        None
     };
+    let subpath = crate_doc.subpath.as_deref().or(inferred_subpath.as_deref());
 
-    let requests = build_requests(crate_doc.subpath.as_deref(), crate_doc.effective_files());
-    let results = fetcher
-        .fetch_files(&repo, &resolved.git_ref, &requests)
-        .await;
+    let source_kind = sync.source_kind();
+    let docsrs_readme = match sync {
+        GithubSync::Hybrid(artifact) => artifact,
+        GithubSync::Plain | GithubSync::LatestFallback => None,
+    };
+
+    let requests = build_requests(
+        subpath,
+        crate_doc.effective_files(),
+        &opts.default_files,
+        &opts.changelog_candidates,
+    );
+    let mut requests = expand_directory_requests(
+        &access,
+        requests,
+        &opts.include_extensions,
+        &crate_name,
+        &version,
+    )
+    .await;
+    if docsrs_readme.is_some() {
+        drop_readme_requests(&mut requests, subpath);
+    }
+    // Optional files confirmed absent at this ref by the previous sync.
+    let known_absent = if opts.force {
+        Vec::new()
+    } else {
+        storage::read_meta(&rust_output_dir, &crate_name, &version)
+            .map(|meta| {
+                storage::known_absent_files(&meta, &resolved.git_ref, commit_sha.as_deref())
+            })
+            .unwrap_or_default()
+    };
+    let fetch_requests: Vec<FileRequest> = requests
+        .iter()
+        .filter(|r| r.required || !known_absent.contains(&r.original_path))
+        .cloned()
+        .collect();
+    if fetch_requests.len() < requests.len() {
+        debug!(
+            "  {crate_name}@{version}: skipping {} optional file(s) absent at {}",
+            requests.len() - fetch_requests.len(),
+            resolved.git_ref
+        );
+    }
+    let headers = crate_doc.request_headers();
+    let budget = RetryBudget::new(opts.max_retries_per_crate);
+    let results = access.fetch_files(&fetch_requests, &headers, &budget).await;
+    let mut fetched_files = collect_fetched_files(results, &crate_name, &version);
+    if !opts
+        .download_guard
+        .charge_files(&crate_name, &version, &fetched_files.files)
+    {
+        return SyncOutcome::Error(SyncFailure::download_limit());
+    }
+    if docsrs_readme.is_none() && probes_docs_dirs(&opts, &crate_doc, subpath) {
+        let probes = docs_dir_probes(&requests, &fetched_files.files);
+        let wanted: Vec<FileRequest> = probes
+            .iter()
+            .filter(|r| !known_absent.contains(&r.original_path))
+            .cloned()
+            .collect();
+        let found =
+            fetch_docs_dir_probes(&access, &wanted, &headers, &budget, &crate_name, &version).await;
+        if !opts
+            .download_guard
+            .charge_files(&crate_name, &version, &found.files)
+        {
+            return SyncOutcome::Error(SyncFailure::download_limit());
+        }
+        requests.extend(probes);
+        fetched_files.files.extend(found.files);
+        fetched_files.absent.extend(found.absent);
+    }
+    fetched_files
+        .absent
+        .extend(known_absent.into_iter().filter(|path| {
+            requests
+                .iter()
+                .any(|r| !r.required && &r.original_path == path)
+        }));
+    if opts.resolve_includes
+        && !resolve_includes(
+            &access,
+            &mut fetched_files.files,
+            &headers,
+            &budget,
+            &opts.download_guard,
+            &crate_name,
+            &version,
+        )
+        .await
+    {
+        return SyncOutcome::Error(SyncFailure::download_limit());
+    }
 
-    let fetched_files = collect_fetched_files(results, &crate_name, &version);
+    if let Some(artifact) = docsrs_readme {
+        merge_docsrs_readme(&mut fetched_files.files, artifact);
+    }
+    let extra_sources = match fetch_additional_sources(
+        &fetcher,
+        &crate_name,
+        &crate_doc,
+        &version,
+        &opts,
+        &budget,
+        &opts.download_guard,
+    )
+    .await
+    {
+        Ok(sources) => {
+            let pins: Vec<storage::SourceRef> =
+                sources.iter().map(|source| source.pin.clone()).collect();
+            merge_additional_sources(&mut requests, &mut fetched_files, sources);
+            pins
+        }
+        Err(AiDocsError::TotalSizeExceeded { .. }) => {
+            return SyncOutcome::Error(SyncFailure::download_limit());
+        }
+        Err(e) => {
+            warn!("  ✗ failed to fetch an extra source for {crate_name}@{version}: {e}");
+            return SyncOutcome::Error(SyncFailure::from(&e));
+        }
+    };
+    if layout_changed(&resolved, &fetched_files) {
+        let top_level = access.list_top_level().await.unwrap_or_else(|e| {
+            debug!("  cannot list {repo}@{fetch_ref}: {e}");
+            Vec::new()
+        });
+        let message = layout_changed_message(&resolved.git_ref, &top_level);
+        warn!("  ✗ {crate_name}@{version}: {message}");
+        return SyncOutcome::Error(SyncFailure::new(SyncErrorKind::NotFound, message));
+    }
+    drop(checkout);
     if fetched_files.files.is_empty() {
         warn!("  ✗ no files fetched for {crate_name}@{version}");
-        return SyncOutcome::Error(SyncErrorKind::NotFound);
+        return SyncOutcome::Error(SyncFailure::new(
+            SyncErrorKind::NotFound,
+            "no files fetched",
+        ));
     }
+    warn_on_partial_fetch(&fetched_files, &crate_name, &version);
+    let metrics = fetched_metrics(started, &fetched_files.files);
 
-    let source_kind = source_kind_override.unwrap_or("github");
+    tracing::Span::current().record("source_kind", source_kind);
+    let changelog = opts.changelog.for_crate(&crate_doc);
+    let source = if use_git {
+        git::source_label(&repo)
+    } else {
+        format!("github.com/{repo}")
+    };
     let save_ctx = storage::SaveContext {
-        repo: &repo,
+        source: &source,
         resolved: &resolved,
-        max_file_size_kb,
+        commit_sha: commit_sha.as_deref(),
+        expected_content_sha256: locked.map(|entry| entry.content_sha256.as_str()),
+        inferred_subpath: inferred_subpath.as_deref(),
+        max_file_size_kb: opts.max_file_size_kb,
+        max_tokens_per_crate: opts.max_tokens_per_crate,
         source_kind,
         artifact_path: None,
         docsrs_input_url: None,
         upstream_latest_version: Some(&version),
         truncated: None,
+        cleanup: opts.cleanup,
+        changelog: &changelog,
+        metrics: Some(metrics),
+        embed_ai_notes: opts.embed_ai_notes,
+        inject_header: opts.inject_header,
+        header_extensions: &opts.header_extensions,
+        prefer_source_readme: opts.prefer_source_readme,
+        include_extensions: &opts.include_extensions,
+        filename_template: &opts.filename_template,
+        extra_sources: &extra_sources,
     };
 
     let save_req = storage::SaveRequest {
         crate_name: &crate_name,
         version: &version,
+        requests: &requests,
         fetched_files: &fetched_files.files,
+        absent_files: &fetched_files.absent,
         crate_config: &crate_doc,
     };
 
     match storage::save_crate_files(&rust_output_dir, &save_ctx, save_req) {
         Ok(saved) => SyncOutcome::Synced(saved),
-        Err(e) => SyncOutcome::Error(e.sync_kind()),
+        Err(e) => SyncOutcome::Error(SyncFailure::from(&e)),
     }
 }
 
@@ -482,220 +2175,511 @@ async fn sync_one_crate(
     rust_output_dir: PathBuf,
     rust_versions: std::collections::HashMap<String, String>,
     fetcher: Arc<GitHubFetcher>,
+    latest_fetcher: Arc<LatestDocsFetcher>,
     crate_name: String,
     crate_doc: crate::config::CrateDoc,
-    force: bool,
-    max_file_size_kb: usize,
+    mut opts: SyncOptions,
 ) -> SyncOutcome {
+    if let Some(dir) = crate_doc.path.clone() {
+        return sync_one_crate_workspace(
+            rust_output_dir,
+            &fetcher,
+            crate_name,
+            crate_doc,
+            &dir,
+            opts,
+        )
+        .await;
+    }
     let Some(version) = rust_versions.get(crate_name.as_str()).cloned() else {
         warn!("Crate '{crate_name}' not found in Cargo.lock, skipping");
         return SyncOutcome::Skipped;
     };
+    tracing::Span::current().record("version", version.as_str());
+    opts.apply_since_last_sync(&crate_name, &version);
+
+    let crate_dir = rust_output_dir.join(format!("{crate_name}@{version}"));
+    let locked = match opts.locked.as_deref() {
+        Some(docs_lock) => match docs_lock.get(&crate_name, &version) {
+            Some(entry) => Some(entry),
+            None => {
+                warn!(
+                    "  ✗ {crate_name}@{version} is not in {}; run sync without --locked to update it",
+                    lock::LOCK_FILE
+                );
+                return SyncOutcome::Error(SyncFailure::new(
+                    SyncErrorKind::Other,
+                    format!("{version} is not in {}", lock::LOCK_FILE),
+                ));
+            }
+        },
+        None => None,
+    };
+    let matches_lock =
+        |entry: &lock::LockedCrate| lock::content_hash(&crate_dir) == entry.content_sha256;
 
-    if !force && storage::is_cached(&rust_output_dir, &crate_name, &version, &crate_doc) {
+    if !opts.force
+        && storage::is_cached(
+            &rust_output_dir,
+            &crate_name,
+            &version,
+            &crate_doc,
+            &opts.hashed_settings(),
+            Some(&opts.filename_template),
+        )
+        && locked.is_none_or(matches_lock)
+    {
         info!("  ⏭ {crate_name}@{version}: cached, skipping");
+        storage::upgrade_meta_schema(&rust_output_dir, &crate_name, &version);
         let cached = storage::read_cached_info(&rust_output_dir, &crate_name, &version, &crate_doc);
         return SyncOutcome::Cached(cached);
     }
 
     info!("Syncing {crate_name}@{version}...");
 
-    sync_one_crate_hybrid(
-        rust_output_dir,
-        fetcher,
-        crate_name,
-        crate_doc,
-        version,
-        max_file_size_kb,
-    )
-    .await
+    // With `--locked`, the save step refuses content that differs from the lock.
+    match crate_doc.docs_format(crate_doc.sync_mode(opts.sync_mode)) {
+        DocsFormat::Hybrid => {
+            sync_one_crate_hybrid(
+                rust_output_dir,
+                fetcher,
+                &latest_fetcher,
+                crate_name.clone(),
+                crate_doc,
+                version.clone(),
+                opts,
+            )
+            .await
+        }
+        DocsFormat::DocsRs => {
+            sync_one_crate_docsrs(
+                rust_output_dir,
+                &latest_fetcher,
+                fetcher,
+                crate_name.clone(),
+                crate_doc,
+                version.clone(),
+                opts,
+            )
+            .await
+        }
+        DocsFormat::GithubReadme => {
+            sync_one_crate_from_github(
+                rust_output_dir,
+                fetcher,
+                crate_name.clone(),
+                crate_doc,
+                version.clone(),
+                GithubSync::Plain,
+                opts,
+            )
+            .await
+        }
+    }
 }
 
-async fn sync_one_crate_hybrid(
+/// Syncs a `path` crate from its local directory at the version in its
+/// `Cargo.toml`, without network requests. Content identical to the saved
+/// docs counts as cached, so unchanged members are not rewritten.
+async fn sync_one_crate_workspace(
     rust_output_dir: PathBuf,
-    github_fetcher: Arc<GitHubFetcher>,
+    github: &GitHubFetcher,
     crate_name: String,
     crate_doc: crate::config::CrateDoc,
-    version: String,
-    max_file_size_kb: usize,
+    dir: &Path,
+    mut opts: SyncOptions,
 ) -> SyncOutcome {
-    // 1. Try fetching from docs.rs first
-    let latest_fetcher = LatestDocsFetcher::new();
-    let docsrs_readme = match latest_fetcher
-        .fetch_api_markdown(&crate_name, &version, max_file_size_kb)
-        .await 
-    {
-        Ok(artifact) => {
-            info!("  ✓ {crate_name}@{version}: description fetched from docs.rs");
-            Some(artifact)
-        }
+    let version = match workspace::member_version(&opts.root, dir) {
+        Ok(version) => version,
         Err(e) => {
-            warn!("  ⚠️ docs.rs fetch failed for {crate_name}@{version}: {e}; will use GitHub README");
-            None
+            warn!("  ✗ cannot read the version of {crate_name}: {e}");
+            return SyncOutcome::Error(SyncFailure::from(&e));
         }
     };
+    tracing::Span::current().record("version", version.as_str());
+    opts.apply_since_last_sync(&crate_name, &version);
 
-    // 2. Resolve GitHub Ref
-    let Some(repo) = crate_doc.github_repo().map(str::to_string) else {
-        warn!("Crate '{crate_name}' has no GitHub repo in config");
-        // Fallback: if we have docs.rs content, save it and consider it synced.
-        if let Some(art) = docsrs_readme {
-             match storage::save_latest_api_markdown(
-                &rust_output_dir,
+    let started = Instant::now();
+    let (requests, fetched_files) =
+        read_workspace_files(github, &crate_name, &crate_doc, dir, &version, &opts).await;
+    if fetched_files.files.is_empty() {
+        warn!(
+            "  ✗ no files found in {} for {crate_name}@{version}",
+            dir.display()
+        );
+        return SyncOutcome::Error(SyncFailure::new(
+            SyncErrorKind::NotFound,
+            format!("no files found in {}", dir.display()),
+        ));
+    }
+
+    let crate_dir = rust_output_dir.join(format!("{crate_name}@{version}"));
+    if !opts.force
+        && storage::is_cached(
+            &rust_output_dir,
+            &crate_name,
+            &version,
+            &crate_doc,
+            &opts.hashed_settings(),
+            Some(&opts.filename_template),
+        )
+        && diff::read_stored_bodies(&crate_dir)
+            == expected_bodies(
+                &fetched_files.files,
                 &crate_name,
-                &version,
-                &art.markdown,
-                &art.docsrs_input_url,
-                art.truncated,
                 &crate_doc,
-            ) {
-                Ok(saved) => return SyncOutcome::Synced(saved),
-                Err(e) => return SyncOutcome::Error(e.sync_kind()),
-            }
-        }
-        return SyncOutcome::Skipped;
-    };
-
-    let resolved = match github_fetcher
-        .resolve_ref(&repo, &crate_name, version.as_str())
-        .await
+                &version,
+                &opts,
+            )
     {
-        Ok(r) => r,
-        Err(e) => {
-            warn!("  ✗ failed to resolve ref for {crate_name}@{version}: {e}");
-            return SyncOutcome::Error(e.sync_kind());
-        }
-    };
-
-    // 3. Build Requests
-    let mut requests = build_requests(crate_doc.subpath.as_deref(), crate_doc.effective_files());
-    
-    // If we have docs.rs README, remove README from GitHub requests
-    if docsrs_readme.is_some() {
-        requests.retain(|r| !is_readme_request(&r.original_path));
-    }
-
-    // 4. Fetch from GitHub
-    let results = github_fetcher
-        .fetch_files(&repo, &resolved.git_ref, &requests)
-        .await;
-
-    let mut fetch_collection = collect_fetched_files(results, &crate_name, &version);
-
-    // 5. Inject docs.rs README if available
-    if let Some(art) = docsrs_readme {
-        fetch_collection.files.push(FetchedFile {
-            path: "README.md".to_string(),
-            source_url: art.docsrs_input_url.clone(), // Point to docs.rs as source
-            content: art.markdown,
-        });
-    }
-
-    if fetch_collection.files.is_empty() {
-        warn!("  ✗ no files fetched for {crate_name}@{version}");
-        return SyncOutcome::Error(SyncErrorKind::NotFound);
+        info!("  ⏭ {crate_name}@{version}: cached, skipping");
+        storage::upgrade_meta_schema(&rust_output_dir, &crate_name, &version);
+        let cached = storage::read_cached_info(&rust_output_dir, &crate_name, &version, &crate_doc);
+        return SyncOutcome::Cached(cached);
     }
 
+    info!("Syncing {crate_name}@{version} from {}...", dir.display());
+    warn_on_partial_fetch(&fetched_files, &crate_name, &version);
+    tracing::Span::current().record("source_kind", "workspace");
+    let changelog = opts.changelog.for_crate(&crate_doc);
+    let source = dir.display().to_string();
+    let resolved = ResolvedRef {
+        git_ref: WORKSPACE_REF.to_string(),
+        is_fallback: false,
+        fallback_reason: None,
+        renamed_to: None,
+    };
     let save_ctx = storage::SaveContext {
-        repo: &repo,
+        source: &source,
         resolved: &resolved,
-        max_file_size_kb,
-        source_kind: "hybrid_docsrs_github",
+        commit_sha: None,
+        expected_content_sha256: None,
+        inferred_subpath: None,
+        max_file_size_kb: opts.max_file_size_kb,
+        max_tokens_per_crate: opts.max_tokens_per_crate,
+        source_kind: "workspace",
         artifact_path: None,
-        docsrs_input_url: None, // We embedded it in the file source_url
+        docsrs_input_url: None,
         upstream_latest_version: Some(&version),
         truncated: None,
+        cleanup: opts.cleanup,
+        changelog: &changelog,
+        metrics: Some(fetched_metrics(started, &fetched_files.files)),
+        embed_ai_notes: opts.embed_ai_notes,
+        inject_header: opts.inject_header,
+        header_extensions: &opts.header_extensions,
+        prefer_source_readme: opts.prefer_source_readme,
+        include_extensions: &opts.include_extensions,
+        filename_template: &opts.filename_template,
+        extra_sources: &[],
     };
-
     let save_req = storage::SaveRequest {
         crate_name: &crate_name,
         version: &version,
-        fetched_files: &fetch_collection.files,
+        requests: &requests,
+        fetched_files: &fetched_files.files,
+        absent_files: &fetched_files.absent,
         crate_config: &crate_doc,
     };
 
     match storage::save_crate_files(&rust_output_dir, &save_ctx, save_req) {
         Ok(saved) => SyncOutcome::Synced(saved),
-        Err(e) => SyncOutcome::Error(e.sync_kind()),
+        Err(e) => SyncOutcome::Error(SyncFailure::from(&e)),
     }
 }
 
-fn is_readme_request(path: &str) -> bool {
-    path.eq_ignore_ascii_case("README.md")
+/// Reads the files `sync` requests for a `path` crate from `dir`.
+async fn read_workspace_files(
+    github: &GitHubFetcher,
+    crate_name: &str,
+    crate_doc: &crate::config::CrateDoc,
+    dir: &Path,
+    version: &str,
+    opts: &SyncOptions,
+) -> (Vec<FileRequest>, FetchCollection) {
+    let label = dir.display().to_string();
+    let tree = LocalTree::new(opts.root.join(dir), &label);
+    let access = RepoAccess {
+        repo: &label,
+        git_ref: WORKSPACE_REF,
+        github,
+        local: Some(&tree),
+    };
+    let requests = build_requests(
+        None,
+        crate_doc.effective_files(),
+        &opts.default_files,
+        &opts.changelog_candidates,
+    );
+    let requests = expand_directory_requests(
+        &access,
+        requests,
+        &opts.include_extensions,
+        crate_name,
+        version,
+    )
+    .await;
+    let headers = crate_doc.request_headers();
+    let budget = RetryBudget::new(opts.max_retries_per_crate);
+    let results = access.fetch_files(&requests, &headers, &budget).await;
+    let mut collected = collect_fetched_files(results, crate_name, version);
+    if opts.resolve_includes {
+        resolve_includes(
+            &access,
+            &mut collected.files,
+            &headers,
+            &budget,
+            &DownloadGuard::default(),
+            crate_name,
+            version,
+        )
+        .await;
+    }
+    (requests, collected)
 }
 
-// Helper to identify likely README requests
-fn is_readme_request(path: &str) -> bool {
-    path.eq_ignore_ascii_case("README.md")
+/// `path` crate bodies as `sync` would save them (`verify`, `check --diff`).
+async fn workspace_bodies(
+    github: &GitHubFetcher,
+    crate_name: &str,
+    crate_doc: &crate::config::CrateDoc,
+    dir: &Path,
+    version: &str,
+    opts: &SyncOptions,
+) -> std::result::Result<BTreeMap<String, String>, String> {
+    let (_, collected) =
+        read_workspace_files(github, crate_name, crate_doc, dir, version, opts).await;
+    if collected.non_optional_errors > 0 {
+        return Err(format!(
+            "{} file(s) could not be read from {}",
+            collected.non_optional_errors,
+            dir.display()
+        ));
+    }
+    Ok(expected_bodies(
+        &collected.files,
+        crate_name,
+        crate_doc,
+        version,
+        opts,
+    ))
 }
 
-async fn sync_one_crate_from_github(
+async fn sync_one_crate_hybrid(
     rust_output_dir: PathBuf,
-    fetcher: Arc<GitHubFetcher>,
+    github_fetcher: Arc<GitHubFetcher>,
+    latest_fetcher: &LatestDocsFetcher,
     crate_name: String,
     crate_doc: crate::config::CrateDoc,
     version: String,
-    max_file_size_kb: usize,
-    source_kind_override: Option<&'static str>,
+    opts: SyncOptions,
 ) -> SyncOutcome {
-    let Some(repo) = crate_doc.github_repo().map(str::to_string) else {
-        warn!("Crate '{crate_name}' has no GitHub repo in config");
-        if source_kind_override.is_some() {
-            return SyncOutcome::Error(SyncErrorKind::Other);
-        }
-        return SyncOutcome::Skipped;
-    };
+    if !opts.download_guard.allows_fetch(&crate_name) {
+        return SyncOutcome::Error(SyncFailure::download_limit());
+    }
 
-    let resolved = match fetcher
-        .resolve_ref(&repo, &crate_name, version.as_str())
+    // 1. Try fetching from docs.rs first
+    let started = Instant::now();
+    let docsrs_readme = match latest_fetcher
+        .fetch_api_markdown(
+            crate_doc.upstream_name(&crate_name),
+            &version,
+            opts.max_file_size_kb,
+        )
         .await
     {
-        Ok(r) => r,
+        Ok(artifact)
+            if !opts
+                .download_guard
+                .charge(&crate_name, &version, artifact.content.len()) =>
+        {
+            return SyncOutcome::Error(SyncFailure::download_limit());
+        }
+        Ok(artifact) => {
+            info!("  ✓ {crate_name}@{version}: description fetched from docs.rs");
+            Some(artifact)
+        }
         Err(e) => {
-            warn!("  ✗ failed to resolve ref for {crate_name}@{version}: {e}");
-            return SyncOutcome::Error(e.sync_kind());
+            emit_sync_warning(
+                &crate_name,
+                &version,
+                &format!("docs.rs fetch failed: {e}; will use GitHub README"),
+            );
+            None
         }
     };
 
-    let requests = build_requests(crate_doc.subpath.as_deref(), crate_doc.effective_files());
-    let results = fetcher
-        .fetch_files(&repo, &resolved.git_ref, &requests)
-        .await;
+    // 2. README from docs.rs (when fetched), everything else from GitHub.
+    if crate_doc.github_repo().is_none() && crate_doc.git_url.is_none() {
+        warn!("Crate '{crate_name}' has no GitHub repo in config");
+        // Fallback: if we have docs.rs content, save it and consider it synced.
+        if let Some(art) = docsrs_readme {
+            tracing::Span::current().record("source_kind", "docsrs");
+            return match storage::save_latest_artifact(
+                &rust_output_dir,
+                &crate_name,
+                &version,
+                &art,
+                opts.max_file_size_kb,
+                opts.max_tokens_per_crate,
+                Some(FetchMetrics::since(started, art.content.len())),
+                &crate_doc,
+                &opts.hashed_settings(),
+                opts.locked_content_sha256(&crate_name, &version),
+            ) {
+                Ok(saved) => SyncOutcome::Synced(saved),
+                Err(e) => SyncOutcome::Error(SyncFailure::from(&e)),
+            };
+        }
+        return SyncOutcome::Skipped;
+    }
 
-    let fetched_files = collect_fetched_files(results, &crate_name, &version);
-    if fetched_files.files.is_empty() {
-        warn!("  ✗ no files fetched for {crate_name}@{version}");
-        return SyncOutcome::Error(SyncErrorKind::NotFound);
+    sync_one_crate_from_github(
+        rust_output_dir,
+        github_fetcher,
+        crate_name,
+        crate_doc,
+        version,
+        GithubSync::Hybrid(docsrs_readme),
+        opts,
+    )
+    .await
+}
+
+fn fetched_metrics(started: Instant, files: &[FetchedFile]) -> FetchMetrics {
+    FetchMetrics::since(started, files.iter().map(|f| f.content.len()).sum())
+}
+
+/// Applies `settings.fallback_policy` to a ref that is not a version tag.
+/// Returns `false` when the crate must fail instead of syncing from a branch.
+fn fallback_allowed(
+    policy: FallbackPolicy,
+    resolved: &ResolvedRef,
+    crate_name: &str,
+    version: &str,
+) -> bool {
+    if !resolved.is_fallback {
+        return true;
     }
 
-    let source_kind = source_kind_override.unwrap_or("github");
-    let save_ctx = storage::SaveContext {
-        repo: &repo,
-        resolved: &resolved,
-        max_file_size_kb,
-        source_kind,
-        artifact_path: None,
-        docsrs_input_url: None,
-        upstream_latest_version: Some(&version),
-        truncated: None,
-    };
+    let reason = resolved
+        .fallback_reason
+        .as_deref()
+        .unwrap_or("no tag found for version");
+    match policy {
+        FallbackPolicy::Allow => {
+            info!("  ↪ {crate_name}@{version}: {reason}");
+            true
+        }
+        FallbackPolicy::Warn => {
+            emit_sync_warning(crate_name, version, reason);
+            true
+        }
+        FallbackPolicy::Error => {
+            warn!("  ✗ {crate_name}@{version}: {reason}; refused by fallback policy \"error\" (settings.fallback_policy or --no-fallback)");
+            false
+        }
+    }
+}
 
-    let save_req = storage::SaveRequest {
-        crate_name: &crate_name,
-        version: &version,
-        fetched_files: &fetched_files.files,
-        crate_config: &crate_doc,
-    };
+/// Directory prefix (`"docs/"`) that default requests use for `subpath`.
+fn subpath_prefix(subpath: Option<&str>) -> String {
+    subpath
+        .map(|s| s.trim_matches('/'))
+        .filter(|s| !s.is_empty())
+        .map(|s| format!("{s}/"))
+        .unwrap_or_default()
+}
 
-    match storage::save_crate_files(&rust_output_dir, &save_ctx, save_req) {
-        Ok(saved) => SyncOutcome::Synced(saved),
-        Err(e) => SyncOutcome::Error(e.sync_kind()),
+/// The crate's own README request (at the `subpath` root), which docs.rs
+/// replaces in hybrid mode.
+fn is_readme_request(path: &str, subpath: Option<&str>) -> bool {
+    path.eq_ignore_ascii_case(&format!("{}README.md", subpath_prefix(subpath)))
+}
+
+/// `settings.probe_docs_dirs`: guide entry points tried for repo-root crates
+/// whose README is missing or shorter than `THIN_README_BYTES`.
+const DOCS_DIR_PROBES: [&str; 2] = ["docs/README.md", "book/src/README.md"];
+const THIN_README_BYTES: usize = 1024;
+
+/// Whether `settings.probe_docs_dirs` applies: default files fetched from the
+/// repo root.
+fn probes_docs_dirs(
+    opts: &SyncOptions,
+    crate_doc: &crate::config::CrateDoc,
+    subpath: Option<&str>,
+) -> bool {
+    opts.probe_docs_dirs && subpath.is_none() && crate_doc.effective_files().is_none()
+}
+
+/// `DOCS_DIR_PROBES` not requested yet, when the README among `files` is
+/// missing or thin.
+fn docs_dir_probes(requests: &[FileRequest], files: &[FetchedFile]) -> Vec<FileRequest> {
+    let readme = files
+        .iter()
+        .find(|file| file.path.eq_ignore_ascii_case("README.md"));
+    if readme.is_some_and(|file| file.content.len() >= THIN_README_BYTES) {
+        return Vec::new();
+    }
+    DOCS_DIR_PROBES
+        .iter()
+        .filter(|path| {
+            !requests
+                .iter()
+                .any(|r| r.original_path.eq_ignore_ascii_case(path))
+        })
+        .map(|path| FileRequest {
+            original_path: (*path).to_string(),
+            candidates: vec![(*path).to_string()],
+            required: false,
+        })
+        .collect()
+}
+
+/// Fetches `probes`, logging the guides that exist.
+async fn fetch_docs_dir_probes(
+    access: &RepoAccess<'_>,
+    probes: &[FileRequest],
+    headers: &reqwest::header::HeaderMap,
+    budget: &RetryBudget,
+    crate_name: &str,
+    version: &str,
+) -> FetchCollection {
+    let results = access.fetch_files(probes, headers, budget).await;
+    let found = collect_fetched_files(results, crate_name, version);
+    for file in &found.files {
+        info!(
+            "  📚 {crate_name}@{version}: thin README, also saving `{}`",
+            file.path
+        );
     }
+    found
+}
+
+/// Hybrid mode: GitHub must not fetch the README docs.rs already supplied.
+fn drop_readme_requests(requests: &mut Vec<FileRequest>, subpath: Option<&str>) {
+    requests.retain(|r| !is_readme_request(&r.original_path, subpath));
+}
+
+/// Hybrid mode: saves the docs.rs API markdown as `README.md`, replacing any
+/// README that still came from GitHub.
+fn merge_docsrs_readme(files: &mut Vec<FetchedFile>, artifact: DocsRsArtifact) {
+    files.retain(|f| !f.path.eq_ignore_ascii_case("README.md"));
+    files.push(FetchedFile {
+        path: "README.md".to_string(),
+        matched_candidate: "API.md".to_string(),
+        source_url: artifact.docsrs_input_url,
+        content: artifact.content,
+    });
 }
 
 struct FetchCollection {
     files: Vec<FetchedFile>,
-    _non_optional_errors: usize,
+    non_optional_errors: usize,
+    /// Non-optional errors that were not-found responses.
+    missing_required: usize,
+    /// Optional requests none of whose candidates exist.
+    absent: Vec<String>,
 }
 
 fn collect_fetched_files(
@@ -705,14 +2689,19 @@ fn collect_fetched_files(
 ) -> FetchCollection {
     let mut files = Vec::new();
     let mut non_optional_errors = 0;
+    let mut missing_required = 0;
+    let mut absent = Vec::new();
 
     for r in results {
         match r {
             Ok(file) => files.push(file),
             Err(e) => match e {
-                AiDocsError::OptionalFileNotFound(_) => {}
+                AiDocsError::OptionalFileNotFound(path) => absent.push(path),
                 other => {
                     non_optional_errors += 1;
+                    if other.sync_kind() == SyncErrorKind::NotFound {
+                        missing_required += 1;
+                    }
                     warn!("  ✗ {crate_name}@{version}: {other}");
                 }
             },
@@ -721,192 +2710,991 @@ fn collect_fetched_files(
 
     FetchCollection {
         files,
-        _non_optional_errors: non_optional_errors,
+        non_optional_errors,
+        missing_required,
+        absent,
+    }
+}
+
+/// Whether a tag or branch was found for the version yet none of the requested
+/// files exist there, e.g. because the README moved into `docs/`.
+fn layout_changed(resolved: &ResolvedRef, collection: &FetchCollection) -> bool {
+    !resolved.is_fallback
+        && collection.files.is_empty()
+        && collection.non_optional_errors == collection.missing_required
+}
+
+/// Error for `layout_changed`, listing `top_level` entries (at most
+/// `MAX_LAYOUT_HINTS`) as hints for `subpath`.
+fn layout_changed_message(git_ref: &str, top_level: &[String]) -> String {
+    const MAX_LAYOUT_HINTS: usize = 12;
+
+    let mut message = format!(
+        "none of the requested files exist at {git_ref}; the repository layout may have changed, set `subpath` or `files` for this crate"
+    );
+    if !top_level.is_empty() {
+        let mut hints = top_level[..top_level.len().min(MAX_LAYOUT_HINTS)].join(", ");
+        if top_level.len() > MAX_LAYOUT_HINTS {
+            hints.push_str(", …");
+        }
+        message.push_str(&format!(" (top-level entries: {hints})"));
+    }
+    message
+}
+
+fn warn_on_partial_fetch(collection: &FetchCollection, crate_name: &str, version: &str) {
+    if collection.non_optional_errors > 0 {
+        emit_sync_warning(
+            crate_name,
+            version,
+            &format!(
+                "partial fetch: {} file(s) failed, {} saved",
+                collection.non_optional_errors,
+                collection.files.len()
+            ),
+        );
     }
 }
 
-fn build_requests(subpath: Option<&str>, explicit_files: Option<Vec<String>>) -> Vec<FileRequest> {
+/// Trailing-slash entries (`docs/`) become directory placeholders without
+/// candidates; `expand_directory_requests` replaces them once the ref is known
+/// and applies `!`-prefixed exclusion entries to the result.
+fn build_requests(
+    subpath: Option<&str>,
+    explicit_files: Option<Vec<String>>,
+    default_files: &[String],
+    changelog_candidates: &[String],
+) -> Vec<FileRequest> {
     if let Some(files) = explicit_files {
         return files
             .into_iter()
             .map(|f| FileRequest {
-                original_path: f.clone(),
-                candidates: vec![f],
+                candidates: if f.ends_with('/') || f.starts_with('!') {
+                    Vec::new()
+                } else {
+                    vec![f.clone()]
+                },
+                original_path: f,
                 required: true,
             })
             .collect();
     }
 
-    let prefix = subpath
-        .map(|s| s.trim_matches('/'))
-        .filter(|s| !s.is_empty())
-        .map(|s| format!("{s}/"))
-        .unwrap_or_default();
-
-    vec![
-        FileRequest {
-            original_path: format!("{prefix}README.md"),
-            candidates: vec![
-                format!("{prefix}README.md"),
-                format!("{prefix}Readme.md"),
-                format!("{prefix}readme.md"),
-            ],
-            required: false,
-        },
-        FileRequest {
-            original_path: format!("{prefix}CHANGELOG.md"),
-            candidates: vec![
-                format!("{prefix}CHANGELOG.md"),
-                format!("{prefix}Changelog.md"),
-                format!("{prefix}changelog.md"),
-            ],
-            required: false,
-        },
-    ]
-}
+    let prefix = subpath_prefix(subpath);
 
-async fn run_orchestrated_sync<F, Fut>(
-    config: &Config,
-    jobs: Vec<(String, crate::config::CrateDoc)>,
-    worker: F,
-) -> Vec<SyncOutcome>
-where
-    F: Fn(String, crate::config::CrateDoc) -> Fut,
-    Fut: std::future::Future<Output = SyncOutcome> + Send + 'static,
-{
-    let concurrency = config.settings.sync_concurrency;
-    let semaphore = Arc::new(Semaphore::new(concurrency));
-    let mut join_set = tokio::task::JoinSet::new();
+    default_files
+        .iter()
+        .map(|file| {
+            if let Some(pattern) = file.strip_prefix('!') {
+                return FileRequest {
+                    original_path: format!("!{prefix}{pattern}"),
+                    candidates: Vec::new(),
+                    required: false,
+                };
+            }
+            let original_path = format!("{prefix}{file}");
+            let candidates = if file.ends_with('/') {
+                Vec::new()
+            } else if file.eq_ignore_ascii_case("CHANGELOG.md") && !changelog_candidates.is_empty()
+            {
+                changelog_candidate_paths(changelog_candidates)
+                    .into_iter()
+                    .map(|path| format!("{prefix}{path}"))
+                    .collect()
+            } else {
+                case_variants(file)
+                    .into_iter()
+                    .map(|variant| format!("{prefix}{variant}"))
+                    .collect()
+            };
+            FileRequest {
+                original_path,
+                candidates,
+                required: false,
+            }
+        })
+        .collect()
+}
 
-    for (name, doc) in jobs {
-        let semaphore = Arc::clone(&semaphore);
-        let fut = worker(name, doc);
-        join_set.spawn(async move {
-            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
-            fut.await
-        });
+/// `settings.include_readme_only`: the README entries of `default_files` (and
+/// its `!` exclusions), or `README.md` when it lists none.
+fn readme_only_files(default_files: &[String]) -> Vec<String> {
+    let is_readme = |file: &String| {
+        let name = file.rsplit('/').next().unwrap_or(file);
+        name.eq_ignore_ascii_case("README.md")
+    };
+    let mut files: Vec<String> = default_files
+        .iter()
+        .filter(|file| is_readme(file) || file.starts_with('!'))
+        .cloned()
+        .collect();
+    if !files.iter().any(is_readme) {
+        files.insert(0, "README.md".to_string());
     }
+    files
+}
 
-    let mut outcomes = Vec::new();
-    while let Some(joined) = join_set.join_next().await {
-        match joined {
-            Ok(outcome) => outcomes.push(outcome),
-            Err(e) => {
-                warn!("sync worker panicked: {e}");
-                outcomes.push(SyncOutcome::Error(SyncErrorKind::Other));
-            }
+/// `settings.changelog_candidates` in order, each with its `case_variants`.
+fn changelog_candidate_paths(changelog_candidates: &[String]) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for path in changelog_candidates
+        .iter()
+        .flat_map(|candidate| case_variants(candidate.trim_matches('/')))
+    {
+        if !paths.contains(&path) {
+            paths.push(path);
         }
     }
-    outcomes
+    paths
 }
 
-fn resolve_sync_mode(mode_override: Option<SyncModeArg>, configured_mode: SyncMode) -> SyncMode {
-    mode_override
-        .map(SyncModeArg::to_sync_mode)
-        .unwrap_or(configured_mode)
+/// `README.md`/`CHANGELOG.md` (in any directory) as upper, title and lower
+/// case; other paths as given.
+fn case_variants(file: &str) -> Vec<String> {
+    let (dir, name) = file.rsplit_once('/').unwrap_or(("", file));
+    let dir = if dir.is_empty() {
+        String::new()
+    } else {
+        format!("{dir}/")
+    };
+    match name.to_ascii_lowercase().as_str() {
+        "readme.md" => ["README.md", "Readme.md", "readme.md"],
+        "changelog.md" => ["CHANGELOG.md", "Changelog.md", "changelog.md"],
+        _ => return vec![file.to_string()],
+    }
+    .iter()
+    .map(|variant| format!("{dir}{variant}"))
+    .collect()
 }
 
-
-
-const fn should_emit_plain_check_errors(format: OutputFormat, github_actions: bool) -> bool {
-    !github_actions && matches!(format, OutputFormat::Table)
+/// Where one crate's repository files are read from at `git_ref`: GitHub's
+/// HTTP APIs, or a local tree (a shallow clone for `source = "git"`, or a
+/// workspace member's directory).
+struct RepoAccess<'a> {
+    repo: &'a str,
+    git_ref: &'a str,
+    github: &'a GitHubFetcher,
+    local: Option<&'a LocalTree>,
 }
 
-fn emit_check_failures_for_ci(format: OutputFormat, statuses: &[crate::status::CrateStatus]) {
-    let github_actions = std::env::var("GITHUB_ACTIONS")
-        .ok()
-        .is_some_and(|v| v == "true");
+impl RepoAccess<'_> {
+    async fn list_dir_files(&self, dir: &str, extensions: &[String]) -> Result<Vec<String>> {
+        match self.local {
+            Some(tree) => Ok(tree.list_dir_files(dir, extensions)),
+            None => {
+                self.github
+                    .list_dir_files(self.repo, self.git_ref, dir, extensions)
+                    .await
+            }
+        }
+    }
 
-    for status in statuses
-        .iter()
-        .filter(|s| !matches!(s.status, DocsStatus::Synced | DocsStatus::SyncedFallback))
-    {
-        if github_actions {
-            eprintln!(
-                "::error title=ai-fdocs check::{} [{}] {}",
-                status.crate_name,
-                status.status.as_str(),
-                status.reason
-            );
-        } else if should_emit_plain_check_errors(format, github_actions) {
-            eprintln!(
-                "[ai-fdocs check] {} [{}] {}",
-                status.crate_name,
-                status.status.as_str(),
-                status.reason
-            );
+    async fn infer_subpath(&self, candidates: &[String]) -> Result<Option<String>> {
+        match self.local {
+            Some(tree) => Ok(tree.infer_subpath(candidates)),
+            None => {
+                self.github
+                    .infer_subpath(self.repo, self.git_ref, candidates)
+                    .await
+            }
         }
     }
-}
 
-fn print_statuses(format: OutputFormat, statuses: &[crate::status::CrateStatus]) -> Result<()> {
-    match format {
-        OutputFormat::Table => print_status_table(statuses),
-        OutputFormat::Json => {
-            let json = status::format_status_json(statuses).map_err(|e| {
-                error::AiDocsError::Other(format!("failed to serialize status JSON: {e}"))
-            })?;
-            println!("{json}");
+    async fn list_top_level(&self) -> Result<Vec<String>> {
+        match self.local {
+            Some(tree) => Ok(tree.list_top_level()),
+            None => self.github.list_top_level(self.repo, self.git_ref).await,
         }
     }
 
-    Ok(())
+    async fn fetch_files(
+        &self,
+        requests: &[FileRequest],
+        headers: &reqwest::header::HeaderMap,
+        budget: &RetryBudget,
+    ) -> Vec<Result<FetchedFile>> {
+        match self.local {
+            Some(tree) => tree.read_files(requests),
+            None => {
+                self.github
+                    .fetch_files(self.repo, self.git_ref, requests, headers, budget)
+                    .await
+            }
+        }
+    }
 }
 
-async fn run_status(
-    config_path: &Path,
-    mode_override: Option<SyncModeArg>,
-    format: OutputFormat,
+/// Expands directory entries into one required request per matching file
+/// under that directory at `git_ref`; empty or missing directories are dropped
+/// with a sync warning. Requests matching a `!` exclusion entry are then
+/// removed.
+async fn expand_directory_requests(
+    access: &RepoAccess<'_>,
+    requests: Vec<FileRequest>,
+    extensions: &[String],
+    crate_name: &str,
+    version: &str,
+) -> Vec<FileRequest> {
+    let (exclusions, requests): (Vec<_>, Vec<_>) = requests
+        .into_iter()
+        .partition(|request| request.exclusion_pattern().is_some());
+    if requests.is_empty() && !exclusions.is_empty() {
+        emit_sync_warning(
+            crate_name,
+            version,
+            "`files` only has `!` exclusion patterns; nothing will be fetched",
+        );
+    }
+
+    let mut expanded = Vec::with_capacity(requests.len());
+    for request in requests {
+        if !request.is_directory() {
+            expanded.push(request);
+            continue;
+        }
+
+        let dir = request.original_path.as_str();
+        let git_ref = access.git_ref;
+        match access.list_dir_files(dir, extensions).await {
+            Ok(paths) if !paths.is_empty() => {
+                expanded.extend(paths.into_iter().map(|path| FileRequest {
+                    original_path: path.clone(),
+                    candidates: vec![path],
+                    required: true,
+                }));
+            }
+            Ok(_) => emit_sync_warning(
+                crate_name,
+                version,
+                &format!("directory `{dir}` is empty or missing at {git_ref}"),
+            ),
+            Err(e) => emit_sync_warning(
+                crate_name,
+                version,
+                &format!("failed to list directory `{dir}` at {git_ref}: {e}"),
+            ),
+        }
+    }
+    expanded.retain(|request| {
+        !exclusions
+            .iter()
+            .filter_map(FileRequest::exclusion_pattern)
+            .any(|pattern| matches_exclusion(pattern, &request.original_path))
+    });
+    expanded
+}
+
+/// `settings.resolve_includes`: fetches the files referenced by `{{#include}}`
+/// directives of the fetched markdown files from the same repo and ref (up to
+/// `includes::MAX_INCLUDE_DEPTH` levels) and inlines them. Directives whose
+/// file cannot be fetched are kept as written. Returns false, leaving `files`
+/// untouched, when the fetched files trip the download limit.
+async fn resolve_includes(
+    access: &RepoAccess<'_>,
+    files: &mut [FetchedFile],
+    headers: &reqwest::header::HeaderMap,
+    budget: &RetryBudget,
+    guard: &DownloadGuard,
+    crate_name: &str,
+    version: &str,
+) -> bool {
+    let mut sources: HashMap<String, String> = files
+        .iter()
+        .map(|file| (file.path.clone(), file.content.clone()))
+        .collect();
+    let mut pending: Vec<String> = files
+        .iter()
+        .filter(|file| markdown::is_markdown_path(&file.path))
+        .flat_map(|file| includes::include_targets(&file.path, &file.content))
+        .collect();
+    let mut missing = HashSet::new();
+    for _ in 0..includes::MAX_INCLUDE_DEPTH {
+        pending.sort();
+        pending.dedup();
+        pending.retain(|path| !sources.contains_key(path) && !missing.contains(path));
+        if pending.is_empty() {
+            break;
+        }
+        let requests: Vec<FileRequest> = pending
+            .iter()
+            .map(|path| FileRequest {
+                original_path: path.clone(),
+                candidates: vec![path.clone()],
+                required: false,
+            })
+            .collect();
+        let results = access.fetch_files(&requests, headers, budget).await;
+        let bytes = results
+            .iter()
+            .flatten()
+            .map(|file| file.content.len())
+            .sum();
+        if !guard.charge(crate_name, version, bytes) {
+            return false;
+        }
+        let mut next = Vec::new();
+        for (path, result) in pending.drain(..).zip(results) {
+            match result {
+                Ok(file) => {
+                    next.extend(includes::include_targets(&path, &file.content));
+                    sources.insert(path, file.content);
+                }
+                Err(e) => {
+                    warn!("  ⚠ {crate_name}@{version}: include `{path}` kept unresolved: {e}");
+                    missing.insert(path);
+                }
+            }
+        }
+        pending = next;
+    }
+
+    for file in files
+        .iter_mut()
+        .filter(|file| markdown::is_markdown_path(&file.path))
+    {
+        file.content = includes::expand_includes(&file.path, &file.content, &sources);
+    }
+    true
+}
+
+/// Files of one extra GitHub source (see `fetch_additional_sources`).
+struct SourceFiles {
+    /// Slug the files were fetched from (the new one after a followed rename).
+    repo: String,
+    pin: storage::SourceRef,
+    requests: Vec<FileRequest>,
+    collection: FetchCollection,
+}
+
+/// Fetches every `CrateDoc::additional_github_sources` entry concurrently, each
+/// at its own resolved ref for `version` (its pinned commit under `--locked`),
+/// over GitHub's API even under `source = "git"`. `fallback_policy` applies to
+/// each entry like to the main repo, and fetched bytes are charged to `guard`
+/// as each source arrives. An entry whose ref cannot be resolved is skipped
+/// with a sync warning, so the crate is saved without it.
+async fn fetch_additional_sources(
+    github: &GitHubFetcher,
+    crate_name: &str,
+    crate_doc: &crate::config::CrateDoc,
+    version: &str,
+    opts: &SyncOptions,
+    budget: &RetryBudget,
+    guard: &DownloadGuard,
+) -> Result<Vec<SourceFiles>> {
+    let fetches = crate_doc
+        .additional_github_sources()
+        .into_iter()
+        .map(|(repo, files)| {
+            let source = ExtraSource {
+                crate_name,
+                crate_doc,
+                version,
+                repo,
+                files,
+            };
+            fetch_additional_source(github, source, opts, budget, guard)
+        });
+    let mut sources = Vec::new();
+    for fetched in utils::join_all(fetches).await {
+        sources.extend(fetched?);
+    }
+    Ok(sources)
+}
+
+/// One entry of `CrateDoc::additional_github_sources` of a crate.
+struct ExtraSource<'a> {
+    crate_name: &'a str,
+    crate_doc: &'a crate::config::CrateDoc,
+    version: &'a str,
+    repo: &'a str,
+    files: Option<Vec<String>>,
+}
+
+/// `fetch_additional_sources` for one entry; `None` when it was skipped.
+async fn fetch_additional_source(
+    github: &GitHubFetcher,
+    source: ExtraSource<'_>,
+    opts: &SyncOptions,
+    budget: &RetryBudget,
+    guard: &DownloadGuard,
+) -> Result<Option<SourceFiles>> {
+    let ExtraSource {
+        crate_name,
+        crate_doc,
+        version,
+        repo,
+        files,
+    } = source;
+    let locked = opts
+        .locked
+        .as_deref()
+        .and_then(|docs_lock| docs_lock.get(crate_name, version));
+    let (resolved, commit) = match locked {
+        Some(entry) => match entry.extra_source(repo) {
+            Some(pin) if pin.commit.is_some() => (
+                ResolvedRef {
+                    git_ref: pin.git_ref.clone(),
+                    is_fallback: pin.is_fallback,
+                    fallback_reason: None,
+                    renamed_to: None,
+                },
+                pin.commit.clone(),
+            ),
+            _ => {
+                return Err(AiDocsError::Other(format!(
+                    "no commit pinned for extra source {repo} in {}; run sync without --locked to update it",
+                    lock::LOCK_FILE
+                )));
+            }
+        },
+        None => {
+            let resolved = match github
+                .resolve_ref(
+                    repo,
+                    crate_doc.upstream_name(crate_name),
+                    version,
+                    None,
+                    opts.follow_repo_renames,
+                )
+                .await
+            {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    emit_sync_warning(
+                        crate_name,
+                        version,
+                        &format!("extra source {repo} skipped: {e}"),
+                    );
+                    return Ok(None);
+                }
+            };
+            if !fallback_allowed(opts.fallback_policy, &resolved, crate_name, version) {
+                return Err(AiDocsError::Other(format!(
+                    "no tag found for {version} in {repo}; refused by fallback policy \"error\""
+                )));
+            }
+            let fetched_repo = resolved.renamed_to.as_deref().unwrap_or(repo);
+            let commit = match github
+                .resolve_commit_sha(fetched_repo, &resolved.git_ref)
+                .await
+            {
+                Ok(sha) => Some(sha),
+                Err(e) => {
+                    warn!(
+                        "  ⚠ could not resolve commit of {} in {repo} for {crate_name}@{version}: {e}",
+                        resolved.git_ref
+                    );
+                    None
+                }
+            };
+            (resolved, commit)
+        }
+    };
+    let fetched_repo = resolved.renamed_to.as_deref().unwrap_or(repo);
+    let fetch_ref = match (locked, commit.as_deref()) {
+        (Some(_), Some(sha)) => sha,
+        _ => resolved.git_ref.as_str(),
+    };
+    let access = RepoAccess {
+        repo: fetched_repo,
+        git_ref: fetch_ref,
+        github,
+        local: None,
+    };
+    let headers = crate_doc.request_headers();
+    let requests = build_requests(None, files, &opts.default_files, &opts.changelog_candidates);
+    let requests = expand_directory_requests(
+        &access,
+        requests,
+        &opts.include_extensions,
+        crate_name,
+        version,
+    )
+    .await;
+    let results = access.fetch_files(&requests, &headers, budget).await;
+    let mut collection = collect_fetched_files(results, crate_name, version);
+    guard.charge_files(crate_name, version, &collection.files);
+    guard.ensure_within_limit()?;
+    if opts.resolve_includes {
+        resolve_includes(
+            &access,
+            &mut collection.files,
+            &headers,
+            budget,
+            guard,
+            crate_name,
+            version,
+        )
+        .await;
+        guard.ensure_within_limit()?;
+    }
+    debug!(
+        "  {crate_name}@{version}: {} file(s) from {fetched_repo}@{}",
+        collection.files.len(),
+        resolved.git_ref
+    );
+    Ok(Some(SourceFiles {
+        repo: fetched_repo.to_string(),
+        pin: storage::SourceRef {
+            repo: repo.to_string(),
+            git_ref: resolved.git_ref.clone(),
+            commit,
+            is_fallback: resolved.is_fallback,
+        },
+        requests,
+        collection,
+    }))
+}
+
+/// Adds the files of extra sources to the crate's own. A path that is
+/// already taken moves under the source's `owner__repo/` directory, so a
+/// second `README.md` from `rust-lang/book` is saved as
+/// `rust-lang__book__README.md` with the default `filename_template`.
+fn merge_additional_sources(
+    requests: &mut Vec<FileRequest>,
+    collection: &mut FetchCollection,
+    sources: Vec<SourceFiles>,
+) {
+    for source in sources {
+        let taken: HashSet<String> = requests
+            .iter()
+            .map(|r| r.original_path.clone())
+            .chain(collection.files.iter().map(|f| f.path.clone()))
+            .collect();
+        let prefix = format!("{}/", source.repo.replace('/', "__"));
+        let place = |path: String| {
+            if taken.contains(&path) {
+                format!("{prefix}{path}")
+            } else {
+                path
+            }
+        };
+        requests.extend(source.requests.into_iter().map(|mut request| {
+            request.original_path = place(request.original_path);
+            request
+        }));
+        collection
+            .files
+            .extend(source.collection.files.into_iter().map(|mut file| {
+                file.path = place(file.path);
+                file
+            }));
+        collection
+            .absent
+            .extend(source.collection.absent.into_iter().map(place));
+        collection.non_optional_errors += source.collection.non_optional_errors;
+        collection.missing_required += source.collection.missing_required;
+    }
+}
+
+async fn run_orchestrated_sync<F, Fut>(
+    config: &Config,
+    jobs: Vec<(String, crate::config::CrateDoc)>,
+    worker: F,
+) -> Vec<(String, SyncOutcome)>
+where
+    F: Fn(String, crate::config::CrateDoc) -> Fut,
+    Fut: std::future::Future<Output = SyncOutcome> + Send + 'static,
+{
+    run_orchestrated_sync_until(config, jobs, worker, ctrl_c()).await
+}
+
+/// Resolves on Ctrl-C; never resolves if the handler cannot be installed.
+async fn ctrl_c() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("failed to install Ctrl-C handler: {e}");
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Once `shutdown` resolves, queued jobs are not started (they report
+/// `SyncOutcome::Interrupted`) while jobs already holding a permit run to
+/// completion, so no crate dir is left half-written.
+async fn run_orchestrated_sync_until<F, Fut, S>(
+    config: &Config,
+    jobs: Vec<(String, crate::config::CrateDoc)>,
+    worker: F,
+    shutdown: S,
+) -> Vec<(String, SyncOutcome)>
+where
+    F: Fn(String, crate::config::CrateDoc) -> Fut,
+    Fut: std::future::Future<Output = SyncOutcome> + Send + 'static,
+    S: std::future::Future<Output = ()>,
+{
+    let concurrency = config.settings.sync_concurrency;
+    let fail_fast = config.settings.fail_fast;
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let mut join_set = tokio::task::JoinSet::new();
+    let mut names = HashMap::new();
+
+    for (name, doc) in jobs {
+        let semaphore = Arc::clone(&semaphore);
+        let span = info_span!(
+            "sync_crate",
+            "crate" = %name,
+            version = tracing::field::Empty,
+            source_kind = tracing::field::Empty,
+        );
+        let fut = worker(name.clone(), doc).instrument(span);
+        let job_name = name.clone();
+        let task = join_set.spawn(async move {
+            let Ok(_permit) = semaphore.acquire_owned().await else {
+                return (job_name, SyncOutcome::Interrupted);
+            };
+            (job_name, fut.await)
+        });
+        names.insert(task.id(), name);
+    }
+
+    let mut shutdown = std::pin::pin!(shutdown);
+    let mut interrupted = false;
+    let mut outcomes = Vec::new();
+    loop {
+        let joined = tokio::select! {
+            joined = join_set.join_next() => joined,
+            () = &mut shutdown, if !interrupted => {
+                interrupted = true;
+                semaphore.close();
+                warn!("Ctrl-C received: finishing in-flight sync jobs, not starting new ones");
+                continue;
+            }
+        };
+        let Some(joined) = joined else {
+            break;
+        };
+        let (name, outcome) = match joined {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("sync worker panicked: {e}");
+                let name = names.remove(&e.id()).unwrap_or_default();
+                let failure =
+                    SyncFailure::new(SyncErrorKind::Other, format!("sync worker panicked: {e}"));
+                (name, SyncOutcome::Error(failure))
+            }
+        };
+        let failed = matches!(outcome, SyncOutcome::Error(_));
+        outcomes.push((name, outcome));
+
+        if fail_fast && failed {
+            if !join_set.is_empty() {
+                warn!(
+                    "fail-fast: aborting {} remaining sync job(s) after first error",
+                    join_set.len()
+                );
+            }
+            join_set.abort_all();
+            break;
+        }
+    }
+    outcomes
+}
+
+fn resolve_sync_mode(mode_override: Option<SyncModeArg>, configured_mode: SyncMode) -> SyncMode {
+    mode_override
+        .map(SyncModeArg::to_sync_mode)
+        .unwrap_or(configured_mode)
+}
+
+fn is_github_actions() -> bool {
+    std::env::var("GITHUB_ACTIONS")
+        .ok()
+        .is_some_and(|v| v == "true")
+}
+
+/// Escapes data for a GitHub Actions workflow command (`%`, CR and LF).
+fn escape_workflow_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn format_sync_warning_annotation(crate_name: &str, version: &str, message: &str) -> String {
+    format!(
+        "::warning title=ai-fdocs sync::{}",
+        escape_workflow_data(&format!("{crate_name}@{version}: {message}"))
+    )
+}
+
+/// Logs a non-fatal sync warning; in GitHub Actions it is also emitted as a
+/// `::warning` annotation so it shows up in the PR checks UI.
+fn emit_sync_warning(crate_name: &str, version: &str, message: &str) {
+    warn!("  ⚠ {crate_name}@{version}: {message}");
+    if is_github_actions() {
+        eprintln!(
+            "{}",
+            format_sync_warning_annotation(crate_name, version, message)
+        );
+    }
+}
+
+const fn should_emit_plain_check_errors(format: OutputFormat, github_actions: bool) -> bool {
+    !github_actions && matches!(format, OutputFormat::Table)
+}
+
+fn emit_check_failures_for_ci(format: OutputFormat, statuses: &[crate::status::CrateStatus]) {
+    let github_actions = is_github_actions();
+
+    for status in statuses
+        .iter()
+        .filter(|s| !matches!(s.status, DocsStatus::Synced | DocsStatus::SyncedFallback))
+    {
+        if github_actions {
+            eprintln!(
+                "::error title=ai-fdocs check::{} [{}] {}",
+                status.crate_name,
+                status.status.as_str(),
+                status.reason
+            );
+        } else if should_emit_plain_check_errors(format, github_actions) {
+            eprintln!(
+                "[ai-fdocs check] {} [{}] {}",
+                status.crate_name,
+                status.status.as_str(),
+                status.reason
+            );
+        }
+    }
+}
+
+fn print_statuses(
+    format: OutputFormat,
+    statuses: &[crate::status::CrateStatus],
+    rust_dir: &Path,
+    color: bool,
+) -> Result<()> {
+    match format {
+        OutputFormat::Table => print_status_table(statuses, color),
+        OutputFormat::Json => {
+            let json = status::format_status_json(statuses).map_err(|e| {
+                error::AiDocsError::Other(format!("failed to serialize status JSON: {e}"))
+            })?;
+            println!("{json}");
+        }
+        OutputFormat::Csv => print!("{}", status::format_status_csv(statuses)),
+        OutputFormat::GithubMarkdown => {
+            print!("{}", status::format_status_github_markdown(statuses));
+        }
+        OutputFormat::Tree => print!("{}", status::format_status_tree(statuses, rust_dir)),
+    }
+
+    Ok(())
+}
+
+/// CSV and GitHub markdown are only implemented for the status report
+/// (`status`/`check`).
+fn reject_status_formats(format: OutputFormat, command: &str) -> Result<()> {
+    if matches!(
+        format,
+        OutputFormat::Csv | OutputFormat::GithubMarkdown | OutputFormat::Tree
+    ) {
+        let name = format
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        return Err(AiDocsError::Other(format!(
+            "{command} does not support --format {name} (use table or json)"
+        )));
+    }
+    Ok(())
+}
+
+async fn run_status(
+    config_path: &Path,
+    overrides: ConfigOverrides<'_>,
+    mode_override: Option<SyncModeArg>,
+    format: OutputFormat,
+    watch_secs: Option<u64>,
+    color: bool,
 ) -> Result<()> {
-    let config = Config::load(config_path)?;
-    info!("Loaded config from {}", config_path.display());
+    let mut config = load_config(config_path, overrides)?;
+    expand_wildcard_crates(&mut config, false).await;
     let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
 
     let sync_mode = resolve_sync_mode(mode_override, config.settings.sync_mode);
+    let fetcher = LatestDocsFetcher::new(&HttpOptions::from_settings(&config.settings))
+        .with_sparse_index(config.settings.crates_io_sparse_index);
 
-    let statuses = match sync_mode {
-        SyncMode::Lockfile | SyncMode::Hybrid => {
-            let rust_versions =
-                resolver::resolve_cargo_versions(PathBuf::from("Cargo.lock").as_path())?;
-            collect_status(&config, &rust_versions, &rust_dir).await
+    if let Some(secs) = watch_secs {
+        let interval = std::time::Duration::from_secs(secs);
+        return run_status_watch(
+            &config, sync_mode, &rust_dir, &fetcher, format, interval, color,
+        )
+        .await;
+    }
+
+    let statuses = collect_mode_statuses(&config, sync_mode, &rust_dir, Some(&fetcher)).await?;
+    print_statuses(format, &statuses, &rust_dir, color)
+}
+
+/// Statuses in `sync_mode`, with each crate evaluated in the mode it is
+/// synced in (`version_source`, see `CrateDoc::sync_mode`). `fetcher` is only
+/// used for latest-docs crates; `None` skips upstream checks.
+async fn collect_mode_statuses(
+    config: &Config,
+    sync_mode: SyncMode,
+    rust_dir: &Path,
+    fetcher: Option<&LatestDocsFetcher>,
+) -> Result<Vec<status::CrateStatus>> {
+    let (latest, pinned): (HashMap<_, _>, HashMap<_, _>) = config
+        .crates
+        .iter()
+        .map(|(name, crate_doc)| (name.clone(), crate_doc.clone()))
+        .partition(|(_, crate_doc)| crate_doc.sync_mode(sync_mode) == SyncMode::LatestDocs);
+    let subset = |crates| Config {
+        settings: config.settings.clone(),
+        crates,
+        wildcard: None,
+        wildcard_unresolved: Default::default(),
+        root: config.root.clone(),
+    };
+
+    let mut statuses = Vec::new();
+    if sync_mode != SyncMode::LatestDocs || !pinned.is_empty() {
+        let pinned = subset(pinned);
+        let (mut rust_versions, origin) = resolver::resolve_versions(&config.cargo_lock_path())?;
+        let manifest_versions =
+            (origin == resolver::VersionOrigin::Manifest).then(|| rust_versions.clone());
+        insert_workspace_versions(&pinned, &mut rust_versions);
+        let mut pinned_statuses = collect_status(&pinned, &rust_versions, rust_dir).await;
+        if let Some(manifest_versions) = &manifest_versions {
+            status::mark_manifest_versions(&mut pinned_statuses, manifest_versions);
         }
-        SyncMode::LatestDocs => {
-            let fetcher = LatestDocsFetcher::new(reqwest::Client::new());
-            collect_status_latest(&config, &rust_dir, Some(&fetcher)).await
+        statuses.extend(pinned_statuses);
+    }
+    if !latest.is_empty() {
+        statuses.extend(collect_status_latest(&subset(latest), rust_dir, fetcher).await);
+    }
+    statuses.sort_by(|a, b| a.crate_name.cmp(&b.crate_name));
+    Ok(statuses)
+}
+
+/// Reprints status every `interval` until Ctrl-C. Upstream (crates.io)
+/// checks run at most once per the shortest effective TTL (per-crate
+/// `ttl_hours` or `latest_ttl_hours`); in between, their outdated verdicts are
+/// carried over onto unchanged crates.
+async fn run_status_watch(
+    config: &Config,
+    sync_mode: SyncMode,
+    rust_dir: &Path,
+    fetcher: &LatestDocsFetcher,
+    format: OutputFormat,
+    interval: std::time::Duration,
+    color: bool,
+) -> Result<()> {
+    let global_ttl_hours = config.settings.latest_ttl_hours;
+    let ttl_hours = config
+        .crates
+        .values()
+        .map(|doc| doc.latest_ttl_hours(global_ttl_hours))
+        .min()
+        .unwrap_or(global_ttl_hours);
+    let ttl = std::time::Duration::from_secs(ttl_hours as u64 * 3600);
+    let mut last_upstream: Option<(Instant, Vec<status::CrateStatus>)> = None;
+    let shutdown = ctrl_c();
+    tokio::pin!(shutdown);
+
+    loop {
+        let check_upstream = sync_mode == SyncMode::LatestDocs
+            && last_upstream
+                .as_ref()
+                .is_none_or(|(checked_at, _)| checked_at.elapsed() >= ttl);
+        let collect = collect_mode_statuses(
+            config,
+            sync_mode,
+            rust_dir,
+            check_upstream.then_some(fetcher),
+        );
+        let mut statuses = tokio::select! {
+            statuses = collect => statuses?,
+            _ = &mut shutdown => break,
+        };
+
+        if check_upstream {
+            last_upstream = Some((Instant::now(), statuses.clone()));
+        } else if let Some((_, previous)) = &last_upstream {
+            status::carry_upstream_verdicts(previous, &mut statuses);
         }
-    };
 
-    print_statuses(format, &statuses)
+        if matches!(format, OutputFormat::Table | OutputFormat::Tree) {
+            // Clear screen and move the cursor home before redrawing.
+            print!("\x1B[2J\x1B[H");
+        }
+        print_statuses(format, &statuses, rust_dir, color)?;
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown => break,
+        }
+    }
+
+    Ok(())
 }
 
 async fn run_check(
     config_path: &Path,
+    overrides: ConfigOverrides<'_>,
     mode_override: Option<SyncModeArg>,
     format: OutputFormat,
+    diff: bool,
+    summary_only: bool,
+    color: bool,
 ) -> Result<()> {
-    let config = Config::load(config_path)?;
-    info!("Loaded config from {}", config_path.display());
+    if diff && format != OutputFormat::Table {
+        return Err(AiDocsError::Other(
+            "check --diff is only supported with --format table".to_string(),
+        ));
+    }
+    if summary_only && format != OutputFormat::Table {
+        return Err(AiDocsError::Other(
+            "check --summary-only is only supported with --format table".to_string(),
+        ));
+    }
+    let mut config = load_config(config_path, overrides)?;
+    expand_wildcard_crates(&mut config, false).await;
     let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
 
     let sync_mode = resolve_sync_mode(mode_override, config.settings.sync_mode);
+    let http = HttpOptions::from_settings(&config.settings);
+    let latest_fetcher = LatestDocsFetcher::new(&http)
+        .with_sparse_index(config.settings.crates_io_sparse_index)
+        .with_max_links(config.settings.docsrs_max_links);
 
-    let statuses = match sync_mode {
-        SyncMode::Lockfile | SyncMode::Hybrid => {
-            let rust_versions =
-                resolver::resolve_cargo_versions(PathBuf::from("Cargo.lock").as_path())?;
-            collect_status(&config, &rust_versions, &rust_dir).await
-        }
-        SyncMode::LatestDocs => {
-            let fetcher = LatestDocsFetcher::new(reqwest::Client::new());
-            collect_status_latest(&config, &rust_dir, Some(&fetcher)).await
-        }
-    };
+    let statuses =
+        collect_mode_statuses(&config, sync_mode, &rust_dir, Some(&latest_fetcher)).await?;
     let failing = statuses
         .iter()
         .any(|s| !matches!(s.status, DocsStatus::Synced | DocsStatus::SyncedFallback));
 
     if failing {
-        print_statuses(format, &statuses)?;
+        if summary_only {
+            println!("{}", status::format_status_summary(&statuses));
+        } else {
+            print_statuses(format, &statuses, &rust_dir, color)?;
+        }
+        if diff {
+            let github_fetcher = GitHubFetcher::new(&http)?;
+            let opts = SyncOptions::from_config(&config, false);
+            let mut diffs = Vec::new();
+            for item in statuses
+                .iter()
+                .filter(|s| matches!(s.status, DocsStatus::Outdated | DocsStatus::Missing))
+            {
+                diffs.push(
+                    preview_crate_drift(
+                        &config,
+                        &rust_dir,
+                        sync_mode,
+                        &github_fetcher,
+                        &latest_fetcher,
+                        item,
+                        &opts,
+                    )
+                    .await,
+                );
+            }
+            diff::print_diff_report(&diffs);
+        }
         emit_check_failures_for_ci(format, &statuses);
         return Err(error::AiDocsError::Other(
             "Documentation is outdated, missing, or corrupted. Run: cargo ai-fdocs sync"
@@ -914,22 +3702,848 @@ async fn run_check(
         ));
     }
 
-    match format {
-        OutputFormat::Table => info!("All configured crate docs are up to date."),
-        OutputFormat::Json => print_statuses(format, &statuses)?,
+    match format {
+        OutputFormat::Table => info!("All configured crate docs are up to date."),
+        OutputFormat::Json
+        | OutputFormat::Csv
+        | OutputFormat::GithubMarkdown
+        | OutputFormat::Tree => {
+            print_statuses(format, &statuses, &rust_dir, color)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `check --diff`: what `sync` would save for an outdated or missing crate,
+/// diffed against its cached docs. Nothing is written.
+async fn preview_crate_drift(
+    config: &Config,
+    rust_dir: &Path,
+    sync_mode: SyncMode,
+    github_fetcher: &GitHubFetcher,
+    latest_fetcher: &LatestDocsFetcher,
+    item: &status::CrateStatus,
+    opts: &SyncOptions,
+) -> diff::CrateDiff {
+    let crate_name = item.crate_name.as_str();
+    let crate_doc = &config.crates[crate_name];
+    let sync_mode = crate_doc.sync_mode(sync_mode);
+    let upstream_name = crate_doc.upstream_name(crate_name);
+    let mut drift = diff::CrateDiff {
+        crate_name: crate_name.to_string(),
+        from_version: item.docs_version.clone(),
+        to_version: item
+            .details
+            .expected
+            .clone()
+            .or_else(|| item.lock_version.clone()),
+        files: Vec::new(),
+        error: None,
+    };
+
+    if let Some(dir) = &crate_doc.path {
+        match workspace::member_version(&opts.root, dir) {
+            Ok(version) => drift.to_version = Some(version),
+            Err(e) => {
+                drift.error = Some(e.to_string());
+                return drift;
+            }
+        }
+    }
+    if drift.to_version.is_none() && sync_mode == SyncMode::LatestDocs {
+        match latest_fetcher
+            .resolve_latest_version(upstream_name, crate_doc.version_req().as_ref())
+            .await
+        {
+            Ok(version) => drift.to_version = Some(version),
+            Err(e) => {
+                drift.error = Some(format!("failed to resolve latest version: {e}"));
+                return drift;
+            }
+        }
+    }
+    let Some(version) = drift.to_version.clone() else {
+        drift.error = Some("target version unknown (crate missing in Cargo.lock?)".to_string());
+        return drift;
+    };
+
+    let cached_meta = item
+        .docs_version
+        .as_deref()
+        .and_then(|v| storage::read_meta(rust_dir, crate_name, v));
+    let upstream = if let Some(dir) = &crate_doc.path {
+        workspace_bodies(github_fetcher, crate_name, crate_doc, dir, &version, opts).await
+    } else if crate_doc.docs_format(sync_mode) == DocsFormat::DocsRs {
+        fetch_latest_artifact(
+            latest_fetcher,
+            github_fetcher,
+            crate_name,
+            crate_doc,
+            &version,
+            opts,
+        )
+        .await
+        .map(|artifact| BTreeMap::from([(artifact.file_name.to_string(), artifact.content)]))
+        .map_err(|e| format!("docs.rs fetch failed: {e}"))
+    } else {
+        async {
+            let (repo, use_git) = repo_location(crate_doc, opts.docs_source)
+                .ok_or_else(|| "no GitHub repo in config".to_string())?;
+            let doc_branch = crate_doc.doc_branch.as_deref();
+            let (repo, git_ref) = if use_git {
+                let refs = git::ls_remote(&repo).await.map_err(|e| e.to_string())?;
+                let (resolved, _) = refs
+                    .resolve(&repo, upstream_name, &version, doc_branch)
+                    .map_err(|e| e.to_string())?;
+                (repo, resolved.git_ref)
+            } else {
+                let resolved = github_fetcher
+                    .resolve_ref(
+                        &repo,
+                        upstream_name,
+                        &version,
+                        doc_branch,
+                        opts.follow_repo_renames,
+                    )
+                    .await
+                    .map_err(|e| format!("failed to resolve ref: {e}"))?;
+                (resolved.renamed_to.unwrap_or(repo), resolved.git_ref)
+            };
+            let revision = RepoRevision {
+                repo: &repo,
+                use_git,
+                git_ref: &git_ref,
+                subpath: crate_doc.subpath.as_deref().or(cached_meta
+                    .as_ref()
+                    .and_then(|meta| meta.inferred_subpath.as_deref())),
+                docsrs_readme: crate_doc.docs_format(sync_mode) == DocsFormat::Hybrid,
+            };
+            fetch_expected_bodies(
+                github_fetcher,
+                latest_fetcher,
+                crate_name,
+                crate_doc,
+                &version,
+                &revision,
+                opts,
+            )
+            .await
+        }
+        .await
+    };
+
+    match upstream {
+        Ok(upstream) => {
+            let cached = item
+                .docs_version
+                .as_deref()
+                .map(|v| diff::read_stored_bodies(&rust_dir.join(format!("{crate_name}@{v}"))))
+                .unwrap_or_default();
+            drift.files = diff::diff_bodies(&cached, &upstream);
+        }
+        Err(e) => drift.error = Some(e),
+    }
+    drift
+}
+
+async fn run_verify(
+    config_path: &Path,
+    overrides: ConfigOverrides<'_>,
+    format: OutputFormat,
+) -> Result<()> {
+    reject_status_formats(format, "verify")?;
+    let mut config = load_config(config_path, overrides)?;
+    expand_wildcard_crates(&mut config, true).await;
+    let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
+
+    let http = HttpOptions::from_settings(&config.settings);
+    let github_fetcher = GitHubFetcher::new(&http)?;
+    let latest_fetcher =
+        LatestDocsFetcher::new(&http).with_max_links(config.settings.docsrs_max_links);
+    let opts = SyncOptions::from_config(&config, false);
+
+    let existing = status::scan_existing_dirs(&rust_dir);
+    let mut crate_names: Vec<_> = config.crates.keys().collect();
+    crate_names.sort();
+
+    let mut results = Vec::new();
+    for crate_name in crate_names {
+        let result = match existing.get(crate_name) {
+            Some((version, _)) => {
+                verify_one_crate(
+                    &rust_dir,
+                    &github_fetcher,
+                    &latest_fetcher,
+                    crate_name,
+                    &config.crates[crate_name],
+                    version,
+                    opts.clone(),
+                )
+                .await
+            }
+            None => CrateVerify::new(
+                crate_name,
+                None,
+                VerifyStatus::Skipped,
+                "no synced docs found for this crate",
+            ),
+        };
+        results.push(result);
+    }
+
+    match format {
+        OutputFormat::Table => verify::print_verify_table(&results),
+        OutputFormat::Json => {
+            let json = verify::format_verify_json(&results).map_err(|e| {
+                error::AiDocsError::Other(format!("failed to serialize verify JSON: {e}"))
+            })?;
+            println!("{json}");
+        }
+        OutputFormat::Csv | OutputFormat::GithubMarkdown | OutputFormat::Tree => {
+            unreachable!("rejected by reject_status_formats")
+        }
+    }
+
+    if results
+        .iter()
+        .any(|r| matches!(r.status, VerifyStatus::Mismatch | VerifyStatus::Error))
+    {
+        return Err(error::AiDocsError::Other(
+            "Cached docs differ from upstream or could not be verified. Run: cargo ai-fdocs sync --force"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Re-fetches the sources a crate was synced from (same ref, same processing
+/// pipeline as `sync`) and compares them with the stored files.
+async fn verify_one_crate(
+    rust_dir: &Path,
+    github_fetcher: &GitHubFetcher,
+    latest_fetcher: &LatestDocsFetcher,
+    crate_name: &str,
+    crate_doc: &crate::config::CrateDoc,
+    version: &str,
+    opts: SyncOptions,
+) -> CrateVerify {
+    let Some(meta) = storage::read_meta(rust_dir, crate_name, version) else {
+        return CrateVerify::new(
+            crate_name,
+            Some(version),
+            VerifyStatus::Error,
+            ".aifd-meta.toml is missing or unreadable",
+        );
+    };
+    let source_kind = meta.source_kind.as_deref().unwrap_or("github");
+    // Same changelog cut as the sync that saved the docs.
+    let mut opts = opts;
+    opts.changelog.since_version = meta.synced_from.clone();
+
+    let mut expected = BTreeMap::new();
+    if source_kind == "docsrs" {
+        // Compare against the format that was saved, not the current setting.
+        opts.docsrs_format = match meta.artifact_path.as_deref() {
+            Some(path) if path == DocsRsFormat::Html.artifact_file() => DocsRsFormat::Html,
+            _ => DocsRsFormat::Markdown,
+        };
+        match fetch_latest_artifact(
+            latest_fetcher,
+            github_fetcher,
+            crate_name,
+            crate_doc,
+            version,
+            &opts,
+        )
+        .await
+        {
+            Ok(artifact) => {
+                expected.insert(artifact.file_name.to_string(), artifact.content);
+            }
+            Err(e) => {
+                return CrateVerify::new(
+                    crate_name,
+                    Some(version),
+                    VerifyStatus::Error,
+                    format!("docs.rs fetch failed: {e}"),
+                );
+            }
+        }
+    } else if source_kind == "workspace" {
+        let Some(dir) = &crate_doc.path else {
+            return CrateVerify::new(
+                crate_name,
+                Some(version),
+                VerifyStatus::Skipped,
+                "synced from a workspace member but `path` is no longer configured",
+            );
+        };
+        match workspace_bodies(github_fetcher, crate_name, crate_doc, dir, version, &opts).await {
+            Ok(bodies) => expected = bodies,
+            Err(reason) => {
+                return CrateVerify::new(crate_name, Some(version), VerifyStatus::Error, reason)
+            }
+        }
+    } else {
+        let Some((repo, use_git)) = repo_location(crate_doc, opts.docs_source) else {
+            return CrateVerify::new(
+                crate_name,
+                Some(version),
+                VerifyStatus::Skipped,
+                "no GitHub repo in config",
+            );
+        };
+        let revision = RepoRevision {
+            repo: &repo,
+            use_git,
+            git_ref: &meta.git_ref,
+            // Same subpath as the sync, including one inferred by `settings.infer_subpath`.
+            subpath: crate_doc
+                .subpath
+                .as_deref()
+                .or(meta.inferred_subpath.as_deref()),
+            docsrs_readme: source_kind == "hybrid_docsrs_github",
+        };
+        match fetch_expected_bodies(
+            github_fetcher,
+            latest_fetcher,
+            crate_name,
+            crate_doc,
+            version,
+            &revision,
+            &opts,
+        )
+        .await
+        {
+            Ok(bodies) => expected = bodies,
+            Err(reason) => {
+                return CrateVerify::new(crate_name, Some(version), VerifyStatus::Error, reason)
+            }
+        }
+    }
+
+    let crate_dir = rust_dir.join(format!("{crate_name}@{version}"));
+    let mismatched = verify::compare_with_stored(&crate_dir, &expected);
+    if mismatched.is_empty() {
+        return CrateVerify::new(
+            crate_name,
+            Some(version),
+            VerifyStatus::Match,
+            format!("content matches upstream ref {}", meta.git_ref),
+        );
+    }
+
+    let mut result = CrateVerify::new(
+        crate_name,
+        Some(version),
+        VerifyStatus::Mismatch,
+        format!(
+            "{} file(s) differ from upstream ref {}",
+            mismatched.len(),
+            meta.git_ref
+        ),
+    );
+    result.mismatched_files = mismatched;
+    result
+}
+
+/// Repository of a crate and whether it is cloned (`source = "git"`; the
+/// location is then a clone URL) instead of fetched from GitHub.
+fn repo_location(
+    crate_doc: &crate::config::CrateDoc,
+    default_source: DocsSource,
+) -> Option<(String, bool)> {
+    if crate_doc.docs_source(default_source) == DocsSource::Git {
+        crate_doc.git_clone_url().map(|url| (url, true))
+    } else {
+        crate_doc
+            .github_repo()
+            .map(|repo| (repo.to_string(), false))
+    }
+}
+
+/// One repository revision rebuilt without saving (`verify`, `check --diff`).
+struct RepoRevision<'a> {
+    repo: &'a str,
+    use_git: bool,
+    git_ref: &'a str,
+    subpath: Option<&'a str>,
+    /// Hybrid syncs: the docs.rs API markdown, when it can be fetched,
+    /// replaces the README.
+    docsrs_readme: bool,
+}
+
+/// Fetches `revision` and runs it through the same processing as `sync`.
+/// Returns `flat name -> body` (no provenance header) or why it failed.
+async fn fetch_expected_bodies(
+    github_fetcher: &GitHubFetcher,
+    latest_fetcher: &LatestDocsFetcher,
+    crate_name: &str,
+    crate_doc: &crate::config::CrateDoc,
+    version: &str,
+    revision: &RepoRevision<'_>,
+    opts: &SyncOptions,
+) -> std::result::Result<BTreeMap<String, String>, String> {
+    let repo = revision.repo;
+    let git_ref = revision.git_ref;
+    let checkout = if revision.use_git {
+        let checkout = GitCheckout::shallow_clone(repo, git_ref)
+            .await
+            .map_err(|e| format!("git clone failed: {e}"))?;
+        Some(checkout)
+    } else {
+        None
+    };
+    let access = RepoAccess {
+        repo,
+        git_ref,
+        github: github_fetcher,
+        local: checkout.as_ref().map(GitCheckout::tree),
+    };
+
+    let subpath = revision.subpath;
+    let requests = build_requests(
+        subpath,
+        crate_doc.effective_files(),
+        &opts.default_files,
+        &opts.changelog_candidates,
+    );
+    let mut requests = expand_directory_requests(
+        &access,
+        requests,
+        &opts.include_extensions,
+        crate_name,
+        version,
+    )
+    .await;
+    let mut files = Vec::new();
+    if revision.docsrs_readme {
+        if let Ok(artifact) = latest_fetcher
+            .fetch_api_markdown(
+                crate_doc.upstream_name(crate_name),
+                version,
+                opts.max_file_size_kb,
+            )
+            .await
+        {
+            drop_readme_requests(&mut requests, subpath);
+            merge_docsrs_readme(&mut files, artifact);
+        }
+    }
+
+    let headers = crate_doc.request_headers();
+    let budget = RetryBudget::new(opts.max_retries_per_crate);
+    let results = access.fetch_files(&requests, &headers, &budget).await;
+    let mut collected = collect_fetched_files(results, crate_name, version);
+    if collected.non_optional_errors > 0 {
+        return Err(format!(
+            "{} file(s) failed to fetch from {repo}@{git_ref}",
+            collected.non_optional_errors
+        ));
+    }
+    // `files` holds the docs.rs README when it replaced the GitHub one.
+    if files.is_empty() && probes_docs_dirs(opts, crate_doc, subpath) {
+        let probes = docs_dir_probes(&requests, &collected.files);
+        let found =
+            fetch_docs_dir_probes(&access, &probes, &headers, &budget, crate_name, version).await;
+        requests.extend(probes);
+        collected.files.extend(found.files);
+    }
+    if opts.resolve_includes {
+        resolve_includes(
+            &access,
+            &mut collected.files,
+            &headers,
+            &budget,
+            &DownloadGuard::default(),
+            crate_name,
+            version,
+        )
+        .await;
+    }
+    files.append(&mut collected.files);
+    let sources = fetch_additional_sources(
+        github_fetcher,
+        crate_name,
+        crate_doc,
+        version,
+        opts,
+        &budget,
+        &DownloadGuard::default(),
+    )
+    .await
+    .map_err(|e| format!("extra source failed: {e}"))?;
+    collected.files = files;
+    merge_additional_sources(&mut requests, &mut collected, sources);
+    if collected.non_optional_errors > 0 {
+        return Err(format!(
+            "{} file(s) failed to fetch from extra sources",
+            collected.non_optional_errors
+        ));
+    }
+    let files = collected.files;
+    Ok(expected_bodies(
+        &files, crate_name, crate_doc, version, opts,
+    ))
+}
+
+/// `flat name -> body` (no provenance header) of `files` after the same
+/// processing as `sync`.
+fn expected_bodies(
+    files: &[FetchedFile],
+    crate_name: &str,
+    crate_doc: &crate::config::CrateDoc,
+    version: &str,
+    opts: &SyncOptions,
+) -> BTreeMap<String, String> {
+    let changelog = opts.changelog.for_crate(crate_doc);
+    let mut bodies: Vec<(&str, String)> = files
+        .iter()
+        .filter(|file| storage::is_saved_file(&file.path, crate_doc, &opts.include_extensions))
+        .map(|file| {
+            let (body, _) = storage::process_file_content(
+                &file.path,
+                &file.content,
+                version,
+                opts.max_file_size_kb,
+                opts.cleanup,
+                &changelog,
+            );
+            (file.path.as_str(), body)
+        })
+        .collect();
+    storage::fit_token_budget(&mut bodies, opts.max_tokens_per_crate);
+
+    let mut expected = BTreeMap::new();
+    for (path, body) in bodies {
+        let body = if opts.embed_ai_notes
+            && storage::should_embed_ai_notes(path, &opts.header_extensions)
+        {
+            storage::embed_ai_notes(&body, &crate_doc.ai_notes)
+        } else {
+            body
+        };
+        let name = storage::render_filename(&opts.filename_template, crate_name, version, path);
+        expected.insert(name, body);
+    }
+    expected
+}
+
+fn run_stats(
+    config_path: &Path,
+    overrides: ConfigOverrides<'_>,
+    format: OutputFormat,
+) -> Result<()> {
+    reject_status_formats(format, "stats")?;
+    let config = load_config(config_path, overrides)?;
+    let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
+
+    let cache_stats = stats::collect_stats(&rust_dir);
+    match format {
+        OutputFormat::Table => stats::print_stats_table(&cache_stats),
+        OutputFormat::Json => {
+            let json = stats::format_stats_json(&cache_stats).map_err(|e| {
+                error::AiDocsError::Other(format!("failed to serialize stats JSON: {e}"))
+            })?;
+            println!("{json}");
+        }
+        OutputFormat::Csv | OutputFormat::GithubMarkdown | OutputFormat::Tree => {
+            unreachable!("rejected by reject_status_formats")
+        }
+    }
+
+    Ok(())
+}
+
+fn run_coverage(
+    config_path: &Path,
+    overrides: ConfigOverrides<'_>,
+    format: OutputFormat,
+) -> Result<()> {
+    reject_status_formats(format, "coverage")?;
+    let config = load_config(config_path, overrides)?;
+    let versions = resolver::resolve_cargo_versions(&config.cargo_lock_path())?;
+
+    let report = coverage::collect_coverage(&config, &versions);
+    match format {
+        OutputFormat::Table => coverage::print_coverage_table(&report),
+        OutputFormat::Json => {
+            let json = coverage::format_coverage_json(&report).map_err(|e| {
+                AiDocsError::Other(format!("failed to serialize coverage JSON: {e}"))
+            })?;
+            println!("{json}");
+        }
+        OutputFormat::Csv | OutputFormat::GithubMarkdown | OutputFormat::Tree => {
+            unreachable!("rejected by reject_status_formats")
+        }
+    }
+
+    Ok(())
+}
+
+fn run_explain(code: Option<&str>) -> Result<()> {
+    let Some(code) = code else {
+        for info in status::REASON_CODES {
+            println!("{:<28} {}", info.code, info.status.as_str());
+        }
+        return Ok(());
+    };
+    match status::reason_code(code.trim()) {
+        Some(info) => {
+            print!("{}", status::format_reason_code(info));
+            Ok(())
+        }
+        None => Err(AiDocsError::Other(format!(
+            "unknown reason code `{code}`; run `cargo ai-fdocs explain` to list them"
+        ))),
+    }
+}
+
+/// Runs every check even when earlier ones fail (falling back to default
+/// settings without a valid config), and fails if any check failed.
+async fn run_doctor(config_path: &Path, profile: Option<&str>) -> Result<()> {
+    let (config_check, config) = doctor::check_config(config_path, profile);
+    let mut config = config.unwrap_or_else(|| Config {
+        settings: Settings::default(),
+        crates: HashMap::new(),
+        wildcard: None,
+        wildcard_unresolved: Default::default(),
+        root: config_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    });
+    config.apply_output_dir_override(None);
+    let settings = &config.settings;
+
+    let mut checks = vec![
+        config_check,
+        doctor::check_cargo_lock(&config.cargo_lock_path(), settings.sync_mode),
+    ];
+    let http = HttpOptions::from_settings(settings);
+    let github = GitHubFetcher::new(&http);
+    checks.push(doctor::check_github_token(github.as_ref()).await);
+    checks.push(doctor::check_output_dir(&settings.output_dir));
+    let client = http.client_builder().build()?;
+    checks.extend(doctor::check_reachability(&client, doctor::network_targets(settings)).await);
+
+    doctor::print_report(&checks);
+    let failed = checks
+        .iter()
+        .filter(|check| check.state == doctor::CheckState::Fail)
+        .count();
+    if failed > 0 {
+        return Err(AiDocsError::Other(format!(
+            "{failed} doctor check(s) failed"
+        )));
+    }
+    Ok(())
+}
+
+fn run_index(config_path: &Path, overrides: ConfigOverrides<'_>) -> Result<()> {
+    let config = load_config(config_path, overrides)?;
+    let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
+
+    let saved_crates = index::collect_saved_crates(&rust_dir, &config);
+    index::generate_index(&rust_dir, &saved_crates)?;
+    info!(
+        "✅ Index rebuilt: {} crate(s) in {} and {}",
+        saved_crates.len(),
+        rust_dir.join("_INDEX.md").display(),
+        rust_dir.join("_INDEX.json").display()
+    );
+
+    Ok(())
+}
+
+fn run_history(
+    config_path: &Path,
+    overrides: ConfigOverrides<'_>,
+    crate_filter: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    reject_status_formats(format, "history")?;
+    let config = load_config(config_path, overrides)?;
+    let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
+
+    let mut histories: Vec<_> = status::scan_existing_dirs(&rust_dir)
+        .into_iter()
+        .filter(|(name, _)| crate_filter.is_none_or(|wanted| wanted == name))
+        .map(|(name, (_, crate_dir))| (name, history::read_history(&crate_dir)))
+        .collect();
+    histories.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if let Some(wanted) = crate_filter {
+        if histories.is_empty() {
+            return Err(AiDocsError::Other(format!(
+                "no synced docs found for crate '{wanted}'"
+            )));
+        }
+    }
+
+    match format {
+        OutputFormat::Table => history::print_history_table(&histories),
+        OutputFormat::Json => {
+            let json = history::format_history_json(&histories).map_err(|e| {
+                AiDocsError::Other(format!("failed to serialize history JSON: {e}"))
+            })?;
+            println!("{json}");
+        }
+        OutputFormat::Csv | OutputFormat::GithubMarkdown | OutputFormat::Tree => {
+            unreachable!("rejected by reject_status_formats")
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_links(
+    config_path: &Path,
+    overrides: ConfigOverrides<'_>,
+    crate_filter: Option<&str>,
+    check_external: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    reject_status_formats(format, "links")?;
+    let config = load_config(config_path, overrides)?;
+    let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
+
+    let mut crates: Vec<_> = status::scan_existing_dirs(&rust_dir)
+        .into_iter()
+        .filter(|(name, _)| crate_filter.is_none_or(|wanted| wanted == name))
+        .collect();
+    crates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if let Some(wanted) = crate_filter {
+        if crates.is_empty() {
+            return Err(AiDocsError::Other(format!(
+                "no synced docs found for crate '{wanted}'"
+            )));
+        }
+    }
+
+    let mut broken = Vec::new();
+    let mut external = Vec::new();
+    for (name, (version, crate_dir)) in &crates {
+        let found = links::scan_crate_links(name, version, crate_dir);
+        broken.extend(found.broken);
+        external.extend(
+            found
+                .external
+                .into_iter()
+                .map(|(file, url)| (name, version, file, url)),
+        );
+    }
+
+    if check_external {
+        // Arbitrary hosts: only proxy, timeout and redirect settings, never
+        // `extra_headers` or the GitHub token.
+        let http = HttpOptions::from_settings(&config.settings);
+        let client = http.client_builder().build()?;
+        let urls = external.iter().map(|(.., url)| url.clone()).collect();
+        let failures =
+            links::check_external_links(&client, urls, config.settings.sync_concurrency).await;
+        broken.extend(
+            external
+                .into_iter()
+                .filter_map(|(name, version, file, url)| {
+                    failures.get(&url).map(|reason| links::BrokenLink {
+                        crate_name: name.clone(),
+                        version: version.clone(),
+                        file,
+                        reason: reason.clone(),
+                        link: url,
+                    })
+                }),
+        );
+        broken.sort_by(|a, b| (&a.crate_name, &a.file).cmp(&(&b.crate_name, &b.file)));
+    }
+
+    match format {
+        OutputFormat::Table => links::print_links_table(&broken),
+        OutputFormat::Json => {
+            let json = links::format_links_json(&broken)
+                .map_err(|e| AiDocsError::Other(format!("failed to serialize links JSON: {e}")))?;
+            println!("{json}");
+        }
+        OutputFormat::Csv | OutputFormat::GithubMarkdown | OutputFormat::Tree => {
+            unreachable!("rejected by reject_status_formats")
+        }
+    }
+
+    if !broken.is_empty() {
+        return Err(AiDocsError::Other(format!(
+            "{} broken link(s) in synced docs",
+            broken.len()
+        )));
+    }
+
+    Ok(())
+}
+
+fn run_chunks(
+    config_path: &Path,
+    overrides: ConfigOverrides<'_>,
+    crate_filter: Option<&str>,
+    chunk_size: usize,
+    overlap: usize,
+) -> Result<()> {
+    if chunk_size == 0 || overlap >= chunk_size {
+        return Err(AiDocsError::Other(format!(
+            "--chunk-size must be greater than 0 and --overlap ({overlap}) below it ({chunk_size})"
+        )));
+    }
+
+    let config = load_config(config_path, overrides)?;
+    let rust_dir = storage::rust_output_dir(&config.settings.output_dir);
+
+    let mut crates: Vec<_> = status::scan_existing_dirs(&rust_dir)
+        .into_iter()
+        .filter(|(name, _)| crate_filter.is_none_or(|wanted| wanted == name))
+        .collect();
+    crates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if let Some(wanted) = crate_filter {
+        if crates.is_empty() {
+            return Err(AiDocsError::Other(format!(
+                "no synced docs found for crate '{wanted}'"
+            )));
+        }
     }
 
+    let records: Vec<_> = crates
+        .iter()
+        .flat_map(|(name, (version, crate_dir))| {
+            chunks::collect_crate_chunks(name, version, crate_dir, chunk_size, overlap)
+        })
+        .collect();
+    let ndjson = chunks::format_chunks_ndjson(&records)
+        .map_err(|e| AiDocsError::Other(format!("failed to serialize chunks: {e}")))?;
+    print!("{ndjson}");
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        build_requests, collect_fetched_files, is_latest_cache_fresh, resolve_sync_mode,
-        should_emit_plain_check_errors, OutputFormat, SyncMode, SyncModeArg,
+        build_requests, collect_fetched_files, docs_dir_probes, drop_readme_requests,
+        expand_directory_requests, fallback_allowed, format_sync_warning_annotation,
+        is_readme_request, layout_changed, layout_changed_message, merge_additional_sources,
+        merge_docsrs_readme, readme_only_files, resolve_includes, resolve_sync_mode,
+        run_orchestrated_sync, run_orchestrated_sync_until, should_emit_plain_check_errors,
+        sync_jobs, sync_one_crate_workspace, DownloadGuard, GithubSync, OutputFormat, RepoAccess,
+        SourceFiles, SyncFailure, SyncMode, SyncModeArg, SyncOptions, SyncOutcome, SyncStats,
+        WORKSPACE_REF,
     };
-    use crate::error::AiDocsError;
-    use crate::fetcher::github::FetchedFile;
+    use crate::config::{Config, FallbackPolicy, Settings};
+    use crate::error::{AiDocsError, SyncErrorKind};
+    use crate::fetcher::github::{FetchedFile, GitHubFetcher, ResolvedRef};
+    use crate::fetcher::local::LocalTree;
+    use crate::fetcher::HttpOptions;
+    use crate::storage;
     use clap::{CommandFactory, Parser};
 
     #[test]
@@ -953,11 +4567,360 @@ mod tests {
         assert!(!should_emit_plain_check_errors(OutputFormat::Json, true));
     }
 
+    #[test]
+    fn sync_warning_annotation_names_crate_and_escapes_newlines() {
+        let line = format_sync_warning_annotation("serde", "1.0.0", "partial fetch\n100% failed");
+        assert_eq!(
+            line,
+            "::warning title=ai-fdocs sync::serde@1.0.0: partial fetch%0A100%25 failed"
+        );
+    }
+
+    #[test]
+    fn fallback_policy_error_rejects_branch_refs_only() {
+        let branch = crate::fetcher::github::ResolvedRef {
+            git_ref: "main".to_string(),
+            is_fallback: true,
+            fallback_reason: Some("no tag found for 1.0.0".to_string()),
+            renamed_to: None,
+        };
+        let tag = crate::fetcher::github::ResolvedRef {
+            git_ref: "v1.0.0".to_string(),
+            is_fallback: false,
+            fallback_reason: None,
+            renamed_to: None,
+        };
+
+        assert!(!fallback_allowed(
+            FallbackPolicy::Error,
+            &branch,
+            "demo",
+            "1.0.0"
+        ));
+        assert!(fallback_allowed(
+            FallbackPolicy::Error,
+            &tag,
+            "demo",
+            "1.0.0"
+        ));
+        assert!(fallback_allowed(
+            FallbackPolicy::Warn,
+            &branch,
+            "demo",
+            "1.0.0"
+        ));
+        assert!(fallback_allowed(
+            FallbackPolicy::Allow,
+            &branch,
+            "demo",
+            "1.0.0"
+        ));
+    }
+
+    #[test]
+    fn additional_sources_are_merged_with_a_repo_prefix_on_collision() {
+        let crate_doc: crate::config::CrateDoc = toml::from_str(
+            r#"sources = [
+                { type = "github", repo = "demo-rs/demo" },
+                { type = "docsrs" },
+                { type = "github", repo = "demo-rs/book", files = ["README.md", "src/intro.md"] },
+            ]"#,
+        )
+        .expect("crate entry");
+        assert_eq!(crate_doc.github_repo(), Some("demo-rs/demo"));
+        let extra = crate_doc.additional_github_sources();
+        assert_eq!(extra.len(), 1);
+        assert_eq!(extra[0].0, "demo-rs/book");
+
+        let file = |repo: &str, path: &str| FetchedFile {
+            path: path.to_string(),
+            matched_candidate: path.to_string(),
+            source_url: format!("https://raw.githubusercontent.com/{repo}/v1.0.0/{path}"),
+            content: format!("{repo} {path}"),
+        };
+        let mut requests = build_requests(None, None, &Settings::default().default_files, &[]);
+        let mut collection =
+            collect_fetched_files(vec![Ok(file("demo-rs/demo", "README.md"))], "demo", "1.0.0");
+        let book_requests = build_requests(None, extra[0].1.clone(), &[], &[]);
+        let book = collect_fetched_files(
+            vec![
+                Ok(file("demo-rs/book", "README.md")),
+                Ok(file("demo-rs/book", "src/intro.md")),
+            ],
+            "demo",
+            "1.0.0",
+        );
+        merge_additional_sources(
+            &mut requests,
+            &mut collection,
+            vec![SourceFiles {
+                repo: "demo-rs/book".to_string(),
+                pin: storage::SourceRef {
+                    repo: "demo-rs/book".to_string(),
+                    git_ref: "v1.0.0".to_string(),
+                    commit: None,
+                    is_fallback: false,
+                },
+                requests: book_requests,
+                collection: book,
+            }],
+        );
+
+        let paths: Vec<&str> = collection.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["README.md", "demo-rs__book/README.md", "src/intro.md"]
+        );
+        assert_eq!(collection.files[1].content, "demo-rs/book README.md");
+        assert!(requests
+            .iter()
+            .any(|r| r.original_path == "demo-rs__book/README.md"));
+        assert_eq!(
+            storage::render_filename("{flat}", "demo", "1.0.0", &collection.files[1].path),
+            "demo-rs__book__README.md"
+        );
+    }
+
+    #[test]
+    fn hybrid_saves_readme_from_docsrs_and_changelog_from_github() {
+        let mut requests = build_requests(None, None, &Settings::default().default_files, &[]);
+        drop_readme_requests(&mut requests, None);
+        assert!(requests.iter().all(|r| r.original_path != "README.md"));
+        assert!(requests.iter().any(|r| r.original_path == "CHANGELOG.md"));
+
+        // A README that still arrives from GitHub (e.g. a directory entry) is replaced.
+        let mut files = vec![
+            FetchedFile {
+                path: "README.md".to_string(),
+                matched_candidate: "README.md".to_string(),
+                source_url: "https://raw.githubusercontent.com/o/r/v1.0.0/README.md".to_string(),
+                content: "# GitHub readme".to_string(),
+            },
+            FetchedFile {
+                path: "CHANGELOG.md".to_string(),
+                matched_candidate: "CHANGELOG.md".to_string(),
+                source_url: "https://raw.githubusercontent.com/o/r/v1.0.0/CHANGELOG.md".to_string(),
+                content: "# Changelog\n\n## 1.0.0\n- first\n".to_string(),
+            },
+        ];
+        merge_docsrs_readme(
+            &mut files,
+            crate::fetcher::latest::DocsRsArtifact {
+                file_name: "API.md",
+                content: "# demo API from docs.rs".to_string(),
+                docsrs_input_url: "https://docs.rs/crate/demo/1.0.0".to_string(),
+                truncated: false,
+            },
+        );
+        assert_eq!(files.len(), 2);
+
+        let tmp = std::env::temp_dir().join(format!("aifd-hybrid-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let resolved = crate::fetcher::github::ResolvedRef {
+            git_ref: "v1.0.0".to_string(),
+            is_fallback: false,
+            fallback_reason: None,
+            renamed_to: None,
+        };
+        let settings = Settings::default();
+        let changelog = crate::processor::changelog::ChangelogOptions::from_settings(&settings);
+        let save_ctx = crate::storage::SaveContext {
+            source: "github.com/o/r",
+            resolved: &resolved,
+            commit_sha: None,
+            expected_content_sha256: None,
+            inferred_subpath: None,
+            max_file_size_kb: settings.max_file_size_kb,
+            max_tokens_per_crate: None,
+            source_kind: "hybrid_docsrs_github",
+            artifact_path: None,
+            docsrs_input_url: None,
+            upstream_latest_version: Some("1.0.0"),
+            truncated: None,
+            cleanup: crate::processor::markdown::CleanupOptions::from_settings(&settings),
+            changelog: &changelog,
+            metrics: None,
+            embed_ai_notes: false,
+            inject_header: settings.inject_header,
+            header_extensions: &settings.inject_header_extensions,
+            prefer_source_readme: settings.prefer_source_readme,
+            include_extensions: &settings.include_extensions,
+            filename_template: &settings.filename_template,
+            extra_sources: &[],
+        };
+        let crate_doc = crate::config::CrateDoc::default();
+        let save_req = crate::storage::SaveRequest {
+            crate_name: "demo",
+            version: "1.0.0",
+            requests: &requests,
+            fetched_files: &files,
+            absent_files: &[],
+            crate_config: &crate_doc,
+        };
+        let saved = crate::storage::save_crate_files(&tmp, &save_ctx, save_req).expect("save");
+        assert_eq!(saved.files.len(), 2);
+
+        let crate_dir = tmp.join("demo@1.0.0");
+        let readme = std::fs::read_to_string(crate_dir.join("README.md")).expect("readme");
+        assert!(readme.contains("# demo API from docs.rs"));
+        assert!(readme.contains("docs.rs/crate/demo/1.0.0"));
+        assert!(!readme.contains("GitHub readme"));
+        let changelog = std::fs::read_to_string(crate_dir.join("CHANGELOG.md")).expect("changelog");
+        assert!(changelog.contains("- first"));
+
+        let meta = crate::storage::read_meta(&tmp, "demo", "1.0.0").expect("meta");
+        let readme = &meta.files["README.md"];
+        assert_eq!(readme.matched_candidate.as_deref(), Some("API.md"));
+        assert!(readme.candidates.is_empty());
+        let changelog = &meta.files["CHANGELOG.md"];
+        assert_eq!(changelog.matched_candidate.as_deref(), Some("CHANGELOG.md"));
+        let fetched = files.iter().find(|f| f.path == "CHANGELOG.md");
+        assert_eq!(Some(changelog.bytes), fetched.map(|f| f.content.len()));
+        assert_eq!(
+            changelog.candidates,
+            requests
+                .iter()
+                .find(|r| r.original_path == "CHANGELOG.md")
+                .expect("changelog request")
+                .candidates
+        );
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn locked_sync_rejects_changed_content() {
+        let requests = build_requests(None, None, &Settings::default().default_files, &[]);
+        let files: Vec<FetchedFile> = [("README.md", "# Demo\n"), ("CHANGELOG.md", "## 1.0.0\n")]
+            .into_iter()
+            .map(|(path, content)| FetchedFile {
+                path: path.to_string(),
+                matched_candidate: path.to_string(),
+                source_url: format!("https://raw.githubusercontent.com/o/r/v1.0.0/{path}"),
+                content: content.to_string(),
+            })
+            .collect();
+
+        let tmp = std::env::temp_dir().join(format!("aifd-locked-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let resolved = crate::fetcher::github::ResolvedRef {
+            git_ref: "v1.0.0".to_string(),
+            is_fallback: false,
+            fallback_reason: None,
+            renamed_to: None,
+        };
+        let settings = Settings::default();
+        let changelog = crate::processor::changelog::ChangelogOptions::from_settings(&settings);
+        let save_ctx = crate::storage::SaveContext {
+            source: "github.com/o/r",
+            resolved: &resolved,
+            commit_sha: None,
+            expected_content_sha256: None,
+            inferred_subpath: None,
+            max_file_size_kb: settings.max_file_size_kb,
+            max_tokens_per_crate: None,
+            source_kind: "github",
+            artifact_path: None,
+            docsrs_input_url: None,
+            upstream_latest_version: None,
+            truncated: None,
+            cleanup: crate::processor::markdown::CleanupOptions::from_settings(&settings),
+            changelog: &changelog,
+            metrics: None,
+            embed_ai_notes: false,
+            inject_header: settings.inject_header,
+            header_extensions: &settings.inject_header_extensions,
+            prefer_source_readme: settings.prefer_source_readme,
+            include_extensions: &settings.include_extensions,
+            filename_template: &settings.filename_template,
+            extra_sources: &[],
+        };
+        let crate_doc = crate::config::CrateDoc::default();
+        let save_req = |files| crate::storage::SaveRequest {
+            crate_name: "demo",
+            version: "1.0.0",
+            requests: &requests,
+            fetched_files: files,
+            absent_files: &[],
+            crate_config: &crate_doc,
+        };
+        crate::storage::save_crate_files(&tmp, &save_ctx, save_req(&files)).expect("save");
+        let crate_dir = tmp.join("demo@1.0.0");
+
+        // `--locked`: the hash is checked before the saved dir is touched.
+        let pinned = crate::lock::content_hash(&crate_dir);
+        let mut changed = files.clone();
+        changed[1].content.push_str("- second\n");
+        let locked_ctx = crate::storage::SaveContext {
+            expected_content_sha256: Some(&pinned),
+            ..save_ctx
+        };
+        assert!(matches!(
+            crate::storage::save_crate_files(&tmp, &locked_ctx, save_req(&changed)),
+            Err(AiDocsError::LockContentMismatch { .. })
+        ));
+        let kept = std::fs::read_to_string(crate_dir.join("CHANGELOG.md")).expect("changelog");
+        assert!(!kept.contains("- second"));
+        crate::storage::save_crate_files(&tmp, &locked_ctx, save_req(&files)).expect("resave");
+        assert_eq!(crate::lock::content_hash(&crate_dir), pinned);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn readme_dedup_follows_subpath_and_source_kind_reflects_docsrs_use() {
+        assert!(is_readme_request("README.md", None));
+        assert!(is_readme_request(
+            "crates/demo/readme.md",
+            Some("/crates/demo/")
+        ));
+        assert!(!is_readme_request("README.md", Some("crates/demo")));
+        assert!(!is_readme_request("docs/README.md", None));
+
+        assert_eq!(GithubSync::Plain.source_kind(), "github");
+        assert_eq!(GithubSync::LatestFallback.source_kind(), "github_fallback");
+        assert_eq!(GithubSync::Hybrid(None).source_kind(), "github");
+    }
+
+    #[test]
+    fn docs_dirs_are_probed_only_behind_a_thin_readme() {
+        let readme = |len: usize| FetchedFile {
+            path: "README.md".to_string(),
+            matched_candidate: "README.md".to_string(),
+            source_url: "https://example.com/README.md".to_string(),
+            content: "x".repeat(len),
+        };
+        let requests = build_requests(None, None, &["README.md".to_string()], &[]);
+
+        assert!(docs_dir_probes(&requests, &[readme(4096)]).is_empty());
+        let probes = docs_dir_probes(&requests, &[readme(200)]);
+        assert_eq!(
+            probes
+                .iter()
+                .map(|r| r.original_path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["docs/README.md", "book/src/README.md"]
+        );
+        assert!(probes.iter().all(|r| !r.required));
+
+        let requests = build_requests(
+            None,
+            None,
+            &["README.md".to_string(), "docs/README.md".to_string()],
+            &[],
+        );
+        let probes = docs_dir_probes(&requests, &[]);
+        assert_eq!(probes.len(), 1);
+        assert_eq!(probes[0].candidates, vec!["book/src/README.md"]);
+    }
+
     #[test]
     fn build_requests_prefers_explicit_files_and_marks_them_required() {
         let requests = build_requests(
             Some("docs"),
             Some(vec!["README.md".to_string(), "guide/intro.md".to_string()]),
+            &["ignored.md".to_string()],
+            &[],
         );
 
         assert_eq!(requests.len(), 2);
@@ -966,11 +4929,248 @@ mod tests {
         assert_eq!(requests[1].candidates, vec!["guide/intro.md"]);
     }
 
+    #[test]
+    fn build_requests_uses_default_files_under_subpath_with_case_variants() {
+        let defaults = vec![
+            "README.md".to_string(),
+            "docs/overview.md".to_string(),
+            "docs/changelog.md".to_string(),
+        ];
+        let requests = build_requests(Some("crates/demo"), None, &defaults, &[]);
+
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|r| !r.required));
+        assert_eq!(requests[0].original_path, "crates/demo/README.md");
+        assert_eq!(
+            requests[0].candidates,
+            vec![
+                "crates/demo/README.md",
+                "crates/demo/Readme.md",
+                "crates/demo/readme.md"
+            ]
+        );
+        assert_eq!(requests[1].candidates, vec!["crates/demo/docs/overview.md"]);
+        assert_eq!(requests[2].candidates[0], "crates/demo/docs/CHANGELOG.md");
+    }
+
+    #[test]
+    fn readme_only_keeps_readme_entries_and_exclusions() {
+        let files = |list: &[&str]| list.iter().map(|f| (*f).to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            readme_only_files(&files(&["README.md", "CHANGELOG.md"])),
+            files(&["README.md"])
+        );
+        assert_eq!(
+            readme_only_files(&files(&["docs/readme.md", "docs/", "!docs/internal/"])),
+            files(&["docs/readme.md", "!docs/internal/"])
+        );
+        assert_eq!(
+            readme_only_files(&files(&["CHANGELOG.md"])),
+            files(&["README.md"])
+        );
+
+        let requests = build_requests(None, None, &readme_only_files(&files(&["README.md"])), &[]);
+        assert_eq!(
+            requests[0].candidates,
+            vec!["README.md", "Readme.md", "readme.md"]
+        );
+    }
+
+    #[test]
+    fn build_requests_marks_trailing_slash_entries_as_directories() {
+        let requests = build_requests(
+            None,
+            Some(vec!["docs/".to_string(), "README.md".to_string()]),
+            &[],
+            &[],
+        );
+
+        assert!(requests[0].is_directory());
+        assert!(requests[0].candidates.is_empty());
+        assert!(!requests[1].is_directory());
+    }
+
+    #[tokio::test]
+    async fn exclusion_entries_filter_expanded_directories() {
+        let tmp = std::env::temp_dir().join(format!("aifd-exclude-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        for path in [
+            "docs/guide.md",
+            "docs/generated/api.md",
+            "docs/generated/deep/x.md",
+        ] {
+            let path = tmp.join(path);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+            std::fs::write(path, "doc\n").expect("write doc");
+        }
+        let tree = LocalTree::new(tmp.clone(), "demo");
+        let github = GitHubFetcher::new(&HttpOptions::default()).expect("fetcher");
+        let access = RepoAccess {
+            repo: "",
+            git_ref: WORKSPACE_REF,
+            github: &github,
+            local: Some(&tree),
+        };
+        let extensions = ["md".to_string()];
+
+        let requests = build_requests(
+            None,
+            Some(vec![
+                "docs/".to_string(),
+                "README.md".to_string(),
+                "!docs/generated/**".to_string(),
+                "!README.md".to_string(),
+            ]),
+            &[],
+            &[],
+        );
+        let expanded =
+            expand_directory_requests(&access, requests, &extensions, "demo", "1.0.0").await;
+        let paths: Vec<_> = expanded.iter().map(|r| r.original_path.as_str()).collect();
+        assert_eq!(paths, vec!["docs/guide.md"]);
+
+        let requests = build_requests(None, Some(vec!["!docs/**".to_string()]), &[], &[]);
+        assert!(requests[0].exclusion_pattern().is_some());
+        assert!(!requests[0].is_directory());
+        assert!(
+            expand_directory_requests(&access, requests, &extensions, "demo", "1.0.0")
+                .await
+                .is_empty()
+        );
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[tokio::test]
+    async fn changelog_entry_discovers_alternate_names() {
+        let settings = Settings::default();
+        let requests = build_requests(
+            None,
+            None,
+            &settings.default_files,
+            &settings.changelog_candidates,
+        );
+        assert_eq!(requests[1].original_path, "CHANGELOG.md");
+        assert_eq!(
+            requests[1].candidates[..5],
+            [
+                "CHANGELOG.md",
+                "Changelog.md",
+                "changelog.md",
+                "CHANGES.md",
+                "HISTORY.md"
+            ]
+        );
+
+        let github = GitHubFetcher::new(&HttpOptions::default()).expect("fetcher");
+        let tmp = std::env::temp_dir().join(format!("aifd-changes-{}", std::process::id()));
+        for (changelogs, matched) in [
+            (&["CHANGES.md", "docs/CHANGELOG.md"][..], "CHANGES.md"),
+            (&["HISTORY.md"][..], "HISTORY.md"),
+            (&["docs/CHANGELOG.md"][..], "docs/CHANGELOG.md"),
+        ] {
+            let _ = std::fs::remove_dir_all(&tmp);
+            std::fs::create_dir_all(tmp.join("docs")).expect("create dir");
+            std::fs::write(tmp.join("README.md"), "# Demo\n").expect("write readme");
+            for changelog in changelogs {
+                std::fs::write(tmp.join(changelog), "## 1.0.0\n").expect("write changelog");
+            }
+            let tree = LocalTree::new(tmp.clone(), "demo");
+            let access = RepoAccess {
+                repo: "",
+                git_ref: WORKSPACE_REF,
+                github: &github,
+                local: Some(&tree),
+            };
+
+            let results = access
+                .fetch_files(
+                    &requests,
+                    &reqwest::header::HeaderMap::new(),
+                    &super::RetryBudget::new(0),
+                )
+                .await;
+            let fetched = collect_fetched_files(results, "demo", "1.0.0");
+            assert_eq!(fetched.files.len(), 2);
+            assert_eq!(fetched.files[1].path, "CHANGELOG.md");
+            assert_eq!(fetched.files[1].matched_candidate, matched);
+        }
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[tokio::test]
+    async fn resolve_includes_fetches_nested_targets_from_the_same_tree() {
+        let tmp = std::env::temp_dir().join(format!("aifd-includes-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        for (path, content) in [
+            (
+                "README.md",
+                "# Demo\n{{#include docs/usage.md}}\n{{#include gone.md}}\n",
+            ),
+            (
+                "docs/usage.md",
+                "Usage:\n{{#include ../examples/run.sh:2}}\n",
+            ),
+            ("examples/run.sh", "#!/bin/sh\ndemo --run\n"),
+        ] {
+            let path = tmp.join(path);
+            std::fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+            std::fs::write(path, content).expect("write file");
+        }
+        let tree = LocalTree::new(tmp.clone(), "demo");
+        let github = GitHubFetcher::new(&HttpOptions::default()).expect("fetcher");
+        let access = RepoAccess {
+            repo: "",
+            git_ref: WORKSPACE_REF,
+            github: &github,
+            local: Some(&tree),
+        };
+        let requests = build_requests(None, Some(vec!["README.md".to_string()]), &[], &[]);
+        let budget = crate::fetcher::github::RetryBudget::new(1);
+        let headers = reqwest::header::HeaderMap::new();
+        let mut files = collect_fetched_files(
+            access.fetch_files(&requests, &headers, &budget).await,
+            "demo",
+            "1.0.0",
+        )
+        .files;
+
+        // Included files are charged as they arrive; over the limit nothing is inlined.
+        let tight = DownloadGuard::new(Some(0));
+        assert!(
+            !resolve_includes(&access, &mut files, &headers, &budget, &tight, "demo", "1.0.0")
+                .await
+        );
+        assert!(tight.ensure_within_limit().is_err());
+        assert!(files[0].content.contains("{{#include docs/usage.md}}"));
+
+        assert!(
+            resolve_includes(
+                &access,
+                &mut files,
+                &headers,
+                &budget,
+                &DownloadGuard::default(),
+                "demo",
+                "1.0.0",
+            )
+            .await
+        );
+        assert_eq!(
+            files[0].content,
+            "# Demo\nUsage:\ndemo --run\n{{#include gone.md}}\n"
+        );
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
     #[test]
     fn collect_fetched_files_keeps_successes_on_partial_failures() {
         let results = vec![
             Ok(FetchedFile {
                 path: "README.md".to_string(),
+                matched_candidate: "README.md".to_string(),
                 source_url: "https://example.invalid/readme".to_string(),
                 content: "hello".to_string(),
             }),
@@ -1004,6 +5204,191 @@ mod tests {
         assert_eq!(kept.non_optional_errors, 0);
     }
 
+    #[test]
+    fn missing_files_at_a_resolved_tag_are_reported_as_a_layout_change() {
+        let tag = |is_fallback: bool| ResolvedRef {
+            git_ref: "v2.0.0".to_string(),
+            is_fallback,
+            fallback_reason: None,
+            renamed_to: None,
+        };
+        let missing = collect_fetched_files(
+            vec![
+                Err(AiDocsError::OptionalFileNotFound("README.md".to_string())),
+                Err(AiDocsError::GitHubFileNotFound {
+                    repo: "owner/repo".to_string(),
+                    path: "docs/guide.md".to_string(),
+                    tried_tags: vec!["v2.0.0".to_string()],
+                }),
+            ],
+            "demo",
+            "2.0.0",
+        );
+        assert!(layout_changed(&tag(false), &missing));
+        assert!(!layout_changed(&tag(true), &missing));
+
+        let failed = collect_fetched_files(
+            vec![Err(AiDocsError::RetryBudgetExhausted {
+                url: "https://example.invalid/README.md".to_string(),
+                max_retries: 3,
+            })],
+            "demo",
+            "2.0.0",
+        );
+        assert!(!layout_changed(&tag(false), &failed));
+
+        let entries = ["Cargo.toml".to_string(), "docs/".to_string()];
+        let message = layout_changed_message("v2.0.0", &entries);
+        assert!(message.starts_with("none of the requested files exist at v2.0.0;"));
+        assert!(message.contains("set `subpath` or `files`"));
+        assert!(message.ends_with("(top-level entries: Cargo.toml, docs/)"));
+        assert!(!layout_changed_message("v2.0.0", &[]).contains("top-level"));
+    }
+
+    #[tokio::test]
+    async fn orchestrated_sync_stops_after_first_error_when_fail_fast() {
+        let config = Config {
+            settings: Settings {
+                sync_concurrency: 1,
+                fail_fast: true,
+                ..Settings::default()
+            },
+            crates: std::collections::HashMap::new(),
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+        let jobs = ["broken", "slow-a", "slow-b"]
+            .into_iter()
+            .map(|name| (name.to_string(), crate::config::CrateDoc::default()))
+            .collect();
+
+        let outcomes = run_orchestrated_sync(&config, jobs, |crate_name, _| async move {
+            if crate_name == "broken" {
+                return SyncOutcome::Error(SyncFailure::new(SyncErrorKind::Auth, "bad token"));
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            SyncOutcome::Skipped
+        })
+        .await;
+
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].0, "broken");
+        assert!(matches!(outcomes[0].1, SyncOutcome::Error(_)));
+    }
+
+    #[test]
+    fn sync_json_summary_lists_errors_with_kind_and_message() {
+        let mut stats = SyncStats {
+            synced: 2,
+            ..SyncStats::default()
+        };
+        let rate_limited = AiDocsError::GitHubRateLimit {
+            url: "https://api.github.com/repos/tokio-rs/tokio".to_string(),
+            status: 403,
+        };
+        stats.record_error("tokio".to_string(), SyncFailure::from(&rate_limited));
+        stats.record_error("anyhow".to_string(), SyncFailure::download_limit());
+
+        let json: serde_json::Value =
+            serde_json::from_str(&stats.format_json(SyncMode::Lockfile).expect("json"))
+                .expect("valid json");
+        assert_eq!(json["mode"], "lockfile");
+        assert_eq!(json["synced"], 2);
+        assert_eq!(json["errors"][0]["crate"], "anyhow");
+        assert_eq!(json["errors"][0]["kind"], "other");
+        assert_eq!(json["errors"][1]["crate"], "tokio");
+        assert_eq!(json["errors"][1]["kind"], "rate_limit");
+        assert!(json["errors"][1]["message"]
+            .as_str()
+            .is_some_and(|m| m.contains("https://api.github.com/repos/tokio-rs/tokio")));
+    }
+
+    #[tokio::test]
+    async fn orchestrated_sync_finishes_in_flight_jobs_on_shutdown() {
+        let config = Config {
+            settings: Settings {
+                sync_concurrency: 1,
+                ..Settings::default()
+            },
+            crates: std::collections::HashMap::new(),
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+        let jobs = ["a", "b", "c"]
+            .into_iter()
+            .map(|name| (name.to_string(), crate::config::CrateDoc::default()))
+            .collect();
+
+        let outcomes = run_orchestrated_sync_until(
+            &config,
+            jobs,
+            |_, _| async move {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                SyncOutcome::Skipped
+            },
+            tokio::time::sleep(std::time::Duration::from_millis(10)),
+        )
+        .await;
+
+        let finished = outcomes
+            .iter()
+            .filter(|(_, o)| matches!(o, SyncOutcome::Skipped))
+            .count();
+        let interrupted = outcomes
+            .iter()
+            .filter(|(_, o)| matches!(o, SyncOutcome::Interrupted))
+            .count();
+        assert_eq!((finished, interrupted), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn workspace_crates_are_read_locally_and_cached_until_they_change() {
+        let tmp = std::env::temp_dir().join(format!("aifd-ws-sync-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&tmp);
+        let member = tmp.join("crates/demo");
+        std::fs::create_dir_all(&member).expect("create member dir");
+        std::fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.3.0\"\n",
+        )
+        .expect("write manifest");
+        std::fs::write(member.join("README.md"), "# demo\n").expect("write readme");
+
+        let output_dir = tmp.join("fdocs");
+        let github = GitHubFetcher::new(&HttpOptions::default()).expect("fetcher");
+        let crate_doc = crate::config::CrateDoc {
+            path: Some(member.clone()),
+            ..Default::default()
+        };
+        let opts = SyncOptions::from_settings(&Settings::default(), false);
+        let sync = || {
+            sync_one_crate_workspace(
+                output_dir.clone(),
+                &github,
+                "demo".to_string(),
+                crate_doc.clone(),
+                &member,
+                opts.clone(),
+            )
+        };
+
+        assert!(matches!(sync().await, SyncOutcome::Synced(_)));
+        let meta = storage::read_meta(&output_dir, "demo", "0.3.0").expect("meta");
+        assert_eq!(meta.source_kind.as_deref(), Some("workspace"));
+        assert_eq!(meta.git_ref, WORKSPACE_REF);
+        assert!(matches!(sync().await, SyncOutcome::Cached(_)));
+
+        std::fs::write(member.join("README.md"), "# demo, edited\n").expect("edit readme");
+        assert!(matches!(sync().await, SyncOutcome::Synced(_)));
+        let saved = std::fs::read_to_string(output_dir.join("demo@0.3.0/README.md"))
+            .expect("read saved readme");
+        assert!(saved.contains("# demo, edited"));
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
     #[test]
     fn resolve_sync_mode_prefers_cli_override() {
         let mode = resolve_sync_mode(Some(SyncModeArg::LatestDocs), SyncMode::Lockfile);
@@ -1038,6 +5423,153 @@ mod tests {
         assert!(mode.is_none(), "status --mode should be optional");
     }
 
+    #[test]
+    fn sync_no_fallback_flag_is_parsed() {
+        let cli = super::Cli::parse_from(["ai-fdocs", "sync", "--no-fallback"]);
+        let super::Commands::Sync { no_fallback, .. } = cli.command else {
+            panic!("expected sync command");
+        };
+        assert!(no_fallback);
+
+        let cli = super::Cli::parse_from(["ai-fdocs", "sync"]);
+        let super::Commands::Sync { no_fallback, .. } = cli.command else {
+            panic!("expected sync command");
+        };
+        assert!(!no_fallback);
+    }
+
+    #[test]
+    fn sync_no_header_flag_is_parsed() {
+        let cli = super::Cli::parse_from(["ai-fdocs", "sync", "--no-header"]);
+        let super::Commands::Sync { no_header, .. } = cli.command else {
+            panic!("expected sync command");
+        };
+        assert!(no_header);
+    }
+
+    #[test]
+    fn sync_jobs_only_include_missing_crates_when_requested() {
+        let mut config = Config {
+            settings: Settings::default(),
+            crates: std::collections::HashMap::new(),
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+        for name in ["serde", "tokio"] {
+            config
+                .crates
+                .insert(name.to_string(), crate::config::CrateDoc::default());
+        }
+
+        let missing = std::collections::HashSet::from(["tokio".to_string()]);
+        let jobs = sync_jobs(&config, Some(&missing));
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].0, "tokio");
+        assert_eq!(sync_jobs(&config, None).len(), 2);
+    }
+
+    #[test]
+    fn crate_override_adds_ephemeral_entry_and_restricts_jobs() {
+        let mut config = Config {
+            settings: Settings::default(),
+            crates: std::collections::HashMap::new(),
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+        config
+            .crates
+            .insert("serde".to_string(), crate::config::CrateDoc::default());
+        let flags = super::SyncFlags {
+            crate_name: Some("foo".to_string()),
+            repo: Some("owner/foo".to_string()),
+            ..Default::default()
+        };
+
+        let selected = super::apply_crate_override(&mut config, &flags).expect("override");
+        assert_eq!(config.crates["foo"].repo.as_deref(), Some("owner/foo"));
+        let jobs = sync_jobs(&config, selected.as_ref());
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].0, "foo");
+
+        let unknown = super::SyncFlags {
+            crate_name: Some("bar".to_string()),
+            ..Default::default()
+        };
+        assert!(super::apply_crate_override(&mut config, &unknown).is_err());
+
+        let bad_repo = super::SyncFlags {
+            crate_name: Some("bar".to_string()),
+            repo: Some("just-a-name".to_string()),
+            ..Default::default()
+        };
+        assert!(super::apply_crate_override(&mut config, &bad_repo).is_err());
+    }
+
+    #[test]
+    fn sync_repo_flag_requires_crate() {
+        assert!(super::Cli::try_parse_from(["ai-fdocs", "sync", "--repo", "o/r"]).is_err());
+        let cli = super::Cli::parse_from([
+            "ai-fdocs",
+            "sync",
+            "--crate",
+            "foo",
+            "--repo",
+            "o/foo",
+            "--version",
+            "1.2.3",
+        ]);
+        let super::Commands::Sync { version, .. } = cli.command else {
+            panic!("expected sync command");
+        };
+        assert_eq!(version.as_deref(), Some("1.2.3"));
+    }
+
+    #[test]
+    fn log_format_is_global_and_defaults_to_text() {
+        let cli = super::Cli::parse_from(["ai-fdocs", "status", "--log-format", "json"]);
+        assert_eq!(cli.log_format, super::LogFormat::Json);
+
+        let cli = super::Cli::parse_from(["ai-fdocs", "sync"]);
+        assert_eq!(cli.log_format, super::LogFormat::Text);
+    }
+
+    #[test]
+    fn color_flag_is_global_and_always_never_ignore_the_terminal() {
+        let cli = super::Cli::parse_from(["ai-fdocs", "check", "--color", "never"]);
+        assert_eq!(cli.color, super::ColorChoice::Never);
+        assert!(!cli.color.enabled());
+        assert!(super::ColorChoice::Always.enabled());
+
+        let cli = super::Cli::parse_from(["ai-fdocs", "status"]);
+        assert_eq!(cli.color, super::ColorChoice::Auto);
+    }
+
+    #[test]
+    fn sync_max_total_size_flag_is_parsed() {
+        let cli = super::Cli::parse_from(["ai-fdocs", "sync", "--max-total-size", "50"]);
+        let super::Commands::Sync { max_total_size, .. } = cli.command else {
+            panic!("expected sync command");
+        };
+        assert_eq!(max_total_size, Some(50));
+    }
+
+    #[test]
+    fn output_dir_flag_is_optional_and_parsed() {
+        let cli = super::Cli::parse_from(["ai-fdocs", "sync", "--output-dir", "/tmp/docs"]);
+        let super::Commands::Sync { output_dir, .. } = cli.command else {
+            panic!("expected sync command");
+        };
+        assert_eq!(output_dir, Some(std::path::PathBuf::from("/tmp/docs")));
+
+        let cli = super::Cli::parse_from(["ai-fdocs", "stats"]);
+        let super::Commands::Stats { output_dir, .. } = cli.command else {
+            panic!("expected stats command");
+        };
+        assert!(output_dir.is_none());
+    }
+
     #[test]
     fn check_mode_parses_latest_docs_override() {
         let cli = super::Cli::parse_from(["ai-fdocs", "check", "--mode", "latest-docs"]);
@@ -1048,12 +5580,71 @@ mod tests {
         assert_eq!(mode, Some(SyncModeArg::LatestDocs));
     }
 
+    #[test]
+    fn check_summary_only_conflicts_with_diff() {
+        let cli = super::Cli::parse_from(["ai-fdocs", "check", "--summary-only"]);
+        let super::Commands::Check { summary_only, .. } = cli.command else {
+            panic!("expected check command");
+        };
+        assert!(summary_only);
+        assert!(
+            super::Cli::try_parse_from(["ai-fdocs", "check", "--summary-only", "--diff"]).is_err()
+        );
+    }
+
+    #[test]
+    fn download_guard_trips_once_limit_is_crossed() {
+        let guard = DownloadGuard::new(Some(1));
+        assert!(guard.allows_fetch("a"));
+        assert!(guard.charge("a", "1.0.0", 600 * 1024));
+        assert!(guard.ensure_within_limit().is_ok());
+
+        assert!(!guard.charge("b", "1.0.0", 600 * 1024));
+        assert!(!guard.allows_fetch("c"));
+        assert!(matches!(
+            guard.ensure_within_limit(),
+            Err(AiDocsError::TotalSizeExceeded { limit_mb: 1 })
+        ));
+
+        let unlimited = DownloadGuard::new(None);
+        assert!(unlimited.charge("a", "1.0.0", usize::MAX));
+        assert!(unlimited.ensure_within_limit().is_ok());
+    }
+
+    #[test]
+    fn chunks_command_parses_size_and_overlap() {
+        let cli = super::Cli::parse_from([
+            "ai-fdocs",
+            "chunks",
+            "--crate",
+            "serde",
+            "--chunk-size",
+            "200",
+            "--overlap",
+            "20",
+        ]);
+        let super::Commands::Chunks {
+            crate_name,
+            chunk_size,
+            overlap,
+            ..
+        } = cli.command
+        else {
+            panic!("expected chunks command");
+        };
+        assert_eq!(crate_name.as_deref(), Some("serde"));
+        assert_eq!((chunk_size, overlap), (200, 20));
+    }
+
     #[test]
     fn cli_subcommands_have_consistent_help_and_config_flag() {
         let mut command = super::Cli::command();
         command.build();
 
-        for sub in ["sync", "status", "check", "init"] {
+        for sub in [
+            "sync", "status", "check", "verify", "stats", "history", "links", "chunks", "index",
+            "init",
+        ] {
             let sub_cmd = command
                 .find_subcommand(sub)
                 .unwrap_or_else(|| panic!("missing subcommand: {sub}"));
@@ -1,11 +1,88 @@
 use regex::Regex;
 use tracing::debug;
 
+use crate::config::{CrateDoc, Settings};
+
+const DEFAULT_HEADING_PATTERN: &str = r"^#{1,3}\s+.*?\b?\[?v?(\d+\.\d+\.\d+(?:-[\w.]+)?)\]?\b";
+
+pub const DEFAULT_KEEP_SECTIONS: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct ChangelogOptions {
+    /// Custom heading regex; capture group 1 is the section's version label.
+    pub version_regex: Option<Regex>,
+    /// Sections kept when versions can't be compared as semver.
+    pub keep_sections: usize,
+    /// When false, changelogs are saved whole.
+    pub truncate: bool,
+    /// `sync --since-last-sync`: the version synced before; only sections
+    /// newer than it are kept.
+    pub since_version: Option<String>,
+}
+
+impl Default for ChangelogOptions {
+    fn default() -> Self {
+        Self {
+            version_regex: None,
+            keep_sections: DEFAULT_KEEP_SECTIONS,
+            truncate: true,
+            since_version: None,
+        }
+    }
+}
+
+impl ChangelogOptions {
+    /// The regex is validated at config load, so an invalid one falls back to the default.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            version_regex: settings
+                .changelog_version_regex
+                .as_deref()
+                .and_then(|pattern| compile_heading_regex(pattern).ok()),
+            keep_sections: settings.changelog_keep_sections,
+            truncate: settings.truncate_changelog,
+            since_version: None,
+        }
+    }
+
+    /// Applies the crate's `full_changelog` override.
+    pub fn for_crate(&self, crate_doc: &CrateDoc) -> Self {
+        Self {
+            truncate: crate_doc.full_changelog.map_or(self.truncate, |full| !full),
+            ..self.clone()
+        }
+    }
+}
+
+/// Compiles a heading pattern in multi-line mode (`^`/`$` match at line bounds).
+pub fn compile_heading_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("(?m){pattern}"))
+}
+
 /// Truncate changelog to entries around the current version:
 /// keep current section(s) + one previous minor series.
-pub fn truncate_changelog(content: &str, current_version: &str) -> String {
-    let heading_re = Regex::new(r"(?m)^#{1,3}\s+.*?\b?\[?v?(\d+\.\d+\.\d+(?:-[\w.]+)?)\]?\b")
-        .expect("valid changelog heading regex");
+///
+/// With `since_version` older than the current version, keeps the sections
+/// newer than `since_version` up to the current version instead (what's new
+/// since the last sync); sections above the current version, e.g. of a
+/// changelog fetched from the default branch, are dropped.
+///
+/// When the headings or the current version aren't semver (dates, build
+/// numbers), keeps the top `keep_sections` sections instead.
+pub fn truncate_changelog(
+    content: &str,
+    current_version: &str,
+    options: &ChangelogOptions,
+) -> String {
+    let default_re;
+    let heading_re = match &options.version_regex {
+        Some(re) => re,
+        None => {
+            default_re = compile_heading_regex(DEFAULT_HEADING_PATTERN)
+                .expect("valid changelog heading regex");
+            &default_re
+        }
+    };
 
     let matches: Vec<(usize, String)> = heading_re
         .captures_iter(content)
@@ -21,6 +98,36 @@ pub fn truncate_changelog(content: &str, current_version: &str) -> String {
         return content.to_string();
     }
 
+    let comparable = parse_minor(current_version).is_some()
+        && matches.iter().all(|(_, ver)| parse_minor(ver).is_some());
+    if !comparable {
+        let keep = options.keep_sections.max(1);
+        return match matches.get(keep) {
+            Some((pos, _)) => truncated_at(content, *pos, EARLIER_ENTRIES_NOTE),
+            None => content.to_string(),
+        };
+    }
+
+    if let Some(since) = options
+        .since_version
+        .as_deref()
+        .filter(|since| is_older_version(since, current_version))
+    {
+        let since_parsed = parse_version(since);
+        let cut = matches
+            .iter()
+            .find(|(_, ver)| parse_version(ver).is_some_and(|v| Some(v) <= since_parsed));
+        let kept = match cut {
+            Some((pos, _)) => truncated_at(
+                content,
+                *pos,
+                &format!("Entries up to {since} (previously synced) truncated by ai-fdocs"),
+            ),
+            None => content.to_string(),
+        };
+        return drop_newer_sections(&kept, &matches, current_version);
+    }
+
     let current_minor = parse_minor(current_version);
     let mut found_current = false;
     let mut found_previous_minor = false;
@@ -48,22 +155,67 @@ pub fn truncate_changelog(content: &str, current_version: &str) -> String {
         }
     }
 
-    if !found_current && matches.len() > 2 {
-        cut_position = Some(matches[2].0);
+    if !found_current {
+        cut_position = matches
+            .get(options.keep_sections.max(1))
+            .map(|(pos, _)| *pos);
     }
 
     match cut_position {
-        Some(pos) => {
-            let truncated = &content[..pos];
-            format!(
-                "{}\n---\n\n*[Earlier entries truncated by ai-fdocs]*\n",
-                truncated.trim_end()
-            )
-        }
+        Some(pos) => truncated_at(content, pos, EARLIER_ENTRIES_NOTE),
         None => content.to_string(),
     }
 }
 
+const EARLIER_ENTRIES_NOTE: &str = "Earlier entries truncated by ai-fdocs";
+
+/// Removes the sections of `matches` above `current_version` from `content`,
+/// keeping the text before the first heading. Unchanged when no heading is
+/// at or below `current_version`.
+fn drop_newer_sections(
+    content: &str,
+    matches: &[(usize, String)],
+    current_version: &str,
+) -> String {
+    let current = parse_version(current_version);
+    let newer = matches
+        .iter()
+        .take_while(|(_, ver)| parse_version(ver) > current)
+        .count();
+    let (Some((first, _)), Some((start, _))) = (matches.first(), matches.get(newer)) else {
+        return content.to_string();
+    };
+    if start == first {
+        return content.to_string();
+    }
+    let preamble = content[..*first].trim_end();
+    let note = format!("*[Entries newer than {current_version} dropped by ai-fdocs]*");
+    if preamble.is_empty() {
+        format!("{note}\n\n{}", &content[*start..])
+    } else {
+        format!("{preamble}\n\n{note}\n\n{}", &content[*start..])
+    }
+}
+
+fn truncated_at(content: &str, pos: usize, note: &str) -> String {
+    format!("{}\n---\n\n*[{note}]*\n", content[..pos].trim_end())
+}
+
+/// Whether `old` is a lower `x.y.z` version than `new` (pre-release and
+/// build suffixes are ignored).
+pub fn is_older_version(old: &str, new: &str) -> bool {
+    matches!((parse_version(old), parse_version(new)), (Some(old), Some(new)) if old < new)
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.');
+    let major = parts.next()?.parse::<u64>().ok()?;
+    let minor = parts.next()?.parse::<u64>().ok()?;
+    let patch = parts.next().map_or(Some(0), |p| p.parse::<u64>().ok())?;
+    Some((major, minor, patch))
+}
+
 fn parse_minor(version: &str) -> Option<(u64, u64)> {
     let mut parts = version.split('.');
     let major = parts.next()?.parse::<u64>().ok()?;
@@ -74,6 +226,7 @@ fn parse_minor(version: &str) -> Option<(u64, u64)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::HashedSettings;
 
     #[test]
     fn test_truncate_keeps_current_and_previous() {
@@ -91,7 +244,7 @@ mod tests {
 ## 0.11.0 - 2023-11-01
 - Ancient feature
 "#;
-        let result = truncate_changelog(changelog, "0.13.1");
+        let result = truncate_changelog(changelog, "0.13.1", &ChangelogOptions::default());
         assert!(result.contains("0.13.1"));
         assert!(result.contains("0.13.0"));
         assert!(result.contains("0.12.0"));
@@ -102,7 +255,112 @@ mod tests {
     #[test]
     fn test_no_version_headings_returns_as_is() {
         let content = "Just some text without versions.";
-        let result = truncate_changelog(content, "1.0.0");
+        let result = truncate_changelog(content, "1.0.0", &ChangelogOptions::default());
         assert_eq!(result, content);
     }
+
+    #[test]
+    fn test_date_headings_keep_top_sections() {
+        let changelog = r#"# Changelog
+
+## 2024-03-01
+- Third
+
+## 2024-02-01
+- Second
+
+## 2024-01-15
+- First
+"#;
+        let options = ChangelogOptions {
+            version_regex: Some(
+                compile_heading_regex(r"^##\s+(\d{4}-\d{2}-\d{2})").expect("valid regex"),
+            ),
+            keep_sections: 2,
+            ..ChangelogOptions::default()
+        };
+
+        let result = truncate_changelog(changelog, "1.4.0", &options);
+        assert!(result.contains("2024-03-01"));
+        assert!(result.contains("2024-02-01"));
+        assert!(!result.contains("2024-01-15"));
+        assert!(result.contains("[Earlier entries truncated by ai-fdocs]"));
+
+        let untouched = truncate_changelog(changelog, "1.4.0", &ChangelogOptions::default());
+        assert_eq!(untouched, changelog);
+    }
+
+    #[test]
+    fn test_since_version_keeps_only_newer_sections() {
+        let changelog = r#"# Changelog
+
+## 0.14.0 - 2024-02-01
+- Newest
+
+## 0.13.1 - 2024-01-15
+- Fix bug
+
+## 0.13.0 - 2024-01-01
+- New feature
+
+## 0.12.0 - 2023-12-01
+- Old feature
+"#;
+        let options = ChangelogOptions {
+            since_version: Some("0.13.0".to_string()),
+            ..ChangelogOptions::default()
+        };
+        let result = truncate_changelog(changelog, "0.14.0", &options);
+        assert!(result.contains("0.14.0"));
+        assert!(result.contains("0.13.1"));
+        assert!(!result.contains("## 0.13.0"));
+        assert!(result.contains("[Entries up to 0.13.0 (previously synced) truncated by ai-fdocs]"));
+
+        // Sections above the synced version are dropped too.
+        let ahead = truncate_changelog(changelog, "0.13.1", &options);
+        assert!(ahead.starts_with(
+            "# Changelog\n\n*[Entries newer than 0.13.1 dropped by ai-fdocs]*\n\n## 0.13.1"
+        ));
+        assert!(!ahead.contains("0.14.0"));
+        assert!(!ahead.contains("## 0.13.0"));
+
+        // Not older than the synced version: the usual truncation applies.
+        let same = ChangelogOptions {
+            since_version: Some("0.14.0".to_string()),
+            ..ChangelogOptions::default()
+        };
+        assert_eq!(
+            truncate_changelog(changelog, "0.14.0", &same),
+            truncate_changelog(changelog, "0.14.0", &ChangelogOptions::default())
+        );
+        assert!(is_older_version("0.9.9", "0.10.0-rc.1"));
+        assert!(!is_older_version("1.0.0", "1.0.0"));
+    }
+
+    #[test]
+    fn test_crate_full_changelog_overrides_global_setting() {
+        let global = ChangelogOptions::default();
+        let full = CrateDoc {
+            full_changelog: Some(true),
+            ..CrateDoc::default()
+        };
+        assert!(!global.for_crate(&full).truncate);
+        assert!(global.for_crate(&CrateDoc::default()).truncate);
+
+        let disabled = ChangelogOptions {
+            truncate: false,
+            ..ChangelogOptions::default()
+        };
+        let opt_in = CrateDoc {
+            full_changelog: Some(false),
+            ..CrateDoc::default()
+        };
+        assert!(disabled.for_crate(&opt_in).truncate);
+        let settings = Settings::default();
+        let hashed = HashedSettings::from(&settings);
+        assert_ne!(
+            full.config_hash(&hashed),
+            CrateDoc::default().config_hash(&hashed)
+        );
+    }
 }
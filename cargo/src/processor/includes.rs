@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+/// Levels of nested includes that are resolved; deeper directives are kept.
+pub const MAX_INCLUDE_DEPTH: usize = 3;
+
+/// mdBook `{{#include path[:selector]}}`; a leading backslash escapes it.
+fn include_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(\\)?\{\{\s*#include\s+([^}\s]+)\s*\}\}").expect("include directive regex")
+    })
+}
+
+/// Repo paths referenced by the include directives of `file_path`, resolved
+/// against its directory, in document order. Targets outside the repo are
+/// skipped.
+pub fn include_targets(file_path: &str, content: &str) -> Vec<String> {
+    include_re()
+        .captures_iter(content)
+        .filter(|caps| caps.get(1).is_none())
+        .filter_map(|caps| {
+            let (target, _) = split_selector(&caps[2]);
+            resolve_path(file_path, target)
+        })
+        .collect()
+}
+
+/// Inlines include directives of `file_path` from `sources` (repo path to raw
+/// content), recursively up to `MAX_INCLUDE_DEPTH`. Directives are kept as
+/// written when their file is missing from `sources`, would include a file
+/// that is already being expanded, or select an unknown anchor.
+pub fn expand_includes(
+    file_path: &str,
+    content: &str,
+    sources: &HashMap<String, String>,
+) -> String {
+    expand(
+        file_path,
+        content,
+        sources,
+        &mut vec![file_path.to_string()],
+    )
+}
+
+fn expand(
+    file_path: &str,
+    content: &str,
+    sources: &HashMap<String, String>,
+    stack: &mut Vec<String>,
+) -> String {
+    include_re()
+        .replace_all(content, |caps: &Captures| {
+            let directive = caps[0].to_string();
+            if caps.get(1).is_some() || stack.len() > MAX_INCLUDE_DEPTH {
+                return directive;
+            }
+            let (target, selector) = split_selector(&caps[2]);
+            let Some(path) = resolve_path(file_path, target) else {
+                return directive;
+            };
+            let Some(source) = sources.get(&path).filter(|_| !stack.contains(&path)) else {
+                return directive;
+            };
+            let Some(selected) = select_lines(source, selector) else {
+                return directive;
+            };
+
+            stack.push(path.clone());
+            let expanded = expand(&path, &selected, sources, stack);
+            stack.pop();
+            expanded.strip_suffix('\n').unwrap_or(&expanded).to_string()
+        })
+        .into_owned()
+}
+
+/// `file.rs:2:10` into `("file.rs", Some("2:10"))`.
+fn split_selector(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once(':') {
+        Some((path, selector)) => (path, Some(selector)),
+        None => (spec, None),
+    }
+}
+
+/// `target` relative to the directory of `file_path`, with `.`/`..`
+/// resolved; `None` when it climbs above the repo root.
+fn resolve_path(file_path: &str, target: &str) -> Option<String> {
+    let mut parts: Vec<&str> = file_path.split('/').collect();
+    parts.pop();
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// mdBook line selection: `N` (one line), `N:M`, `N:` and `:M` (1-based,
+/// inclusive), or an anchor name (the lines between `ANCHOR: name` and
+/// `ANCHOR_END: name`). Anchor marker lines are always dropped. `None` for an
+/// anchor that does not exist.
+fn select_lines(content: &str, selector: Option<&str>) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let selected: Vec<&str> = match selector.map(str::trim).filter(|s| !s.is_empty()) {
+        None => lines,
+        Some(selector) => match parse_range(selector) {
+            Some((start, end)) => {
+                let end = end.unwrap_or(lines.len()).min(lines.len());
+                lines
+                    .get(start.saturating_sub(1)..end.max(start.saturating_sub(1)))
+                    .unwrap_or_default()
+                    .to_vec()
+            }
+            None => {
+                let start = lines
+                    .iter()
+                    .position(|line| anchor_name(line, "ANCHOR:") == Some(selector))?;
+                let len = lines[start + 1..]
+                    .iter()
+                    .position(|line| anchor_name(line, "ANCHOR_END:") == Some(selector))
+                    .unwrap_or(lines.len() - start - 1);
+                lines[start + 1..start + 1 + len].to_vec()
+            }
+        },
+    };
+
+    let mut out: String = selected
+        .into_iter()
+        .filter(|line| !line.contains("ANCHOR:") && !line.contains("ANCHOR_END:"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    out.push('\n');
+    Some(out)
+}
+
+/// `(start, end)` of a numeric selector; `None` for anchors.
+fn parse_range(selector: &str) -> Option<(usize, Option<usize>)> {
+    let number = |s: &str| -> Option<Option<usize>> {
+        if s.is_empty() {
+            Some(None)
+        } else {
+            s.parse().ok().map(Some)
+        }
+    };
+    match selector.split_once(':') {
+        Some((start, end)) => Some((number(start)?.unwrap_or(1), number(end)?)),
+        None => {
+            let line = number(selector)??;
+            Some((line, Some(line)))
+        }
+    }
+}
+
+fn anchor_name<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let (_, rest) = line.split_once(marker)?;
+    rest.split_whitespace().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_includes, include_targets};
+    use std::collections::HashMap;
+
+    fn sources(files: &[(&str, &str)]) -> HashMap<String, String> {
+        files
+            .iter()
+            .map(|(path, content)| ((*path).to_string(), (*content).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn inlines_relative_includes_with_line_ranges_and_anchors() {
+        let readme = "# Demo\n\n{{#include ../docs/intro.md}}\n\n```rust\n{{#include ../examples/main.rs:2:3}}\n{{ #include ../examples/main.rs:setup }}\n```\n\\{{#include ../docs/intro.md}}\n{{#playground ../examples/main.rs}}\n";
+        assert_eq!(
+            include_targets("crates/demo/README.md", readme),
+            vec![
+                "crates/docs/intro.md",
+                "crates/examples/main.rs",
+                "crates/examples/main.rs"
+            ]
+        );
+
+        let files = sources(&[
+            ("crates/docs/intro.md", "Intro text.\n"),
+            (
+                "crates/examples/main.rs",
+                "fn main() {\n    demo::run();\n    demo::stop();\n    // ANCHOR: setup\n    let cfg = demo::Config::new();\n    // ANCHOR_END: setup\n}\n",
+            ),
+        ]);
+        assert_eq!(
+            expand_includes("crates/demo/README.md", readme, &files),
+            "# Demo\n\nIntro text.\n\n```rust\n    demo::run();\n    demo::stop();\n    let cfg = demo::Config::new();\n```\n\\{{#include ../docs/intro.md}}\n{{#playground ../examples/main.rs}}\n"
+        );
+    }
+
+    #[test]
+    fn keeps_directives_for_missing_files_cycles_and_excess_depth() {
+        let files = sources(&[
+            ("a.md", "A {{#include b.md}}\n"),
+            ("b.md", "B {{#include a.md}}\n"),
+            ("c.md", "C {{#include d.md}}\n"),
+            ("d.md", "D {{#include e.md}}\n"),
+            ("e.md", "E {{#include f.md}}\n"),
+            ("f.md", "F\n"),
+        ]);
+        assert_eq!(
+            expand_includes("a.md", "{{#include b.md}}", &files),
+            "B {{#include a.md}}"
+        );
+        assert_eq!(
+            expand_includes("README.md", "{{#include c.md}}", &files),
+            "C D E {{#include f.md}}"
+        );
+        assert_eq!(
+            expand_includes(
+                "docs/README.md",
+                "{{#include ../../x.md}} {{#include gone.md}}",
+                &files
+            ),
+            "{{#include ../../x.md}} {{#include gone.md}}"
+        );
+    }
+}
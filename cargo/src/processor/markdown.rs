@@ -0,0 +1,256 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::config::Settings;
+
+/// Optional content cleanups applied before header injection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanupOptions {
+    pub strip_frontmatter: bool,
+    pub strip_badges: bool,
+    /// Applies to every saved file, not only markdown.
+    pub normalize_line_endings: bool,
+}
+
+impl CleanupOptions {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            strip_frontmatter: settings.strip_frontmatter,
+            strip_badges: settings.strip_badges,
+            normalize_line_endings: settings.normalize_line_endings,
+        }
+    }
+
+    pub const fn is_noop(self) -> bool {
+        !self.strip_frontmatter && !self.strip_badges && !self.normalize_line_endings
+    }
+}
+
+pub fn is_markdown_path(file_path: &str) -> bool {
+    let path = std::path::Path::new(file_path);
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+/// Applies the enabled cleanups. Callers are expected to check `is_markdown_path`.
+pub fn clean_markdown(content: &str, options: CleanupOptions) -> String {
+    let mut out = content.to_string();
+    if options.strip_frontmatter {
+        out = strip_frontmatter(&out);
+    }
+    if options.strip_badges {
+        out = strip_badge_rows(&out);
+    }
+    out
+}
+
+/// Converts CRLF to LF and strips trailing whitespace, except inside fenced
+/// code blocks where trailing whitespace may be significant.
+pub fn normalize_line_endings(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut fence: Option<(char, usize)> = None;
+
+    for raw in content.split_inclusive('\n') {
+        let has_newline = raw.ends_with('\n');
+        let line = raw.trim_end_matches('\n').trim_end_matches('\r');
+
+        let in_code = match (fence, fence_marker(line)) {
+            (None, Some((ch, len, _))) => {
+                fence = Some((ch, len));
+                false
+            }
+            (Some((ch, len)), Some((m_ch, m_len, bare))) if ch == m_ch && m_len >= len && bare => {
+                fence = None;
+                false
+            }
+            (open, _) => open.is_some(),
+        };
+
+        out.push_str(if in_code { line } else { line.trim_end() });
+        if has_newline {
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// Closing line for a fenced code block still open at the end of `content`
+/// (e.g. ```` ``` ```` for an unterminated ```` ```rust ````), if any.
+pub fn unclosed_fence(content: &str) -> Option<String> {
+    let mut fence: Option<(char, usize)> = None;
+    for line in content.lines() {
+        match (fence, fence_marker(line)) {
+            (None, Some((ch, len, _))) => fence = Some((ch, len)),
+            (Some((ch, len)), Some((m_ch, m_len, true))) if ch == m_ch && m_len >= len => {
+                fence = None;
+            }
+            _ => {}
+        }
+    }
+    fence.map(|(ch, len)| ch.to_string().repeat(len))
+}
+
+/// For a ```` ``` ```` / `~~~` fence line returns the fence character, run
+/// length and whether nothing but whitespace follows (required to close).
+fn fence_marker(line: &str) -> Option<(char, usize, bool)> {
+    let trimmed = line.trim_start();
+    let ch = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == ch).count();
+    let bare = trimmed[len..].trim().is_empty();
+    (len >= 3).then_some((ch, len, bare))
+}
+
+/// Removes a leading YAML front matter block (`---` ... `---`/`...`).
+/// Content without a properly closed block is returned unchanged.
+pub fn strip_frontmatter(content: &str) -> String {
+    let body = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = body.split_inclusive('\n');
+
+    let Some(first) = lines.next() else {
+        return content.to_string();
+    };
+    if first.trim_end() != "---" {
+        return content.to_string();
+    }
+
+    let mut consumed = first.len();
+    for line in lines {
+        consumed += line.len();
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return body[consumed..]
+                .trim_start_matches(['\r', '\n'])
+                .to_string();
+        }
+    }
+
+    content.to_string()
+}
+
+fn badge_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^(?:\[!\[[^\]]*\]\(https?://img\.shields\.io/[^)\s]*\)\]\([^)\s]*\)|!\[[^\]]*\]\(https?://img\.shields\.io/[^)\s]*\))",
+        )
+        .expect("valid badge regex")
+    })
+}
+
+/// Drops lines made up solely of shields.io badge images (optionally wrapped in
+/// links) and collapses the blank lines left behind. Lines mixing badges with
+/// other text, and fenced code blocks, are kept as-is.
+pub fn strip_badge_rows(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut removed_any = false;
+    let mut prev_blank = false;
+    let mut fence: Option<(char, usize)> = None;
+
+    for line in content.split_inclusive('\n') {
+        let in_code = match (fence, fence_marker(line)) {
+            (None, Some((ch, len, _))) => {
+                fence = Some((ch, len));
+                true
+            }
+            (Some((ch, len)), Some((m_ch, m_len, true))) if ch == m_ch && m_len >= len => {
+                fence = None;
+                true
+            }
+            (open, _) => open.is_some(),
+        };
+        if !in_code && is_badge_row(line) {
+            removed_any = true;
+            continue;
+        }
+
+        let blank = line.trim().is_empty();
+        if !in_code && removed_any && blank && (prev_blank || out.is_empty()) {
+            continue;
+        }
+        prev_blank = blank;
+        out.push_str(line);
+    }
+
+    if removed_any {
+        out
+    } else {
+        content.to_string()
+    }
+}
+
+fn is_badge_row(line: &str) -> bool {
+    let mut rest = line.trim();
+    if rest.is_empty() {
+        return false;
+    }
+
+    while !rest.is_empty() {
+        let Some(m) = badge_re().find(rest) else {
+            return false;
+        };
+        rest = rest[m.end()..].trim_start();
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clean_markdown, is_markdown_path, normalize_line_endings, strip_frontmatter,
+        unclosed_fence, CleanupOptions,
+    };
+
+    #[test]
+    fn strips_frontmatter_and_badge_row_from_readme() {
+        let readme = "---\ntitle: demo\nlayout: page\n---\n\n# Demo\n\n[![Crates.io](https://img.shields.io/crates/v/demo.svg)](https://crates.io/crates/demo) ![License](https://img.shields.io/badge/license-MIT-blue.svg)\n\nDemo does things. ![inline](https://img.shields.io/badge/x-y-z.svg)\n";
+        let options = CleanupOptions {
+            strip_frontmatter: true,
+            strip_badges: true,
+            normalize_line_endings: false,
+        };
+
+        let cleaned = clean_markdown(readme, options);
+        assert_eq!(
+            cleaned,
+            "# Demo\n\nDemo does things. ![inline](https://img.shields.io/badge/x-y-z.svg)\n"
+        );
+
+        // Badge rows inside fenced code (e.g. a README snippet) are kept.
+        let fenced = "```md\n![CI](https://img.shields.io/badge/ci-ok-green.svg)\n\n\n```\n";
+        assert_eq!(clean_markdown(fenced, options), fenced);
+    }
+
+    #[test]
+    fn leaves_unclosed_frontmatter_and_non_markdown_alone() {
+        let content = "---\nnot actually front matter\n\n# Title\n";
+        assert_eq!(strip_frontmatter(content), content);
+
+        assert!(is_markdown_path("docs/README.md"));
+        assert!(!is_markdown_path("docs/index.html"));
+    }
+
+    #[test]
+    fn finds_the_fence_left_open_at_the_end() {
+        assert_eq!(
+            unclosed_fence("```rust\nfn main() {}\n"),
+            Some("```".to_string())
+        );
+        assert_eq!(
+            unclosed_fence("~~~~\n```\nstill code\n"),
+            Some("~~~~".to_string())
+        );
+        assert_eq!(unclosed_fence("```\ncode\n```\ntext\n"), None);
+        assert_eq!(unclosed_fence("````\n```\n"), Some("````".to_string()));
+    }
+
+    #[test]
+    fn normalizes_crlf_and_trailing_whitespace_outside_code_fences() {
+        let content = "# Title  \r\n\r\ntext\t\r\n````text\r\nkeep  \r\n```\r\nstill code  \r\n````\r\nafter   \r\nend";
+        assert_eq!(
+            normalize_line_endings(content),
+            "# Title\n\ntext\n````text\nkeep  \n```\nstill code  \n````\nafter\nend"
+        );
+    }
+}
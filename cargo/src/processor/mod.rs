@@ -1 +1,3 @@
 pub mod changelog;
+pub mod includes;
+pub mod markdown;
@@ -2,9 +2,40 @@ use std::collections::HashMap;
 use std::path::Path;
 
 use toml::Value;
+use tracing::warn;
 
 use crate::error::{AiDocsError, Result};
 
+/// Where the versions of `resolve_versions` come from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOrigin {
+    Lockfile,
+    /// Minimal versions of the `Cargo.toml` requirements; no lockfile.
+    Manifest,
+}
+
+/// `Cargo.lock` versions or, when there is no lockfile but a `Cargo.toml`
+/// next to where it would be (library repos often don't commit one), the
+/// minimal versions of the manifest's dependency requirements.
+pub fn resolve_versions(lock_path: &Path) -> Result<(HashMap<String, String>, VersionOrigin)> {
+    match resolve_cargo_versions(lock_path) {
+        Err(AiDocsError::CargoLockNotFound) => {
+            let manifest_path = lock_path.with_file_name("Cargo.toml");
+            if !manifest_path.is_file() {
+                return Err(AiDocsError::CargoLockNotFound);
+            }
+            warn!(
+                "{} not found; using the minimal versions of the requirements in {} (not exact, run `cargo generate-lockfile` for the resolved versions)",
+                lock_path.display(),
+                manifest_path.display()
+            );
+            let versions = resolve_manifest_versions(&manifest_path)?;
+            Ok((versions, VersionOrigin::Manifest))
+        }
+        result => result.map(|versions| (versions, VersionOrigin::Lockfile)),
+    }
+}
+
 pub fn resolve_cargo_versions(path: &Path) -> Result<HashMap<String, String>> {
     if !path.exists() {
         return Err(AiDocsError::CargoLockNotFound);
@@ -32,9 +63,90 @@ pub fn resolve_cargo_versions(path: &Path) -> Result<HashMap<String, String>> {
     Ok(versions)
 }
 
+/// Dependency tables of a manifest, including `[target.*]` ones.
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Package name to the minimal version allowed by its requirement, for every
+/// dependency of `manifest_path` and its `[workspace.dependencies]`.
+/// `workspace = true` entries take the workspace requirement; renamed
+/// dependencies are keyed by their `package`. Dependencies without a usable
+/// requirement (path/git only, `*`, upper bounds only) are skipped.
+pub fn resolve_manifest_versions(manifest_path: &Path) -> Result<HashMap<String, String>> {
+    let content = std::fs::read_to_string(manifest_path)?;
+    let manifest: Value = toml::from_str(&content).map_err(|e| {
+        AiDocsError::Other(format!(
+            "{} is not valid TOML: {e}",
+            manifest_path.display()
+        ))
+    })?;
+
+    let workspace_deps = manifest
+        .get("workspace")
+        .and_then(|w| w.get("dependencies"))
+        .and_then(Value::as_table);
+    let mut tables: Vec<&toml::Table> = workspace_deps.into_iter().collect();
+    let targets = manifest
+        .get("target")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values());
+    for scope in std::iter::once(&manifest).chain(targets) {
+        tables.extend(
+            DEPENDENCY_TABLES
+                .iter()
+                .filter_map(|table| scope.get(*table).and_then(Value::as_table)),
+        );
+    }
+
+    let mut versions = HashMap::new();
+    for (key, dep) in tables.into_iter().flatten() {
+        let inherits = dep.get("workspace").and_then(Value::as_bool) == Some(true);
+        let spec = match (inherits, workspace_deps.and_then(|deps| deps.get(key))) {
+            (true, Some(workspace_dep)) => workspace_dep,
+            _ => dep,
+        };
+        let requirement = spec
+            .as_str()
+            .or_else(|| spec.get("version").and_then(Value::as_str));
+        let Some(version) = requirement.and_then(minimal_version) else {
+            continue;
+        };
+        let name = dep
+            .get("package")
+            .or_else(|| spec.get("package"))
+            .and_then(Value::as_str)
+            .unwrap_or(key);
+        versions.insert(name.to_string(), version);
+    }
+    Ok(versions)
+}
+
+/// Lowest version matching the first comparator of `requirement`, with
+/// omitted parts as `0` (`"1.2"` and `"^1.2"` give `1.2.0`).
+fn minimal_version(requirement: &str) -> Option<String> {
+    let req = semver::VersionReq::parse(requirement).ok()?;
+    let comparator = req.comparators.first()?;
+    match comparator.op {
+        semver::Op::Exact
+        | semver::Op::GreaterEq
+        | semver::Op::Tilde
+        | semver::Op::Caret
+        | semver::Op::Wildcard => {}
+        _ => return None,
+    }
+    let version = semver::Version {
+        major: comparator.major,
+        minor: comparator.minor.unwrap_or(0),
+        patch: comparator.patch.unwrap_or(0),
+        pre: comparator.pre.clone(),
+        build: semver::BuildMetadata::EMPTY,
+    };
+    Some(version.to_string())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::resolve_cargo_versions;
+    use super::{resolve_cargo_versions, resolve_versions, VersionOrigin};
     use std::fs;
 
     #[test]
@@ -63,4 +175,57 @@ version = "1.44.0"
 
         let _ = fs::remove_file(&tmp);
     }
+
+    #[test]
+    fn falls_back_to_minimal_manifest_versions_without_a_lockfile() {
+        let dir = std::env::temp_dir().join(format!("ai-fdocs-manifest-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create dir");
+        fs::write(
+            dir.join("Cargo.toml"),
+            r#"[package]
+name = "demo"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1.0.200", features = ["derive"] }
+tokio = { workspace = true }
+yaml = { package = "serde_yaml", version = "~0.9" }
+local = { path = "../local" }
+anything = "*"
+
+[dev-dependencies]
+insta = "1"
+
+[target.'cfg(unix)'.dependencies]
+libc = ">=0.2.150, <0.3"
+
+[workspace.dependencies]
+tokio = { version = "=1.40.0", default-features = false }
+"#,
+        )
+        .expect("write manifest");
+
+        let (versions, origin) = resolve_versions(&dir.join("Cargo.lock")).expect("manifest");
+        assert_eq!(origin, VersionOrigin::Manifest);
+        let get = |name: &str| versions.get(name).map(String::as_str);
+        assert_eq!(get("serde"), Some("1.0.200"));
+        assert_eq!(get("tokio"), Some("1.40.0"));
+        assert_eq!(get("serde_yaml"), Some("0.9.0"));
+        assert_eq!(get("insta"), Some("1.0.0"));
+        assert_eq!(get("libc"), Some("0.2.150"));
+        assert_eq!(get("local"), None);
+        assert_eq!(get("anything"), None);
+
+        fs::write(
+            dir.join("Cargo.lock"),
+            "[[package]]\nname = \"serde\"\nversion = \"1.0.210\"\n",
+        )
+        .expect("write lockfile");
+        let (versions, origin) = resolve_versions(&dir.join("Cargo.lock")).expect("lockfile");
+        assert_eq!(origin, VersionOrigin::Lockfile);
+        assert_eq!(versions.len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
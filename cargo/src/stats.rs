@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::status::scan_existing_dirs;
+use crate::storage::{self, FileManifestEntry};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrateCacheStats {
+    pub crate_name: String,
+    pub version: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// Recorded at sync time (`storage::estimate_tokens`), else `bytes / 4`.
+    pub estimated_tokens: u64,
+    pub source_kind: String,
+    pub is_fallback: bool,
+    /// Wall-clock time of the last fetch, when recorded at sync time.
+    pub fetch_duration_ms: Option<u64>,
+    /// Raw upstream bytes of the last fetch, when recorded at sync time.
+    pub fetched_bytes: Option<usize>,
+    /// Requested-vs-fetched manifest from the meta's `files` table.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_manifest: BTreeMap<String, FileManifestEntry>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CacheStats {
+    pub total_crates: usize,
+    pub total_files: usize,
+    pub total_bytes: u64,
+    pub total_estimated_tokens: u64,
+    pub fallback_crates: usize,
+    pub by_source_kind: BTreeMap<String, usize>,
+    pub crates: Vec<CrateCacheStats>,
+}
+
+/// Summarizes the local cache from `.aifd-meta.toml` files and directory contents.
+/// Purely local: no network access.
+pub fn collect_stats(output_dir: &Path) -> CacheStats {
+    let mut entries: Vec<_> = scan_existing_dirs(output_dir).into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut stats = CacheStats::default();
+    for (crate_name, (version, crate_dir)) in entries {
+        let meta = storage::read_meta(output_dir, &crate_name, &version);
+        let (files, bytes) = doc_files_usage(&crate_dir);
+
+        let source_kind = meta
+            .as_ref()
+            .and_then(|m| m.source_kind.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+        let is_fallback = meta
+            .as_ref()
+            .is_some_and(|m| m.is_fallback || source_kind == "github_fallback");
+        let estimated_tokens = meta
+            .as_ref()
+            .and_then(|m| m.estimated_tokens)
+            .map_or(bytes.div_ceil(4), |tokens| tokens as u64);
+
+        stats.total_crates += 1;
+        stats.total_files += files;
+        stats.total_bytes += bytes;
+        stats.total_estimated_tokens += estimated_tokens;
+        if is_fallback {
+            stats.fallback_crates += 1;
+        }
+        *stats.by_source_kind.entry(source_kind.clone()).or_default() += 1;
+
+        stats.crates.push(CrateCacheStats {
+            crate_name,
+            version,
+            files,
+            bytes,
+            estimated_tokens,
+            source_kind,
+            is_fallback,
+            fetch_duration_ms: meta.as_ref().and_then(|m| m.fetch_duration_ms),
+            fetched_bytes: meta.as_ref().and_then(|m| m.total_bytes),
+            file_manifest: meta.map(|m| m.files).unwrap_or_default(),
+        });
+    }
+
+    stats
+}
+
+/// Counts saved doc files (same set as `read_cached_info`) and their total size.
+fn doc_files_usage(crate_dir: &Path) -> (usize, u64) {
+    let Ok(entries) = fs::read_dir(crate_dir) else {
+        return (0, 0);
+    };
+
+    let mut files = 0;
+    let mut bytes = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name == "_SUMMARY.md" {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_file() {
+            files += 1;
+            bytes += metadata.len();
+        }
+    }
+    (files, bytes)
+}
+
+pub fn print_stats_table(stats: &CacheStats) {
+    print!("{}", format_stats_table(stats));
+}
+
+pub fn format_stats_json(stats: &CacheStats) -> std::result::Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(stats)
+}
+
+fn format_stats_table(stats: &CacheStats) -> String {
+    const COL_CRATE: usize = 24;
+    const COL_VERSION: usize = 16;
+    const COL_FILES: usize = 6;
+    const COL_BYTES: usize = 12;
+    const COL_TOKENS: usize = 10;
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "{:<COL_CRATE$} {:<COL_VERSION$} {:>COL_FILES$} {:>COL_BYTES$} {:>COL_TOKENS$} Source",
+        "Crate", "Docs Version", "Files", "Bytes", "~Tokens"
+    );
+    let _ = writeln!(
+        output,
+        "{:-<COL_CRATE$} {:-<COL_VERSION$} {:->COL_FILES$} {:->COL_BYTES$} {:->COL_TOKENS$} {:-<6}",
+        "", "", "", "", "", ""
+    );
+
+    for item in &stats.crates {
+        let _ = writeln!(
+            output,
+            "{:<COL_CRATE$} {:<COL_VERSION$} {:>COL_FILES$} {:>COL_BYTES$} {:>COL_TOKENS$} {}",
+            item.crate_name,
+            item.version,
+            item.files,
+            item.bytes,
+            item.estimated_tokens,
+            item.source_kind
+        );
+    }
+
+    let _ = writeln!(output);
+    let _ = writeln!(
+        output,
+        "Crates: {} | Files: {} | Bytes: {} | Fallback syncs: {} | Est. tokens: {}",
+        stats.total_crates,
+        stats.total_files,
+        stats.total_bytes,
+        stats.fallback_crates,
+        stats.total_estimated_tokens
+    );
+
+    if !stats.by_source_kind.is_empty() {
+        let breakdown: Vec<String> = stats
+            .by_source_kind
+            .iter()
+            .map(|(kind, count)| format!("{kind}={count}"))
+            .collect();
+        let _ = writeln!(output, "By source kind: {}", breakdown.join(", "));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect_stats, format_stats_table};
+    use std::fs;
+
+    #[test]
+    fn collects_totals_and_source_kind_breakdown() {
+        let tmp = std::env::temp_dir().join(format!("aifd-stats-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("serde@1.0.0")).expect("create serde dir");
+        fs::create_dir_all(tmp.join("tokio@1.44.0")).expect("create tokio dir");
+
+        fs::write(
+            tmp.join("serde@1.0.0/.aifd-meta.toml"),
+            "schema_version = 2\nversion = \"1.0.0\"\ngit_ref = \"v1.0.0\"\nfetched_at = \"2026-01-01\"\nis_fallback = false\nsource_kind = \"github\"\nfetch_duration_ms = 420\ntotal_bytes = 5\nestimated_tokens = 40\n",
+        )
+        .expect("write serde meta");
+        fs::write(tmp.join("serde@1.0.0/README.md"), "12345").expect("write readme");
+        fs::write(tmp.join("serde@1.0.0/_SUMMARY.md"), "ignored").expect("write summary");
+
+        fs::write(
+            tmp.join("tokio@1.44.0/.aifd-meta.toml"),
+            "schema_version = 2\nversion = \"1.44.0\"\ngit_ref = \"master\"\nfetched_at = \"2026-01-01\"\nis_fallback = true\nsource_kind = \"github\"\n",
+        )
+        .expect("write tokio meta");
+        fs::write(tmp.join("tokio@1.44.0/README.md"), "abc").expect("write readme");
+        fs::write(tmp.join("tokio@1.44.0/CHANGELOG.md"), "de").expect("write changelog");
+
+        let stats = collect_stats(&tmp);
+        assert_eq!(stats.total_crates, 2);
+        assert_eq!(stats.total_files, 3);
+        assert_eq!(stats.total_bytes, 10);
+        assert_eq!(stats.fallback_crates, 1);
+        assert_eq!(stats.by_source_kind.get("github"), Some(&2));
+        assert_eq!(stats.crates[0].crate_name, "serde");
+        assert_eq!(stats.crates[0].fetch_duration_ms, Some(420));
+        assert_eq!(stats.crates[0].fetched_bytes, Some(5));
+        assert_eq!(stats.crates[1].fetch_duration_ms, None);
+        assert_eq!(stats.crates[0].estimated_tokens, 40);
+        assert_eq!(stats.crates[1].estimated_tokens, 2);
+        assert_eq!(stats.total_estimated_tokens, 42);
+
+        let table = format_stats_table(&stats);
+        assert!(table
+            .contains("Crates: 2 | Files: 3 | Bytes: 10 | Fallback syncs: 1 | Est. tokens: 42"));
+        assert!(table.contains("By source kind: github=2"));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
 
-use serde::{Deserialize, Serialize};
+use owo_colors::OwoColorize;
+use serde::Serialize;
 
 use crate::config::Config;
+use crate::status_cache::StatusCache;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum DocsStatus {
@@ -51,16 +53,44 @@ impl StatusMode {
 pub struct CrateStatus {
     pub crate_name: String,
     pub lock_version: Option<String>,
+    /// `lock_version` is the minimal version of a `Cargo.toml` requirement
+    /// because there is no `Cargo.lock`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub version_from_manifest: bool,
     pub docs_version: Option<String>,
     pub status: DocsStatus,
     pub reason: String,
     pub reason_code: String,
     pub mode: String,
     pub source_kind: Option<String>,
+    pub details: StatusDetails,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetch_duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_bytes: Option<usize>,
+    /// The meta's requested-vs-fetched `files` manifest.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_manifest: BTreeMap<String, FileManifestEntry>,
 }
 
-use crate::storage::CrateMeta;
+/// Typed data behind `reason_code`, so tooling can branch on values instead of
+/// parsing `reason`. `expected`/`actual` are set for mismatch codes (versions,
+/// or schema versions for `meta_schema_unsupported`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StatusDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+}
+
+use crate::storage::{CrateMeta, FileManifestEntry, META_SCHEMA_VERSION};
 
+#[allow(clippy::too_many_arguments)]
 fn crate_status(
     crate_name: String,
     lock_version: Option<String>,
@@ -71,15 +101,208 @@ fn crate_status(
     mode: StatusMode,
     source_kind: Option<String>,
 ) -> CrateStatus {
+    let details = StatusDetails {
+        lock_version: lock_version.clone(),
+        docs_version: docs_version.clone(),
+        ..StatusDetails::default()
+    };
     CrateStatus {
         crate_name,
         lock_version,
+        version_from_manifest: false,
         docs_version,
         status,
         reason: reason.into(),
         reason_code: reason_code.into(),
         mode: mode.as_str().to_string(),
         source_kind,
+        details,
+        fetch_duration_ms: None,
+        total_bytes: None,
+        file_manifest: BTreeMap::new(),
+    }
+}
+
+impl CrateStatus {
+    /// `lock_version` for tables, marked when it came from `Cargo.toml`.
+    fn lock_version_label(&self) -> String {
+        match (&self.lock_version, self.version_from_manifest) {
+            (Some(version), true) => format!("{version} (manifest)"),
+            (Some(version), false) => version.clone(),
+            (None, _) => "-".to_string(),
+        }
+    }
+
+    fn with_expected(mut self, expected: impl ToString, actual: impl ToString) -> Self {
+        self.details.expected = Some(expected.to_string());
+        self.details.actual = Some(actual.to_string());
+        self
+    }
+}
+
+/// What a `reason_code` means and how to act on it (`cargo ai-fdocs explain`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReasonCode {
+    pub code: &'static str,
+    pub status: DocsStatus,
+    pub meaning: &'static str,
+    pub remediation: &'static str,
+}
+
+/// Every reason code `collect_status`/`collect_status_latest` produce.
+pub const REASON_CODES: &[ReasonCode] = &[
+    ReasonCode {
+        code: "lockfile_ok",
+        status: DocsStatus::Synced,
+        meaning: "The docs are saved for the exact version in Cargo.lock and were fetched from a version tag.",
+        remediation: "Nothing to do.",
+    },
+    ReasonCode {
+        code: "lockfile_fallback_branch",
+        status: DocsStatus::SyncedFallback,
+        meaning: "The docs match the Cargo.lock version, but no release tag was found upstream, so they were fetched from a branch (`doc_branch` or the default branch) and may describe a newer, unreleased state.",
+        remediation: "Usually fine. To pin the docs to a tag, check the repo's tag naming, set `upstream_name` for renamed crates, or set `settings.fallback_policy = \"error\"` to make this a failure.",
+    },
+    ReasonCode {
+        code: "lockfile_version_mismatch",
+        status: DocsStatus::Outdated,
+        meaning: "Docs are saved for another version of the crate than the one in Cargo.lock, typically after a dependency upgrade.",
+        remediation: "Run `cargo ai-fdocs sync` to fetch the docs for the locked version; `prune` removes the old version dir.",
+    },
+    ReasonCode {
+        code: "lockfile_missing_artifacts",
+        status: DocsStatus::Missing,
+        meaning: "The crate is in Cargo.lock and the config, but no docs were synced for it yet.",
+        remediation: "Run `cargo ai-fdocs sync` (or `sync --only-missing`). If it keeps failing, check the crate's `repo` and the sync log.",
+    },
+    ReasonCode {
+        code: "lockfile_missing_crate",
+        status: DocsStatus::Missing,
+        meaning: "The crate is configured but not in Cargo.lock, so there is no version to sync in lockfile mode.",
+        remediation: "Remove the `[crates.<name>]` entry, add the dependency, or run `cargo generate-lockfile` if the lockfile is stale.",
+    },
+    ReasonCode {
+        code: "meta_version_mismatch",
+        status: DocsStatus::Outdated,
+        meaning: "The version dir matches Cargo.lock, but its `.aifd-meta.toml` records a different version, e.g. after the dir was renamed or copied by hand.",
+        remediation: "Run `cargo ai-fdocs sync --force` to rewrite the crate's docs and metadata.",
+    },
+    ReasonCode {
+        code: "meta_schema_unsupported",
+        status: DocsStatus::Corrupted,
+        meaning: "`.aifd-meta.toml` was written by a newer ai-fdocs with a metadata schema this version cannot read.",
+        remediation: "Upgrade cargo-ai-fdocs to the version that synced the docs, or run `cargo ai-fdocs sync --force` to rewrite them with this version.",
+    },
+    ReasonCode {
+        code: "meta_invalid_toml",
+        status: DocsStatus::Corrupted,
+        meaning: "`.aifd-meta.toml` exists but is not valid TOML, e.g. after a manual edit or an interrupted write.",
+        remediation: "Run `cargo ai-fdocs sync --force` to rewrite the crate's docs and metadata.",
+    },
+    ReasonCode {
+        code: "meta_unreadable",
+        status: DocsStatus::Corrupted,
+        meaning: "The crate's version dir exists, but its `.aifd-meta.toml` is missing or cannot be read.",
+        remediation: "Check file permissions, then run `cargo ai-fdocs sync --force` to rewrite the crate's docs and metadata.",
+    },
+    ReasonCode {
+        code: "latest_ok_docsrs",
+        status: DocsStatus::Synced,
+        meaning: "latest-docs mode: the docs are for the newest release (or the newest one matching `version_req`), from docs.rs or the repository.",
+        remediation: "Nothing to do.",
+    },
+    ReasonCode {
+        code: "latest_ok_fallback",
+        status: DocsStatus::SyncedFallback,
+        meaning: "latest-docs mode: docs.rs was unavailable, so the docs were fetched from the GitHub repository instead.",
+        remediation: "Usually fine. Run `cargo ai-fdocs sync --force` later to retry docs.rs.",
+    },
+    ReasonCode {
+        code: "latest_version_mismatch",
+        status: DocsStatus::Outdated,
+        meaning: "latest-docs mode: a newer release (matching `version_req`, when set) was published than the one the docs were saved for.",
+        remediation: "Run `cargo ai-fdocs sync`. Set `version_req` on the crate to stay on a major version.",
+    },
+    ReasonCode {
+        code: "latest_yanked",
+        status: DocsStatus::Outdated,
+        meaning: "latest-docs mode: the version the docs were saved for was yanked from crates.io.",
+        remediation: "Run `cargo ai-fdocs sync` to switch to the newest non-yanked version.",
+    },
+    ReasonCode {
+        code: "latest_missing_artifacts",
+        status: DocsStatus::Missing,
+        meaning: "latest-docs mode: no docs were synced for this crate yet.",
+        remediation: "Run `cargo ai-fdocs sync`. If it keeps failing, check the sync log for docs.rs or crates.io errors.",
+    },
+    ReasonCode {
+        code: "workspace_ok",
+        status: DocsStatus::Synced,
+        meaning: "The docs of the `path` crate match the version in its own Cargo.toml.",
+        remediation: "Nothing to do.",
+    },
+    ReasonCode {
+        code: "workspace_version_mismatch",
+        status: DocsStatus::Outdated,
+        meaning: "The `path` crate's Cargo.toml version changed since its docs were synced.",
+        remediation: "Run `cargo ai-fdocs sync` to re-read the crate's local files.",
+    },
+];
+
+pub fn reason_code(code: &str) -> Option<&'static ReasonCode> {
+    REASON_CODES.iter().find(|info| info.code == code)
+}
+
+/// `explain` output for one code.
+pub fn format_reason_code(info: &ReasonCode) -> String {
+    format!(
+        "{} ({})\n\n{}\n\nRemediation: {}\n",
+        info.code,
+        info.status.as_str(),
+        info.meaning,
+        info.remediation
+    )
+}
+
+/// Marks entries whose lock version is the one `manifest_versions` (from
+/// `resolver::resolve_manifest_versions`) gave, and says so in the reason.
+pub fn mark_manifest_versions(
+    statuses: &mut [CrateStatus],
+    manifest_versions: &HashMap<String, String>,
+) {
+    for entry in statuses {
+        if entry.lock_version.is_some()
+            && entry.lock_version.as_ref() == manifest_versions.get(&entry.crate_name)
+        {
+            entry.version_from_manifest = true;
+            entry.reason = format!(
+                "{} (version from Cargo.toml requirement, no Cargo.lock)",
+                entry.reason
+            );
+        }
+    }
+}
+
+/// Copies fetch metrics and the file manifest recorded at sync time from each
+/// crate's meta, when present.
+fn attach_fetch_metrics(results: &mut [CrateStatus], output_dir: &Path, cache: &mut StatusCache) {
+    for entry in results {
+        let Some(docs_version) = entry.docs_version.as_deref() else {
+            continue;
+        };
+        let meta_path = output_dir
+            .join(format!("{}@{docs_version}", entry.crate_name))
+            .join(".aifd-meta.toml");
+        let Some(meta) = cache
+            .read_meta(&meta_path)
+            .ok()
+            .and_then(|raw| toml::from_str::<CrateMeta>(&raw).ok())
+        else {
+            continue;
+        };
+        entry.fetch_duration_ms = meta.fetch_duration_ms;
+        entry.total_bytes = meta.total_bytes;
+        entry.file_manifest = meta.files;
     }
 }
 
@@ -91,7 +314,8 @@ pub async fn collect_status(
     let mut crate_names: Vec<_> = config.crates.keys().cloned().collect();
     crate_names.sort();
 
-    let existing_map = scan_existing_dirs(output_dir);
+    let mut cache = StatusCache::open(output_dir, config.settings.status_cache);
+    let existing_map = cache.existing_dirs();
 
     let mut results = Vec::new();
     for crate_name in crate_names {
@@ -112,6 +336,7 @@ pub async fn collect_status(
                         StatusMode::Lockfile,
                         None,
                     )
+                    .with_expected(&lock_version, &existing)
                 } else {
                     crate_status(
                         crate_name,
@@ -126,24 +351,25 @@ pub async fn collect_status(
                 }
             } else {
                 let meta_path = expected_dir.join(".aifd-meta.toml");
-                match std::fs::read_to_string(&meta_path) {
+                match cache.read_meta(&meta_path) {
                     Ok(meta_raw) => {
                         match toml::from_str::<CrateMeta>(&meta_raw) {
                             Ok(meta) => {
-                                if meta.schema_version > 1 {
+                                if meta.schema_version > META_SCHEMA_VERSION {
                                     crate_status(
                                         crate_name,
                                         Some(lock_version.clone()),
                                         Some(lock_version),
                                         DocsStatus::Corrupted,
                                         format!(
-                                            ".aifd-meta.toml schema version {} is newer than supported version 1",
-                                            meta.schema_version
+                                            ".aifd-meta.toml schema version {} is newer than supported version {}",
+                                            meta.schema_version, META_SCHEMA_VERSION
                                         ),
                                         "meta_schema_unsupported",
                                         StatusMode::Lockfile,
                                         meta.source_kind.clone(),
                                     )
+                                    .with_expected(META_SCHEMA_VERSION, meta.schema_version)
                                 } else {
                                     let docs_version = meta.version.clone();
                                     if docs_version != lock_version {
@@ -157,6 +383,7 @@ pub async fn collect_status(
                                             StatusMode::Lockfile,
                                             meta.source_kind.clone(),
                                         )
+                                        .with_expected(&lock_version, &docs_version)
                                     } else if meta.is_fallback {
                                         crate_status(
                                             crate_name,
@@ -224,6 +451,8 @@ pub async fn collect_status(
         };
         results.push(status);
     }
+    attach_fetch_metrics(&mut results, output_dir, &mut cache);
+    cache.close();
     results
 }
 
@@ -235,69 +464,79 @@ pub async fn collect_status_latest(
     let mut crate_names: Vec<_> = config.crates.keys().cloned().collect();
     crate_names.sort();
 
-    let existing_map = scan_existing_dirs(output_dir);
+    let mut cache = StatusCache::open(output_dir, config.settings.status_cache);
+    let existing_map = cache.existing_dirs();
 
     let mut results = Vec::new();
+    // (index into `results`, upstream crate name, cached version, version_req)
+    // needing an upstream check.
+    let mut upstream_checks = Vec::new();
     for crate_name in crate_names {
         let status = if let Some((docs_version, crate_dir)) = existing_map.get(&crate_name) {
             let docs_version = docs_version.clone();
             let meta_path = crate_dir.join(".aifd-meta.toml");
-            match std::fs::read_to_string(&meta_path) {
+            match cache.read_meta(&meta_path) {
                 Ok(meta_raw) => {
                     match toml::from_str::<CrateMeta>(&meta_raw) {
                         Ok(meta) => {
-                            if meta.schema_version > 1 {
+                            if meta.schema_version > META_SCHEMA_VERSION {
                                 crate_status(
                                     crate_name.clone(),
                                     None,
                                     Some(docs_version),
                                     DocsStatus::Corrupted,
                                     format!(
-                                        ".aifd-meta.toml schema version {} is newer than supported version 1",
-                                        meta.schema_version
+                                        ".aifd-meta.toml schema version {} is newer than supported version {}",
+                                        meta.schema_version, META_SCHEMA_VERSION
                                     ),
                                     "meta_schema_unsupported",
                                     StatusMode::LatestDocs,
                                     meta.source_kind.clone(),
                                 )
+                                .with_expected(META_SCHEMA_VERSION, meta.schema_version)
                             } else {
                                 let source_kind = meta.source_kind.clone().unwrap_or_else(|| "docsrs".to_string());
                                 let is_fallback = meta.is_fallback || source_kind == "github_fallback";
 
-                                // Check freshness if fetcher is provided
-                                let mut status = if is_fallback {
+                                let status = if is_fallback {
                                     DocsStatus::SyncedFallback
                                 } else {
                                     DocsStatus::Synced
                                 };
-                                let mut reason = if is_fallback {
-                                    "latest-docs synced via GitHub fallback".to_string()
+                                let reason = if is_fallback {
+                                    "latest-docs synced via GitHub fallback"
                                 } else {
-                                    "latest-docs up to date".to_string()
+                                    "latest-docs up to date"
                                 };
-                                let mut reason_code = if is_fallback {
-                                    "latest_ok_fallback".to_string()
+                                let reason_code = if is_fallback {
+                                    "latest_ok_fallback"
                                 } else {
-                                    "latest_ok_docsrs".to_string()
+                                    "latest_ok_docsrs"
                                 };
 
-                                if let Some(f) = fetcher {
-                                    let mut needs_check = true;
-                                    if let Some(checked_at) = &meta.upstream_checked_at {
-                                        if crate::utils::is_latest_cache_fresh(checked_at, config.settings.latest_ttl_hours) {
-                                            needs_check = false;
-                                        }
-                                    }
-
-                                    if needs_check {
-                                        if let Ok(latest) = f.resolve_latest_version(&crate_name).await {
-                                            if latest != docs_version {
-                                                status = DocsStatus::Outdated;
-                                                reason = format!("latest version {latest} is newer than cached {docs_version}");
-                                                reason_code = "latest_version_mismatch".to_string();
-                                            }
-                                        }
-                                    }
+                                // Upstream freshness is checked below, concurrently.
+                                let checked_recently = meta
+                                    .upstream_checked_at
+                                    .as_deref()
+                                    .is_some_and(|checked_at| {
+                                        let ttl_hours = config.crates[&crate_name]
+                                            .latest_ttl_hours(config.settings.latest_ttl_hours);
+                                        crate::utils::is_latest_cache_fresh(checked_at, ttl_hours)
+                                    });
+                                if fetcher.is_some()
+                                    && !checked_recently
+                                    && config.crates[&crate_name].path.is_none()
+                                {
+                                    let upstream_name = config.crates[&crate_name]
+                                        .upstream_name(&crate_name)
+                                        .to_string();
+                                    let version_req = config.crates[&crate_name].version_req();
+                                    upstream_checks.push((
+                                        results.len(),
+                                        upstream_name,
+                                        docs_version.clone(),
+                                        version_req,
+                                    ));
                                 }
 
                                 crate_status(
@@ -353,10 +592,150 @@ pub async fn collect_status_latest(
         };
         results.push(status);
     }
+    check_workspace_versions(config, &mut results);
+
+    if let Some(fetcher) = fetcher {
+        for (idx, outdated) in check_upstream_concurrently(
+            fetcher,
+            upstream_checks,
+            config.settings.sync_concurrency,
+        )
+        .await
+        {
+            let entry = &mut results[idx];
+            entry.status = DocsStatus::Outdated;
+            entry.reason = outdated.reason;
+            entry.reason_code = outdated.reason_code.to_string();
+            entry.details.expected = outdated.expected;
+            entry.details.actual = entry.docs_version.clone();
+        }
+    }
+
+    attach_fetch_metrics(&mut results, output_dir, &mut cache);
+    cache.close();
     results
 }
 
-fn scan_existing_dirs(output_dir: &Path) -> HashMap<String, (String, PathBuf)> {
+/// Latest-docs mode: `path` crates are compared with the version in their
+/// `Cargo.toml` instead of crates.io.
+fn check_workspace_versions(config: &Config, results: &mut [CrateStatus]) {
+    for entry in results {
+        let Some(dir) = &config.crates[&entry.crate_name].path else {
+            continue;
+        };
+        if entry.status != DocsStatus::Synced {
+            continue;
+        }
+        let Ok(version) = crate::workspace::member_version(&config.root, dir) else {
+            continue;
+        };
+        if entry.docs_version.as_deref() == Some(version.as_str()) {
+            entry.reason = "workspace member up to date".to_string();
+            entry.reason_code = "workspace_ok".to_string();
+        } else {
+            entry.status = DocsStatus::Outdated;
+            entry.reason = format!("workspace manifest version is {version}");
+            entry.reason_code = "workspace_version_mismatch".to_string();
+            entry.details.expected = Some(version);
+            entry.details.actual = entry.docs_version.clone();
+        }
+    }
+}
+
+/// Copies upstream `Outdated` verdicts from `previous` onto entries of
+/// `current` still reported synced at the same docs version. Used by
+/// `status --watch` between upstream checks.
+pub fn carry_upstream_verdicts(previous: &[CrateStatus], current: &mut [CrateStatus]) {
+    for entry in current.iter_mut() {
+        if !matches!(entry.status, DocsStatus::Synced | DocsStatus::SyncedFallback) {
+            continue;
+        }
+        let Some(prev) = previous.iter().find(|p| {
+            p.crate_name == entry.crate_name
+                && p.status == DocsStatus::Outdated
+                && p.docs_version == entry.docs_version
+        }) else {
+            continue;
+        };
+        entry.status = prev.status;
+        entry.reason = prev.reason.clone();
+        entry.reason_code = prev.reason_code.clone();
+        entry.details = prev.details.clone();
+    }
+}
+
+struct UpstreamOutdated {
+    reason: String,
+    reason_code: &'static str,
+    /// Latest upstream version, when the check got that far.
+    expected: Option<String>,
+}
+
+/// Runs latest-docs upstream checks with bounded concurrency (like `sync`).
+/// Returns only the entries found outdated, keyed by their index in the status vec.
+async fn check_upstream_concurrently(
+    fetcher: &crate::fetcher::latest::LatestDocsFetcher,
+    checks: Vec<(usize, String, String, Option<semver::VersionReq>)>,
+    concurrency: usize,
+) -> Vec<(usize, UpstreamOutdated)> {
+    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
+    let mut join_set = tokio::task::JoinSet::new();
+
+    for (idx, crate_name, docs_version, version_req) in checks {
+        let semaphore = std::sync::Arc::clone(&semaphore);
+        let fetcher = fetcher.clone();
+        join_set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+            let outdated =
+                check_upstream(&fetcher, &crate_name, &docs_version, version_req.as_ref()).await;
+            outdated.map(|o| (idx, o))
+        });
+    }
+
+    let mut outdated = Vec::new();
+    while let Some(joined) = join_set.join_next().await {
+        if let Ok(Some(entry)) = joined {
+            outdated.push(entry);
+        }
+    }
+    outdated
+}
+
+async fn check_upstream(
+    fetcher: &crate::fetcher::latest::LatestDocsFetcher,
+    crate_name: &str,
+    docs_version: &str,
+    version_req: Option<&semver::VersionReq>,
+) -> Option<UpstreamOutdated> {
+    if let Ok(true) = fetcher.is_version_yanked(crate_name, docs_version).await {
+        return Some(UpstreamOutdated {
+            reason: format!(
+                "cached version {docs_version} was yanked from crates.io; run sync to refresh"
+            ),
+            reason_code: "latest_yanked",
+            expected: None,
+        });
+    }
+
+    match fetcher
+        .resolve_latest_version(crate_name, version_req)
+        .await
+    {
+        Ok(latest) if latest != docs_version => Some(UpstreamOutdated {
+            reason: match version_req {
+                Some(req) => {
+                    format!("latest version matching `{req}` is {latest}, cached is {docs_version}")
+                }
+                None => format!("latest version {latest} is newer than cached {docs_version}"),
+            },
+            reason_code: "latest_version_mismatch",
+            expected: Some(latest),
+        }),
+        _ => None,
+    }
+}
+
+pub fn scan_existing_dirs(output_dir: &Path) -> HashMap<String, (String, PathBuf)> {
     let mut map: HashMap<String, (String, PathBuf)> = HashMap::new();
     
     let Ok(entries) = std::fs::read_dir(output_dir) else {
@@ -364,7 +743,8 @@ fn scan_existing_dirs(output_dir: &Path) -> HashMap<String, (String, PathBuf)> {
     };
 
     for entry in entries.flatten() {
-        if !entry.path().is_dir() {
+        // Symlinked entries are skipped, like by `storage::prune`.
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
             continue;
         }
 
@@ -387,14 +767,15 @@ fn scan_existing_dirs(output_dir: &Path) -> HashMap<String, (String, PathBuf)> {
     map
 }
 
+/// See `storage::split_name_version`.
 fn split_crate_version(dir_name: &str) -> Option<(&str, &str)> {
-    dir_name.rsplit_once('@')
+    crate::storage::split_name_version(dir_name)
 }
 
 
 
-pub fn print_status_table(statuses: &[CrateStatus]) {
-    print!("{}", format_status_table(statuses));
+pub fn print_status_table(statuses: &[CrateStatus], color: bool) {
+    print!("{}", format_status_table(statuses, color));
 }
 
 #[derive(Debug, Serialize)]
@@ -414,7 +795,147 @@ pub fn format_status_json(
     serde_json::to_string_pretty(&report)
 }
 
-fn format_status_table(statuses: &[CrateStatus]) -> String {
+/// One row per crate: `crate,lock_version,docs_version,status,reason_code,source_kind`,
+/// with a header row; missing values are empty fields.
+pub fn format_status_csv(statuses: &[CrateStatus]) -> String {
+    let mut output =
+        String::from("crate,lock_version,docs_version,status,reason_code,source_kind\n");
+    for item in statuses {
+        let fields = [
+            item.crate_name.as_str(),
+            item.lock_version.as_deref().unwrap_or(""),
+            item.docs_version.as_deref().unwrap_or(""),
+            item.status.as_str(),
+            item.reason_code.as_str(),
+            item.source_kind.as_deref().unwrap_or(""),
+        ];
+        let row: Vec<_> = fields.into_iter().map(csv_field).collect();
+        let _ = writeln!(output, "{}", row.join(","));
+    }
+    output
+}
+
+/// GitHub-flavored markdown for PR comments (e.g. piped into `gh pr
+/// comment`): the summary line, a table with status emojis and, when there
+/// are problems, their reasons in a collapsed `<details>` block.
+pub fn format_status_github_markdown(statuses: &[CrateStatus]) -> String {
+    let mut output = String::from("### ai-fdocs status\n\n");
+    let _ = writeln!(output, "{}\n", format_status_summary(statuses));
+    output.push_str("| Crate | Lock Version | Docs Version | Status |\n");
+    output.push_str("| --- | --- | --- | --- |\n");
+    for item in statuses {
+        let _ = writeln!(
+            output,
+            "| {} | {} | {} | {} {} |",
+            markdown_cell(&item.crate_name),
+            markdown_cell(&item.lock_version_label()),
+            markdown_cell(item.docs_version.as_deref().unwrap_or("-")),
+            status_emoji(item.status),
+            item.status.as_str()
+        );
+    }
+
+    let problems: Vec<_> = statuses.iter().filter(|s| s.status.is_problem()).collect();
+    if !problems.is_empty() {
+        let _ = write!(
+            output,
+            "\n<details>\n<summary>{} problem(s)</summary>\n\n",
+            problems.len()
+        );
+        for item in problems {
+            let _ = writeln!(
+                output,
+                "- **{}** ({}): {}",
+                markdown_cell(&item.crate_name),
+                item.status.as_str(),
+                markdown_cell(&item.reason)
+            );
+        }
+        output.push_str("\nRun `cargo ai-fdocs sync` to update them.\n\n</details>\n");
+    }
+    output
+}
+
+/// Each crate with its saved files (as listed by `storage::read_cached_info`)
+/// and their sizes indented beneath, then the summary line.
+pub fn format_status_tree(statuses: &[CrateStatus], output_dir: &Path) -> String {
+    let mut output = String::new();
+    for item in statuses {
+        let _ = writeln!(
+            output,
+            "{} {} [{}]",
+            item.crate_name,
+            item.docs_version.as_deref().unwrap_or("-"),
+            item.status.as_str()
+        );
+        let Some(version) = item.docs_version.as_deref() else {
+            continue;
+        };
+        let crate_dir = output_dir.join(format!("{}@{version}", item.crate_name));
+        let files = crate::storage::read_cached_info(
+            output_dir,
+            &item.crate_name,
+            version,
+            &crate::config::CrateDoc::default(),
+        )
+        .map(|saved| saved.files)
+        .unwrap_or_default();
+        if files.is_empty() {
+            output.push_str("└── (no files)\n");
+        }
+        for (i, name) in files.iter().enumerate() {
+            let branch = if i + 1 == files.len() {
+                "└──"
+            } else {
+                "├──"
+            };
+            let bytes = std::fs::metadata(crate_dir.join(name)).map_or(0, |meta| meta.len());
+            let _ = writeln!(output, "{branch} {name} ({})", format_size(bytes));
+        }
+    }
+    let _ = writeln!(output, "\n{}", format_status_summary(statuses));
+    output
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn status_emoji(status: DocsStatus) -> &'static str {
+    match status {
+        DocsStatus::Synced => "✅",
+        DocsStatus::SyncedFallback => "☑️",
+        DocsStatus::Outdated => "⚠️",
+        DocsStatus::Missing => "❌",
+        DocsStatus::Corrupted => "🛑",
+    }
+}
+
+/// Escapes characters that would end a table cell or open an HTML tag.
+fn markdown_cell(value: &str) -> String {
+    value
+        .replace('|', "\\|")
+        .replace('<', "&lt;")
+        .replace(['\n', '\r'], " ")
+}
+
+/// RFC 4180 quoting: fields with commas, quotes or line breaks are wrapped in
+/// quotes, with embedded quotes doubled.
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+/// With `color`, the status cell is padded first and then wrapped in ANSI
+/// codes so columns stay aligned.
+fn format_status_table(statuses: &[CrateStatus], color: bool) -> String {
     const COL_CRATE: usize = 24;
     const COL_LOCK: usize = 16;
     const COL_DOCS: usize = 16;
@@ -433,26 +954,23 @@ fn format_status_table(statuses: &[CrateStatus]) -> String {
     );
 
     for item in statuses {
-        let lock = item.lock_version.as_deref().unwrap_or("-");
+        let lock = item.lock_version_label();
         let docs = item.docs_version.as_deref().unwrap_or("-");
+        let status = format!("{:<COL_STATUS$}", item.status.as_str());
         let _ = writeln!(
             output,
-            "{:<COL_CRATE$} {:<COL_LOCK$} {:<COL_DOCS$} {:<COL_STATUS$}",
+            "{:<COL_CRATE$} {:<COL_LOCK$} {:<COL_DOCS$} {}",
             item.crate_name,
             lock,
             docs,
-            item.status.as_str(),
+            colorize_status(item.status, &status, color),
         );
         let _ = writeln!(output, "  ↳ {}", item.reason);
     }
 
     let summary = summarize(statuses);
     let _ = writeln!(output);
-    let _ = writeln!(
-        output,
-        "Total: {} | Synced: {} | Missing: {} | Outdated: {} | Corrupted: {}",
-        summary.total, summary.synced, summary.missing, summary.outdated, summary.corrupted
-    );
+    let _ = writeln!(output, "{}", format_status_summary(statuses));
 
     if summary.has_problems() {
         let _ = writeln!(
@@ -479,6 +997,26 @@ fn format_status_table(statuses: &[CrateStatus]) -> String {
     output
 }
 
+/// The one-line count summary of the status table (`check --summary-only`).
+pub fn format_status_summary(statuses: &[CrateStatus]) -> String {
+    let summary = summarize(statuses);
+    format!(
+        "Total: {} | Synced: {} | Missing: {} | Outdated: {} | Corrupted: {}",
+        summary.total, summary.synced, summary.missing, summary.outdated, summary.corrupted
+    )
+}
+
+fn colorize_status(status: DocsStatus, cell: &str, color: bool) -> String {
+    if !color {
+        return cell.to_string();
+    }
+    match status {
+        DocsStatus::Synced | DocsStatus::SyncedFallback => cell.green().to_string(),
+        DocsStatus::Outdated => cell.yellow().to_string(),
+        DocsStatus::Missing | DocsStatus::Corrupted => cell.red().to_string(),
+    }
+}
+
 #[derive(Debug, Default, Serialize)]
 pub struct StatusSummary {
     total: usize,
@@ -515,16 +1053,200 @@ pub fn summarize(statuses: &[CrateStatus]) -> StatusSummary {
 #[cfg(test)]
 mod tests {
     use super::{
-        collect_status_latest, format_status_json, format_status_table, CrateStatus, DocsStatus,
-        StatusMode,
+        carry_upstream_verdicts, check_upstream_concurrently, collect_status,
+        collect_status_latest, crate_status, format_reason_code, format_status_csv,
+        format_status_github_markdown, format_status_json, format_status_table, format_status_tree,
+        mark_manifest_versions, reason_code, scan_existing_dirs, split_crate_version, CrateStatus,
+        DocsStatus, StatusDetails, StatusMode, REASON_CODES,
     };
     use crate::config::{Config, CrateDoc, Settings};
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
     use std::fs;
 
+    #[test]
+    fn every_produced_reason_code_is_explained() {
+        let source = include_str!("status.rs");
+        let source = &source[..source.find("#[cfg(test)]").expect("test module")];
+        let literal = regex::Regex::new(r#"(?m)^\s*"([a-z]+(?:_[a-z]+)+)",?$"#).expect("regex");
+        let assigned = regex::Regex::new(r#"reason_code(?::| =) "([a-z_]+)""#).expect("regex");
+        let produced: std::collections::BTreeSet<&str> = literal
+            .captures_iter(source)
+            .chain(assigned.captures_iter(source))
+            .map(|caps| caps.get(1).expect("code").as_str())
+            .collect();
+        let explained: std::collections::BTreeSet<&str> =
+            REASON_CODES.iter().map(|info| info.code).collect();
+        assert_eq!(produced, explained);
+
+        let text = format_reason_code(reason_code("meta_schema_unsupported").expect("known"));
+        assert!(text.starts_with("meta_schema_unsupported (Corrupted)\n\n"));
+        assert!(text.contains("\n\nRemediation: Upgrade cargo-ai-fdocs"));
+        assert!(reason_code("nope").is_none());
+    }
+
+    #[test]
+    fn split_crate_version_rejects_empty_name_or_version() {
+        assert_eq!(split_crate_version("serde@1.0.0"), Some(("serde", "1.0.0")));
+        assert_eq!(
+            split_crate_version("foo@1.0.0+build"),
+            Some(("foo", "1.0.0+build"))
+        );
+        assert_eq!(split_crate_version("foo@"), None);
+        assert_eq!(split_crate_version("@1.0"), None);
+        assert_eq!(split_crate_version("foo"), None);
+
+        let tmp = std::env::temp_dir().join(format!("aifd-status-split-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        for dir in ["foo@", "@1.0", "foo@1.0.0+build"] {
+            fs::create_dir_all(tmp.join(dir)).expect("create crate dir");
+        }
+        // A symlink to a crate dir is not another version of it.
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(tmp.join("foo@1.0.0+build"), tmp.join("foo@9.0.0"))
+            .expect("create symlink");
+        let dirs = scan_existing_dirs(&tmp);
+        assert_eq!(dirs.len(), 1);
+        assert_eq!(dirs["foo"].0, "1.0.0+build");
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn carries_outdated_verdict_only_for_unchanged_docs_version() {
+        let synced = |name: &str, version: &str| {
+            crate_status(
+                name.to_string(),
+                None,
+                Some(version.to_string()),
+                DocsStatus::Synced,
+                "latest-docs up to date",
+                "latest_ok_docsrs",
+                StatusMode::LatestDocs,
+                Some("docsrs".to_string()),
+            )
+        };
+        let mut outdated = synced("serde", "1.0.0");
+        outdated.status = DocsStatus::Outdated;
+        outdated.reason_code = "latest_outdated".to_string();
+        outdated.details.expected = Some("1.0.1".to_string());
+        let mut resynced = outdated.clone();
+        resynced.crate_name = "tokio".to_string();
+
+        let mut current = vec![synced("serde", "1.0.0"), synced("tokio", "1.1.0")];
+        carry_upstream_verdicts(&[outdated, resynced], &mut current);
+
+        assert_eq!(current[0].status, DocsStatus::Outdated);
+        assert_eq!(current[0].details.expected.as_deref(), Some("1.0.1"));
+        assert_eq!(current[1].status, DocsStatus::Synced);
+    }
+
+    #[test]
+    fn formats_status_csv_quoting_fields_with_commas() {
+        let status = crate_status(
+            "axum".to_string(),
+            Some("0.8.1".to_string()),
+            None,
+            DocsStatus::Missing,
+            "not synced",
+            "lockfile_missing_docs",
+            StatusMode::Lockfile,
+            None,
+        );
+        let mut quoted = status.clone();
+        quoted.crate_name = "odd,\"name\"".to_string();
+        quoted.source_kind = Some("github".to_string());
+
+        assert_eq!(
+            format_status_csv(&[status, quoted]),
+            "crate,lock_version,docs_version,status,reason_code,source_kind\n\
+             axum,0.8.1,,Missing,lockfile_missing_docs,\n\
+             \"odd,\"\"name\"\"\",0.8.1,,Missing,lockfile_missing_docs,github\n"
+        );
+    }
+
+    #[test]
+    fn formats_github_markdown_with_collapsed_problem_details() {
+        let synced = crate_status(
+            "serde".to_string(),
+            Some("1.0.0".to_string()),
+            Some("1.0.0".to_string()),
+            DocsStatus::Synced,
+            "up to date",
+            "lockfile_ok",
+            StatusMode::Lockfile,
+            None,
+        );
+        let missing = crate_status(
+            "odd|name".to_string(),
+            Some("0.8.1".to_string()),
+            None,
+            DocsStatus::Missing,
+            "no <synced> docs",
+            "lockfile_missing_artifacts",
+            StatusMode::Lockfile,
+            None,
+        );
+
+        let markdown = format_status_github_markdown(&[synced.clone(), missing]);
+        assert!(markdown.starts_with(
+            "### ai-fdocs status\n\nTotal: 2 | Synced: 1 | Missing: 1 | Outdated: 0 | Corrupted: 0\n\n"
+        ));
+        assert!(markdown.contains("| serde | 1.0.0 | 1.0.0 | ✅ Synced |\n"));
+        assert!(markdown.contains("| odd\\|name | 0.8.1 | - | ❌ Missing |\n"));
+        assert!(markdown.contains(
+            "<summary>1 problem(s)</summary>\n\n- **odd\\|name** (Missing): no &lt;synced> docs\n"
+        ));
+        assert!(markdown.ends_with("</details>\n"));
+        assert!(!format_status_github_markdown(&[synced]).contains("<details>"));
+    }
+
+    #[test]
+    fn tree_lists_saved_files_with_sizes_under_each_crate() {
+        let tmp = std::env::temp_dir().join(format!("aifd-status-tree-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("serde@1.0.0")).expect("create crate dir");
+        fs::write(
+            tmp.join("serde@1.0.0/.aifd-meta.toml"),
+            "schema_version = 2\nversion = \"1.0.0\"\ngit_ref = \"v1.0.0\"\nfetched_at = \"2026-01-01\"\nis_fallback = false\n",
+        )
+        .expect("write meta");
+        fs::write(tmp.join("serde@1.0.0/README.md"), "x".repeat(2048)).expect("write readme");
+        fs::write(tmp.join("serde@1.0.0/CHANGELOG.md"), "## 1.0.0\n").expect("write changelog");
+        fs::write(tmp.join("serde@1.0.0/_SUMMARY.md"), "ignored").expect("write summary");
+
+        let synced = crate_status(
+            "serde".to_string(),
+            Some("1.0.0".to_string()),
+            Some("1.0.0".to_string()),
+            DocsStatus::Synced,
+            "up to date",
+            "lockfile_ok",
+            StatusMode::Lockfile,
+            None,
+        );
+        let missing = crate_status(
+            "tokio".to_string(),
+            Some("1.40.0".to_string()),
+            None,
+            DocsStatus::Missing,
+            "no synced docs",
+            "lockfile_missing_artifacts",
+            StatusMode::Lockfile,
+            None,
+        );
+
+        let tree = format_status_tree(&[synced, missing], &tmp);
+        assert!(tree.starts_with(
+            "serde 1.0.0 [Synced]\n├── CHANGELOG.md (9 B)\n└── README.md (2.0 KB)\ntokio - [Missing]\n"
+        ));
+        assert!(!tree.contains("_SUMMARY.md"));
+        assert!(tree.ends_with("Total: 2 | Synced: 1 | Missing: 1 | Outdated: 0 | Corrupted: 0\n"));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
     #[test]
     fn formats_empty_status_table_with_zero_summary() {
-        let table = format_status_table(&[]);
+        let table = format_status_table(&[], false);
 
         assert!(table.contains("Crate"));
         assert!(table.contains("Lock Version"));
@@ -539,12 +1261,21 @@ mod tests {
         let statuses = vec![CrateStatus {
             crate_name: "axum".to_string(),
             lock_version: Some("0.8.1".to_string()),
+            version_from_manifest: false,
             docs_version: Some("0.8.1".to_string()),
             status: DocsStatus::Synced,
             reason: "up to date".to_string(),
             reason_code: "lockfile_ok".to_string(),
             mode: StatusMode::Lockfile.as_str().to_string(),
             source_kind: Some("github".to_string()),
+            details: StatusDetails {
+                lock_version: Some("0.8.1".to_string()),
+                docs_version: Some("0.8.1".to_string()),
+                ..StatusDetails::default()
+            },
+            fetch_duration_ms: Some(120),
+            total_bytes: Some(2048),
+            file_manifest: BTreeMap::new(),
         }];
 
         let json = format_status_json(&statuses).expect("json serialization");
@@ -556,6 +1287,42 @@ mod tests {
         assert!(json.contains("\"reason_code\": \"lockfile_ok\""));
         assert!(json.contains("\"mode\": \"lockfile\""));
         assert!(json.contains("\"source_kind\": \"github\""));
+        assert!(json.contains("\"fetch_duration_ms\": 120"));
+        assert!(json.contains("\"total_bytes\": 2048"));
+        assert!(json.contains("\"details\": {"));
+        assert!(!json.contains("\"expected\""));
+    }
+
+    #[tokio::test]
+    async fn collect_status_reports_version_mismatch_details() {
+        let tmp = std::env::temp_dir().join(format!("aifd-status-details-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("serde@1.0.0")).expect("create crate dir");
+
+        let mut crates = HashMap::new();
+        crates.insert("serde".to_string(), CrateDoc::default());
+        let config = Config {
+            settings: Settings::default(),
+            crates,
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+        let lock_versions = HashMap::from([("serde".to_string(), "1.0.1".to_string())]);
+
+        let statuses = collect_status(&config, &lock_versions, tmp.as_path()).await;
+        assert_eq!(statuses[0].reason_code, "lockfile_version_mismatch");
+        assert_eq!(
+            statuses[0].details,
+            StatusDetails {
+                lock_version: Some("1.0.1".to_string()),
+                docs_version: Some("1.0.0".to_string()),
+                expected: Some("1.0.1".to_string()),
+                actual: Some("1.0.0".to_string()),
+            }
+        );
+
+        let _ = fs::remove_dir_all(&tmp);
     }
 
     #[test]
@@ -563,15 +1330,20 @@ mod tests {
         let statuses = vec![CrateStatus {
             crate_name: "serde".to_string(),
             lock_version: None,
+            version_from_manifest: false,
             docs_version: None,
             status: DocsStatus::Missing,
             reason: "crate missing in Cargo.lock".to_string(),
             reason_code: "lockfile_missing_crate".to_string(),
             mode: StatusMode::Lockfile.as_str().to_string(),
             source_kind: None,
+            details: StatusDetails::default(),
+            fetch_duration_ms: None,
+            total_bytes: None,
+            file_manifest: BTreeMap::new(),
         }];
 
-        let table = format_status_table(&statuses);
+        let table = format_status_table(&statuses, false);
 
         assert!(table.contains("serde"));
         assert!(table.contains("Missing"));
@@ -582,13 +1354,62 @@ mod tests {
     }
 
     #[test]
-    fn collect_status_latest_marks_github_fallback_as_synced_fallback() {
+    fn marks_versions_taken_from_manifest_requirements() {
+        let ok = |name: &str, version: &str| {
+            crate_status(
+                name.to_string(),
+                Some(version.to_string()),
+                Some(version.to_string()),
+                DocsStatus::Synced,
+                "up to date".to_string(),
+                "lockfile_ok",
+                StatusMode::Lockfile,
+                None,
+            )
+        };
+        let mut statuses = vec![ok("serde", "1.0.0"), ok("local", "0.1.0")];
+        let manifest = HashMap::from([("serde".to_string(), "1.0.0".to_string())]);
+        mark_manifest_versions(&mut statuses, &manifest);
+
+        assert!(statuses[0].version_from_manifest);
+        assert!(statuses[0].reason.contains("no Cargo.lock"));
+        assert!(!statuses[1].version_from_manifest);
+        assert!(format_status_table(&statuses, false).contains("1.0.0 (manifest)"));
+        let json = format_status_json(&statuses).expect("json");
+        assert_eq!(json.matches("\"version_from_manifest\": true").count(), 1);
+    }
+
+    #[test]
+    fn colored_table_wraps_padded_status_cell() {
+        let mut item = crate_status(
+            "serde".to_string(),
+            Some("1.0.0".to_string()),
+            None,
+            DocsStatus::Corrupted,
+            "meta unreadable",
+            "docs_meta_corrupted",
+            StatusMode::Lockfile,
+            None,
+        );
+        let plain = format_status_table(std::slice::from_ref(&item), false);
+        assert!(!plain.contains('\x1b'));
+
+        let colored = format_status_table(std::slice::from_ref(&item), true);
+        assert!(colored.contains("\x1b[31mCorrupted     \x1b[39m"));
+
+        item.status = DocsStatus::Synced;
+        let colored = format_status_table(&[item], true);
+        assert!(colored.contains("\x1b[32mSynced        \x1b[39m"));
+    }
+
+    #[tokio::test]
+    async fn collect_status_latest_marks_github_fallback_as_synced_fallback() {
         let tmp = std::env::temp_dir().join(format!("aifd-status-latest-{}", std::process::id()));
         let _ = fs::remove_dir_all(&tmp);
         fs::create_dir_all(tmp.join("serde@1.0.0")).expect("create crate dir");
         fs::write(
             tmp.join("serde@1.0.0/.aifd-meta.toml"),
-            "schema_version = 1\nversion = \"1.0.0\"\nsource_kind = \"github_fallback\"\n",
+            "schema_version = 1\nversion = \"1.0.0\"\ngit_ref = \"main\"\nfetched_at = \"2026-01-01\"\nis_fallback = true\nsource_kind = \"github_fallback\"\n",
         )
         .expect("write meta");
 
@@ -597,19 +1418,19 @@ mod tests {
             "serde".to_string(),
             CrateDoc {
                 repo: Some("serde-rs/serde".to_string()),
-                subpath: None,
-                files: None,
-                sources: None,
-                ai_notes: String::new(),
+                ..CrateDoc::default()
             },
         );
 
         let config = Config {
             settings: Settings::default(),
             crates,
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
         };
 
-        let statuses = collect_status_latest(&config, tmp.as_path());
+        let statuses = collect_status_latest(&config, tmp.as_path(), None).await;
         assert_eq!(statuses.len(), 1);
         assert_eq!(statuses[0].status, DocsStatus::SyncedFallback);
         assert_eq!(statuses[0].reason_code, "latest_ok_fallback");
@@ -618,4 +1439,64 @@ mod tests {
 
         let _ = fs::remove_dir_all(&tmp);
     }
+
+    #[tokio::test]
+    async fn upstream_checks_run_concurrently_and_keep_their_indices() {
+        use std::io::{Read, Write};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        // Answers each connection on its own thread after a delay, recording
+        // the most requests in flight at once.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let addr = listener.local_addr().expect("local addr");
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (server_in_flight, server_peak) = (Arc::clone(&in_flight), Arc::clone(&peak));
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let (in_flight, peak) = (Arc::clone(&server_in_flight), Arc::clone(&server_peak));
+                std::thread::spawn(move || {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    let mut buf = [0_u8; 4096];
+                    let Ok(read) = stream.read(&mut buf) else {
+                        return;
+                    };
+                    let req = String::from_utf8_lossy(&buf[..read]).to_string();
+                    let path = req.split_whitespace().nth(1).unwrap_or("/").to_string();
+                    std::thread::sleep(Duration::from_millis(200));
+                    let body = match path.as_str() {
+                        "/crates/demo1" => r#"{"crate":{"max_stable_version":"2.0.0"}}"#,
+                        p if p.ends_with("/1.0.0") => r#"{"version":{"yanked":false}}"#,
+                        _ => r#"{"crate":{"max_stable_version":"1.0.0"}}"#,
+                    };
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let _ = stream.write_all(response.as_bytes());
+                });
+            }
+        });
+        let fetcher =
+            crate::fetcher::latest::LatestDocsFetcher::new(&crate::fetcher::HttpOptions {
+                registry_api_base: format!("http://{addr}"),
+                ..crate::fetcher::HttpOptions::default()
+            });
+
+        let checks = (0..4)
+            .map(|i| (i * 2, format!("demo{i}"), "1.0.0".to_string(), None))
+            .collect();
+        let outdated = check_upstream_concurrently(&fetcher, checks, 4).await;
+
+        assert!(peak.load(Ordering::SeqCst) > 1);
+        assert_eq!(outdated.len(), 1);
+        assert_eq!(outdated[0].0, 2);
+        assert_eq!(outdated[0].1.reason_code, "latest_version_mismatch");
+        assert_eq!(outdated[0].1.expected.as_deref(), Some("2.0.0"));
+    }
 }
@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::status;
+
+/// `settings.status_cache` file, inside the output dir it describes.
+pub const CACHE_FILE: &str = ".aifd-status-cache.json";
+
+/// A file touched this close to (or after) the moment it was read may have
+/// changed again within the same mtime tick, so such reads are never reused.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Size and modification time of a file or directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    mtime_secs: u64,
+    mtime_nanos: u32,
+    len: u64,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+            len: meta.len(),
+        })
+    }
+
+    fn mtime(self) -> Duration {
+        Duration::new(self.mtime_secs, self.mtime_nanos)
+    }
+}
+
+/// Something read from disk, with the stamp it had and when it was read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry<T> {
+    stamp: Stamp,
+    read_at: Duration,
+    value: T,
+}
+
+impl<T> Entry<T> {
+    /// Reusable only while the stamp is unchanged and was already settled
+    /// when the value was read.
+    fn is_fresh(&self, current: Option<Stamp>) -> bool {
+        current == Some(self.stamp) && self.stamp.mtime() + RACY_WINDOW < self.read_at
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheData {
+    /// `scan_existing_dirs` result as crate name to (version, dir name).
+    dirs: Option<Entry<BTreeMap<String, (String, String)>>>,
+    /// Raw `.aifd-meta.toml` contents by path relative to the output dir.
+    metas: BTreeMap<String, Entry<String>>,
+}
+
+fn memo() -> &'static Mutex<HashMap<PathBuf, CacheData>> {
+    static MEMO: OnceLock<Mutex<HashMap<PathBuf, CacheData>>> = OnceLock::new();
+    MEMO.get_or_init(Default::default)
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Output dir scans and meta reads of one `status`/`check` evaluation. Results
+/// are kept in-process for the next evaluation of the same dir (e.g. `status
+/// --watch`) and, with `settings.status_cache`, in `CACHE_FILE` for the next
+/// run. Every entry is revalidated against the current mtime and size.
+#[derive(Debug)]
+pub struct StatusCache {
+    output_dir: PathBuf,
+    persist: bool,
+    data: CacheData,
+    dirty: bool,
+}
+
+impl StatusCache {
+    pub fn open(output_dir: &Path, persist: bool) -> Self {
+        let memoized = memo()
+            .lock()
+            .ok()
+            .and_then(|mut memo| memo.remove(output_dir));
+        let data = match memoized {
+            Some(data) => data,
+            None if persist => load_persisted(output_dir).unwrap_or_default(),
+            None => CacheData::default(),
+        };
+        Self {
+            output_dir: output_dir.to_path_buf(),
+            persist,
+            data,
+            dirty: false,
+        }
+    }
+
+    /// `status::scan_existing_dirs`, rescanned when the output dir changed.
+    pub fn existing_dirs(&mut self) -> HashMap<String, (String, PathBuf)> {
+        let current = Stamp::of(&self.output_dir);
+        if let Some(entry) = self.data.dirs.as_ref().filter(|e| e.is_fresh(current)) {
+            return entry
+                .value
+                .iter()
+                .map(|(name, (version, dir))| {
+                    (name.clone(), (version.clone(), self.output_dir.join(dir)))
+                })
+                .collect();
+        }
+
+        let read_at = now();
+        let map = status::scan_existing_dirs(&self.output_dir);
+        self.data.dirs = current.map(|stamp| Entry {
+            stamp,
+            read_at,
+            value: map
+                .iter()
+                .filter_map(|(name, (version, path))| {
+                    let dir = path.file_name()?.to_string_lossy().into_owned();
+                    Some((name.clone(), (version.clone(), dir)))
+                })
+                .collect(),
+        });
+        self.dirty = true;
+        map
+    }
+
+    /// Contents of `meta_path`, re-read when the file changed. Read errors
+    /// are never cached.
+    pub fn read_meta(&mut self, meta_path: &Path) -> io::Result<String> {
+        let key = meta_path
+            .strip_prefix(&self.output_dir)
+            .unwrap_or(meta_path)
+            .to_string_lossy()
+            .into_owned();
+        let current = Stamp::of(meta_path);
+        if let Some(entry) = self.data.metas.get(&key).filter(|e| e.is_fresh(current)) {
+            return Ok(entry.value.clone());
+        }
+
+        let read_at = now();
+        let raw = match std::fs::read_to_string(meta_path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                self.dirty |= self.data.metas.remove(&key).is_some();
+                return Err(e);
+            }
+        };
+        if let Some(stamp) = current {
+            self.data.metas.insert(
+                key,
+                Entry {
+                    stamp,
+                    read_at,
+                    value: raw.clone(),
+                },
+            );
+            self.dirty = true;
+        }
+        Ok(raw)
+    }
+
+    /// Keeps the results for the next evaluation, writing `CACHE_FILE` when
+    /// persisting and something was re-read. The file is rewritten in place,
+    /// so after it first appears it no longer changes the dir's mtime.
+    pub fn close(self) {
+        if self.persist && self.dirty && self.output_dir.is_dir() {
+            let path = self.output_dir.join(CACHE_FILE);
+            let written = serde_json::to_string(&self.data)
+                .map_err(|e| e.to_string())
+                .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                debug!("cannot write {}: {e}", path.display());
+            }
+        }
+        if let Ok(mut memo) = memo().lock() {
+            memo.insert(self.output_dir, self.data);
+        }
+    }
+}
+
+fn load_persisted(output_dir: &Path) -> Option<CacheData> {
+    let raw = std::fs::read_to_string(output_dir.join(CACHE_FILE)).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_persisted, StatusCache, CACHE_FILE};
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+
+    fn backdate(path: &std::path::Path) {
+        let old = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(old))
+            .expect("set mtime");
+    }
+
+    #[test]
+    fn reuses_only_settled_unchanged_reads() {
+        let tmp = std::env::temp_dir().join(format!("aifd-status-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("serde@1.0.0")).expect("create crate dir");
+        let meta = tmp.join("serde@1.0.0/.aifd-meta.toml");
+
+        // Written just now: a same-size rewrite in the same tick must be seen.
+        fs::write(&meta, "version = \"1\"").expect("write meta");
+        let mut cache = StatusCache::open(&tmp, false);
+        assert_eq!(cache.read_meta(&meta).expect("read"), "version = \"1\"");
+        fs::write(&meta, "version = \"2\"").expect("rewrite meta");
+        assert_eq!(cache.read_meta(&meta).expect("read"), "version = \"2\"");
+
+        // Settled: served from the cache until the stamp changes.
+        backdate(&meta);
+        assert_eq!(cache.read_meta(&meta).expect("read"), "version = \"2\"");
+        fs::write(&meta, "version = \"30\"").expect("grow meta");
+        assert_eq!(cache.read_meta(&meta).expect("read"), "version = \"30\"");
+        fs::remove_file(&meta).expect("remove meta");
+        assert!(cache.read_meta(&meta).is_err());
+
+        let dirs = cache.existing_dirs();
+        assert_eq!(dirs["serde"].0, "1.0.0");
+        fs::create_dir_all(tmp.join("tokio@1.40.0")).expect("create crate dir");
+        assert!(cache.existing_dirs().contains_key("tokio"));
+
+        cache.close();
+        assert!(!tmp.join(CACHE_FILE).exists());
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn persisted_cache_is_written_into_the_output_dir() {
+        let tmp =
+            std::env::temp_dir().join(format!("aifd-status-cache-persist-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(tmp.join("serde@1.0.0")).expect("create crate dir");
+        let meta = tmp.join("serde@1.0.0/.aifd-meta.toml");
+        fs::write(&meta, "version = \"1.0.0\"").expect("write meta");
+
+        let mut cache = StatusCache::open(&tmp, true);
+        cache.read_meta(&meta).expect("read");
+        assert!(cache.existing_dirs().contains_key("serde"));
+        cache.close();
+
+        let persisted = load_persisted(&tmp).expect("cache file");
+        assert!(persisted.dirs.is_some());
+        assert_eq!(
+            persisted.metas["serde@1.0.0/.aifd-meta.toml"].value,
+            "version = \"1.0.0\""
+        );
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
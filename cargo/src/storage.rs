@@ -1,16 +1,17 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use chrono::Utc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::config::{Config, CrateDoc};
+use crate::config::{Config, CrateDoc, HashedSettings, SyncMode};
 use crate::error::{AiDocsError, Result};
-use crate::fetcher::github::{FetchedFile, ResolvedRef};
-use crate::processor::changelog;
+use crate::fetcher::github::{FetchedFile, FileRequest, ResolvedRef};
+use crate::fetcher::latest::DocsRsArtifact;
+use crate::history::{self, HistoryEntry};
+use crate::processor::{changelog, markdown};
 
-const META_SCHEMA_VERSION: u32 = 2;
+pub const META_SCHEMA_VERSION: u32 = 2;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct CrateMeta {
@@ -18,9 +19,17 @@ pub struct CrateMeta {
     pub schema_version: u32,
     pub version: String,
     pub git_ref: String,
+    /// Commit `git_ref` resolved to at sync time (GitHub sources only).
+    #[serde(default)]
+    pub commit_sha: Option<String>,
+    /// `subpath` guessed by `settings.infer_subpath` (none configured).
+    #[serde(default)]
+    pub inferred_subpath: Option<String>,
     pub fetched_at: String,
     pub is_fallback: bool,
     #[serde(default)]
+    pub fallback_reason: Option<String>,
+    #[serde(default)]
     pub config_hash: Option<String>, // Renamed from config_fingerprint
     #[serde(default)]
     pub source_kind: Option<String>,
@@ -44,6 +53,140 @@ pub struct CrateMeta {
     pub artifact_sha256: Option<String>,
     #[serde(default)]
     pub artifact_bytes: Option<usize>,
+    /// `estimate_tokens` of the saved files, without provenance headers and
+    /// `ai_notes`.
+    #[serde(default)]
+    pub estimated_tokens: Option<usize>,
+    /// Wall-clock time spent fetching upstream sources for this sync.
+    #[serde(default)]
+    pub fetch_duration_ms: Option<u64>,
+    /// Raw upstream bytes fetched, before truncation and header injection.
+    #[serde(default)]
+    pub total_bytes: Option<usize>,
+    /// crates.io categories (`settings.fetch_crate_metadata`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// crates.io keywords (`settings.fetch_crate_metadata`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// `settings.filename_template` the files were saved under, when not the
+    /// default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename_template: Option<String>,
+    /// Version synced before this one, when `sync --since-last-sync` cut the
+    /// changelog back to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_from: Option<String>,
+    /// Refs the extra GitHub `sources` entries were fetched at.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_sources: Vec<SourceRef>,
+    /// Requested path -> what was fetched for it (repository syncs only).
+    /// Kept last: TOML tables must follow plain values.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, FileManifestEntry>,
+}
+
+/// Ref one extra GitHub `sources` entry was fetched at; pinned in
+/// `ai-fdocs.lock` next to the crate's own ref.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SourceRef {
+    /// `repo` of the `sources` entry as configured.
+    pub repo: String,
+    pub git_ref: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub is_fallback: bool,
+}
+
+/// How one requested path was resolved, e.g. why `Readme.md` matched
+/// instead of `README.md`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileManifestEntry {
+    /// Unset when no candidate was found (optional file missing or failed).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched_candidate: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// Raw upstream bytes, before processing and header injection.
+    #[serde(default)]
+    pub bytes: usize,
+    /// Candidates tried, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<String>,
+    /// Optional file none of whose candidates exist at the synced ref; not
+    /// requested again until the ref changes or `sync --force`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub absent: bool,
+}
+
+/// Manifest of `requests` and the files fetched for them; files without a
+/// request (e.g. the docs.rs README in hybrid mode) get their own entry.
+/// `absent_files` are marked `absent`.
+fn file_manifest(
+    requests: &[FileRequest],
+    fetched_files: &[FetchedFile],
+    absent_files: &[String],
+) -> BTreeMap<String, FileManifestEntry> {
+    let mut manifest: BTreeMap<String, FileManifestEntry> = requests
+        .iter()
+        .map(|request| {
+            let entry = FileManifestEntry {
+                candidates: request.candidates.clone(),
+                ..FileManifestEntry::default()
+            };
+            (request.original_path.clone(), entry)
+        })
+        .collect();
+    for file in fetched_files {
+        let entry = manifest.entry(file.path.clone()).or_default();
+        entry.matched_candidate = Some(file.matched_candidate.clone());
+        entry.source_url = Some(file.source_url.clone());
+        entry.bytes = file.content.len();
+    }
+    for path in absent_files {
+        manifest.entry(path.clone()).or_default().absent = true;
+    }
+    manifest
+}
+
+/// Optional files `meta` recorded as absent, when it was synced at the same
+/// `git_ref` and commit; a changed ref (or moved branch) invalidates them.
+pub fn known_absent_files(
+    meta: &CrateMeta,
+    git_ref: &str,
+    commit_sha: Option<&str>,
+) -> Vec<String> {
+    if meta.git_ref != git_ref || meta.commit_sha.as_deref() != commit_sha {
+        return Vec::new();
+    }
+    meta.files
+        .iter()
+        .filter(|(_, entry)| entry.absent)
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// Fetch-path measurements recorded into `.aifd-meta.toml`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchMetrics {
+    pub duration_ms: u64,
+    pub total_bytes: usize,
+}
+
+impl FetchMetrics {
+    /// `duration_ms` is 0 under `AI_FDOCS_FIXED_TIME`, for stable output.
+    pub fn since(started: std::time::Instant, total_bytes: usize) -> Self {
+        let duration_ms = if crate::utils::is_time_fixed() {
+            0
+        } else {
+            u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+        };
+        Self {
+            duration_ms,
+            total_bytes,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -58,6 +201,7 @@ pub struct SavedCrate {
 
 // crate_config_fingerprint removed in favor of CrateDoc::config_hash
 
+#[cfg(test)]
 fn render_crate_summary(saved: &SavedCrate) -> String {
     render_summary_with_provenance(saved, None)
 }
@@ -114,6 +258,48 @@ fn latest_docs_summary_provenance(
     lines.join("\n")
 }
 
+/// `settings.filename_template` default: today's flattened names.
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{flat}";
+
+const FILENAME_PLACEHOLDERS: [&str; 4] = ["{crate}", "{version}", "{path}", "{flat}"];
+
+/// Saved file name of `path` under `template`: `{crate}`, `{version}`,
+/// `{path}` (the upstream path) and `{flat}` (`flatten_filename(path)`) are
+/// substituted, then any `/` left (e.g. from `{path}`) is flattened.
+pub fn render_filename(template: &str, crate_name: &str, version: &str, path: &str) -> String {
+    let name = template
+        .replace("{crate}", crate_name)
+        .replace("{version}", version)
+        .replace("{flat}", &flatten_filename(path))
+        .replace("{path}", path);
+    flatten_filename(&name)
+}
+
+/// Rejects templates whose names could escape the crate dir, be hidden (and
+/// so skipped as bookkeeping files), or collide across paths.
+pub fn validate_filename_template(template: &str) -> std::result::Result<(), String> {
+    let fixed = FILENAME_PLACEHOLDERS
+        .iter()
+        .fold(template.to_string(), |rest, placeholder| {
+            rest.replace(placeholder, "")
+        });
+    if fixed.contains(['{', '}']) {
+        return Err(format!(
+            "has an unknown placeholder (use {}): {template}",
+            FILENAME_PLACEHOLDERS.join(", ")
+        ));
+    }
+    if !template.contains("{path}") && !template.contains("{flat}") {
+        return Err(format!("must contain {{path}} or {{flat}}: {template}"));
+    }
+    if template.contains("..") || template.contains('\\') || template.starts_with(['.', '/']) {
+        return Err(format!(
+            "must not contain `..` or `\\` or start with `.` or `/`: {template}"
+        ));
+    }
+    Ok(())
+}
+
 pub fn flatten_filename(file_path: &str) -> String {
     if file_path.contains('/') {
         file_path.replace('/', "__")
@@ -124,34 +310,207 @@ pub fn flatten_filename(file_path: &str) -> String {
 
 fn inject_header(
     content: &str,
-    owner_repo: &str,
+    source: &str,
     git_ref: &str,
     original_path: &str,
     is_fallback: bool,
     version: &str,
     source_url: &str,
 ) -> String {
-    let date = Utc::now().format("%Y-%m-%d").to_string();
-    let mut header = format!(
-        "<!-- AI-FDOCS: source=github.com/{owner_repo} ref={git_ref} path={original_path} fetched={date} -->\n<!-- AI-FDOCS: url={source_url} -->\n"
-    );
+    let date = crate::utils::now().format("%Y-%m-%d").to_string();
+    let style = HeaderStyle::for_path(original_path);
+    let mut lines = vec![
+        format!("AI-FDOCS: source={source} ref={git_ref} path={original_path} fetched={date}"),
+        format!("AI-FDOCS: url={source_url}"),
+    ];
 
     if is_fallback {
-        header.push_str(&format!(
-            "<!-- AI-FDOCS WARNING: No tag found for version {version}. Fetched from '{git_ref}' branch. Content may not match installed version. -->\n"
+        lines.push(format!(
+            "AI-FDOCS WARNING: No tag found for version {version}. Fetched from '{git_ref}' branch. Content may not match installed version."
         ));
     }
 
+    let mut header = String::new();
+    for line in lines {
+        header.push_str(&style.wrap(&line));
+        header.push('\n');
+    }
     format!("{header}\n{content}")
 }
 
-fn should_inject_header(file_path: &str) -> bool {
-    let path = std::path::Path::new(file_path);
-    path.extension().is_some_and(|ext| {
-        ext.eq_ignore_ascii_case("md")
-            || ext.eq_ignore_ascii_case("html")
-            || ext.eq_ignore_ascii_case("htm")
-    })
+/// Comment syntax of the provenance header, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeaderStyle {
+    /// `<!-- … -->` for markdown and HTML.
+    Html,
+    /// `{/* … */}`: MDX does not accept HTML comments.
+    Mdx,
+    /// `.. …` reStructuredText comment.
+    Rst,
+    /// `// …` AsciiDoc comment.
+    AsciiDoc,
+    /// Plain leading lines for `.txt`, extension-less files and anything else.
+    Plain,
+}
+
+impl HeaderStyle {
+    fn for_path(file_path: &str) -> Self {
+        let ext = std::path::Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("md" | "markdown" | "html" | "htm") => Self::Html,
+            Some("mdx") => Self::Mdx,
+            Some("rst") => Self::Rst,
+            Some("adoc" | "asciidoc") => Self::AsciiDoc,
+            _ => Self::Plain,
+        }
+    }
+
+    fn wrap(self, line: &str) -> String {
+        match self {
+            Self::Html => format!("<!-- {line} -->"),
+            Self::Mdx => format!("{{/* {line} */}}"),
+            Self::Rst => format!(".. {line}"),
+            Self::AsciiDoc => format!("// {line}"),
+            Self::Plain => line.to_string(),
+        }
+    }
+}
+
+/// Line starts of every `HeaderStyle`, for `strip_injected_header`.
+const HEADER_LINE_PREFIXES: [&str; 5] = [
+    "<!-- AI-FDOCS",
+    "{/* AI-FDOCS",
+    ".. AI-FDOCS",
+    "// AI-FDOCS",
+    "AI-FDOCS",
+];
+
+/// Prepends the crate's `ai_notes` as a blockquote, so the guidance stays with
+/// the file once the header is injected above it. Empty notes are skipped.
+pub fn embed_ai_notes(content: &str, ai_notes: &str) -> String {
+    let notes = ai_notes.trim();
+    if notes.is_empty() {
+        return content.to_string();
+    }
+
+    let mut out = String::from("> **AI notes:**\n");
+    for line in notes.lines() {
+        out.push_str(if line.trim().is_empty() { ">" } else { "> " });
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out.push('\n');
+    out.push_str(content);
+    out
+}
+
+/// Returns saved content without the leading `AI-FDOCS` provenance comments.
+pub fn strip_injected_header(content: &str) -> &str {
+    let mut rest = content;
+    let mut stripped = false;
+    while HEADER_LINE_PREFIXES
+        .iter()
+        .any(|prefix| rest.starts_with(prefix))
+    {
+        let Some(end) = rest.find('\n') else {
+            return "";
+        };
+        rest = &rest[end + 1..];
+        stripped = true;
+    }
+
+    if stripped {
+        rest.strip_prefix('\n').unwrap_or(rest)
+    } else {
+        content
+    }
+}
+
+/// Content pipeline applied to every fetched file before header injection.
+/// Shared by `sync` and `verify` so both see identical output.
+pub fn process_file_content(
+    file_path: &str,
+    content: &str,
+    version: &str,
+    max_file_size_kb: usize,
+    cleanup: markdown::CleanupOptions,
+    changelog_options: &changelog::ChangelogOptions,
+) -> (String, bool) {
+    let mut content = content.to_string();
+
+    if cleanup.normalize_line_endings {
+        content = markdown::normalize_line_endings(&content);
+    }
+
+    if !cleanup.is_noop() && markdown::is_markdown_path(file_path) {
+        content = markdown::clean_markdown(&content, cleanup);
+    }
+
+    if changelog_options.truncate && file_path.to_lowercase().contains("changelog") {
+        content = changelog::truncate_changelog(&content, version, changelog_options);
+    }
+
+    truncate_if_needed(&content, max_file_size_kb)
+}
+
+/// Notes go into markdown files that also get a provenance header.
+pub fn should_embed_ai_notes(file_path: &str, header_extensions: &[String]) -> bool {
+    should_inject_header(file_path, header_extensions) && markdown::is_markdown_path(file_path)
+}
+
+/// Text docs get a provenance header: files whose extension is in
+/// `settings.inject_header_extensions`, and extension-less files such as
+/// `LICENSE` or `AUTHORS`.
+fn should_inject_header(file_path: &str, header_extensions: &[String]) -> bool {
+    extension_listed(file_path, header_extensions)
+}
+
+/// `settings.include_extensions`: files with another extension are not
+/// saved; extension-less files (`LICENSE`) always are.
+pub fn has_included_extension(file_path: &str, include_extensions: &[String]) -> bool {
+    extension_listed(file_path, include_extensions)
+}
+
+/// Whether a fetched file is saved: its extension passes
+/// `has_included_extension`, or it is listed by name (not as a directory) in
+/// the `files` of the crate or of one of its extra `sources`.
+pub fn is_saved_file(
+    file_path: &str,
+    crate_config: &CrateDoc,
+    include_extensions: &[String],
+) -> bool {
+    has_included_extension(file_path, include_extensions)
+        || crate_config
+            .effective_files()
+            .into_iter()
+            .chain(
+                crate_config
+                    .additional_github_sources()
+                    .into_iter()
+                    .filter_map(|(_, files)| files),
+            )
+            .flatten()
+            .any(|listed| listed == file_path)
+}
+
+/// Whether the extension of `file_path` is in `extensions` (case-insensitive,
+/// leading dots ignored); true for files without one.
+fn extension_listed(file_path: &str, extensions: &[String]) -> bool {
+    match std::path::Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some(ext) => extensions.iter().any(|allowed| {
+            allowed
+                .trim()
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(ext)
+        }),
+        None => true,
+    }
 }
 
 pub fn truncate_if_needed(content: &str, max_size_kb: usize) -> (String, bool) {
@@ -159,33 +518,142 @@ pub fn truncate_if_needed(content: &str, max_size_kb: usize) -> (String, bool) {
     if content.len() <= max_bytes {
         return (content.to_string(), false);
     }
+    let marker = format!("[TRUNCATED by ai-fdocs at {max_size_kb}KB]");
+    (cut_with_marker(content, max_bytes, &marker), true)
+}
+
+/// Rough token count of `content`: about four bytes per token.
+pub fn estimate_tokens(content: &str) -> usize {
+    content.len().div_ceil(4)
+}
+
+fn token_budget_marker(max_tokens: usize) -> String {
+    format!("[TRUNCATED by ai-fdocs: settings.max_tokens_per_crate = {max_tokens}]")
+}
+
+/// Order in which `fit_token_budget` cuts files: changelogs first, READMEs
+/// last.
+fn budget_priority(file_path: &str) -> u8 {
+    let name = file_path
+        .rsplit('/')
+        .next()
+        .unwrap_or(file_path)
+        .to_lowercase();
+    if name.contains("changelog") {
+        0
+    } else if name.starts_with("readme") {
+        2
+    } else {
+        1
+    }
+}
+
+/// Outcome of `fit_token_budget`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetFit {
+    /// Some body was cut.
+    pub cut: bool,
+    /// The bodies fit the budget afterwards; false when even the cut bodies
+    /// (each at least its truncation marker) exceed it.
+    pub met: bool,
+}
+
+/// `settings.max_tokens_per_crate`: cuts the processed `(path, body)` pairs
+/// of one crate, lowest `budget_priority` first, until their estimated
+/// tokens fit in `max_tokens`. Bodies a cut would not shrink are kept as is.
+pub fn fit_token_budget(bodies: &mut [(&str, String)], max_tokens: Option<usize>) -> BudgetFit {
+    let untouched = BudgetFit {
+        cut: false,
+        met: true,
+    };
+    let Some(max_tokens) = max_tokens else {
+        return untouched;
+    };
+    let mut total: usize = bodies.iter().map(|(_, body)| estimate_tokens(body)).sum();
+    if total <= max_tokens {
+        return untouched;
+    }
+
+    let marker = token_budget_marker(max_tokens);
+    let mut order: Vec<usize> = (0..bodies.len()).collect();
+    order.sort_by_key(|&i| budget_priority(bodies[i].0));
+    let mut any_cut = false;
+    for i in order {
+        if total <= max_tokens {
+            break;
+        }
+        let body = &mut bodies[i].1;
+        let tokens = estimate_tokens(body);
+        let keep_bytes =
+            (tokens.saturating_sub(total - max_tokens) * 4).saturating_sub(marker.len() + 8);
+        let cut = cut_with_marker(body, keep_bytes, &marker);
+        let cut_tokens = estimate_tokens(&cut);
+        if cut_tokens >= tokens {
+            continue;
+        }
+        total = total - tokens + cut_tokens;
+        *body = cut;
+        any_cut = true;
+    }
+    BudgetFit {
+        cut: any_cut,
+        met: total <= max_tokens,
+    }
+}
 
+/// `content` cut to at most `max_bytes`, followed by `marker`.
+fn cut_with_marker(content: &str, max_bytes: usize, marker: &str) -> String {
+    // Cut before the last line break in the final quarter of the limit, so
+    // no line is split, and close a code fence the cut left open.
     let boundary = crate::utils::floor_char_boundary(content, max_bytes);
-    let truncated = &content[..boundary];
-    (
-        format!("{truncated}\n\n[TRUNCATED by ai-fdocs at {max_size_kb}KB]\n"),
-        true,
-    )
+    let cut = content[..boundary]
+        .rfind('\n')
+        .filter(|&newline| newline >= boundary - boundary / 4)
+        .unwrap_or(boundary);
+    let mut truncated = content[..cut].to_string();
+    if let Some(fence) = markdown::unclosed_fence(&truncated) {
+        truncated.push('\n');
+        truncated.push_str(&fence);
+    }
+    format!("{truncated}\n\n{marker}\n")
 }
 
+/// Reads a meta, migrating older schemas in memory only: read-only commands
+/// (`status`, `stats`, ...) never rewrite it, `sync` persists the upgrade via
+/// `upgrade_meta_schema`.
 fn load_meta_with_migration(meta_path: &Path) -> Option<CrateMeta> {
     let content = fs::read_to_string(meta_path).ok()?;
     let mut meta: CrateMeta = toml::from_str(&content).ok()?;
 
-    if meta.schema_version < META_SCHEMA_VERSION {
-        // Auto-migrate if needed, but for hash check we might just invalidate if hash is missing.
-        // For now, allow reading old meta, but is_cached will fail if hash is missing/mismatch.
-        // We don't strictly need to write back immediately unless we have new data.
-        // But let's treat it as valid structure.
-    }
-
     if meta.schema_version > META_SCHEMA_VERSION {
         return None;
     }
 
+    // Legacy metadata keeps its fields; is_cached still invalidates it when
+    // config_hash is missing, so migrating only bumps the schema marker.
+    meta.schema_version = META_SCHEMA_VERSION;
     Some(meta)
 }
 
+/// `sync`: rewrites a cached crate's meta saved with an older schema.
+pub fn upgrade_meta_schema(output_dir: &Path, crate_name: &str, version: &str) {
+    let meta_path = output_dir
+        .join(format!("{crate_name}@{version}"))
+        .join(".aifd-meta.toml");
+    let Some(mut meta) = fs::read_to_string(&meta_path)
+        .ok()
+        .and_then(|content| toml::from_str::<CrateMeta>(&content).ok())
+    else {
+        return;
+    };
+    if meta.schema_version < META_SCHEMA_VERSION {
+        meta.schema_version = META_SCHEMA_VERSION;
+        if let Err(e) = save_meta(&meta_path, &meta) {
+            debug!("Failed to rewrite migrated meta {:?}: {e}", meta_path);
+        }
+    }
+}
+
 fn save_meta(meta_path: &Path, meta: &CrateMeta) -> Result<()> {
     let meta_content = toml::to_string_pretty(meta)
         .map_err(|e| AiDocsError::Other(format!("Failed to serialize meta: {e}")))?;
@@ -193,11 +661,15 @@ fn save_meta(meta_path: &Path, meta: &CrateMeta) -> Result<()> {
     Ok(())
 }
 
+/// Whether `crate@version` was saved with the current config. Repository
+/// syncs pass their `filename_template`, so changing it re-syncs the crate.
 pub fn is_cached(
     output_dir: &Path,
     crate_name: &str,
     version: &str,
     crate_config: &CrateDoc,
+    settings: &HashedSettings<'_>,
+    filename_template: Option<&str>,
 ) -> bool {
     let crate_dir = output_dir.join(format!("{crate_name}@{version}"));
     let meta_path = crate_dir.join(".aifd-meta.toml");
@@ -208,10 +680,17 @@ pub fn is_cached(
 
     match load_meta_with_migration(&meta_path) {
         Some(meta) => {
-            let current_hash = crate_config.config_hash();
+            let current_hash = crate_config.config_hash(settings);
             if meta.version != version {
                 return false;
             }
+            let saved_template = meta
+                .filename_template
+                .as_deref()
+                .unwrap_or(DEFAULT_FILENAME_TEMPLATE);
+            if filename_template.is_some_and(|template| template != saved_template) {
+                return false;
+            }
             // Check config hash (new v2 way)
             if let Some(cached_hash) = &meta.config_hash {
                 return cached_hash == &current_hash;
@@ -228,7 +707,11 @@ pub fn is_cached(
 pub struct SaveRequest<'a> {
     pub crate_name: &'a str,
     pub version: &'a str,
+    /// Requests behind `fetched_files`, recorded in the meta's `files` manifest.
+    pub requests: &'a [FileRequest],
     pub fetched_files: &'a [FetchedFile],
+    /// Optional requests confirmed absent at this ref.
+    pub absent_files: &'a [String],
     pub crate_config: &'a CrateDoc,
 }
 
@@ -238,11 +721,8 @@ pub fn save_crate_files(
     req: SaveRequest<'_>,
 ) -> Result<SavedCrate> {
     let crate_dir = output_dir.join(format!("{}@{}", req.crate_name, req.version));
-
-    if crate_dir.exists() {
-        fs::remove_dir_all(&crate_dir)?;
-    }
-    fs::create_dir_all(&crate_dir)?;
+    let previous_history = history::previous_history(output_dir, req.crate_name, req.version);
+    let (categories, keywords) = previous_crate_metadata(&crate_dir);
 
     let mut saved_names = Vec::new();
 
@@ -254,25 +734,62 @@ pub fn save_crate_files(
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
 
+    let mut files = Vec::new();
+    let mut bodies = Vec::new();
     for file in req.fetched_files {
-        let flat_name = flatten_filename(&file.path);
-        let mut content = file.content.clone();
-
-        if file.path.to_lowercase().contains("changelog") {
-            content = changelog::truncate_changelog(&content, req.version);
+        if !is_saved_file(&file.path, req.crate_config, save_ctx.include_extensions) {
+            debug!(
+                "Skipping {}: extension not in settings.include_extensions",
+                file.path
+            );
+            continue;
         }
-
-        let (truncated_content, is_truncated) =
-            truncate_if_needed(&content, save_ctx.max_file_size_kb);
-        content = truncated_content;
+        let (content, is_truncated) = process_file_content(
+            &file.path,
+            &file.content,
+            req.version,
+            save_ctx.max_file_size_kb,
+            save_ctx.cleanup,
+            save_ctx.changelog,
+        );
         if is_truncated {
             any_truncated = true;
         }
+        files.push(file);
+        bodies.push((file.path.as_str(), content));
+    }
+    let fit = fit_token_budget(&mut bodies, save_ctx.max_tokens_per_crate);
+    let budget_cut = fit.cut;
+    let estimated_tokens = bodies.iter().map(|(_, body)| estimate_tokens(body)).sum();
+    if budget_cut {
+        info!(
+            "  ✂ {}@{}: cut to ~{estimated_tokens} tokens (settings.max_tokens_per_crate)",
+            req.crate_name, req.version
+        );
+    }
+    if !fit.met {
+        warn!(
+            "  ⚠ {}@{}: ~{estimated_tokens} tokens still exceed settings.max_tokens_per_crate",
+            req.crate_name, req.version
+        );
+    }
 
-        if should_inject_header(&file.path) {
+    let mut rendered = Vec::with_capacity(files.len());
+    for (file, (_, mut content)) in files.into_iter().zip(bodies) {
+        let flat_name = render_filename(
+            save_ctx.filename_template,
+            req.crate_name,
+            req.version,
+            &file.path,
+        );
+        if save_ctx.embed_ai_notes && should_embed_ai_notes(&file.path, save_ctx.header_extensions)
+        {
+            content = embed_ai_notes(&content, &req.crate_config.ai_notes);
+        }
+        if save_ctx.inject_header && should_inject_header(&file.path, save_ctx.header_extensions) {
             content = inject_header(
                 &content,
-                save_ctx.repo,
+                save_ctx.source,
                 &save_ctx.resolved.git_ref,
                 &file.path,
                 save_ctx.resolved.is_fallback,
@@ -281,6 +798,15 @@ pub fn save_crate_files(
             );
         }
 
+        rendered.push((flat_name, content));
+    }
+    check_locked_content(&rendered, save_ctx.expected_content_sha256)?;
+
+    if crate_dir.exists() {
+        fs::remove_dir_all(&crate_dir)?;
+    }
+    fs::create_dir_all(&crate_dir)?;
+    for (flat_name, content) in rendered {
         let file_path = crate_dir.join(&flat_name);
         let content_bytes = content.as_bytes();
         fs::write(&file_path, content_bytes)?;
@@ -298,18 +824,23 @@ pub fn save_crate_files(
         schema_version: META_SCHEMA_VERSION,
         version: req.version.to_string(),
         git_ref: save_ctx.resolved.git_ref.clone(),
-        fetched_at: Utc::now().format("%Y-%m-%d").to_string(),
+        commit_sha: save_ctx.commit_sha.map(str::to_string),
+        inferred_subpath: save_ctx.inferred_subpath.map(str::to_string),
+        fetched_at: crate::utils::now().format("%Y-%m-%d").to_string(),
         is_fallback: save_ctx.resolved.is_fallback,
-        config_hash: Some(req.crate_config.config_hash()),
+        fallback_reason: save_ctx.resolved.fallback_reason.clone(),
+        config_hash: Some(req.crate_config.config_hash(&save_ctx.hashed_settings())),
         source_kind: Some(save_ctx.source_kind.to_string()),
         artifact_path: save_ctx.artifact_path.map(str::to_string),
         docsrs_input_url: save_ctx.docsrs_input_url.map(str::to_string),
         docsrs_canonical_base_url: None, // Will be filled when needed
         upstream_latest_version: save_ctx.upstream_latest_version.map(str::to_string),
-        upstream_checked_at: Some(Utc::now().format("%Y-%m-%d").to_string()),
+        upstream_checked_at: Some(crate::utils::now().format("%Y-%m-%d").to_string()),
         ttl_expires_at: None, // Calculated by orchestrator
-        truncated: Some(any_truncated),
-        truncation_marker: if any_truncated {
+        truncated: Some(any_truncated || budget_cut || save_ctx.truncated.unwrap_or(false)),
+        truncation_marker: if budget_cut {
+            save_ctx.max_tokens_per_crate.map(token_budget_marker)
+        } else if any_truncated {
             Some(format!(
                 "[TRUNCATED by ai-fdocs at {}KB]",
                 save_ctx.max_file_size_kb
@@ -319,9 +850,34 @@ pub fn save_crate_files(
         },
         artifact_sha256: Some(artifact_sha256),
         artifact_bytes: Some(total_bytes),
+        estimated_tokens: Some(estimated_tokens),
+        fetch_duration_ms: save_ctx.metrics.map(|m| m.duration_ms),
+        total_bytes: save_ctx.metrics.map(|m| m.total_bytes),
+        categories,
+        keywords,
+        filename_template: Some(save_ctx.filename_template)
+            .filter(|template| *template != DEFAULT_FILENAME_TEMPLATE)
+            .map(str::to_string),
+        synced_from: save_ctx
+            .changelog
+            .since_version
+            .clone()
+            .filter(|_| save_ctx.changelog.truncate),
+        extra_sources: save_ctx.extra_sources.to_vec(),
+        files: file_manifest(req.requests, req.fetched_files, req.absent_files),
     };
 
     save_meta(&crate_dir.join(".aifd-meta.toml"), &meta)?;
+    history::write_history(
+        &crate_dir,
+        previous_history,
+        HistoryEntry::now(
+            req.version,
+            &meta.git_ref,
+            save_ctx.source_kind,
+            meta.is_fallback,
+        ),
+    )?;
 
     info!(
         "  💾 {}@{}: {} files saved to {:?}",
@@ -356,65 +912,130 @@ pub fn save_crate_files(
     Ok(saved)
 }
 
-pub fn save_latest_api_markdown(
+/// `sync --locked`: fails unless the files about to be saved, as (file name,
+/// content), hash to `expected` (`lock::content_hash` of the saved dir).
+fn check_locked_content(files: &[(String, String)], expected: Option<&str>) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let actual = crate::lock::bodies_hash(
+        files
+            .iter()
+            .map(|(name, content)| (name.as_str(), content.as_str())),
+    );
+    if actual != expected {
+        return Err(AiDocsError::LockContentMismatch {
+            lock_file: crate::lock::LOCK_FILE,
+        });
+    }
+    Ok(())
+}
+
+/// Saves a docs.rs artifact (`API.md` or `API.html`) as the only doc file.
+/// It never gets a provenance header; `settings` only go into the recorded
+/// `config_hash`, like for repository syncs.
+#[allow(clippy::too_many_arguments)]
+pub fn save_latest_artifact(
     output_dir: &Path,
     crate_name: &str,
     version: &str,
-    api_markdown: &str,
-    docsrs_input_url: &str,
-    truncated: bool,
+    artifact: &DocsRsArtifact,
+    max_file_size_kb: usize,
+    max_tokens_per_crate: Option<usize>,
+    metrics: Option<FetchMetrics>,
     crate_config: &CrateDoc,
+    settings: &HashedSettings<'_>,
+    expected_content_sha256: Option<&str>,
 ) -> Result<SavedCrate> {
+    let DocsRsArtifact {
+        file_name,
+        content,
+        docsrs_input_url,
+        truncated,
+    } = artifact;
+    let mut bodies = [(*file_name, content.clone())];
+    let fit = fit_token_budget(&mut bodies, max_tokens_per_crate);
+    let budget_cut = fit.cut;
+    let [(_, content)] = &bodies;
+    if !fit.met {
+        warn!(
+            "  ⚠ {crate_name}@{version}: ~{} tokens still exceed settings.max_tokens_per_crate",
+            estimate_tokens(content)
+        );
+    }
+    let truncated = *truncated || budget_cut;
+    check_locked_content(
+        &[(file_name.to_string(), content.clone())],
+        expected_content_sha256,
+    )?;
     let crate_dir = output_dir.join(format!("{crate_name}@{version}"));
+    let previous_history = history::previous_history(output_dir, crate_name, version);
+    let (categories, keywords) = previous_crate_metadata(&crate_dir);
 
     if crate_dir.exists() {
         fs::remove_dir_all(&crate_dir)?;
     }
     fs::create_dir_all(&crate_dir)?;
 
-    fs::write(crate_dir.join("API.md"), api_markdown)?;
+    fs::write(crate_dir.join(file_name), content)?;
 
     // Calculate SHA256
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
-    hasher.update(api_markdown.as_bytes());
+    hasher.update(content.as_bytes());
     let sha256 = format!("{:x}", hasher.finalize());
 
     let meta = CrateMeta {
         schema_version: META_SCHEMA_VERSION,
         version: version.to_string(),
         git_ref: format!("docsrs/{version}"),
-        fetched_at: Utc::now().format("%Y-%m-%d").to_string(),
+        commit_sha: None,
+        inferred_subpath: None,
+        fetched_at: crate::utils::now().format("%Y-%m-%d").to_string(),
         is_fallback: false,
-        config_hash: Some(crate_config.config_hash()),
+        fallback_reason: None,
+        config_hash: Some(crate_config.config_hash(settings)),
         source_kind: Some("docsrs".to_string()),
-        artifact_path: Some("API.md".to_string()),
+        artifact_path: Some(file_name.to_string()),
         docsrs_input_url: Some(docsrs_input_url.to_string()),
         docsrs_canonical_base_url: Some(format!("https://docs.rs/{crate_name}/{version}")),
         upstream_latest_version: Some(version.to_string()),
-        upstream_checked_at: Some(Utc::now().format("%Y-%m-%d").to_string()),
+        upstream_checked_at: Some(crate::utils::now().format("%Y-%m-%d").to_string()),
         ttl_expires_at: None, // Default TTL handled by status logic
         truncated: Some(truncated),
-        truncation_marker: if truncated {
-            Some(format!(
-                "[TRUNCATED by ai-fdocs at {}KB]",
-                crate_config.max_file_size_kb
-            ))
+        truncation_marker: if budget_cut {
+            max_tokens_per_crate.map(token_budget_marker)
+        } else if truncated {
+            Some(format!("[TRUNCATED by ai-fdocs at {max_file_size_kb}KB]"))
         } else {
             None
-        }, // Fixed marker logic
+        },
         artifact_sha256: Some(sha256),
-        artifact_bytes: Some(api_markdown.len()),
+        artifact_bytes: Some(content.len()),
+        estimated_tokens: Some(estimate_tokens(content)),
+        fetch_duration_ms: metrics.map(|m| m.duration_ms),
+        total_bytes: metrics.map(|m| m.total_bytes),
+        categories,
+        keywords,
+        filename_template: None,
+        synced_from: None,
+        extra_sources: Vec::new(),
+        files: BTreeMap::new(),
     };
 
     save_meta(&crate_dir.join(".aifd-meta.toml"), &meta)?;
+    history::write_history(
+        &crate_dir,
+        previous_history,
+        HistoryEntry::now(version, &meta.git_ref, "docsrs", false),
+    )?;
 
     let saved = SavedCrate {
         name: crate_name.to_string(),
         version: version.to_string(),
         git_ref: format!("docsrs/{version}"),
         is_fallback: false,
-        files: vec!["API.md".to_string()],
+        files: vec![file_name.to_string()],
         ai_notes: crate_config.ai_notes.clone(),
     };
 
@@ -427,14 +1048,79 @@ pub fn save_latest_api_markdown(
 }
 
 pub struct SaveContext<'a> {
-    pub repo: &'a str,
+    /// `source=` of the provenance header, e.g. `github.com/owner/repo`.
+    pub source: &'a str,
     pub resolved: &'a ResolvedRef,
+    /// Recorded as `commit_sha` for `ai-fdocs.lock`.
+    pub commit_sha: Option<&'a str>,
+    /// `sync --locked`: `content_sha256` of the lock entry; other content is
+    /// refused before anything is written.
+    pub expected_content_sha256: Option<&'a str>,
+    pub inferred_subpath: Option<&'a str>,
     pub max_file_size_kb: usize,
+    /// `settings.max_tokens_per_crate`, applied by `fit_token_budget`.
+    pub max_tokens_per_crate: Option<usize>,
     pub source_kind: &'a str,
     pub artifact_path: Option<&'a str>,
     pub docsrs_input_url: Option<&'a str>,
     pub upstream_latest_version: Option<&'a str>,
     pub truncated: Option<bool>,
+    pub cleanup: markdown::CleanupOptions,
+    pub changelog: &'a changelog::ChangelogOptions,
+    pub metrics: Option<FetchMetrics>,
+    /// `settings.embed_ai_notes`: prepend `ai_notes` to each saved markdown file.
+    pub embed_ai_notes: bool,
+    /// `settings.inject_header`: `false` saves files without the provenance
+    /// header (it stays in `.aifd-meta.toml`).
+    pub inject_header: bool,
+    /// `settings.inject_header_extensions`.
+    pub header_extensions: &'a [String],
+    /// `settings.prefer_source_readme`, only recorded in the `config_hash`.
+    pub prefer_source_readme: bool,
+    /// `settings.include_extensions`; other fetched files are skipped.
+    pub include_extensions: &'a [String],
+    /// `settings.filename_template`.
+    pub filename_template: &'a str,
+    /// Refs of the extra GitHub `sources` entries merged into the files.
+    pub extra_sources: &'a [SourceRef],
+}
+
+impl<'a> SaveContext<'a> {
+    fn hashed_settings(&self) -> HashedSettings<'a> {
+        HashedSettings {
+            inject_header: self.inject_header,
+            header_extensions: self.header_extensions,
+            prefer_source_readme: self.prefer_source_readme,
+            truncate_changelog: self.changelog.truncate,
+        }
+    }
+}
+
+/// Categories/keywords of the meta a re-save replaces, so a resync keeps
+/// them until `record_crate_metadata` refreshes them.
+fn previous_crate_metadata(crate_dir: &Path) -> (Vec<String>, Vec<String>) {
+    load_meta_with_migration(&crate_dir.join(".aifd-meta.toml"))
+        .map(|meta| (meta.categories, meta.keywords))
+        .unwrap_or_default()
+}
+
+/// Stores crates.io categories/keywords in an already saved crate's meta.
+pub fn record_crate_metadata(
+    output_dir: &Path,
+    crate_name: &str,
+    version: &str,
+    categories: Vec<String>,
+    keywords: Vec<String>,
+) -> Result<()> {
+    let meta_path = output_dir
+        .join(format!("{crate_name}@{version}"))
+        .join(".aifd-meta.toml");
+    let mut meta = load_meta_with_migration(&meta_path).ok_or_else(|| {
+        AiDocsError::Other(format!("no readable meta for {crate_name}@{version}"))
+    })?;
+    meta.categories = categories;
+    meta.keywords = keywords;
+    save_meta(&meta_path, &meta)
 }
 
 pub fn read_meta(output_dir: &Path, crate_name: &str, version: &str) -> Option<CrateMeta> {
@@ -477,6 +1163,9 @@ pub fn read_cached_info(
     })
 }
 
+/// Removes `name@version` dirs of crates no longer in the config and, unless
+/// `settings.prune_unconfigured_only`, of versions other than `lock_versions`.
+/// Crates synced at their latest version (`version_source`) are kept.
 pub fn prune(
     output_dir: &Path,
     config: &Config,
@@ -486,13 +1175,13 @@ pub fn prune(
         return Ok(());
     }
 
-    let configured: HashSet<&str> = config.crates.keys().map(String::as_str).collect();
-
     for entry in fs::read_dir(output_dir)? {
         let entry = entry?;
         let path = entry.path();
+        // Not followed: a symlink may point outside the output dir.
+        let file_type = entry.file_type()?;
 
-        if !path.is_dir() {
+        if !file_type.is_dir() && !file_type.is_symlink() {
             continue;
         }
 
@@ -504,15 +1193,26 @@ pub fn prune(
             continue;
         };
 
-        let should_remove = if configured.contains(crate_name) {
-            lock_versions
-                .get(crate_name)
-                .is_none_or(|lock_ver| lock_ver != dir_version)
-        } else {
-            true
+        let should_remove = match config.crates.get(crate_name) {
+            // Not pinned by the lockfile (`version_source = "latest"`).
+            Some(crate_doc)
+                if crate_doc.sync_mode(config.settings.sync_mode) == SyncMode::LatestDocs =>
+            {
+                false
+            }
+            Some(_) => {
+                !config.settings.prune_unconfigured_only
+                    && lock_versions
+                        .get(crate_name)
+                        .is_none_or(|lock_ver| lock_ver != dir_version)
+            }
+            // Maybe still configured through `[crates."*"]`.
+            None => !config.wildcard_unresolved.contains(crate_name),
         };
 
-        if should_remove {
+        if should_remove && file_type.is_symlink() {
+            warn!("  ⚠ not pruning {dir_name}: it is a symlink");
+        } else if should_remove {
             info!("  🗑 Pruning {dir_name}");
             fs::remove_dir_all(path)?;
         }
@@ -521,12 +1221,22 @@ pub fn prune(
     Ok(())
 }
 
-fn split_name_version(dir_name: &str) -> Option<(&str, &str)> {
-    let (name, version) = dir_name.rsplit_once('@')?;
-    if name.is_empty() || version.is_empty() {
-        return None;
+/// `name@version` of a crate dir; `None` unless the name is a valid crate
+/// name (so has no `@`) and the version starts with a digit (`foo@`,
+/// `@1.0`, `a@b@1.0` and `notes@draft` are not crate dirs). Skipped entries
+/// are logged at debug level.
+pub fn split_name_version(dir_name: &str) -> Option<(&str, &str)> {
+    let split = dir_name.rsplit_once('@').filter(|(name, version)| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            && version.starts_with(|c: char| c.is_ascii_digit())
+    });
+    if split.is_none() {
+        debug!("Skipping `{dir_name}`: not a `name@version` crate dir");
     }
-    Some((name, version))
+    split
 }
 
 pub fn rust_output_dir(base_output_dir: &Path) -> PathBuf {
@@ -539,7 +1249,7 @@ pub fn rust_output_dir(base_output_dir: &Path) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::CrateDoc;
+    use crate::config::{CrateDoc, HashedSettings, Settings};
 
     #[test]
     fn test_flatten_root_file() {
@@ -556,41 +1266,231 @@ mod tests {
 
     #[test]
     fn test_should_inject_header() {
-        assert!(should_inject_header("README.md"));
-        assert!(should_inject_header("guide.html"));
-        assert!(!should_inject_header("example.rs"));
+        let extensions = Settings::default().inject_header_extensions;
+        assert!(should_inject_header("README.md", &extensions));
+        assert!(should_inject_header("guide.html", &extensions));
+        assert!(should_inject_header("notes.TXT", &extensions));
+        assert!(should_inject_header("LICENSE", &extensions));
+        assert!(!should_inject_header("example.rs", &extensions));
+        assert!(should_inject_header("example.rs", &[".rs".to_string()]));
+    }
+
+    #[test]
+    fn include_extensions_keep_listed_and_extensionless_files() {
+        let extensions = Settings::default().include_extensions;
+        assert!(has_included_extension("README.MD", &extensions));
+        assert!(has_included_extension("docs/guide.adoc", &extensions));
+        assert!(has_included_extension("LICENSE", &extensions));
+        assert!(!has_included_extension("docs/logo.png", &extensions));
+        assert!(!has_included_extension("examples/demo.rs", &extensions));
+
+        // Files listed by name are kept whatever their extension.
+        let crate_doc = CrateDoc {
+            files: Some(vec!["examples/demo.rs".to_string(), "assets/".to_string()]),
+            ..CrateDoc::default()
+        };
+        assert!(is_saved_file("examples/demo.rs", &crate_doc, &extensions));
+        assert!(!is_saved_file("assets/logo.png", &crate_doc, &extensions));
+        assert!(!is_saved_file(
+            "examples/demo.rs",
+            &CrateDoc::default(),
+            &extensions
+        ));
+    }
+
+    #[test]
+    fn header_comment_syntax_follows_extension_and_strips_back() {
+        let body = "Body\n";
+        for (path, first_line_start) in [
+            ("guide.mdx", "{/* AI-FDOCS: source="),
+            ("index.rst", ".. AI-FDOCS: source="),
+            ("manual.adoc", "// AI-FDOCS: source="),
+            ("LICENSE", "AI-FDOCS: source="),
+        ] {
+            let with_header = inject_header(
+                body,
+                "github.com/o/r",
+                "main",
+                path,
+                true,
+                "1.0.0",
+                "https://x.invalid",
+            );
+            assert!(
+                with_header.starts_with(first_line_start),
+                "{path}: {with_header}"
+            );
+            assert_eq!(strip_injected_header(&with_header), body, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_strip_injected_header_roundtrips_inject_header() {
+        let body = "# Title\n\nBody\n";
+        let with_header = inject_header(
+            body,
+            "github.com/owner/repo",
+            "main",
+            "README.md",
+            true,
+            "1.0.0",
+            "https://example.invalid/README.md",
+        );
+
+        assert_eq!(strip_injected_header(&with_header), body);
+        assert_eq!(strip_injected_header(body), body);
     }
 
     #[test]
     fn test_truncate_large_file() {
         let content = "x".repeat(300 * 1024);
         let result = truncate_if_needed(&content, 200);
-        assert!(result.contains("[TRUNCATED by ai-fdocs at 200KB]"));
+        assert!(result.0.contains("[TRUNCATED by ai-fdocs at 200KB]"));
+    }
+
+    #[test]
+    fn truncation_cuts_at_a_line_and_closes_open_fences() {
+        let line = "let value = demo::call();\n";
+        let content = format!("# Demo\n\n````rust\n{}````\n", line.repeat(100));
+        let (truncated, is_truncated) = truncate_if_needed(&content, 1);
+        assert!(is_truncated);
+        let (body, marker) = truncated
+            .split_once("\n\n[TRUNCATED")
+            .expect("truncation marker");
+        assert!(body.ends_with("let value = demo::call();\n````"));
+        assert!(body.len() <= 1024 + "\n````".len());
+        assert_eq!(markdown::unclosed_fence(body), None);
+        assert_eq!(marker, " by ai-fdocs at 1KB]\n");
+
+        let closed = format!("```\ncode\n```\n{}", "text line\n".repeat(200));
+        let (truncated, _) = truncate_if_needed(&closed, 1);
+        assert!(truncated.contains("text line\n\n[TRUNCATED by ai-fdocs at 1KB]\n"));
+        assert!(!truncated.contains("text line\n```"));
+    }
+
+    #[test]
+    fn token_budget_cuts_changelogs_before_readmes() {
+        let readme = "readme line\n".repeat(166);
+        let bodies = || {
+            vec![
+                ("README.md", readme.clone()),
+                ("CHANGELOG.md", "- change\n".repeat(800)),
+                ("docs/guide.md", "guide line\n".repeat(363)),
+            ]
+        };
+        let total = |bodies: &[(&str, String)]| -> usize {
+            bodies.iter().map(|(_, body)| estimate_tokens(body)).sum()
+        };
+
+        let untouched_fit = BudgetFit {
+            cut: false,
+            met: true,
+        };
+        let cut_fit = BudgetFit {
+            cut: true,
+            met: true,
+        };
+        let mut untouched = bodies();
+        assert_eq!(fit_token_budget(&mut untouched, None), untouched_fit);
+        assert_eq!(
+            fit_token_budget(&mut untouched, Some(10_000)),
+            untouched_fit
+        );
+
+        let mut cut = bodies();
+        assert_eq!(fit_token_budget(&mut cut, Some(2000)), cut_fit);
+        assert!(total(&cut) <= 2000);
+        assert!(cut[1].1.ends_with(
+            "- change\n\n[TRUNCATED by ai-fdocs: settings.max_tokens_per_crate = 2000]\n"
+        ));
+        assert_eq!(cut[2].1, bodies()[2].1);
+        assert_eq!(cut[0].1, readme);
+
+        let mut tight = bodies();
+        assert_eq!(fit_token_budget(&mut tight, Some(600)), cut_fit);
+        assert!(total(&tight) <= 600);
+        assert!(tight[2].1.contains("[TRUNCATED by ai-fdocs"));
+        assert_eq!(tight[0].1, readme);
+
+        // Bodies shorter than the marker are kept; the budget is reported unmet.
+        let small = || {
+            vec![
+                ("CHANGELOG.md", "- a\n".to_string()),
+                ("README.md", "hi\n".to_string()),
+            ]
+        };
+        let mut unmet = small();
+        assert_eq!(
+            fit_token_budget(&mut unmet, Some(1)),
+            BudgetFit {
+                cut: false,
+                met: false,
+            }
+        );
+        assert_eq!(unmet, small());
     }
 
     #[test]
     fn test_split_name_version() {
         assert_eq!(split_name_version("serde@1.0.0"), Some(("serde", "1.0.0")));
         assert_eq!(split_name_version("serde"), None);
+        assert_eq!(split_name_version("foo@"), None);
+        assert_eq!(split_name_version("@1.0"), None);
+        assert_eq!(
+            split_name_version("foo@1.0.0+build"),
+            Some(("foo", "1.0.0+build"))
+        );
+        assert_eq!(split_name_version("a@b@1.0.0"), None);
+        assert_eq!(split_name_version("notes@draft"), None);
     }
 
     #[test]
     fn test_config_fingerprint_changes_when_repo_changes() {
         let mut cfg = CrateDoc {
             repo: Some("serde-rs/serde".to_string()),
-            subpath: None,
-            files: None,
-            sources: None,
-            ai_notes: String::new(),
+            ..CrateDoc::default()
         };
 
-        let fp1 = crate_config_fingerprint(&cfg);
+        let settings = Settings::default();
+        let fp1 = cfg.config_hash(&HashedSettings::from(&settings));
         cfg.repo = Some("tokio-rs/tokio".to_string());
-        let fp2 = crate_config_fingerprint(&cfg);
+        let fp2 = cfg.config_hash(&HashedSettings::from(&settings));
 
         assert_ne!(fp1, fp2);
     }
 
+    #[test]
+    fn test_config_fingerprint_changes_with_output_settings() {
+        let cfg = CrateDoc {
+            repo: Some("serde-rs/serde".to_string()),
+            ..CrateDoc::default()
+        };
+        let hashed = |cfg: &CrateDoc, edit: fn(&mut Settings)| {
+            let mut settings = Settings::default();
+            edit(&mut settings);
+            cfg.config_hash(&HashedSettings::from(&settings))
+        };
+        let defaults = hashed(&cfg, |_| {});
+
+        assert_ne!(defaults, hashed(&cfg, |s| s.inject_header = false));
+        assert_ne!(
+            defaults,
+            hashed(&cfg, |s| s.inject_header_extensions.push("rs".to_string()))
+        );
+        assert_ne!(defaults, hashed(&cfg, |s| s.prefer_source_readme = true));
+        assert_ne!(defaults, hashed(&cfg, |s| s.truncate_changelog = false));
+
+        // A crate that sets `full_changelog` is unaffected by the global setting.
+        let full = CrateDoc {
+            full_changelog: Some(true),
+            ..cfg.clone()
+        };
+        assert_eq!(
+            hashed(&full, |_| {}),
+            hashed(&full, |s| s.truncate_changelog = false)
+        );
+    }
+
     #[test]
     fn test_latest_docs_summary_provenance_includes_source_and_truncation() {
         let p = latest_docs_summary_provenance("docsrs", "https://docs.rs/crate/serde/1.0.0", true);
@@ -614,6 +1514,19 @@ mod tests {
         assert!(summary.contains("## AI Notes"));
         assert!(summary.contains("[README.md](README.md)"));
     }
+
+    #[test]
+    fn test_embed_ai_notes_as_blockquote_below_header() {
+        assert_eq!(
+            embed_ai_notes("# Serde\n", "Use derive macros.\n\nAvoid `serde_derive` directly.\n"),
+            "> **AI notes:**\n> Use derive macros.\n>\n> Avoid `serde_derive` directly.\n\n# Serde\n"
+        );
+        assert_eq!(embed_ai_notes("# Serde\n", "  \n"), "# Serde\n");
+
+        let extensions = Settings::default().inject_header_extensions;
+        assert!(should_embed_ai_notes("docs/guide.md", &extensions));
+        assert!(!should_embed_ai_notes("docs/index.html", &extensions));
+    }
     #[test]
     fn test_load_meta_migrates_legacy_schema() {
         let tmp =
@@ -632,9 +1545,18 @@ config_fingerprint = "abc"
 
         let migrated = load_meta_with_migration(&meta_path).expect("load migrated");
         assert_eq!(migrated.schema_version, META_SCHEMA_VERSION);
+        assert_eq!(
+            fs::read_to_string(&meta_path).expect("read meta"),
+            legacy,
+            "reads must not rewrite the meta"
+        );
 
-        let rewritten = fs::read_to_string(&meta_path).expect("read rewritten meta");
-        assert!(rewritten.contains("schema_version = 1"));
+        let crate_dir = tmp.join("demo@1.0.0");
+        fs::create_dir_all(&crate_dir).expect("create crate dir");
+        fs::write(crate_dir.join(".aifd-meta.toml"), legacy).expect("write legacy meta");
+        upgrade_meta_schema(&tmp, "demo", "1.0.0");
+        let rewritten = fs::read_to_string(crate_dir.join(".aifd-meta.toml")).expect("read meta");
+        assert!(rewritten.contains(&format!("schema_version = {META_SCHEMA_VERSION}")));
 
         let _ = fs::remove_dir_all(&tmp);
     }
@@ -659,4 +1581,198 @@ config_fingerprint = "abc"
 
         let _ = fs::remove_dir_all(&tmp);
     }
+
+    #[test]
+    fn test_absent_files_are_recorded_and_reused_only_at_the_same_ref() {
+        let request = |path: &str| FileRequest {
+            original_path: path.to_string(),
+            candidates: vec![path.to_string()],
+            required: false,
+        };
+        let files = file_manifest(
+            &[request("README.md"), request("CHANGELOG.md")],
+            &[],
+            &["CHANGELOG.md".to_string()],
+        );
+        assert!(files["CHANGELOG.md"].absent);
+        assert!(!files["README.md"].absent);
+
+        let mut meta: CrateMeta = toml::from_str(
+            r#"version = "1.0.0"
+git_ref = "v1.0.0"
+commit_sha = "abc"
+fetched_at = "2026-01-01"
+is_fallback = false
+"#,
+        )
+        .expect("parse meta");
+        meta.files = files;
+        let saved = toml::to_string(&meta).expect("serialize meta");
+        assert_eq!(saved.matches("absent = true").count(), 1);
+
+        assert_eq!(
+            known_absent_files(&meta, "v1.0.0", Some("abc")),
+            vec!["CHANGELOG.md"]
+        );
+        assert!(known_absent_files(&meta, "v1.0.1", Some("abc")).is_empty());
+        assert!(known_absent_files(&meta, "v1.0.0", Some("def")).is_empty());
+    }
+
+    #[test]
+    fn test_filename_template_renders_and_rejects_traversal() {
+        let render = |template| render_filename(template, "serde", "1.0.0", "docs/guide.md");
+        assert_eq!(render(DEFAULT_FILENAME_TEMPLATE), "docs__guide.md");
+        assert_eq!(render("{crate}__{path}"), "serde__docs__guide.md");
+        assert_eq!(
+            render("{crate}-{version}-{flat}"),
+            "serde-1.0.0-docs__guide.md"
+        );
+
+        assert!(validate_filename_template("{crate}__{path}").is_ok());
+        assert!(validate_filename_template("../{flat}").is_err());
+        assert!(validate_filename_template("{crate}..{flat}").is_err());
+        assert!(validate_filename_template(".{flat}").is_err());
+        assert!(validate_filename_template("{crate}.md").is_err());
+        assert!(validate_filename_template("{name}-{flat}").is_err());
+    }
+
+    #[test]
+    fn test_is_cached_requires_same_filename_template() {
+        let tmp = std::env::temp_dir().join(format!("ai-fdocs-template-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        let crate_doc = CrateDoc::default();
+        let settings = Settings::default();
+        fs::create_dir_all(tmp.join("serde@1.0.0")).expect("create crate dir");
+        let mut meta = CrateMeta {
+            schema_version: META_SCHEMA_VERSION,
+            version: "1.0.0".to_string(),
+            git_ref: "v1.0.0".to_string(),
+            commit_sha: None,
+            inferred_subpath: None,
+            fetched_at: "2026-01-01".to_string(),
+            is_fallback: false,
+            fallback_reason: None,
+            config_hash: Some(crate_doc.config_hash(&HashedSettings::from(&settings))),
+            source_kind: Some("github".to_string()),
+            artifact_path: None,
+            docsrs_input_url: None,
+            docsrs_canonical_base_url: None,
+            upstream_latest_version: None,
+            upstream_checked_at: None,
+            ttl_expires_at: None,
+            truncated: None,
+            truncation_marker: None,
+            artifact_sha256: None,
+            artifact_bytes: None,
+            estimated_tokens: None,
+            fetch_duration_ms: None,
+            total_bytes: None,
+            categories: Vec::new(),
+            keywords: Vec::new(),
+            filename_template: None,
+            synced_from: None,
+            extra_sources: Vec::new(),
+            files: BTreeMap::new(),
+        };
+        let meta_path = tmp.join("serde@1.0.0/.aifd-meta.toml");
+        save_meta(&meta_path, &meta).expect("save meta");
+
+        let hashed = HashedSettings::from(&settings);
+        let cached = |template| is_cached(&tmp, "serde", "1.0.0", &crate_doc, &hashed, template);
+        assert!(cached(Some(DEFAULT_FILENAME_TEMPLATE)));
+        assert!(!cached(Some("{crate}__{flat}")));
+        assert!(cached(None));
+        // `settings.inject_header` is part of the config hash.
+        let no_header = HashedSettings {
+            inject_header: false,
+            ..hashed
+        };
+        assert!(!is_cached(
+            &tmp, "serde", "1.0.0", &crate_doc, &no_header, None
+        ));
+
+        meta.filename_template = Some("{crate}__{flat}".to_string());
+        save_meta(&meta_path, &meta).expect("save meta");
+        assert!(cached(Some("{crate}__{flat}")));
+        assert!(!cached(Some(DEFAULT_FILENAME_TEMPLATE)));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_prune_unconfigured_only_keeps_other_versions() {
+        let tmp = std::env::temp_dir().join(format!("ai-fdocs-prune-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        for dir in ["serde@1.0.0", "serde@1.0.1", "dropped@0.1.0"] {
+            fs::create_dir_all(tmp.join(dir)).expect("create crate dir");
+        }
+
+        let mut config = Config {
+            settings: Settings {
+                prune_unconfigured_only: true,
+                ..Settings::default()
+            },
+            crates: HashMap::from([("serde".to_string(), CrateDoc::default())]),
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+        let lock_versions = HashMap::from([("serde".to_string(), "1.0.1".to_string())]);
+
+        prune(&tmp, &config, &lock_versions).expect("prune");
+        assert!(tmp.join("serde@1.0.0").exists());
+        assert!(tmp.join("serde@1.0.1").exists());
+        assert!(!tmp.join("dropped@0.1.0").exists());
+
+        config.settings.prune_unconfigured_only = false;
+        prune(&tmp, &config, &lock_versions).expect("prune");
+        assert!(!tmp.join("serde@1.0.0").exists());
+        assert!(tmp.join("serde@1.0.1").exists());
+
+        fs::create_dir_all(tmp.join("tokio@1.40.0")).expect("create crate dir");
+        config.crates.insert(
+            "tokio".to_string(),
+            CrateDoc {
+                version_source: Some(crate::config::VersionSource::Latest),
+                ..CrateDoc::default()
+            },
+        );
+        prune(&tmp, &config, &lock_versions).expect("prune");
+        assert!(tmp.join("tokio@1.40.0").exists());
+
+        // A failed `[crates."*"]` lookup must not look like an unconfigured crate.
+        fs::create_dir_all(tmp.join("anyhow@1.0.0")).expect("create crate dir");
+        config.wildcard_unresolved.insert("anyhow".to_string());
+        prune(&tmp, &config, &lock_versions).expect("prune");
+        assert!(tmp.join("anyhow@1.0.0").exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prune_leaves_symlinked_entries_and_their_targets_alone() {
+        let tmp = std::env::temp_dir().join(format!("ai-fdocs-prune-link-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        let outside = tmp.join("elsewhere");
+        let output_dir = tmp.join("rust");
+        fs::create_dir_all(&outside).expect("create target dir");
+        fs::write(outside.join("keep.md"), "keep").expect("write target file");
+        fs::create_dir_all(&output_dir).expect("create output dir");
+        std::os::unix::fs::symlink(&outside, output_dir.join("dropped@0.1.0"))
+            .expect("create symlink");
+
+        let config = Config {
+            settings: Settings::default(),
+            crates: HashMap::new(),
+            wildcard: None,
+            wildcard_unresolved: Default::default(),
+            root: Default::default(),
+        };
+        prune(&output_dir, &config, &HashMap::new()).expect("prune");
+        assert!(fs::symlink_metadata(output_dir.join("dropped@0.1.0")).is_ok());
+        assert!(outside.join("keep.md").exists());
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
 }
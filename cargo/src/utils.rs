@@ -1,5 +1,41 @@
-use crate::error::Result;
-use chrono::{NaiveDate, Utc};
+use std::future::Future;
+use std::sync::OnceLock;
+use std::task::Poll;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Test-only: RFC 3339 timestamp used instead of the clock, so generated
+/// headers, meta, history and index are byte-for-byte reproducible.
+pub const FIXED_TIME_ENV: &str = "AI_FDOCS_FIXED_TIME";
+
+/// Current time, or `AI_FDOCS_FIXED_TIME` when set to a valid RFC 3339 value.
+/// Every timestamp ai-fdocs writes goes through this.
+pub fn now() -> DateTime<Utc> {
+    fixed_time().unwrap_or_else(Utc::now)
+}
+
+/// Whether `AI_FDOCS_FIXED_TIME` is in effect; measured durations are then
+/// recorded as 0 too.
+pub fn is_time_fixed() -> bool {
+    fixed_time().is_some()
+}
+
+/// Read once per process, so an invalid value is only warned about once.
+fn fixed_time() -> Option<DateTime<Utc>> {
+    static FIXED_TIME: OnceLock<Option<DateTime<Utc>>> = OnceLock::new();
+    *FIXED_TIME.get_or_init(|| parse_fixed_time(std::env::var(FIXED_TIME_ENV).ok().as_deref()))
+}
+
+fn parse_fixed_time(value: Option<&str>) -> Option<DateTime<Utc>> {
+    let value = value.map(str::trim).filter(|v| !v.is_empty())?;
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => Some(time.with_timezone(&Utc)),
+        Err(e) => {
+            tracing::warn!("ignoring {FIXED_TIME_ENV}={value}: {e}");
+            None
+        }
+    }
+}
 
 /// Semver-aware version comparison. Returns true if new_v > current_best.
 pub fn is_version_better(new_v: &str, current_best: Option<&str>) -> bool {
@@ -51,7 +87,74 @@ pub fn is_latest_cache_fresh(fetched_at: &str, latest_ttl_hours: usize) -> bool
         return false;
     };
 
-    let now = Utc::now().naive_utc();
+    let now = now().naive_utc();
     let age = now - fetched_dt;
     age.num_hours() < latest_ttl_hours as i64
 }
+
+/// Drives `futures` concurrently on the current task and returns their
+/// outputs in input order; for futures that borrow and cannot be spawned.
+pub async fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output> {
+    let mut pending: Vec<_> = futures.into_iter().map(|f| Some(Box::pin(f))).collect();
+    let mut outputs: Vec<Option<F::Output>> = pending.iter().map(|_| None).collect();
+    std::future::poll_fn(|cx| {
+        let mut done = true;
+        for (slot, output) in pending.iter_mut().zip(outputs.iter_mut()) {
+            let Some(future) = slot else {
+                continue;
+            };
+            match future.as_mut().poll(cx) {
+                Poll::Ready(value) => {
+                    *output = Some(value);
+                    *slot = None;
+                }
+                Poll::Pending => done = false,
+            }
+        }
+        if done {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+    outputs.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{join_all, parse_fixed_time};
+
+    #[test]
+    fn fixed_time_accepts_rfc3339_and_ignores_invalid_values() {
+        let fixed = parse_fixed_time(Some("2026-01-02T03:04:05+02:00")).expect("valid time");
+        assert_eq!(fixed.to_rfc3339(), "2026-01-02T01:04:05+00:00");
+        assert!(parse_fixed_time(Some("2026-01-02")).is_none());
+        assert!(parse_fixed_time(Some("")).is_none());
+        assert!(parse_fixed_time(None).is_none());
+    }
+
+    #[tokio::test]
+    async fn join_all_runs_futures_concurrently_in_input_order() {
+        use std::cell::Cell;
+
+        let in_flight = Cell::new(0);
+        let peak = Cell::new(0);
+        let outputs = join_all([3, 1, 2].map(|yields| {
+            let (in_flight, peak) = (&in_flight, &peak);
+            async move {
+                in_flight.set(in_flight.get() + 1);
+                peak.set(peak.get().max(in_flight.get()));
+                for _ in 0..yields {
+                    tokio::task::yield_now().await;
+                }
+                in_flight.set(in_flight.get() - 1);
+                yields
+            }
+        }))
+        .await;
+        // Outputs come back in input order although the futures finish 1, 2, 3.
+        assert_eq!(outputs, vec![3, 1, 2]);
+        assert_eq!(peak.get(), 3);
+    }
+}
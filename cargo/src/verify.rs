@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::storage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum VerifyStatus {
+    Match,
+    Mismatch,
+    Skipped,
+    Error,
+}
+
+impl VerifyStatus {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Match => "Match",
+            Self::Mismatch => "Mismatch",
+            Self::Skipped => "Skipped",
+            Self::Error => "Error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrateVerify {
+    pub crate_name: String,
+    pub version: Option<String>,
+    pub status: VerifyStatus,
+    pub reason: String,
+    pub mismatched_files: Vec<String>,
+}
+
+impl CrateVerify {
+    pub fn new(
+        crate_name: &str,
+        version: Option<&str>,
+        status: VerifyStatus,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            crate_name: crate_name.to_string(),
+            version: version.map(str::to_string),
+            status,
+            reason: reason.into(),
+            mismatched_files: Vec::new(),
+        }
+    }
+}
+
+/// Compares freshly processed upstream content (`flat name -> body`, without
+/// provenance header) against the files stored in `crate_dir`.
+///
+/// Returns the sorted list of files that differ, are missing locally, or are
+/// no longer produced upstream.
+pub fn compare_with_stored(crate_dir: &Path, expected: &BTreeMap<String, String>) -> Vec<String> {
+    let mut mismatched = Vec::new();
+
+    for (name, body) in expected {
+        match fs::read_to_string(crate_dir.join(name)) {
+            Ok(stored) if storage::strip_injected_header(&stored) == body => {}
+            _ => mismatched.push(name.clone()),
+        }
+    }
+
+    for name in stored_doc_files(crate_dir) {
+        if !expected.contains_key(&name) {
+            mismatched.push(name);
+        }
+    }
+
+    mismatched.sort();
+    mismatched
+}
+
+/// Saved doc file names (same set as `read_cached_info` / `stats`).
+pub fn stored_doc_files(crate_dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(crate_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| !name.starts_with('.') && name != "_SUMMARY.md")
+        .collect()
+}
+
+pub fn print_verify_table(results: &[CrateVerify]) {
+    print!("{}", format_verify_table(results));
+}
+
+pub fn format_verify_json(
+    results: &[CrateVerify],
+) -> std::result::Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(results)
+}
+
+fn format_verify_table(results: &[CrateVerify]) -> String {
+    const COL_CRATE: usize = 24;
+    const COL_VERSION: usize = 16;
+    const COL_STATUS: usize = 10;
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "{:<COL_CRATE$} {:<COL_VERSION$} {:<COL_STATUS$}",
+        "Crate", "Docs Version", "Verify"
+    );
+    let _ = writeln!(
+        output,
+        "{:-<COL_CRATE$} {:-<COL_VERSION$} {:-<COL_STATUS$}",
+        "", "", ""
+    );
+
+    for item in results {
+        let _ = writeln!(
+            output,
+            "{:<COL_CRATE$} {:<COL_VERSION$} {:<COL_STATUS$}",
+            item.crate_name,
+            item.version.as_deref().unwrap_or("-"),
+            item.status.as_str(),
+        );
+        let _ = writeln!(output, "  ↳ {}", item.reason);
+        for file in &item.mismatched_files {
+            let _ = writeln!(output, "    - {file}");
+        }
+    }
+
+    let count = |status: VerifyStatus| results.iter().filter(|r| r.status == status).count();
+    let _ = writeln!(output);
+    let _ = writeln!(
+        output,
+        "Total: {} | Match: {} | Mismatch: {} | Skipped: {} | Error: {}",
+        results.len(),
+        count(VerifyStatus::Match),
+        count(VerifyStatus::Mismatch),
+        count(VerifyStatus::Skipped),
+        count(VerifyStatus::Error)
+    );
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_with_stored, format_verify_table, CrateVerify, VerifyStatus};
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    #[test]
+    fn detects_edited_missing_and_extra_files() {
+        let tmp = std::env::temp_dir().join(format!("aifd-verify-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        fs::create_dir_all(&tmp).expect("create crate dir");
+
+        fs::write(
+            tmp.join("README.md"),
+            "<!-- AI-FDOCS: source=github.com/o/r ref=v1 path=README.md fetched=2026-01-01 -->\n<!-- AI-FDOCS: url=https://example.invalid -->\n\n# Readme\n",
+        )
+        .expect("write readme");
+        fs::write(tmp.join("CHANGELOG.md"), "old changelog\n").expect("write changelog");
+        fs::write(tmp.join("removed.md"), "gone upstream\n").expect("write extra");
+        fs::write(tmp.join("_SUMMARY.md"), "ignored").expect("write summary");
+        fs::write(tmp.join(".aifd-meta.toml"), "ignored").expect("write meta");
+
+        let mut expected = BTreeMap::new();
+        expected.insert("README.md".to_string(), "# Readme\n".to_string());
+        expected.insert("CHANGELOG.md".to_string(), "edited changelog\n".to_string());
+        expected.insert("new.md".to_string(), "new upstream\n".to_string());
+
+        let mismatched = compare_with_stored(&tmp, &expected);
+        assert_eq!(mismatched, vec!["CHANGELOG.md", "new.md", "removed.md"]);
+
+        let mut result = CrateVerify::new("demo", Some("1.0.0"), VerifyStatus::Mismatch, "differs");
+        result.mismatched_files = mismatched;
+        let table = format_verify_table(&[result]);
+        assert!(table.contains("    - CHANGELOG.md"));
+        assert!(table.contains("Total: 1 | Match: 0 | Mismatch: 1 | Skipped: 0 | Error: 0"));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::error::{AiDocsError, Result};
+
+/// A crate of the current Cargo workspace, synced from its local directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceMember {
+    pub name: String,
+    pub version: String,
+    /// Crate directory relative to the workspace root (`.` for a root package).
+    pub dir: PathBuf,
+}
+
+/// Members of the workspace rooted at `root` (`[workspace] members`, minus
+/// `exclude`), plus the root package if there is one. Member globs may use
+/// `*` in their last component (e.g. `crates/*`). Sorted by name.
+pub fn workspace_members(root: &Path) -> Result<Vec<WorkspaceMember>> {
+    let manifest = read_manifest(&root.join("Cargo.toml"))?;
+    let workspace = manifest.get("workspace");
+    let patterns = |key: &str| -> Vec<String> {
+        workspace
+            .and_then(|w| w.get(key))
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|s| s.trim_end_matches('/').to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let excluded: Vec<PathBuf> = patterns("exclude").iter().map(PathBuf::from).collect();
+
+    let mut dirs = Vec::new();
+    if manifest.get("package").is_some() {
+        dirs.push(PathBuf::from("."));
+    }
+    for pattern in patterns("members") {
+        for dir in expand_member_pattern(root, &pattern) {
+            if !excluded.contains(&dir) && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+
+    let mut members = dirs
+        .into_iter()
+        .map(|dir| {
+            let name = package_name(&read_manifest(&root.join(&dir).join("Cargo.toml"))?, &dir)?;
+            let version = member_version(root, &dir)?;
+            Ok(WorkspaceMember { name, version, dir })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(members)
+}
+
+/// `[package] version` of the member in `dir` (relative to `root`), resolving
+/// `version.workspace = true` from `[workspace.package]`. Cargo's default
+/// `0.0.0` when neither is set.
+pub fn member_version(root: &Path, dir: &Path) -> Result<String> {
+    let manifest = read_manifest(&root.join(dir).join("Cargo.toml"))?;
+    let version = manifest.get("package").and_then(|p| p.get("version"));
+    let inherits = version
+        .and_then(|v| v.get("workspace"))
+        .and_then(Value::as_bool)
+        == Some(true);
+    let version = if inherits {
+        read_manifest(&root.join("Cargo.toml"))?
+            .get("workspace")
+            .and_then(|w| w.get("package"))
+            .and_then(|p| p.get("version"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| {
+                AiDocsError::Other(format!(
+                    "{} inherits its version but [workspace.package] has none",
+                    dir.display()
+                ))
+            })?
+    } else {
+        version
+            .and_then(Value::as_str)
+            .unwrap_or("0.0.0")
+            .to_string()
+    };
+    Ok(version)
+}
+
+fn read_manifest(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| AiDocsError::Other(format!("cannot read {}: {e}", path.display())))?;
+    Ok(toml::from_str(&content)?)
+}
+
+fn package_name(manifest: &Value, dir: &Path) -> Result<String> {
+    manifest
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            AiDocsError::Other(format!(
+                "{}/Cargo.toml has no [package] name",
+                dir.display()
+            ))
+        })
+}
+
+/// Directories matched by one `members` entry that contain a `Cargo.toml`.
+fn expand_member_pattern(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let pattern = Path::new(pattern);
+    let last = pattern
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let Some((prefix, suffix)) = last.split_once('*') else {
+        return vec![pattern.to_path_buf()];
+    };
+
+    let parent = pattern.parent().unwrap_or_else(|| Path::new(""));
+    let Ok(entries) = std::fs::read_dir(root.join(parent)) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.path().join("Cargo.toml").is_file())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let matches = name.len() >= prefix.len() + suffix.len()
+                && name.starts_with(prefix)
+                && name.ends_with(suffix);
+            matches.then(|| parent.join(name))
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{workspace_members, WorkspaceMember};
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn finds_globbed_members_with_inherited_versions() {
+        let tmp = std::env::temp_dir().join(format!("aifd-workspace-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&tmp);
+        let write = |path: &str, content: &str| {
+            let path = tmp.join(path);
+            fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+            fs::write(path, content).expect("write manifest");
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\", \"tools/cli\"]\nexclude = [\"crates/scratch\"]\n\n[workspace.package]\nversion = \"0.4.0\"\n",
+        );
+        write(
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"demo-core\"\nversion.workspace = true\n",
+        );
+        write(
+            "crates/macros/Cargo.toml",
+            "[package]\nname = \"demo-macros\"\nversion = \"0.1.2\"\n",
+        );
+        write(
+            "crates/scratch/Cargo.toml",
+            "[package]\nname = \"scratch\"\n",
+        );
+        write("crates/notes/README.md", "not a crate\n");
+        write("tools/cli/Cargo.toml", "[package]\nname = \"demo-cli\"\n");
+
+        let members = workspace_members(&tmp).expect("members");
+        let member = |name: &str, version: &str, dir: &str| WorkspaceMember {
+            name: name.to_string(),
+            version: version.to_string(),
+            dir: PathBuf::from(dir),
+        };
+        assert_eq!(
+            members,
+            vec![
+                member("demo-cli", "0.0.0", "tools/cli"),
+                member("demo-core", "0.4.0", "crates/core"),
+                member("demo-macros", "0.1.2", "crates/macros"),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+}